@@ -5,22 +5,34 @@
 
 use eframe::egui;
 use memmap2::Mmap;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::path::PathBuf;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
 use std::thread;
 
 use crate::analytics;
-use crate::computed::{ComputedChannel, ComputedChannelLibrary, FormulaEditorState};
-use crate::parsers::{Aim, EcuMaster, EcuType, Haltech, Link, Parseable, RomRaider, Speeduino};
+use crate::computed::{
+    computed_dependencies, evaluation_order, run_evaluation_steps, ComputedChannel,
+    ComputedChannelLibrary, EvaluationStep, FormulaEditorState, StepInput,
+};
+use crate::expression::{
+    build_channel_bindings, extract_channel_references, extract_constant_references,
+    extract_table_references, CompiledFormula, FormulaContext, InputColumn,
+};
+use crate::parsers::{Channel, EcuType, Marker, ParserRegistry};
 use crate::state::{
-    ActiveTool, CacheKey, LoadResult, LoadedFile, LoadingState, ScatterPlotConfig,
-    ScatterPlotState, SelectedChannel, Tab, ToastType, CHART_COLORS, COLORBLIND_COLORS,
+    ActiveTool, CacheKey, ComputedJob, CsvExportState, FormulaPreviewJob, LoadResult, LoadedFile,
+    LoadingState, PendingLibraryImport, ProfileEditorState, ScatterPlotConfig, ScatterPlotState,
+    SelectedChannel, Tab, TableEditorState, ToastType, CHART_COLORS, COLORBLIND_COLORS,
     MAX_CHANNELS,
 };
-use crate::units::UnitPreferences;
+use crate::units::{to_canonical, UnitPreferences};
 use crate::updater::{DownloadResult, UpdateCheckResult, UpdateState};
+use crate::vehicle_profile::VehicleProfiles;
 
 // ============================================================================
 // Main Application State
@@ -78,6 +90,10 @@ pub struct UltraLogApp {
     pub(crate) custom_normalizations: HashMap<String, String>,
     /// Whether to show the normalization editor window
     pub(crate) show_normalization_editor: bool,
+    /// Whether the laps window is open
+    pub(crate) show_laps_window: bool,
+    /// Whether the chart overlay window is open
+    pub(crate) show_overlay_window: bool,
     /// Input field for source name in "Extend Built-in" section
     pub(crate) norm_editor_extend_source: String,
     /// Selected built-in target in the extend dropdown
@@ -112,12 +128,29 @@ pub struct UltraLogApp {
     // === Computed Channels ===
     /// Global library of computed channel templates
     pub(crate) computed_library: ComputedChannelLibrary,
+    /// Vehicle profiles whose constants formulas reference as `$name`
+    pub(crate) vehicle_profiles: VehicleProfiles,
+    /// State for the vehicle profiles window
+    pub(crate) profile_editor_state: ProfileEditorState,
     /// Computed channels applied per file (file_index -> `Vec<ComputedChannel>`)
     pub(crate) file_computed_channels: HashMap<usize, Vec<ComputedChannel>>,
     /// Whether to show the computed channels manager dialog
     pub(crate) show_computed_channels_manager: bool,
     /// State for the formula editor dialog
     pub(crate) formula_editor_state: FormulaEditorState,
+    /// Whole-log preview of the formula being edited
+    pub(crate) formula_preview_job: Option<FormulaPreviewJob>,
+    /// State for the lookup table editor dialog
+    pub(crate) table_editor_state: TableEditorState,
+    /// Imported library whose conflicts the user is resolving
+    pub(crate) pending_library_import: Option<PendingLibraryImport>,
+    /// Computed channel evaluations running in the background
+    computed_jobs: Vec<ComputedJob>,
+    /// Id for the next background evaluation
+    next_computed_job_id: u64,
+    // === Data Export ===
+    /// Options for the CSV export dialog
+    pub(crate) csv_export_state: CsvExportState,
 }
 
 impl Default for UltraLogApp {
@@ -145,6 +178,8 @@ impl Default for UltraLogApp {
             unit_preferences: UnitPreferences::default(),
             custom_normalizations: HashMap::new(),
             show_normalization_editor: false,
+            show_laps_window: false,
+            show_overlay_window: false,
             norm_editor_extend_source: String::new(),
             norm_editor_selected_target: None,
             norm_editor_custom_source: String::new(),
@@ -160,9 +195,17 @@ impl Default for UltraLogApp {
             startup_check_done: false,
             should_exit_for_update: false,
             computed_library: ComputedChannelLibrary::load(),
+            vehicle_profiles: VehicleProfiles::load(),
+            profile_editor_state: ProfileEditorState::default(),
             file_computed_channels: HashMap::new(),
             show_computed_channels_manager: false,
             formula_editor_state: FormulaEditorState::default(),
+            formula_preview_job: None,
+            table_editor_state: TableEditorState::default(),
+            pending_library_import: None,
+            computed_jobs: Vec::new(),
+            next_computed_job_id: 0,
+            csv_export_state: CsvExportState::default(),
         }
     }
 }
@@ -305,7 +348,7 @@ impl UltraLogApp {
             }
         };
 
-        Self::parse_binary_data(&mmap, path)
+        Self::parse_binary_data(&mmap)
     }
 
     /// Load file using regular file read (for smaller files)
@@ -315,124 +358,14 @@ impl UltraLogApp {
             Err(e) => return Err(LoadResult::Error(format!("Failed to read file: {}", e))),
         };
 
-        Self::parse_binary_data(&binary_data, path)
-    }
-
-    /// Parse binary data and detect file format
-    fn parse_binary_data(
-        binary_data: &[u8],
-        path: &PathBuf,
-    ) -> Result<(crate::parsers::Log, EcuType), LoadResult> {
-        // Check for Haltech HEPS format (.hlgzip) - proprietary compressed format
-        if binary_data.len() >= 4 && &binary_data[0..4] == b"HEPS" {
-            return Err(LoadResult::Error(
-                "This is a Haltech .hlgzip file which uses proprietary compression.\n\n\
-                To use this log in UltraLog, please export it as CSV from Haltech's ESP or NSP software:\n\
-                1. Open the .hlgzip file in Haltech ESP/NSP\n\
-                2. Go to File → Export → CSV\n\
-                3. Load the exported .csv file in UltraLog"
-                    .to_string(),
-            ));
-        }
-
-        // Check for AEM .daq format - proprietary format (starts with "EMERALD")
-        if binary_data.len() >= 7 && &binary_data[0..7] == b"EMERALD" {
-            return Err(LoadResult::Error(
-                "This is an AEM .daq file which uses a proprietary format.\n\n\
-                To use this log in UltraLog, please export it as CSV from AEM's software:\n\
-                1. Open the .daq file in AEMdata or AEM Pro\n\
-                2. Go to File → Export → CSV\n\
-                3. Load the exported .csv file in UltraLog"
-                    .to_string(),
-            ));
-        }
-
-        // Check for AIM XRK format - parse using pure Rust implementation
-        if Aim::detect(binary_data) {
-            match Aim::parse_file(path) {
-                Ok(l) => return Ok((l, EcuType::Aim)),
-                Err(e) => {
-                    return Err(LoadResult::Error(format!(
-                        "Failed to parse AIM XRK file: {}",
-                        e
-                    )))
-                }
-            }
-        }
-
-        // Auto-detect file format and parse
-        if Speeduino::detect(binary_data) {
-            // Speeduino/rusEFI MLG format detected (binary)
-            match Speeduino::parse_binary(binary_data) {
-                Ok(l) => Ok((l, EcuType::Speeduino)),
-                Err(e) => Err(LoadResult::Error(format!(
-                    "Failed to parse Speeduino/rusEFI MLG file: {}",
-                    e
-                ))),
-            }
-        } else if Link::detect(binary_data) {
-            // Link ECU LLG format detected (binary)
-            match Link::parse_binary(binary_data) {
-                Ok(l) => Ok((l, EcuType::Link)),
-                Err(e) => Err(LoadResult::Error(format!(
-                    "Failed to parse Link ECU LLG file: {}",
-                    e
-                ))),
-            }
-        } else {
-            // Try parsing as text-based formats
-            // For mmap, we use from_utf8 which doesn't copy the data
-            let contents = match std::str::from_utf8(binary_data) {
-                Ok(c) => c,
-                Err(_) => {
-                    // Fall back to lossy conversion for files with encoding issues
-                    return Self::parse_text_lossy(binary_data, path);
-                }
-            };
-
-            Self::parse_text_content(contents)
-        }
-    }
-
-    /// Parse text content after UTF-8 validation
-    fn parse_text_content(contents: &str) -> Result<(crate::parsers::Log, EcuType), LoadResult> {
-        if EcuMaster::detect(contents) {
-            // ECUMaster format detected
-            let parser = EcuMaster;
-            match parser.parse(contents) {
-                Ok(l) => Ok((l, EcuType::EcuMaster)),
-                Err(e) => Err(LoadResult::Error(format!(
-                    "Failed to parse ECUMaster file: {}",
-                    e
-                ))),
-            }
-        } else if RomRaider::detect(contents) {
-            // RomRaider format detected
-            let parser = RomRaider;
-            match parser.parse(contents) {
-                Ok(l) => Ok((l, EcuType::RomRaider)),
-                Err(e) => Err(LoadResult::Error(format!(
-                    "Failed to parse RomRaider file: {}",
-                    e
-                ))),
-            }
-        } else {
-            // Default to Haltech format
-            let parser = Haltech;
-            match parser.parse(contents) {
-                Ok(l) => Ok((l, EcuType::Haltech)),
-                Err(e) => Err(LoadResult::Error(format!("Failed to parse file: {}", e))),
-            }
-        }
+        Self::parse_binary_data(&binary_data)
     }
 
-    /// Parse text with lossy UTF-8 conversion for files with encoding issues
-    fn parse_text_lossy(
-        binary_data: &[u8],
-        _path: &PathBuf,
-    ) -> Result<(crate::parsers::Log, EcuType), LoadResult> {
-        let contents = String::from_utf8_lossy(binary_data);
-        Self::parse_text_content(&contents)
+    /// Detect the file format and parse the data using the parser registry
+    fn parse_binary_data(binary_data: &[u8]) -> Result<(crate::parsers::Log, EcuType), LoadResult> {
+        ParserRegistry::new()
+            .parse(binary_data)
+            .map_err(|e| LoadResult::Error(e.to_string()))
     }
 
     /// Check for completed background loads
@@ -458,7 +391,13 @@ impl UltraLogApp {
                                 None
                             };
 
-                        self.files.push(*file);
+                        let mut file = *file;
+                        file.vehicle_profile = self
+                            .vehicle_profiles
+                            .default_profile
+                            .clone()
+                            .filter(|id| self.vehicle_profiles.find(id).is_some());
+                        self.files.push(file);
                         self.selected_file = Some(file_index);
                         self.update_time_range();
 
@@ -519,31 +458,7 @@ impl UltraLogApp {
     /// Find the record index closest to the given time
     pub fn find_record_at_time(&self, time: f64) -> Option<usize> {
         // Use the first file with data for record indexing
-        if let Some(file) = self.files.first() {
-            let times = file.log.get_times_as_f64();
-            if times.is_empty() {
-                return None;
-            }
-            // Binary search for closest time
-            let mut low = 0;
-            let mut high = times.len() - 1;
-            while low < high {
-                let mid = (low + high) / 2;
-                if times[mid] < time {
-                    low = mid + 1;
-                } else {
-                    high = mid;
-                }
-            }
-            // Check if low or low-1 is closer
-            if low > 0 && (times[low] - time).abs() > (times[low - 1] - time).abs() {
-                Some(low - 1)
-            } else {
-                Some(low)
-            }
-        } else {
-            None
-        }
+        self.files.first()?.log.find_record_at_time(time)
     }
 
     /// Get value at a specific record index for a channel (handles computed channels)
@@ -562,18 +477,18 @@ impl UltraLogApp {
 
         if channel_index < regular_count {
             // Regular channel
-            if record < file.log.data.len() && channel_index < file.log.data[record].len() {
-                return Some(file.log.data[record][channel_index].as_f64());
-            }
+            return file
+                .log
+                .data
+                .get(record, channel_index)
+                .filter(|v| !v.is_nan());
         } else {
             // Computed channel
             let computed_idx = channel_index - regular_count;
             if let Some(computed_channels) = self.file_computed_channels.get(&file_index) {
                 if let Some(computed) = computed_channels.get(computed_idx) {
                     if let Some(cached_data) = &computed.cached_data {
-                        if record < cached_data.len() {
-                            return Some(cached_data[record]);
-                        }
+                        return cached_data.get(record).copied().filter(|v| !v.is_nan());
                     }
                 }
             }
@@ -581,10 +496,27 @@ impl UltraLogApp {
         None
     }
 
+    /// Get the value of a channel at a time (handles computed channels).
+    /// Channels recorded at their own rate hold their last sample.
+    pub fn get_value_at_time(
+        &self,
+        file_index: usize,
+        channel_index: usize,
+        time: f64,
+    ) -> Option<f64> {
+        let file = self.files.get(file_index)?;
+        if channel_index < file.log.channels.len() {
+            file.log.value_at_time(channel_index, time)
+        } else {
+            let record = file.log.find_record_at_time(time)?;
+            self.get_value_at_record(file_index, channel_index, record)
+        }
+    }
+
     /// Get all data for a channel (handles computed channels)
-    pub fn get_channel_data(&self, file_index: usize, channel_index: usize) -> Vec<f64> {
+    pub fn get_channel_data(&self, file_index: usize, channel_index: usize) -> &[f64] {
         if file_index >= self.files.len() {
-            return Vec::new();
+            return &[];
         }
 
         let file = &self.files[file_index];
@@ -599,11 +531,23 @@ impl UltraLogApp {
             if let Some(computed_channels) = self.file_computed_channels.get(&file_index) {
                 if let Some(computed) = computed_channels.get(computed_idx) {
                     if let Some(cached_data) = &computed.cached_data {
-                        return cached_data.clone();
+                        return cached_data;
                     }
                 }
             }
-            Vec::new()
+            &[]
+        }
+    }
+
+    /// Get the sample times matching `get_channel_data` (handles computed channels)
+    pub fn get_channel_times(&self, file_index: usize, channel_index: usize) -> &[f64] {
+        match self.files.get(file_index) {
+            Some(file) if channel_index < file.log.channels.len() => {
+                file.log.get_channel_times(channel_index)
+            }
+            // Computed channels are evaluated on the log's record times
+            Some(file) => &file.log.times,
+            None => &[],
         }
     }
 
@@ -629,13 +573,14 @@ impl UltraLogApp {
         // Compute min/max (handles both regular and computed channels)
         let data = self.get_channel_data(file_index, channel_index);
 
-        if data.is_empty() {
-            return None;
-        }
-
-        let (min_val, max_val) = data.iter().fold((f64::MAX, f64::MIN), |(min, max), &v| {
-            (min.min(v), max.max(v))
-        });
+        // Missing samples are skipped; a channel with no data has no range
+        let (min_val, max_val) = data.iter().filter(|v| !v.is_nan()).fold(
+            None,
+            |acc: Option<(f64, f64)>, &v| match acc {
+                Some((min, max)) => Some((min.min(v), max.max(v))),
+                None => Some((v, v)),
+            },
+        )?;
 
         // Cache the result
         self.minmax_cache.insert(cache_key, (min_val, max_val));
@@ -706,6 +651,18 @@ impl UltraLogApp {
             }
             self.file_computed_channels = new_computed_channels;
 
+            // Drop the file from chart overlays and update the others
+            for tab in &mut self.tabs {
+                let overlay = &mut tab.overlay_state;
+                overlay.files.retain(|f| f.file_index != index);
+                for file in &mut overlay.files {
+                    if file.file_index > index {
+                        file.file_index -= 1;
+                    }
+                }
+                overlay.time_maps = None;
+            }
+
             // Update file indices for remaining tabs and their channels
             for tab in &mut self.tabs {
                 if tab.file_index > index {
@@ -891,9 +848,12 @@ impl UltraLogApp {
         }
     }
 
-    /// Get the time range for the active tab
+    /// Get the time range for the active tab, narrowed to the selected lap
     pub fn get_time_range(&self) -> Option<(f64, f64)> {
-        self.active_tab.and_then(|idx| self.tabs[idx].time_range)
+        self.active_tab.and_then(|idx| {
+            let tab = &self.tabs[idx];
+            tab.lap_state.selected_range().or(tab.time_range)
+        })
     }
 
     /// Set the time range for the active tab
@@ -936,6 +896,36 @@ impl UltraLogApp {
         }
     }
 
+    /// Get the pending request to show a time range on the active tab's chart
+    pub fn get_view_request(&self) -> Option<(f64, f64)> {
+        self.active_tab.and_then(|idx| self.tabs[idx].view_request)
+    }
+
+    /// Request the active tab's chart to show exactly this time range
+    pub fn set_view_request(&mut self, range: Option<(f64, f64)>) {
+        if let Some(tab_idx) = self.active_tab {
+            self.tabs[tab_idx].view_request = range;
+        }
+    }
+
+    /// Get the markers of the active tab's file
+    pub fn get_markers(&self) -> &[Marker] {
+        self.active_tab
+            .and_then(|idx| self.files.get(self.tabs[idx].file_index))
+            .map(|file| file.log.markers.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Move the cursor to a time and center the chart on it (e.g. marker navigation)
+    pub fn jump_to_time(&mut self, time: f64) {
+        self.is_playing = false;
+        self.last_frame_time = None;
+        self.set_cursor_time(Some(time));
+        let record = self.find_record_at_time(time);
+        self.set_cursor_record(record);
+        self.set_jump_to_time(Some(time));
+    }
+
     /// Get the scatter plot state for the active tab
     pub fn get_scatter_plot_state(&self) -> Option<&ScatterPlotState> {
         self.active_tab
@@ -978,18 +968,9 @@ impl UltraLogApp {
 
     /// Get the list of available channel names for the active file
     pub fn get_available_channel_names(&self) -> Vec<String> {
-        if let Some(tab_idx) = self.active_tab {
-            let file_idx = self.tabs[tab_idx].file_index;
-            if file_idx < self.files.len() {
-                return self.files[file_idx]
-                    .log
-                    .channels
-                    .iter()
-                    .map(|c| c.name())
-                    .collect();
-            }
-        }
-        Vec::new()
+        self.active_tab
+            .map(|tab_idx| self.file_channel_names(self.tabs[tab_idx].file_index))
+            .unwrap_or_default()
     }
 
     /// Get computed channels for the active file
@@ -1026,15 +1007,365 @@ impl UltraLogApp {
             .push(computed);
     }
 
-    /// Remove a computed channel from the active file
+    /// Remove a computed channel from the active file. Channels that
+    /// referenced it are re-evaluated and report the missing channel.
     pub fn remove_computed_channel(&mut self, index: usize) {
-        if let Some(tab_idx) = self.active_tab {
-            let file_idx = self.tabs[tab_idx].file_index;
-            if let Some(channels) = self.file_computed_channels.get_mut(&file_idx) {
-                if index < channels.len() {
-                    channels.remove(index);
+        let Some(tab_idx) = self.active_tab else {
+            return;
+        };
+        let file_idx = self.tabs[tab_idx].file_index;
+        let regular_names: Vec<String> = self.files[file_idx]
+            .log
+            .channels
+            .iter()
+            .map(|c| c.name())
+            .collect();
+        let Some(channels) = self.file_computed_channels.get_mut(&file_idx) else {
+            return;
+        };
+        if index >= channels.len() {
+            return;
+        }
+
+        let dependents: Vec<usize> = computed_dependencies(channels, &regular_names)
+            .iter()
+            .enumerate()
+            .filter(|(i, deps)| *i != index && deps.contains(&index))
+            .map(|(i, _)| if i > index { i - 1 } else { i })
+            .collect();
+        channels.remove(index);
+        if !dependents.is_empty() {
+            self.evaluate_computed_channels(file_idx, &dependents);
+        }
+    }
+
+    /// Names formulas in a file can reference: its log channels followed by
+    /// its computed channels, matching the indices used in channel bindings
+    pub fn file_channel_names(&self, file_index: usize) -> Vec<String> {
+        let Some(file) = self.files.get(file_index) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = file.log.channels.iter().map(|c| c.name()).collect();
+        if let Some(channels) = self.file_computed_channels.get(&file_index) {
+            names.extend(channels.iter().map(|c| c.name().to_string()));
+        }
+        names
+    }
+
+    /// Unit of a channel of a file, by its index in
+    /// [`file_channel_names`](Self::file_channel_names)
+    pub fn file_channel_unit(&self, file_index: usize, channel: usize) -> &str {
+        let Some(file) = self.files.get(file_index) else {
+            return "";
+        };
+        match file.log.channels.get(channel) {
+            Some(c) => c.unit(),
+            None => self
+                .file_computed_channels
+                .get(&file_index)
+                .and_then(|channels| channels.get(channel - file.log.channels.len()))
+                .map_or("", |c| c.unit()),
+        }
+    }
+
+    /// Evaluate computed channels of a file, and every computed channel that
+    /// depends on them, on a background thread in dependency order. Channels
+    /// have no data until their results arrive.
+    pub fn evaluate_computed_channels(&mut self, file_index: usize, targets: &[usize]) {
+        let Some(file) = self.files.get(file_index) else {
+            return;
+        };
+        let available = self.file_channel_names(file_index);
+        let regular_names: Vec<String> = file.log.channels.iter().map(|c| c.name()).collect();
+        let regular_count = regular_names.len();
+        let context = FormulaContext {
+            tables: &self.computed_library.tables,
+            constants: file
+                .vehicle_profile
+                .as_deref()
+                .and_then(|id| self.vehicle_profiles.find(id))
+                .map_or(&[][..], |p| p.constants.as_slice()),
+        };
+        let Some(channels) = self.file_computed_channels.get_mut(&file_index) else {
+            return;
+        };
+
+        // Upstream channels still waiting for data are evaluated in this run
+        let deps = computed_dependencies(channels, &regular_names);
+        let mut targets: Vec<usize> = targets
+            .iter()
+            .copied()
+            .filter(|&t| t < deps.len())
+            .collect();
+        let mut i = 0;
+        while i < targets.len() {
+            for &dep in &deps[targets[i]] {
+                if channels[dep].cached_data.is_none() && !targets.contains(&dep) {
+                    targets.push(dep);
                 }
             }
+            i += 1;
+        }
+        let plan = evaluation_order(&deps, &targets);
+
+        for cycle in &plan.cycles {
+            let path: Vec<&str> = cycle
+                .iter()
+                .chain(cycle.first())
+                .map(|&i| channels[i].name())
+                .collect();
+            let message = format!("Circular reference: {}", path.join(" → "));
+            for &i in cycle {
+                channels[i].cached_data = None;
+                channels[i].pending_job = None;
+                channels[i].error = Some(message.clone());
+            }
+        }
+
+        let id = self.next_computed_job_id;
+        let mut steps: Vec<EvaluationStep> = Vec::new();
+        for &idx in &plan.order {
+            let computed = &channels[idx];
+            let refs = extract_channel_references(computed.formula());
+            let compiled = build_channel_bindings(&refs, &available).and_then(|bindings| {
+                CompiledFormula::compile_with_context(computed.formula(), &bindings, &context)
+                    .map(|c| (c, bindings))
+            });
+
+            // Collect the inputs in canonical units: log channels, results of
+            // earlier steps in this run, or data already evaluated for other
+            // computed channels
+            let result = compiled.and_then(|(compiled, bindings)| {
+                let channel_unit = |channel: usize| {
+                    if channel < regular_count {
+                        file.log.channels[channel].unit()
+                    } else {
+                        channels[channel - regular_count].unit()
+                    }
+                };
+                let inputs = compiled
+                    .channels()
+                    .iter()
+                    .map(|&channel| {
+                        if channel < regular_count {
+                            let input = InputColumn::from_log(&file.log.data, channel)
+                                .map_values(|values| to_canonical(channel_unit(channel), values));
+                            return Ok(StepInput::Data(input.into_owned()));
+                        }
+                        let upstream = &channels[channel - regular_count];
+                        if let Some(step) = steps.iter().position(|s| s.name == upstream.name()) {
+                            Ok(StepInput::Step(step))
+                        } else if let Some(data) = &upstream.cached_data {
+                            Ok(StepInput::Data(InputColumn::Records(Cow::Owned(
+                                to_canonical(upstream.unit(), Cow::Borrowed(data)).into_owned(),
+                            ))))
+                        } else {
+                            Err(format!("'{}' has no data", upstream.name()))
+                        }
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                let units: Vec<&str> = compiled
+                    .channels()
+                    .iter()
+                    .map(|&c| channel_unit(c))
+                    .collect();
+                let unit = compiled.analyze_units(&units).unit;
+                Ok((compiled, bindings, inputs, unit))
+            });
+
+            let computed = &mut channels[idx];
+            computed.cached_data = None;
+            computed.pending_job = None;
+            computed.result_unit = None;
+            match result {
+                Ok((formula, bindings, inputs, unit)) => {
+                    computed.channel_bindings = bindings;
+                    computed.error = None;
+                    computed.pending_job = Some(id);
+                    computed.result_unit = unit;
+                    steps.push(EvaluationStep {
+                        name: computed.name().to_string(),
+                        formula,
+                        inputs,
+                        unit: computed.unit().to_string(),
+                    });
+                }
+                Err(e) => computed.error = Some(e),
+            }
+        }
+
+        // Charted copies show the current name, formula and unit
+        for tab in &mut self.tabs {
+            for selected in &mut tab.selected_channels {
+                if selected.file_index != file_index || selected.channel_index < regular_count {
+                    continue;
+                }
+                if let (Channel::Computed(info), Some(computed)) = (
+                    &mut selected.channel,
+                    channels.get(selected.channel_index - regular_count),
+                ) {
+                    info.name = computed.name().to_string();
+                    info.formula = computed.formula().to_string();
+                    info.unit = computed.unit().to_string();
+                }
+            }
+        }
+
+        for idx in (0..channels.len()).map(|i| regular_count + i) {
+            self.minmax_cache.remove(&CacheKey {
+                file_index,
+                channel_index: idx,
+            });
+        }
+        if steps.is_empty() {
+            return;
+        }
+        self.next_computed_job_id += 1;
+
+        // Copy the timeline so the worker doesn't borrow the log
+        let times = file.log.times.clone();
+        let num_records = file.log.data.len();
+        let progress = Arc::new(AtomicU32::new(0));
+        let worker_progress = Arc::clone(&progress);
+        let (sender, receiver) = channel();
+
+        thread::spawn(move || {
+            let report = |fraction: f32| {
+                worker_progress.fetch_max((fraction * 1000.0) as u32, Ordering::Relaxed);
+            };
+            run_evaluation_steps(&steps, &times, num_records, &report, |name, values| {
+                let _ = sender.send((name.to_string(), values.to_vec()));
+            });
+        });
+
+        self.computed_jobs.push(ComputedJob {
+            id,
+            progress,
+            receiver,
+        });
+    }
+
+    /// Tables and vehicle profile constants available to formulas of a file
+    /// (only the tables when there is no file)
+    pub fn formula_context(&self, file_index: Option<usize>) -> FormulaContext<'_> {
+        let profile = file_index
+            .and_then(|i| self.files.get(i))
+            .and_then(|file| file.vehicle_profile.as_deref())
+            .and_then(|id| self.vehicle_profiles.find(id));
+        FormulaContext {
+            tables: &self.computed_library.tables,
+            constants: profile.map_or(&[][..], |p| p.constants.as_slice()),
+        }
+    }
+
+    /// Choose the vehicle profile a file's formulas read constants from
+    pub fn set_file_vehicle_profile(&mut self, file_index: usize, profile_id: Option<String>) {
+        let Some(file) = self.files.get_mut(file_index) else {
+            return;
+        };
+        if file.vehicle_profile != profile_id {
+            file.vehicle_profile = profile_id;
+            self.reevaluate_constant_users(file_index);
+        }
+    }
+
+    /// Re-evaluate the computed channels of one file that read any `$name`
+    /// constant, e.g. after its vehicle profile changed
+    pub fn reevaluate_constant_users(&mut self, file_index: usize) {
+        let Some(channels) = self.file_computed_channels.get(&file_index) else {
+            return;
+        };
+        let targets: Vec<usize> = channels
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !extract_constant_references(c.formula()).is_empty())
+            .map(|(i, _)| i)
+            .collect();
+        if !targets.is_empty() {
+            self.evaluate_computed_channels(file_index, &targets);
+        }
+    }
+
+    /// Re-evaluate the computed channels in every file that read any of the
+    /// named lookup tables
+    pub fn reevaluate_table_users(&mut self, table_names: &[String]) {
+        let mut updates = Vec::new();
+        for (&file_idx, channels) in &self.file_computed_channels {
+            let targets: Vec<usize> = channels
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| {
+                    extract_table_references(c.formula())
+                        .iter()
+                        .any(|t| table_names.iter().any(|n| n.eq_ignore_ascii_case(t)))
+                })
+                .map(|(i, _)| i)
+                .collect();
+            if !targets.is_empty() {
+                updates.push((file_idx, targets));
+            }
+        }
+
+        for (file_idx, targets) in updates {
+            self.evaluate_computed_channels(file_idx, &targets);
+        }
+    }
+
+    /// Completed fraction (0.0 to 1.0) of a background evaluation
+    pub fn computed_job_progress(&self, job_id: u64) -> Option<f32> {
+        self.computed_jobs
+            .iter()
+            .find(|job| job.id == job_id)
+            .map(|job| job.progress.load(Ordering::Relaxed) as f32 / 1000.0)
+    }
+
+    /// Store the results of background evaluations as they arrive
+    fn check_computed_jobs(&mut self) {
+        let mut results = Vec::new();
+        let mut finished = Vec::new();
+        for job in &self.computed_jobs {
+            loop {
+                match job.receiver.try_recv() {
+                    Ok((name, values)) => results.push((job.id, name, values)),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        finished.push(job.id);
+                        break;
+                    }
+                }
+            }
+        }
+        self.computed_jobs.retain(|job| !finished.contains(&job.id));
+        if results.is_empty() && finished.is_empty() {
+            return;
+        }
+
+        // Channels may have been removed or moved to another index meanwhile
+        for (&file_index, channels) in &mut self.file_computed_channels {
+            let regular_count = self
+                .files
+                .get(file_index)
+                .map_or(0, |file| file.log.channels.len());
+            for (idx, computed) in channels.iter_mut().enumerate() {
+                let Some(job) = computed.pending_job else {
+                    continue;
+                };
+                if let Some(pos) = results.iter().position(|(id, name, _)| {
+                    *id == job && name.eq_ignore_ascii_case(computed.name())
+                }) {
+                    computed.cached_data = Some(results.swap_remove(pos).2);
+                    computed.pending_job = None;
+                } else if finished.contains(&job) {
+                    computed.error = Some("Evaluation failed".to_string());
+                    computed.pending_job = None;
+                } else {
+                    continue;
+                }
+                self.minmax_cache.remove(&CacheKey {
+                    file_index,
+                    channel_index: regular_count + idx,
+                });
+            }
         }
     }
 
@@ -1265,6 +1596,9 @@ impl eframe::App for UltraLogApp {
         // Check for completed background loads
         self.check_loading_complete();
 
+        // Check for finished computed channel evaluations
+        self.check_computed_jobs();
+
         // Handle file drops
         self.handle_dropped_files(ctx);
 
@@ -1277,8 +1611,10 @@ impl eframe::App for UltraLogApp {
         // Apply dark theme
         ctx.set_visuals(egui::Visuals::dark());
 
-        // Request repaint while loading or updating (for spinner animation)
+        // Request repaint while loading, evaluating or updating (for spinner animation)
         if matches!(self.loading_state, LoadingState::Loading(_))
+            || !self.computed_jobs.is_empty()
+            || self.formula_preview_job.is_some()
             || matches!(
                 self.update_state,
                 UpdateState::Checking | UpdateState::Downloading
@@ -1295,6 +1631,12 @@ impl eframe::App for UltraLogApp {
         self.render_update_dialog(ctx);
         self.render_computed_channels_manager(ctx);
         self.render_formula_editor(ctx);
+        self.render_table_editor(ctx);
+        self.render_vehicle_profiles(ctx);
+        self.render_library_import_dialog(ctx);
+        self.render_csv_export_dialog(ctx);
+        self.render_laps_window(ctx);
+        self.render_overlay_window(ctx);
 
         // Menu bar at top with padding
         let menu_frame = egui::Frame::NONE.inner_margin(egui::Margin {
@@ -1388,6 +1730,14 @@ impl eframe::App for UltraLogApp {
                     ui.add_space(10.0);
                     self.render_scatter_plot_view(ui);
                 }
+                ActiveTool::VeAnalyze => {
+                    ui.add_space(10.0);
+                    self.render_ve_analyze_view(ui);
+                }
+                ActiveTool::TrackMap => {
+                    ui.add_space(10.0);
+                    self.render_track_map_view(ui);
+                }
             }
         });
     }
@@ -0,0 +1,149 @@
+//! Built-in computed channel templates
+//!
+//! A curated set of standard formulas new libraries start with. They
+//! reference channels by their normalized names (see [`crate::normalize`]),
+//! so `MAP` also finds "Manifold Pressure" and `"Pulse Width"` finds
+//! "Inj Actual PW", and read per-car values from the file's vehicle profile.
+
+use crate::computed::ComputedChannelTemplate;
+
+/// Prefix of the IDs of built-in templates
+pub const BUILTIN_ID_PREFIX: &str = "builtin-";
+
+/// A built-in template with a stable ID
+fn template(
+    id: &str,
+    name: &str,
+    formula: &str,
+    unit: &str,
+    description: &str,
+) -> ComputedChannelTemplate {
+    ComputedChannelTemplate {
+        id: format!("{}{}", BUILTIN_ID_PREFIX, id),
+        name: name.to_string(),
+        formula: formula.to_string(),
+        unit: unit.to_string(),
+        description: description.to_string(),
+        created_at: 0,
+        modified_at: 0,
+    }
+}
+
+/// The built-in templates
+pub fn builtin_templates() -> Vec<ComputedChannelTemplate> {
+    vec![
+        template(
+            "lambda-petrol",
+            "Lambda (Petrol)",
+            "AFR / 14.7",
+            "λ",
+            "Lambda from a wideband AFR reading on petrol (stoichiometric 14.7:1)",
+        ),
+        template(
+            "lambda-e85",
+            "Lambda (E85)",
+            "AFR / 9.77",
+            "λ",
+            "Lambda from a wideband AFR reading on E85 (stoichiometric 9.77:1)",
+        ),
+        template(
+            "lambda-profile",
+            "Lambda (Profile Fuel)",
+            "AFR / $stoich_afr",
+            "λ",
+            "Lambda from AFR using the vehicle profile's $stoich_afr",
+        ),
+        template(
+            "injector-duty",
+            "Injector Duty Cycle",
+            "\"Pulse Width\" * RPM / 1200",
+            "%",
+            "Injector on-time as a share of the engine cycle (four-stroke, sequential)",
+        ),
+        template(
+            "fuel-flow",
+            "Estimated Fuel Flow",
+            "\"Pulse Width\" * RPM / 1200 / 100 * $injector_cc * $cylinders",
+            "cc/min",
+            "Total fuel flow from injector duty and the profile's $injector_cc and $cylinders",
+        ),
+        template(
+            "boost-gauge",
+            "Boost (Gauge)",
+            "MAP - Baro",
+            "kPa",
+            "Manifold pressure relative to barometric pressure",
+        ),
+        template(
+            "acceleration",
+            "Longitudinal Acceleration",
+            "deriv(lowpass(\"Vehicle Speed\", 2)) / 3.6",
+            "m/s²",
+            "Rate of change of the smoothed vehicle speed",
+        ),
+        template(
+            "wheel-power",
+            "Estimated Wheel Power",
+            "max($vehicle_mass_kg * deriv(lowpass(\"Vehicle Speed\", 2)) / 3.6 * lowpass(\"Vehicle Speed\", 2) / 3.6 / 745.7, 0)",
+            "hp",
+            "Power needed to accelerate the profile's $vehicle_mass_kg, ignoring drag and rolling resistance",
+        ),
+        template(
+            "gear-ratio",
+            "Gear Ratio Estimate",
+            "if(\"Vehicle Speed\" > 5, RPM * 0.06 * $tyre_circumference_m / (\"Vehicle Speed\" * $final_drive), 0)",
+            "",
+            "Gearbox ratio from RPM and speed using the profile's $tyre_circumference_m and $final_drive; 0 below 5 km/h",
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{
+        build_channel_bindings, extract_channel_references, validate_formula, CompiledFormula,
+        FormulaContext,
+    };
+    use crate::vehicle_profile::VehicleProfiles;
+
+    #[test]
+    fn test_builtin_templates_compile() {
+        let available: Vec<String> = [
+            "Engine Speed",
+            "Manifold Pressure",
+            "Barometric Pressure",
+            "AFR Avg",
+            "Inj Actual PW",
+            "VSS",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        // The profile a fresh install assigns to new files
+        let profiles = VehicleProfiles::with_default();
+        let profile = profiles.find(profiles.default_profile.as_ref().unwrap());
+        let context = FormulaContext {
+            tables: &[],
+            constants: &profile.unwrap().constants,
+        };
+
+        for template in builtin_templates() {
+            assert!(template.id.starts_with(BUILTIN_ID_PREFIX));
+            validate_formula(&template.formula, &available)
+                .unwrap_or_else(|e| panic!("{}: {}", template.name, e));
+            let refs = extract_channel_references(&template.formula);
+            let bindings = build_channel_bindings(&refs, &available).unwrap();
+            CompiledFormula::compile_with_context(&template.formula, &bindings, &context)
+                .unwrap_or_else(|e| panic!("{}: {}", template.name, e));
+        }
+    }
+
+    #[test]
+    fn test_builtin_template_ids_are_unique() {
+        let templates = builtin_templates();
+        for (i, template) in templates.iter().enumerate() {
+            assert!(templates[..i].iter().all(|t| t.id != template.id));
+        }
+    }
+}
@@ -0,0 +1,632 @@
+//! Headless command-line interface
+//!
+//! Implements the `ultralog-cli` subcommands on top of the library's parsers
+//! and expression engine, so logs can be inspected and exported from scripts:
+//!
+//! - `info` - format, metadata, channels, duration and sample rate
+//! - `stats` - min / max / mean per channel
+//! - `export` - selected channels and time range as CSV or JSON
+//! - `computed` - evaluate a computed channel template against a log
+
+use serde::Serialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::computed::{ComputedChannelLibrary, ComputedChannelTemplate};
+use crate::data_export::{record_range, ExportColumn, ExportTable};
+use crate::expression::{
+    build_channel_bindings, extract_channel_references, CompiledFormula, FormulaContext,
+    InputColumn,
+};
+use crate::parsers::{EcuType, Log, ParserRegistry};
+use crate::units::to_canonical;
+use crate::vehicle_profile::{VehicleProfile, VehicleProfiles};
+
+/// Usage text printed by `help` and on argument errors
+pub const USAGE: &str = "\
+Usage: ultralog-cli <command> <file> [options]
+
+Commands:
+  info <file>                 Format, metadata, channels, duration and sample rate
+  stats <file>                Min / max / mean for each channel
+  export <file>               Write channel data as CSV or JSON
+  computed <file>             Evaluate a computed channel template
+  help                        Show this message
+
+Options:
+  -c, --channels <a,b,...>    Channels to include (default: all)
+      --start <seconds>       Start of the exported time range
+      --end <seconds>         End of the exported time range
+  -f, --format <csv|json>     Output format for export and computed (default: csv)
+  -o, --output <path>         Write to a file instead of stdout
+      --json                  Print info and stats as JSON
+  -t, --template <name|id>    Computed channel template from the library
+      --formula <expr>        Evaluate a formula directly instead of a template
+      --library <path>        Template and table library (default: the app's library)
+      --profile <name|id>     Vehicle profile for $constants (default: the app's default)
+";
+
+/// Output format for exported data
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+/// A parsed command line
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Info {
+        file: PathBuf,
+        json: bool,
+    },
+    Stats {
+        file: PathBuf,
+        channels: Vec<String>,
+        json: bool,
+    },
+    Export {
+        file: PathBuf,
+        channels: Vec<String>,
+        range: TimeRange,
+        format: OutputFormat,
+        output: Option<PathBuf>,
+    },
+    Computed {
+        file: PathBuf,
+        source: FormulaSource,
+        library: Option<PathBuf>,
+        /// Vehicle profile name (case-insensitive) or ID
+        profile: Option<String>,
+        range: TimeRange,
+        format: OutputFormat,
+        output: Option<PathBuf>,
+    },
+    Help,
+}
+
+/// Optional start / end of an exported time range, in seconds
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimeRange {
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+}
+
+impl TimeRange {
+    fn bounds(&self) -> Option<(f64, f64)> {
+        if self.start.is_none() && self.end.is_none() {
+            return None;
+        }
+        Some((
+            self.start.unwrap_or(f64::NEG_INFINITY),
+            self.end.unwrap_or(f64::INFINITY),
+        ))
+    }
+}
+
+/// Where the `computed` command takes its formula from
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormulaSource {
+    /// Template name (case-insensitive) or ID from the library
+    Template(String),
+    Formula(String),
+}
+
+/// Parse command-line arguments (without the program name)
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let command = match args.next() {
+        None => return Ok(Command::Help),
+        Some(c) => c,
+    };
+    match command.as_str() {
+        "help" | "-h" | "--help" => return Ok(Command::Help),
+        "info" | "stats" | "export" | "computed" => {}
+        other => return Err(format!("Unknown command: {}", other)),
+    }
+
+    let mut file: Option<PathBuf> = None;
+    let mut channels = Vec::new();
+    let mut range = TimeRange::default();
+    let mut format = OutputFormat::default();
+    let mut output = None;
+    let mut json = false;
+    let mut source = None;
+    let mut library = None;
+    let mut profile = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+        match arg.as_str() {
+            "-c" | "--channels" => {
+                channels.extend(
+                    value(&arg)?
+                        .split(',')
+                        .map(|c| c.trim().to_string())
+                        .filter(|c| !c.is_empty()),
+                );
+            }
+            "--start" => range.start = Some(parse_seconds(&value(&arg)?)?),
+            "--end" => range.end = Some(parse_seconds(&value(&arg)?)?),
+            "-f" | "--format" => {
+                format = match value(&arg)?.to_lowercase().as_str() {
+                    "csv" => OutputFormat::Csv,
+                    "json" => OutputFormat::Json,
+                    other => return Err(format!("Unknown format: {}", other)),
+                }
+            }
+            "-o" | "--output" => output = Some(PathBuf::from(value(&arg)?)),
+            "--json" => json = true,
+            "-t" | "--template" => source = Some(FormulaSource::Template(value(&arg)?)),
+            "--formula" => source = Some(FormulaSource::Formula(value(&arg)?)),
+            "--library" => library = Some(PathBuf::from(value(&arg)?)),
+            "--profile" => profile = Some(value(&arg)?),
+            other if other.starts_with('-') => return Err(format!("Unknown option: {}", other)),
+            path if file.is_none() => file = Some(PathBuf::from(path)),
+            extra => return Err(format!("Unexpected argument: {}", extra)),
+        }
+    }
+
+    let file = file.ok_or_else(|| format!("Missing log file for '{}'", command))?;
+
+    match command.as_str() {
+        "info" => Ok(Command::Info { file, json }),
+        "stats" => Ok(Command::Stats {
+            file,
+            channels,
+            json,
+        }),
+        "export" => Ok(Command::Export {
+            file,
+            channels,
+            range,
+            format,
+            output,
+        }),
+        "computed" => Ok(Command::Computed {
+            file,
+            source: source.ok_or("computed requires --template or --formula")?,
+            library,
+            profile,
+            range,
+            format,
+            output,
+        }),
+        _ => unreachable!("command validated above"),
+    }
+}
+
+fn parse_seconds(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| format!("Invalid time: {}", value))
+}
+
+/// Run a command, writing results to `out` unless an output file is given
+pub fn run<W: Write>(command: &Command, out: &mut W) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Help => write!(out, "{}", USAGE)?,
+        Command::Info { file, json } => {
+            let (log, ecu_type) = load_log(file)?;
+            let info = LogInfo::new(file, &log, ecu_type);
+            if *json {
+                serde_json::to_writer_pretty(&mut *out, &info)?;
+                writeln!(out)?;
+            } else {
+                info.write_text(out)?;
+            }
+        }
+        Command::Stats {
+            file,
+            channels,
+            json,
+        } => {
+            let (log, _) = load_log(file)?;
+            let stats: Vec<ChannelStats> = resolve_channels(&log, channels)?
+                .into_iter()
+                .map(|idx| ChannelStats::new(&log, idx))
+                .collect();
+            if *json {
+                serde_json::to_writer_pretty(&mut *out, &stats)?;
+                writeln!(out)?;
+            } else {
+                write_stats_text(out, &stats)?;
+            }
+        }
+        Command::Export {
+            file,
+            channels,
+            range,
+            format,
+            output,
+        } => {
+            let (log, _) = load_log(file)?;
+            let indices = resolve_channels(&log, channels)?;
+            let table = ExportTable::from_log(&log, &indices, range.bounds());
+            write_table(&table, *format, output.as_deref(), out)?;
+        }
+        Command::Computed {
+            file,
+            source,
+            library,
+            profile,
+            range,
+            format,
+            output,
+        } => {
+            let (log, _) = load_log(file)?;
+            let library = load_library(library.as_deref())?;
+            let template = resolve_template(source, &library)?;
+            let profiles = VehicleProfiles::load();
+            let profile = resolve_profile(&profiles, profile.as_deref())?;
+            let context = FormulaContext {
+                tables: &library.tables,
+                constants: profile.map_or(&[][..], |p| p.constants.as_slice()),
+            };
+            let (values, unit) = evaluate_template(&log, &template, &context)?;
+
+            let rows = record_range(&log.times, range.bounds());
+            let table = ExportTable {
+                times: log.times[rows.clone()].to_vec(),
+                channels: vec![ExportColumn {
+                    name: template.name,
+                    unit,
+                    values: values[rows].to_vec(),
+                }],
+            };
+            write_table(&table, *format, output.as_deref(), out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Read, decompress, detect and parse a log file
+pub fn load_log(path: &Path) -> Result<(Log, EcuType), Box<dyn Error>> {
+    let data =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    ParserRegistry::new().parse(&data)
+}
+
+/// Map channel names (case-insensitive) to indices; empty means all channels
+pub fn resolve_channels(log: &Log, names: &[String]) -> Result<Vec<usize>, String> {
+    if names.is_empty() {
+        return Ok((0..log.channels.len()).collect());
+    }
+    names
+        .iter()
+        .map(|name| {
+            log.channels
+                .iter()
+                .position(|c| c.name().eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("Channel not found: {}", name))
+        })
+        .collect()
+}
+
+/// Read a template library file, or the app's library when no path is given
+fn load_library(path: Option<&Path>) -> Result<ComputedChannelLibrary, Box<dyn Error>> {
+    match path {
+        Some(path) => {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            Ok(ComputedChannelLibrary::from_json(&content)?)
+        }
+        None => Ok(ComputedChannelLibrary::load()),
+    }
+}
+
+/// Look up a library template, or wrap an ad-hoc formula as one
+fn resolve_template(
+    source: &FormulaSource,
+    library: &ComputedChannelLibrary,
+) -> Result<ComputedChannelTemplate, Box<dyn Error>> {
+    match source {
+        FormulaSource::Formula(formula) => Ok(ComputedChannelTemplate::new(
+            "Computed".to_string(),
+            formula.clone(),
+            String::new(),
+            String::new(),
+        )),
+        FormulaSource::Template(key) => library
+            .find_template(key)
+            .or_else(|| {
+                library
+                    .templates
+                    .iter()
+                    .find(|t| t.name.eq_ignore_ascii_case(key))
+            })
+            .cloned()
+            .ok_or_else(|| format!("Template not found: {}", key).into()),
+    }
+}
+
+/// Look up a vehicle profile by name (case-insensitive) or ID, or the
+/// default profile when no key is given
+pub fn resolve_profile<'a>(
+    profiles: &'a VehicleProfiles,
+    key: Option<&str>,
+) -> Result<Option<&'a VehicleProfile>, String> {
+    match key {
+        Some(key) => profiles
+            .find(key)
+            .or_else(|| {
+                profiles
+                    .profiles
+                    .iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(key))
+            })
+            .map(Some)
+            .ok_or_else(|| format!("Vehicle profile not found: {}", key)),
+        None => Ok(profiles
+            .default_profile
+            .as_deref()
+            .and_then(|id| profiles.find(id))),
+    }
+}
+
+/// Evaluate a computed channel template over every record of a log, with
+/// inputs in canonical units and tables and constants from `context`.
+/// Returns the values and their unit.
+pub fn evaluate_template(
+    log: &Log,
+    template: &ComputedChannelTemplate,
+    context: &FormulaContext,
+) -> Result<(Vec<f64>, String), String> {
+    let available: Vec<String> = log.channels.iter().map(|c| c.name()).collect();
+    let refs = extract_channel_references(&template.formula);
+    let bindings = build_channel_bindings(&refs, &available)?;
+    let compiled = CompiledFormula::compile_with_context(&template.formula, &bindings, context)?;
+
+    let units: Vec<&str> = compiled
+        .channels()
+        .iter()
+        .map(|&c| log.channels[c].unit())
+        .collect();
+    let inputs: Vec<InputColumn> = compiled
+        .channels()
+        .iter()
+        .zip(&units)
+        .map(|(&c, unit)| {
+            InputColumn::from_log(&log.data, c).map_values(|values| to_canonical(unit, values))
+        })
+        .collect();
+
+    let values = compiled.evaluate_inputs(&inputs, &log.times, log.data.len(), &|_| {});
+    let unit = compiled
+        .analyze_units(&units)
+        .unit
+        .map_or_else(|| template.unit.clone(), str::to_string);
+    Ok((values, unit))
+}
+
+fn write_table<W: Write>(
+    table: &ExportTable,
+    format: OutputFormat,
+    output: Option<&Path>,
+    out: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let Some(path) = output else {
+        return match format {
+            OutputFormat::Csv => table.write_csv(out),
+            OutputFormat::Json => table.write_json(out),
+        };
+    };
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    write_table(table, format, None, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// ============================================================================
+// Reports
+// ============================================================================
+
+/// Summary of a log for the `info` command
+#[derive(Clone, Debug, Serialize)]
+pub struct LogInfo {
+    pub file: String,
+    pub format: String,
+    pub meta: serde_json::Value,
+    pub records: usize,
+    /// Seconds between the first and last record
+    pub duration: f64,
+    /// Mean record rate in Hz
+    pub sample_rate: Option<f64>,
+    pub markers: usize,
+    pub channels: Vec<ChannelInfo>,
+}
+
+/// Per-channel entry of `LogInfo`
+#[derive(Clone, Debug, Serialize)]
+pub struct ChannelInfo {
+    pub name: String,
+    pub unit: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub samples: usize,
+    /// Mean sample rate in Hz
+    pub sample_rate: Option<f64>,
+}
+
+impl LogInfo {
+    pub fn new(path: &Path, log: &Log, ecu_type: EcuType) -> Self {
+        // Meta serializes as `{ "Format": { ... } }`; keep only the fields
+        let meta = match serde_json::to_value(&log.meta) {
+            Ok(serde_json::Value::Object(map)) if map.len() == 1 => {
+                map.into_iter().next().map(|(_, v)| v).unwrap_or_default()
+            }
+            Ok(value) => value,
+            Err(_) => serde_json::Value::Null,
+        };
+
+        let channels = log
+            .channels
+            .iter()
+            .enumerate()
+            .map(|(idx, channel)| {
+                let times = log.get_channel_times(idx);
+                ChannelInfo {
+                    name: channel.name(),
+                    unit: channel.unit().to_string(),
+                    type_name: channel.type_name(),
+                    samples: times.len(),
+                    sample_rate: mean_rate(times),
+                }
+            })
+            .collect();
+
+        Self {
+            file: path.display().to_string(),
+            format: ecu_type.name().to_string(),
+            meta,
+            records: log.times.len(),
+            duration: match (log.times.first(), log.times.last()) {
+                (Some(first), Some(last)) => last - first,
+                _ => 0.0,
+            },
+            sample_rate: mean_rate(&log.times),
+            markers: log.markers.len(),
+            channels,
+        }
+    }
+
+    fn write_text<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "File:        {}", self.file)?;
+        writeln!(out, "Format:      {}", self.format)?;
+        writeln!(out, "Records:     {}", self.records)?;
+        writeln!(out, "Duration:    {:.3} s", self.duration)?;
+        match self.sample_rate {
+            Some(rate) => writeln!(out, "Sample rate: {:.1} Hz", rate)?,
+            None => writeln!(out, "Sample rate: -")?,
+        }
+        writeln!(out, "Markers:     {}", self.markers)?;
+
+        if let serde_json::Value::Object(map) = &self.meta {
+            let fields: Vec<_> = map
+                .iter()
+                .filter(|(_, v)| !v.is_null() && v.as_str() != Some(""))
+                .collect();
+            if !fields.is_empty() {
+                writeln!(out, "\nMetadata:")?;
+                for (key, value) in fields {
+                    match value.as_str() {
+                        Some(s) => writeln!(out, "  {}: {}", key, s)?,
+                        None => writeln!(out, "  {}: {}", key, value)?,
+                    }
+                }
+            }
+        }
+
+        writeln!(out, "\nChannels ({}):", self.channels.len())?;
+        for (i, channel) in self.channels.iter().enumerate() {
+            let unit = if channel.unit.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", channel.unit)
+            };
+            let rate = channel
+                .sample_rate
+                .map(|r| format!(", {:.1} Hz", r))
+                .unwrap_or_default();
+            writeln!(
+                out,
+                "  {:3}. {}{} ({}{})",
+                i + 1,
+                channel.name,
+                unit,
+                channel.type_name,
+                rate
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Mean rate of a sorted list of sample times
+fn mean_rate(times: &[f64]) -> Option<f64> {
+    let span = times.last()? - times.first()?;
+    (span > 0.0).then(|| (times.len() - 1) as f64 / span)
+}
+
+/// Summary statistics of one channel for the `stats` command
+#[derive(Clone, Debug, Serialize)]
+pub struct ChannelStats {
+    pub name: String,
+    pub unit: String,
+    /// Number of samples present (missing samples are excluded)
+    pub samples: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+}
+
+impl ChannelStats {
+    pub fn new(log: &Log, channel_index: usize) -> Self {
+        let channel = &log.channels[channel_index];
+        let values = log
+            .get_channel_data(channel_index)
+            .iter()
+            .copied()
+            .filter(|v| !v.is_nan());
+
+        let mut samples = 0;
+        let mut sum = 0.0;
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        for v in values {
+            samples += 1;
+            sum += v;
+            min = min.min(v);
+            max = max.max(v);
+        }
+
+        let present = samples > 0;
+        Self {
+            name: channel.name(),
+            unit: channel.unit().to_string(),
+            samples,
+            min: present.then_some(min),
+            max: present.then_some(max),
+            mean: present.then(|| sum / samples as f64),
+        }
+    }
+}
+
+fn write_stats_text<W: Write>(out: &mut W, stats: &[ChannelStats]) -> std::io::Result<()> {
+    let width = stats
+        .iter()
+        .map(|s| s.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Channel".len());
+    let fmt = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.3}", v));
+
+    writeln!(
+        out,
+        "{:<width$}  {:>12}  {:>12}  {:>12}  {:>8}  Unit",
+        "Channel", "Min", "Max", "Mean", "Samples"
+    )?;
+    for s in stats {
+        writeln!(
+            out,
+            "{:<width$}  {:>12}  {:>12}  {:>12}  {:>8}  {}",
+            s.name,
+            fmt(s.min),
+            fmt(s.max),
+            fmt(s.mean),
+            s.samples,
+            s.unit
+        )?;
+    }
+    Ok(())
+}
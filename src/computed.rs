@@ -5,8 +5,18 @@
 //! global reusable template library.
 
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::ops::Range;
 use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::builtin_templates::{builtin_templates, BUILTIN_ID_PREFIX};
+use crate::expression::{
+    extract_channel_references, CompiledFormula, Completions, InputColumn, UnitAnalysis,
+};
+use crate::lookup_table::LookupTable;
+use crate::units::to_canonical;
 
 /// A template for a computed channel stored in the global library
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -47,6 +57,11 @@ impl ComputedChannelTemplate {
         }
     }
 
+    /// Check if this is one of the templates shipped with the app
+    pub fn is_builtin(&self) -> bool {
+        self.id.starts_with(BUILTIN_ID_PREFIX)
+    }
+
     /// Update the modified timestamp
     pub fn touch(&mut self) {
         self.modified_at = std::time::SystemTime::now()
@@ -67,6 +82,11 @@ pub struct ComputedChannel {
     pub cached_data: Option<Vec<f64>>,
     /// Any errors from binding or evaluation
     pub error: Option<String>,
+    /// Id of the background evaluation that will fill `cached_data`
+    pub pending_job: Option<u64>,
+    /// Unit inferred from the formula's inputs, used instead of the
+    /// template's unit
+    pub result_unit: Option<&'static str>,
 }
 
 impl ComputedChannel {
@@ -77,6 +97,8 @@ impl ComputedChannel {
             channel_bindings: HashMap::new(),
             cached_data: None,
             error: None,
+            pending_job: None,
+            result_unit: None,
         }
     }
 
@@ -90,9 +112,15 @@ impl ComputedChannel {
         &self.template.formula
     }
 
-    /// Get the unit
+    /// Get the unit: the inferred one, or the template's when the formula's
+    /// unit is unknown
     pub fn unit(&self) -> &str {
-        &self.template.unit
+        self.result_unit.unwrap_or(&self.template.unit)
+    }
+
+    /// Check if this channel is being evaluated in the background
+    pub fn is_evaluating(&self) -> bool {
+        self.pending_job.is_some()
     }
 
     /// Check if this channel has been evaluated successfully
@@ -138,6 +166,9 @@ pub struct ComputedChannelLibrary {
     /// The stored templates
     #[serde(default)]
     pub templates: Vec<ComputedChannelTemplate>,
+    /// Lookup tables available to formulas through `table()` and `table2d()`
+    #[serde(default)]
+    pub tables: Vec<LookupTable>,
 }
 
 impl ComputedChannelLibrary {
@@ -149,9 +180,29 @@ impl ComputedChannelLibrary {
         Self {
             version: Self::CURRENT_VERSION,
             templates: Vec::new(),
+            tables: Vec::new(),
         }
     }
 
+    /// Create a library holding the built-in templates
+    pub fn with_builtin_templates() -> Self {
+        let mut library = Self::new();
+        library.add_builtin_templates();
+        library
+    }
+
+    /// Add the built-in templates missing from the library, returning how
+    /// many were added
+    pub fn add_builtin_templates(&mut self) -> usize {
+        let missing: Vec<ComputedChannelTemplate> = builtin_templates()
+            .into_iter()
+            .filter(|t| self.find_template(&t.id).is_none())
+            .collect();
+        let count = missing.len();
+        self.templates.extend(missing);
+        count
+    }
+
     /// Add a template to the library
     pub fn add_template(&mut self, template: ComputedChannelTemplate) {
         self.templates.push(template);
@@ -176,6 +227,34 @@ impl ComputedChannelLibrary {
         self.templates.iter_mut().find(|t| t.id == id)
     }
 
+    /// Find a lookup table by name (case-insensitive)
+    pub fn find_table(&self, name: &str) -> Option<&LookupTable> {
+        self.tables
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Add a lookup table, replacing any table with the same name
+    pub fn set_table(&mut self, table: LookupTable) {
+        match self
+            .tables
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(&table.name))
+        {
+            Some(pos) => self.tables[pos] = table,
+            None => self.tables.push(table),
+        }
+    }
+
+    /// Remove a lookup table by name
+    pub fn remove_table(&mut self, name: &str) -> Option<LookupTable> {
+        let pos = self
+            .tables
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(name))?;
+        Some(self.tables.remove(pos))
+    }
+
     /// Get the config directory path for UltraLog
     pub fn get_config_dir() -> Option<PathBuf> {
         #[cfg(target_os = "macos")]
@@ -214,12 +293,12 @@ impl ComputedChannelLibrary {
         };
 
         if !path.exists() {
-            tracing::info!("Computed channels library not found, using empty library");
-            return Self::new();
+            tracing::info!("Computed channels library not found, using built-in templates");
+            return Self::with_builtin_templates();
         }
 
         match std::fs::read_to_string(&path) {
-            Ok(content) => match serde_json::from_str(&content) {
+            Ok(content) => match Self::from_json(&content) {
                 Ok(library) => {
                     tracing::info!("Loaded computed channels library from {:?}", path);
                     library
@@ -247,8 +326,7 @@ impl ComputedChannelLibrary {
                 .map_err(|e| format!("Failed to create config directory: {}", e))?;
         }
 
-        let content = serde_json::to_string_pretty(self)
-            .map_err(|e| format!("Failed to serialize library: {}", e))?;
+        let content = self.to_json()?;
 
         std::fs::write(&path, content)
             .map_err(|e| format!("Failed to write library file: {}", e))?;
@@ -258,6 +336,217 @@ impl ComputedChannelLibrary {
     }
 }
 
+// ============================================================================
+// Dependencies Between Computed Channels
+// ============================================================================
+
+/// For each `(name, formula)` entry, the indices of other entries its formula
+/// references. Names matching a regular channel resolve to that channel.
+fn reference_graph(entries: &[(&str, &str)], regular_names: &[String]) -> Vec<Vec<usize>> {
+    entries
+        .iter()
+        .map(|(_, formula)| {
+            let mut deps: Vec<usize> = extract_channel_references(formula)
+                .iter()
+                .filter(|r| {
+                    !regular_names
+                        .iter()
+                        .any(|n| n.eq_ignore_ascii_case(&r.name))
+                })
+                .filter_map(|r| {
+                    entries
+                        .iter()
+                        .position(|(name, _)| name.eq_ignore_ascii_case(&r.name))
+                })
+                .collect();
+            deps.sort_unstable();
+            deps.dedup();
+            deps
+        })
+        .collect()
+}
+
+/// Indices of the computed channels each computed channel references
+pub fn computed_dependencies(
+    channels: &[ComputedChannel],
+    regular_names: &[String],
+) -> Vec<Vec<usize>> {
+    let entries: Vec<(&str, &str)> = channels.iter().map(|c| (c.name(), c.formula())).collect();
+    reference_graph(&entries, regular_names)
+}
+
+/// Computed channels to evaluate, in dependency order
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EvaluationPlan {
+    /// Channels ordered so each comes after the channels it references
+    pub order: Vec<usize>,
+    /// Reference cycles; their channels are left out of `order`
+    pub cycles: Vec<Vec<usize>>,
+}
+
+/// Plan the evaluation of `targets` and every channel that depends on them,
+/// directly or indirectly, given the graph from [`computed_dependencies`]
+pub fn evaluation_order(deps: &[Vec<usize>], targets: &[usize]) -> EvaluationPlan {
+    // Collect the targets and their dependents
+    let mut included = vec![false; deps.len()];
+    let mut pending: Vec<usize> = targets
+        .iter()
+        .copied()
+        .filter(|&t| t < deps.len())
+        .collect();
+    while let Some(node) = pending.pop() {
+        if std::mem::replace(&mut included[node], true) {
+            continue;
+        }
+        pending.extend((0..deps.len()).filter(|&i| !included[i] && deps[i].contains(&node)));
+    }
+
+    // Depth-first topological sort, recording cycles as they are found
+    let mut plan = EvaluationPlan::default();
+    let mut state = vec![0u8; deps.len()]; // 0 = new, 1 = on stack, 2 = done
+    let mut stack = Vec::new();
+    for node in (0..deps.len()).filter(|&i| included[i]) {
+        visit(node, deps, &included, &mut state, &mut stack, &mut plan);
+    }
+
+    let in_cycle = |node: &usize| plan.cycles.iter().any(|cycle| cycle.contains(node));
+    plan.order = plan
+        .order
+        .iter()
+        .copied()
+        .filter(|n| !in_cycle(n))
+        .collect();
+    plan
+}
+
+fn visit(
+    node: usize,
+    deps: &[Vec<usize>],
+    included: &[bool],
+    state: &mut [u8],
+    stack: &mut Vec<usize>,
+    plan: &mut EvaluationPlan,
+) {
+    if state[node] != 0 {
+        return;
+    }
+    state[node] = 1;
+    stack.push(node);
+    for &dep in &deps[node] {
+        if !included[dep] {
+            continue;
+        }
+        match state[dep] {
+            0 => visit(dep, deps, included, state, stack, plan),
+            1 => {
+                let start = stack.iter().position(|&n| n == dep).unwrap_or(0);
+                plan.cycles.push(stack[start..].to_vec());
+            }
+            _ => {}
+        }
+    }
+    stack.pop();
+    state[node] = 2;
+    plan.order.push(node);
+}
+
+/// Check whether giving the computed channel `name` the formula `formula`
+/// would create a reference cycle among `channels`. Returns the cycle as a
+/// path of names starting and ending with `name`.
+pub fn find_reference_cycle(
+    name: &str,
+    formula: &str,
+    channels: &[ComputedChannel],
+    regular_names: &[String],
+) -> Option<Vec<String>> {
+    let mut entries: Vec<(&str, &str)> = channels
+        .iter()
+        .filter(|c| !c.name().eq_ignore_ascii_case(name))
+        .map(|c| (c.name(), c.formula()))
+        .collect();
+    entries.push((name, formula));
+    let deps = reference_graph(&entries, regular_names);
+    let start = entries.len() - 1;
+
+    // Breadth-first search for the shortest path back to the start
+    let mut previous: Vec<Option<usize>> = vec![None; entries.len()];
+    let mut queue = std::collections::VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for &dep in &deps[node] {
+            if dep == start {
+                let mut path = vec![name.to_string()];
+                let mut current = node;
+                while current != start {
+                    path.push(entries[current].0.to_string());
+                    current = previous[current].unwrap_or(start);
+                }
+                path.push(name.to_string());
+                path.reverse();
+                return Some(path);
+            }
+            if previous[dep].is_none() {
+                previous[dep] = Some(node);
+                queue.push_back(dep);
+            }
+        }
+    }
+    None
+}
+
+/// Where an input column of an [`EvaluationStep`] comes from
+#[derive(Clone, Debug)]
+pub enum StepInput {
+    /// Values of a log channel or an already evaluated computed channel
+    Data(InputColumn<'static>),
+    /// The result of an earlier step in the same run
+    Step(usize),
+}
+
+/// One computed channel evaluated as part of a dependency-ordered run
+#[derive(Clone, Debug)]
+pub struct EvaluationStep {
+    /// Name of the computed channel
+    pub name: String,
+    pub formula: CompiledFormula,
+    /// One input per entry of [`CompiledFormula::channels`], in canonical
+    /// units
+    pub inputs: Vec<StepInput>,
+    /// Unit of the results; later steps read them in its canonical unit
+    pub unit: String,
+}
+
+/// Evaluate steps in order, handing each result to `on_result` as it
+/// completes. `progress` receives the overall completed fraction.
+pub fn run_evaluation_steps(
+    steps: &[EvaluationStep],
+    times: &[f64],
+    num_records: usize,
+    progress: &(dyn Fn(f32) + Sync),
+    mut on_result: impl FnMut(&str, &[f64]),
+) {
+    let mut results: Vec<Vec<f64>> = Vec::with_capacity(steps.len());
+    for (i, step) in steps.iter().enumerate() {
+        let inputs: Vec<InputColumn> = step
+            .inputs
+            .iter()
+            .map(|input| match input {
+                StepInput::Data(input) => input.borrowed(),
+                StepInput::Step(index) => InputColumn::Records(to_canonical(
+                    &steps[*index].unit,
+                    Cow::Borrowed(results.get(*index).map_or(&[][..], |r| r.as_slice())),
+                )),
+            })
+            .collect();
+        let values = step
+            .formula
+            .evaluate_inputs(&inputs, times, num_records, &|fraction| {
+                progress((i as f32 + fraction) / steps.len() as f32)
+            });
+        on_result(&step.name, &values);
+        results.push(values);
+    }
+}
+
 /// State for the formula editor dialog
 #[derive(Clone, Debug, Default)]
 pub struct FormulaEditorState {
@@ -273,8 +562,18 @@ pub struct FormulaEditorState {
     pub unit: String,
     /// Validation error message
     pub validation_error: Option<String>,
+    /// Byte range of the formula the validation error points at
+    pub error_span: Option<Range<usize>>,
+    /// Inferred result unit and dimension warnings of a valid formula
+    pub unit_analysis: Option<UnitAnalysis>,
     /// Preview values (first few computed values)
     pub preview_values: Option<Vec<f64>>,
+    /// Downsampled `[time, value]` points of the formula over the whole log
+    pub preview_plot: Option<Vec<[f64; 2]>>,
+    /// When the formula last changed, to preview it once typing pauses
+    pub preview_requested: Option<Instant>,
+    /// Suggestions for the word at the formula cursor
+    pub completions: Option<Completions>,
     /// Whether the dialog is open
     pub is_open: bool,
 }
@@ -287,8 +586,7 @@ impl FormulaEditorState {
         self.formula = String::new();
         self.description = String::new();
         self.unit = String::new();
-        self.validation_error = None;
-        self.preview_values = None;
+        self.clear_results();
         self.is_open = true;
     }
 
@@ -299,8 +597,7 @@ impl FormulaEditorState {
         self.formula = template.formula.clone();
         self.description = template.description.clone();
         self.unit = template.unit.clone();
-        self.validation_error = None;
-        self.preview_values = None;
+        self.clear_results();
         self.is_open = true;
     }
 
@@ -308,8 +605,18 @@ impl FormulaEditorState {
     pub fn close(&mut self) {
         self.is_open = false;
         self.editing_template_id = None;
+        self.clear_results();
+    }
+
+    /// Forget the validation, preview and suggestions of the last formula
+    pub fn clear_results(&mut self) {
         self.validation_error = None;
+        self.error_span = None;
+        self.unit_analysis = None;
         self.preview_values = None;
+        self.preview_plot = None;
+        self.preview_requested = None;
+        self.completions = None;
     }
 
     /// Check if we're editing an existing template
@@ -322,6 +629,106 @@ impl FormulaEditorState {
 mod tests {
     use super::*;
 
+    fn applied(name: &str, formula: &str) -> ComputedChannel {
+        ComputedChannel::from_template(ComputedChannelTemplate::new(
+            name.to_string(),
+            formula.to_string(),
+            String::new(),
+            String::new(),
+        ))
+    }
+
+    #[test]
+    fn test_evaluation_order_follows_dependencies() {
+        let regular = vec!["RPM".to_string(), "IPW".to_string()];
+        let channels = vec![
+            applied("Fuel Flow", "\"Duty\" * 4"),
+            applied("Duty", "IPW * RPM / 1200"),
+            applied("Other", "RPM"),
+        ];
+        let deps = computed_dependencies(&channels, &regular);
+        assert_eq!(deps, vec![vec![1], vec![], vec![]]);
+
+        // Re-evaluating Duty pulls in its dependent Fuel Flow, after it
+        let plan = evaluation_order(&deps, &[1]);
+        assert_eq!(plan.order, vec![1, 0]);
+        assert!(plan.cycles.is_empty());
+    }
+
+    #[test]
+    fn test_evaluation_order_reports_cycles() {
+        let regular = vec!["RPM".to_string()];
+        let channels = vec![
+            applied("A", "B + 1"),
+            applied("B", "A * 2"),
+            applied("C", "RPM"),
+        ];
+        let deps = computed_dependencies(&channels, &regular);
+        let plan = evaluation_order(&deps, &[0, 2]);
+        assert_eq!(plan.order, vec![2]);
+        assert_eq!(plan.cycles.len(), 1);
+        let mut cycle = plan.cycles[0].clone();
+        cycle.sort();
+        assert_eq!(cycle, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_find_reference_cycle() {
+        let regular = vec!["IPW".to_string()];
+        let channels = vec![applied("Duty", "IPW * 2"), applied("Fuel Flow", "Duty * 4")];
+
+        // Editing Duty to use Fuel Flow would loop back to Duty
+        let cycle = find_reference_cycle("Duty", "\"Fuel Flow\" / 4", &channels, &regular);
+        assert_eq!(
+            cycle,
+            Some(vec![
+                "Duty".to_string(),
+                "Fuel Flow".to_string(),
+                "Duty".to_string()
+            ])
+        );
+        assert!(find_reference_cycle("Duty", "Duty + 1", &channels, &regular).is_some());
+        assert!(find_reference_cycle("Duty", "IPW * 3", &channels, &regular).is_none());
+    }
+
+    #[test]
+    fn test_run_evaluation_steps_chains_results() {
+        let mut bindings = HashMap::new();
+        bindings.insert("RPM".to_string(), 0);
+        let first = CompiledFormula::compile("RPM * 2", &bindings).unwrap();
+        bindings.insert("Double".to_string(), 1);
+        let second = CompiledFormula::compile("Double + 1", &bindings).unwrap();
+
+        let steps = vec![
+            EvaluationStep {
+                name: "Double".to_string(),
+                formula: first,
+                inputs: vec![StepInput::Data(InputColumn::Records(Cow::Owned(vec![
+                    1.0, 2.0,
+                ])))],
+                unit: String::new(),
+            },
+            EvaluationStep {
+                name: "Plus One".to_string(),
+                formula: second,
+                inputs: vec![StepInput::Step(0)],
+                unit: String::new(),
+            },
+        ];
+
+        let mut results = Vec::new();
+        run_evaluation_steps(&steps, &[0.0, 0.1], 2, &|_| {}, |name, values| {
+            results.push((name.to_string(), values.to_vec()))
+        });
+        assert_eq!(
+            results,
+            vec![
+                ("Double".to_string(), vec![2.0, 4.0]),
+                ("Plus One".to_string(), vec![3.0, 5.0]),
+            ]
+        );
+    }
+
     #[test]
     fn test_template_creation() {
         let template = ComputedChannelTemplate::new(
@@ -361,6 +768,21 @@ mod tests {
         assert!(library.templates.is_empty());
     }
 
+    #[test]
+    fn test_add_builtin_templates() {
+        let mut library = ComputedChannelLibrary::with_builtin_templates();
+        let count = library.templates.len();
+        assert!(count > 0);
+        assert!(library.templates.iter().all(|t| t.is_builtin()));
+        assert_eq!(library.add_builtin_templates(), 0);
+
+        // Deleted built-ins can be restored without duplicating the others
+        let id = library.templates[0].id.clone();
+        library.remove_template(&id);
+        assert_eq!(library.add_builtin_templates(), 1);
+        assert_eq!(library.templates.len(), count);
+    }
+
     #[test]
     fn test_time_shift() {
         assert_eq!(TimeShift::default(), TimeShift::None);
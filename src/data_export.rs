@@ -0,0 +1,226 @@
+//! Tabular export of channel data
+//!
+//! Builds a time-indexed table of channel values and writes it as CSV or
+//! JSON. Used by the command-line interface and the export menu.
+
+use serde::Serialize;
+use std::error::Error;
+use std::io::Write;
+use std::ops::Range;
+
+use crate::parsers::Log;
+
+/// One exported channel
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportColumn {
+    pub name: String,
+    pub unit: String,
+    /// One value per table row; missing samples are `NaN` (`null` in JSON)
+    #[serde(serialize_with = "serialize_values")]
+    pub values: Vec<f64>,
+}
+
+/// Channel values sharing a common time column
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ExportTable {
+    /// Row times in seconds
+    pub times: Vec<f64>,
+    pub channels: Vec<ExportColumn>,
+}
+
+impl ExportTable {
+    /// Build a table of the given log channels over an optional time range
+    /// (inclusive, in seconds). Channels recorded at their own rate are held
+    /// onto the log's record times.
+    pub fn from_log(log: &Log, channel_indices: &[usize], range: Option<(f64, f64)>) -> Self {
+        let rows = record_range(&log.times, range);
+
+        let channels = channel_indices
+            .iter()
+            .filter(|&&idx| idx < log.channels.len())
+            .map(|&idx| {
+                let channel = &log.channels[idx];
+                ExportColumn {
+                    name: channel.name(),
+                    unit: channel.unit().to_string(),
+                    values: log.get_aligned_channel_data(idx)[rows.clone()].to_vec(),
+                }
+            })
+            .collect();
+
+        Self {
+            times: log.times[rows].to_vec(),
+            channels,
+        }
+    }
+
+    /// Resample every channel onto a fixed rate starting at the first row,
+    /// holding the last value at or before each new row time
+    pub fn resampled(&self, rate_hz: f64) -> Self {
+        let (Some(&first), Some(&last)) = (self.times.first(), self.times.last()) else {
+            return self.clone();
+        };
+        if !(rate_hz > 0.0 && rate_hz.is_finite()) {
+            return self.clone();
+        }
+
+        // Step by index rather than accumulating to avoid drift
+        let count = ((last - first) * rate_hz + 1e-9).floor() as usize + 1;
+        let times: Vec<f64> = (0..count).map(|i| first + i as f64 / rate_hz).collect();
+        let sources: Vec<usize> = times
+            .iter()
+            .map(|&t| {
+                self.times
+                    .partition_point(|&s| s <= t + 1e-9)
+                    .saturating_sub(1)
+            })
+            .collect();
+
+        let channels = self
+            .channels
+            .iter()
+            .map(|channel| ExportColumn {
+                name: channel.name.clone(),
+                unit: channel.unit.clone(),
+                values: sources
+                    .iter()
+                    .map(|&i| channel.values.get(i).copied().unwrap_or(f64::NAN))
+                    .collect(),
+            })
+            .collect();
+
+        Self { times, channels }
+    }
+
+    /// Write as CSV with a `Time (s)` column followed by one column per
+    /// channel. Missing samples are written as empty cells.
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> Result<(), Box<dyn Error>> {
+        let mut header = vec!["Time (s)".to_string()];
+        header.extend(self.channels.iter().map(|c| {
+            let name = if c.unit.is_empty() {
+                c.name.clone()
+            } else {
+                format!("{} ({})", c.name, c.unit)
+            };
+            csv_field(&name)
+        }));
+        writeln!(out, "{}", header.join(","))?;
+
+        for (row, time) in self.times.iter().enumerate() {
+            write!(out, "{}", time)?;
+            for channel in &self.channels {
+                match channel.values.get(row) {
+                    Some(v) if !v.is_nan() => write!(out, ",{}", v)?,
+                    _ => write!(out, ",")?,
+                }
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Write as pretty-printed JSON
+    pub fn write_json<W: Write>(&self, out: &mut W) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+/// Records of sorted `times` within an inclusive time range (all if `None`)
+pub fn record_range(times: &[f64], range: Option<(f64, f64)>) -> Range<usize> {
+    let (start, end) = range.unwrap_or((f64::NEG_INFINITY, f64::INFINITY));
+    let first = times.partition_point(|&t| t < start);
+    let last = times.partition_point(|&t| t <= end).max(first);
+    first..last
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn serialize_values<S: serde::Serializer>(
+    values: &[f64],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.iter().map(|v| v.is_finite().then_some(*v)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> ExportTable {
+        ExportTable {
+            times: vec![0.0, 0.5],
+            channels: vec![
+                ExportColumn {
+                    name: "RPM".to_string(),
+                    unit: "RPM".to_string(),
+                    values: vec![1000.0, f64::NAN],
+                },
+                ExportColumn {
+                    name: "Gear, current".to_string(),
+                    unit: String::new(),
+                    values: vec![1.0, 2.0],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_record_range() {
+        let times = [0.0, 1.0, 2.0, 3.0];
+        assert_eq!(record_range(&times, None), 0..4);
+        assert_eq!(record_range(&times, Some((0.5, 2.0))), 1..3);
+        assert_eq!(record_range(&times, Some((5.0, 9.0))), 4..4);
+        assert_eq!(record_range(&times, Some((2.0, 1.0))), 2..2);
+    }
+
+    #[test]
+    fn test_resampled_holds_values() {
+        let table = ExportTable {
+            times: vec![0.0, 0.3, 0.45],
+            channels: vec![ExportColumn {
+                name: "RPM".to_string(),
+                unit: String::new(),
+                values: vec![1.0, 2.0, 3.0],
+            }],
+        };
+
+        let resampled = table.resampled(10.0);
+
+        assert_eq!(resampled.times.len(), 5);
+        assert!((resampled.times[4] - 0.4).abs() < 1e-9);
+        assert_eq!(resampled.channels[0].values, vec![1.0, 1.0, 1.0, 2.0, 2.0]);
+        // Invalid rates leave the table unchanged
+        assert_eq!(table.resampled(0.0).times, table.times);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut out = Vec::new();
+        table().write_csv(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Time (s),RPM (RPM),\"Gear, current\"\n0,1000,1\n0.5,,2\n"
+        );
+    }
+
+    #[test]
+    fn test_write_json_uses_null_for_missing() {
+        let mut out = Vec::new();
+        table().write_json(&mut out).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["channels"][0]["values"][1], serde_json::Value::Null);
+        assert_eq!(json["channels"][1]["values"][1], 2.0);
+        assert_eq!(json["times"][1], 0.5);
+    }
+}
@@ -0,0 +1,310 @@
+//! Autocompletion of channel names, functions, constants and tables
+//!
+//! Given a formula and a cursor position, finds the partly typed word before
+//! the cursor and the names it could be completed to.
+
+use std::ops::Range;
+
+use super::FormulaContext;
+
+/// Most suggestions offered at once
+const MAX_COMPLETIONS: usize = 8;
+
+/// Built-in functions and their arguments
+const FUNCTIONS: &[(&str, &str)] = &[
+    ("abs", "(x)"),
+    ("acos", "(x)"),
+    ("acosh", "(x)"),
+    ("asin", "(x)"),
+    ("asinh", "(x)"),
+    ("atan", "(x)"),
+    ("atan2", "(y, x)"),
+    ("atanh", "(x)"),
+    ("avg", "(channel, window)"),
+    ("ceil", "(x)"),
+    ("cos", "(x)"),
+    ("cosh", "(x)"),
+    ("deriv", "(channel)"),
+    ("exp", "(x)"),
+    ("floor", "(x)"),
+    ("fract", "(x)"),
+    ("hold", "(channel, condition)"),
+    ("if", "(condition, then, else)"),
+    ("integ", "(channel)"),
+    ("ln", "(x)"),
+    ("log10", "(x)"),
+    ("log2", "(x)"),
+    ("lowpass", "(channel, cutoff_hz)"),
+    ("max", "(a, b, ...)"),
+    ("max_over", "(channel, window)"),
+    ("min", "(a, b, ...)"),
+    ("min_over", "(channel, window)"),
+    ("rms", "(channel, window)"),
+    ("round", "(x)"),
+    ("signum", "(x)"),
+    ("sin", "(x)"),
+    ("sinh", "(x)"),
+    ("sqrt", "(x)"),
+    ("table", "(\"table\", x)"),
+    ("table2d", "(\"table\", x, y)"),
+    ("tan", "(x)"),
+    ("tanh", "(x)"),
+    ("trunc", "(x)"),
+];
+
+/// What a suggestion names
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionKind {
+    Channel,
+    Function,
+    Constant,
+    Table,
+}
+
+/// One suggestion for the word at the cursor
+#[derive(Clone, Debug, PartialEq)]
+pub struct Completion {
+    /// Text shown in the suggestion list
+    pub label: String,
+    /// Text replacing the partly typed word
+    pub insert: String,
+    pub kind: CompletionKind,
+    /// Arguments of a function, value of a constant, ...
+    pub detail: String,
+}
+
+/// Suggestions for the word at the cursor
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Completions {
+    /// Byte range of the formula a suggestion replaces
+    pub range: Range<usize>,
+    pub items: Vec<Completion>,
+}
+
+impl Completions {
+    /// Replace the partly typed word with `item`, returning the byte offset
+    /// just after the inserted text
+    pub fn apply(&self, formula: &mut String, item: &Completion) -> usize {
+        formula.replace_range(self.range.clone(), &item.insert);
+        self.range.start + item.insert.len()
+    }
+}
+
+/// Suggest completions for the word before byte offset `cursor`
+pub fn complete(
+    formula: &str,
+    cursor: usize,
+    channels: &[String],
+    context: &FormulaContext,
+) -> Option<Completions> {
+    let before = formula.get(..cursor)?;
+
+    // Inside quotes: a channel name, or a table name in table(...)
+    if before.matches('"').count() % 2 == 1 {
+        let quote = before.rfind('"')?;
+        let prefix = &before[quote + 1..];
+        let end = if formula[cursor..].starts_with('"') {
+            cursor + 1
+        } else {
+            cursor
+        };
+        let in_table_call = before[..quote]
+            .trim_end()
+            .strip_suffix('(')
+            .map(str::trim_end)
+            .is_some_and(|call| {
+                let call = call.to_lowercase();
+                call.ends_with("table") || call.ends_with("table2d")
+            });
+        let items = if in_table_call {
+            matching(prefix, context.tables.iter().map(|t| t.name.as_str()))
+                .map(|name| Completion {
+                    label: name.to_string(),
+                    insert: format!("\"{}\"", name),
+                    kind: CompletionKind::Table,
+                    detail: "table".to_string(),
+                })
+                .collect()
+        } else {
+            matching(prefix, channels.iter().map(String::as_str))
+                .map(|name| Completion {
+                    label: name.to_string(),
+                    insert: format!("\"{}\"", name),
+                    kind: CompletionKind::Channel,
+                    detail: "channel".to_string(),
+                })
+                .collect()
+        };
+        return completions(quote..end, items);
+    }
+
+    let word_start = before
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |i| {
+            i + before[i..].chars().next().map_or(1, char::len_utf8)
+        });
+    let word = &before[word_start..];
+
+    // `$name` constants of the vehicle profile
+    if before[..word_start].ends_with('$') {
+        let items = matching(word, context.constants.iter().map(|c| c.name.as_str()))
+            .map(|name| {
+                let constant = context.constants.iter().find(|c| c.name == name);
+                Completion {
+                    label: format!("${}", name),
+                    insert: format!("${}", name),
+                    kind: CompletionKind::Constant,
+                    detail: constant.map_or_else(String::new, |c| {
+                        format!("{} {}", c.value, c.unit).trim().to_string()
+                    }),
+                }
+            })
+            .collect();
+        return completions(word_start - 1..cursor, items);
+    }
+
+    if word.is_empty() || word.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut items: Vec<Completion> = matching(word, channels.iter().map(String::as_str))
+        .map(|name| Completion {
+            label: name.to_string(),
+            insert: if is_identifier(name) {
+                name.to_string()
+            } else {
+                format!("\"{}\"", name)
+            },
+            kind: CompletionKind::Channel,
+            detail: "channel".to_string(),
+        })
+        .collect();
+    items.extend(
+        matching(word, FUNCTIONS.iter().map(|(name, _)| *name)).map(|name| {
+            let args = FUNCTIONS
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map_or("", |f| f.1);
+            Completion {
+                label: name.to_string(),
+                insert: format!("{}(", name),
+                kind: CompletionKind::Function,
+                detail: format!("{}{}", name, args),
+            }
+        }),
+    );
+    completions(word_start..cursor, items)
+}
+
+/// `None` when there is nothing to suggest
+fn completions(range: Range<usize>, mut items: Vec<Completion>) -> Option<Completions> {
+    items.truncate(MAX_COMPLETIONS);
+    (!items.is_empty()).then_some(Completions { range, items })
+}
+
+/// Names starting with `prefix` followed by names containing it
+/// (case-insensitive), leaving out a name already typed in full
+fn matching<'a>(
+    prefix: &str,
+    names: impl Iterator<Item = &'a str> + Clone,
+) -> impl Iterator<Item = &'a str> {
+    let prefix = prefix.to_lowercase();
+    let starts: Vec<&str> = names
+        .clone()
+        .filter(|n| {
+            let n = n.to_lowercase();
+            n.starts_with(&prefix) && n != prefix
+        })
+        .collect();
+    let contains: Vec<&str> = names
+        .filter(|n| {
+            let n = n.to_lowercase();
+            !n.starts_with(&prefix) && n.contains(&prefix)
+        })
+        .collect();
+    starts.into_iter().chain(contains)
+}
+
+/// Check whether a channel name can be written in a formula without quotes
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup_table::LookupTable;
+    use crate::vehicle_profile::ProfileConstant;
+
+    fn channels() -> Vec<String> {
+        ["RPM", "Manifold Pressure", "MAP", "Coolant Temp"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    fn labels(completions: &Completions) -> Vec<&str> {
+        completions.items.iter().map(|c| c.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_completes_channels_and_functions() {
+        let result = complete("RPM * ma", 8, &channels(), &FormulaContext::default()).unwrap();
+        assert_eq!(result.range, 6..8);
+        assert_eq!(
+            labels(&result),
+            ["Manifold Pressure", "MAP", "max", "max_over"]
+        );
+
+        let mut formula = "RPM * ma".to_string();
+        let cursor = result.apply(&mut formula, &result.items[0]);
+        assert_eq!(formula, "RPM * \"Manifold Pressure\"");
+        assert_eq!(cursor, formula.len());
+
+        let result = complete("lowp", 4, &channels(), &FormulaContext::default()).unwrap();
+        assert_eq!(result.items[0].insert, "lowpass(");
+        assert_eq!(result.items[0].detail, "lowpass(channel, cutoff_hz)");
+    }
+
+    #[test]
+    fn test_completes_inside_quotes() {
+        let formula = "\"cool\" + 1";
+        let result = complete(formula, 5, &channels(), &FormulaContext::default()).unwrap();
+        assert_eq!(labels(&result), ["Coolant Temp"]);
+        // The closing quote is replaced too
+        assert_eq!(result.range, 0..6);
+    }
+
+    #[test]
+    fn test_completes_constants_and_tables() {
+        let constants = vec![ProfileConstant::new("injector_cc", 550.0, "cc/min")];
+        let tables = vec![LookupTable::new_1d(
+            "VE Trim".to_string(),
+            vec![0.0, 1.0],
+            vec![1.0, 1.0],
+        )];
+        let context = FormulaContext {
+            tables: &tables,
+            constants: &constants,
+        };
+
+        let result = complete("IPW * $inj", 10, &channels(), &context).unwrap();
+        assert_eq!(result.range, 6..10);
+        assert_eq!(result.items[0].insert, "$injector_cc");
+        assert_eq!(result.items[0].detail, "550 cc/min");
+
+        let result = complete("table(\"ve", 9, &channels(), &context).unwrap();
+        assert_eq!(result.items[0].kind, CompletionKind::Table);
+        assert_eq!(result.items[0].insert, "\"VE Trim\"");
+    }
+
+    #[test]
+    fn test_no_completions() {
+        let context = FormulaContext::default();
+        assert!(complete("RPM + ", 6, &channels(), &context).is_none());
+        assert!(complete("RPM + 12", 8, &channels(), &context).is_none());
+        // Already typed in full
+        assert!(complete("RPM", 3, &channels(), &context).is_none());
+    }
+}
@@ -0,0 +1,283 @@
+//! Dimensional analysis of formulas
+//!
+//! Infers the unit of a formula's result from the units of its inputs and
+//! reports operations that combine incompatible dimensions, such as adding a
+//! pressure to a temperature. Inputs are assumed to be in the canonical unit
+//! of their dimension, as they are when formulas are evaluated.
+
+use super::parser::{BinaryOp, Function, Node, UnaryOp};
+use super::window::ColumnFunction;
+use crate::units::{same_scale, Dimension, Unit};
+
+/// What is known about the unit of a (sub)expression
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Quantity {
+    /// Read from an input with an unrecognised unit; not checked
+    Unknown,
+    /// A plain number, taking the unit of whatever it is combined with
+    Number,
+    /// A dimension and the size of its unit in SI base units
+    Measured(Dimension, f64),
+}
+
+impl Quantity {
+    /// The quantity of values logged in `unit`, after conversion to its
+    /// canonical unit
+    fn of_unit(unit: &str) -> Self {
+        match Unit::parse(unit) {
+            Some(unit) => {
+                let canonical = unit.canonical();
+                Self::Measured(canonical.dimension, canonical.scale)
+            }
+            None => Self::Unknown,
+        }
+    }
+
+    fn dimensionless() -> Self {
+        Self::Measured(Dimension::NONE, 1.0)
+    }
+
+    fn describe(dimension: Dimension, scale: f64) -> String {
+        match Unit::find(dimension, scale) {
+            Some(unit) if !dimension.is_dimensionless() => {
+                format!("{} ({})", dimension, unit.symbol)
+            }
+            _ => dimension.to_string(),
+        }
+    }
+}
+
+/// Result of [`analyze`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnitAnalysis {
+    /// Dimension of the result, when it could be inferred
+    pub dimension: Option<Dimension>,
+    /// Unit of the result, when it is one the unit preferences convert
+    pub unit: Option<&'static str>,
+    /// Operations that mix incompatible dimensions or units
+    pub warnings: Vec<String>,
+}
+
+/// Analyze `expr`, whose variable slots read values logged in `slot_units`
+pub fn analyze(expr: &Node, slot_units: &[&str]) -> UnitAnalysis {
+    let inputs: Vec<Quantity> = slot_units.iter().map(|u| Quantity::of_unit(u)).collect();
+    let mut warnings = Vec::new();
+    let result = infer(expr, &inputs, &mut warnings);
+    warnings.dedup();
+    let (dimension, unit) = match result {
+        Quantity::Measured(dimension, scale) => (
+            Some(dimension),
+            Unit::find(dimension, scale)
+                .filter(Unit::has_preference)
+                .map(|u| u.symbol),
+        ),
+        _ => (None, None),
+    };
+    UnitAnalysis {
+        dimension,
+        unit,
+        warnings,
+    }
+}
+
+/// Infer the quantity of `node`, reading variable slots from `inputs`
+fn infer(node: &Node, inputs: &[Quantity], warnings: &mut Vec<String>) -> Quantity {
+    match node {
+        Node::Number(_) | Node::Constant(_) => Quantity::Number,
+        Node::Var(slot) => inputs.get(*slot).copied().unwrap_or(Quantity::Unknown),
+        Node::Unary(UnaryOp::Neg, arg) => infer(arg, inputs, warnings),
+        Node::Unary(UnaryOp::Not, arg) => {
+            infer(arg, inputs, warnings);
+            Quantity::dimensionless()
+        }
+        Node::Binary(op, lhs, rhs) => {
+            let a = infer(lhs, inputs, warnings);
+            let b = infer(rhs, inputs, warnings);
+            match op {
+                BinaryOp::Add | BinaryOp::Rem => combine("Adding", a, b, warnings),
+                BinaryOp::Sub => combine("Subtracting", a, b, warnings),
+                BinaryOp::Eq
+                | BinaryOp::Ne
+                | BinaryOp::Lt
+                | BinaryOp::Le
+                | BinaryOp::Gt
+                | BinaryOp::Ge => {
+                    combine("Comparing", a, b, warnings);
+                    Quantity::dimensionless()
+                }
+                BinaryOp::And | BinaryOp::Or => Quantity::dimensionless(),
+                BinaryOp::Mul => multiply(a, b, 1),
+                BinaryOp::Div => multiply(a, b, -1),
+                BinaryOp::Pow => power(a, rhs),
+            }
+        }
+        Node::Call(function, args) => {
+            let quantities: Vec<Quantity> = args
+                .iter()
+                .map(|arg| infer(arg, inputs, warnings))
+                .collect();
+            let x = quantities[0];
+            match function {
+                Function::Abs
+                | Function::Floor
+                | Function::Ceil
+                | Function::Round
+                | Function::Trunc
+                | Function::Fract => x,
+                Function::Sqrt => match x {
+                    Quantity::Measured(dimension, scale) => dimension
+                        .sqrt()
+                        .map_or(Quantity::Unknown, |d| Quantity::Measured(d, scale.sqrt())),
+                    other => other,
+                },
+                Function::Max | Function::Min => quantities
+                    .into_iter()
+                    .reduce(|a, b| combine("Comparing", a, b, warnings))
+                    .unwrap_or(Quantity::Unknown),
+                Function::Atan2 => {
+                    combine("Comparing", x, quantities[1], warnings);
+                    Quantity::dimensionless()
+                }
+                _ => Quantity::dimensionless(),
+            }
+        }
+        Node::If(cond, then, otherwise) => {
+            infer(cond, inputs, warnings);
+            let a = infer(then, inputs, warnings);
+            let b = infer(otherwise, inputs, warnings);
+            combine("Choosing between", a, b, warnings)
+        }
+        Node::Column(function, args) => {
+            let quantities: Vec<Quantity> = args
+                .iter()
+                .map(|arg| infer(arg, inputs, warnings))
+                .collect();
+            let time = Quantity::Measured(Dimension::TIME, 1.0);
+            match function {
+                ColumnFunction::Deriv => multiply(quantities[0], time, -1),
+                ColumnFunction::Integ => multiply(quantities[0], time, 1),
+                _ => quantities[0],
+            }
+        }
+        Node::Table(_, args) => {
+            for arg in args {
+                infer(arg, inputs, warnings);
+            }
+            Quantity::Unknown
+        }
+    }
+}
+
+/// Quantity of an operation whose operands must share a unit, such as `+`
+fn combine(verb: &str, a: Quantity, b: Quantity, warnings: &mut Vec<String>) -> Quantity {
+    match (a, b) {
+        (Quantity::Unknown, _) | (_, Quantity::Unknown) => Quantity::Unknown,
+        (Quantity::Number, other) | (other, Quantity::Number) => other,
+        (Quantity::Measured(da, sa), Quantity::Measured(db, sb)) => {
+            if da != db {
+                warnings.push(format!(
+                    "{} {} and {}",
+                    verb,
+                    Quantity::describe(da, sa),
+                    Quantity::describe(db, sb)
+                ));
+                // Don't repeat the warning for every enclosing operation
+                Quantity::Unknown
+            } else {
+                if !same_scale(sa, sb) && !da.is_dimensionless() {
+                    warnings.push(format!("{} {} values in different units", verb, da));
+                }
+                a
+            }
+        }
+    }
+}
+
+/// Quantity of `a * b` (`sign` 1) or `a / b` (`sign` -1)
+fn multiply(a: Quantity, b: Quantity, sign: i8) -> Quantity {
+    match (a, b) {
+        (Quantity::Unknown, _) | (_, Quantity::Unknown) => Quantity::Unknown,
+        (Quantity::Number, Quantity::Number) => Quantity::Number,
+        (measured, Quantity::Number) => measured,
+        (Quantity::Number, Quantity::Measured(d, s)) => {
+            Quantity::Measured(d.powi(sign), s.powi(sign.into()))
+        }
+        (Quantity::Measured(da, sa), Quantity::Measured(db, sb)) => {
+            Quantity::Measured(da * db.powi(sign), sa * sb.powi(sign.into()))
+        }
+    }
+}
+
+/// Quantity of `base ^ exponent`; only constant integer powers of a
+/// dimension are known
+fn power(base: Quantity, exponent: &Node) -> Quantity {
+    match (base, exponent) {
+        (Quantity::Measured(d, _), _) if d.is_dimensionless() => Quantity::dimensionless(),
+        (Quantity::Measured(d, s), Node::Number(n)) if n.fract() == 0.0 && n.abs() <= 4.0 => {
+            Quantity::Measured(d.powi(*n as i8), s.powi(*n as i32))
+        }
+        (Quantity::Number, _) => Quantity::Number,
+        _ => Quantity::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::parse;
+
+    fn analyze_formula(formula: &str, vars: &[&str], units: &[&str]) -> UnitAnalysis {
+        let vars: Vec<String> = vars.iter().map(|v| v.to_string()).collect();
+        analyze(&parse(formula, &vars).unwrap(), units)
+    }
+
+    #[test]
+    fn test_infers_canonical_units() {
+        let result = analyze_formula("ect - 10", &["ect"], &["K"]);
+        assert_eq!(result.unit, Some("°C"));
+        assert!(result.warnings.is_empty());
+
+        let result = analyze_formula("map - baro", &["map", "baro"], &["psi", "kPa"]);
+        assert_eq!(result.unit, Some("kPa"));
+
+        let result = analyze_formula("deriv(speed) / 3.6", &["speed"], &["km/h"]);
+        assert_eq!(result.dimension, Some(Dimension::ACCELERATION));
+        assert_eq!(result.unit, None);
+
+        // Units without a preference keep the template's unit
+        let result = analyze_formula("rpm * 2", &["rpm"], &["RPM"]);
+        assert_eq!(result.dimension, Some(Dimension::FREQUENCY));
+        assert_eq!(result.unit, None);
+    }
+
+    #[test]
+    fn test_warns_on_incompatible_dimensions() {
+        let result = analyze_formula("map + ect", &["map", "ect"], &["kPa", "K"]);
+        assert_eq!(
+            result.warnings,
+            vec!["Adding pressure (kPa) and temperature (°C)".to_string()]
+        );
+        assert_eq!(result.dimension, None);
+
+        let result = analyze_formula(
+            "if(tps > 50, map, ect)",
+            &["tps", "map", "ect"],
+            &["%", "kPa", "K"],
+        );
+        assert_eq!(result.warnings.len(), 1);
+
+        let result = analyze_formula("ipw + dwell", &["ipw", "dwell"], &["ms", "s"]);
+        assert!(result.warnings[0].contains("different units"));
+    }
+
+    #[test]
+    fn test_unknown_units_are_not_checked() {
+        let result = analyze_formula("map + volts", &["map", "volts"], &["kPa", "V"]);
+        assert!(result.warnings.is_empty());
+        assert_eq!(result.unit, None);
+
+        let result = analyze_formula("tps / 100", &["tps"], &["%"]);
+        assert_eq!(result.dimension, Some(Dimension::NONE));
+        assert_eq!(result.unit, None);
+    }
+}
@@ -0,0 +1,1190 @@
+//! Expression parsing and evaluation engine for computed channels
+//!
+//! This module handles parsing mathematical formulas that reference channel data,
+//! including support for time-shifted values (both index-based and time-based),
+//! comparison and boolean operators, `if(cond, a, b)`, and windowed functions
+//! such as `avg(ch, 0.5s)` that are computed over whole columns. Lookup
+//! tables from the library are read with `table("Name", x)` and
+//! `table2d("Name", x, y)`, and vehicle profile constants with `$name`.
+//!
+//! Formulas are compiled once into a [`CompiledFormula`] with channel
+//! references resolved to input slots, then evaluated column-wise in
+//! parallel chunks. Their result unit is inferred from the units of the
+//! inputs by [`CompiledFormula::analyze_units`].
+
+mod completion;
+mod dimension;
+mod parser;
+mod window;
+
+pub use completion::{complete, Completion, CompletionKind, Completions};
+pub use dimension::UnitAnalysis;
+pub use parser::{parse, parse_spanned, Node, ParseError, TableLookup};
+
+use window::SampledColumn;
+
+use crate::computed::{ChannelReference, TimeShift};
+use crate::lookup_table::LookupTable;
+use crate::normalize::normalize_channel_name;
+use crate::parsers::types::LogData;
+use crate::vehicle_profile::ProfileConstant;
+use rayon::prelude::*;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, LazyLock};
+
+/// Regex for parsing quoted channel references with optional time shifts
+/// Pattern: "Channel Name" (anything in quotes) with optional time shift
+static QUOTED_CHANNEL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#""([^"]+)"(?:\[([+-]?\d+)\]|@([+-]?\d+\.?\d*)s)?"#).expect("Invalid regex pattern")
+});
+
+/// Regex for parsing unquoted channel references with optional time shifts
+/// Pattern: ChannelName (identifier-like) with optional time shift
+static UNQUOTED_CHANNEL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([a-zA-Z_][a-zA-Z0-9_]*)(?:\[([+-]?\d+)\]|@([+-]?\d+\.?\d*)s)?"#)
+        .expect("Invalid regex pattern")
+});
+
+/// Regex for the table name argument of `table(...)` and `table2d(...)`
+static TABLE_NAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\btable(?:2d)?\s*\(\s*("[^"]*"|[a-zA-Z_][a-zA-Z0-9_]*)"#)
+        .expect("Invalid regex pattern")
+});
+
+/// Regex for `$name` profile constants
+static CONSTANT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\$([a-zA-Z_][a-zA-Z0-9_]*)"#).expect("Invalid regex pattern"));
+
+/// Built-in functions and constants that should not be treated as channel names
+const RESERVED_NAMES: &[&str] = &[
+    "sin", "cos", "tan", "asin", "acos", "atan", "atan2", "sinh", "cosh", "tanh", "asinh", "acosh",
+    "atanh", "sqrt", "abs", "exp", "ln", "log", "log2", "log10", "floor", "ceil", "round", "trunc",
+    "fract", "signum", "max", "min", "if", "avg", "rms", "max_over", "min_over", "deriv", "integ",
+    "lowpass", "hold", "table", "table2d", "pi", "e", "tau", "phi",
+];
+
+/// Byte ranges of the table names in `table(...)` and `table2d(...)` calls
+fn table_name_spans(formula: &str) -> Vec<Range<usize>> {
+    TABLE_NAME_REGEX
+        .captures_iter(formula)
+        .filter_map(|caps| caps.get(1).map(|m| m.range()))
+        .collect()
+}
+
+/// Names of the `$name` constants a formula reads (without `$`), without duplicates
+pub fn extract_constant_references(formula: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for caps in CONSTANT_REGEX.captures_iter(formula) {
+        let name = caps[1].to_string();
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Names of the lookup tables a formula reads, without duplicates
+pub fn extract_table_references(formula: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for span in table_name_spans(formula) {
+        let name = formula[span].trim_matches('"').to_string();
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Extract all channel references from a formula
+pub fn extract_channel_references(formula: &str) -> Vec<ChannelReference> {
+    let mut references: Vec<ChannelReference> = locate_channel_references(formula)
+        .into_iter()
+        .map(|(r, _)| r)
+        .collect();
+
+    // Deduplicate by full_match
+    references.sort_by_key(|r| std::cmp::Reverse(r.full_match.len())); // Sort by length descending
+    let mut seen = std::collections::HashSet::new();
+    references.retain(|r| seen.insert(r.full_match.clone()));
+
+    references
+}
+
+/// Every channel reference of a formula with its byte range, in the order
+/// quoted then unquoted references appear
+fn locate_channel_references(formula: &str) -> Vec<(ChannelReference, Range<usize>)> {
+    let mut references = Vec::new();
+    let table_names = table_name_spans(formula);
+    let is_table_name = |start: usize| table_names.iter().any(|span| span.contains(&start));
+    let mut quoted_spans: Vec<Range<usize>> = Vec::new();
+
+    // First, extract quoted channel names (these take precedence)
+    for caps in QUOTED_CHANNEL_REGEX.captures_iter(formula) {
+        quoted_spans.push(caps.get(0).unwrap().range());
+        if is_table_name(caps.get(0).unwrap().start()) {
+            continue;
+        }
+        let name = caps.get(1).unwrap().as_str().to_string();
+        let index_shift = caps.get(2).map(|m| m.as_str());
+        let time_shift_str = caps.get(3).map(|m| m.as_str());
+        let full_match = caps.get(0).unwrap().as_str().to_string();
+
+        let time_shift = parse_time_shift(index_shift, time_shift_str);
+
+        references.push((
+            ChannelReference {
+                name,
+                time_shift,
+                full_match,
+            },
+            caps.get(0).unwrap().range(),
+        ));
+    }
+
+    // Then extract unquoted channel names
+    for caps in UNQUOTED_CHANNEL_REGEX.captures_iter(formula) {
+        let name = caps.get(1).unwrap().as_str().to_string();
+        let index_shift = caps.get(2).map(|m| m.as_str());
+        let time_shift_str = caps.get(3).map(|m| m.as_str());
+        let full_match = caps.get(0).unwrap().as_str().to_string();
+
+        // Skip reserved names (built-in functions/constants)
+        if RESERVED_NAMES.contains(&name.to_lowercase().as_str()) {
+            continue;
+        }
+
+        // Skip number suffixes such as the `s` in `0.5s`, table names and
+        // `$name` constants
+        let start_pos = caps.get(0).unwrap().start();
+        if is_table_name(start_pos) || formula[..start_pos].ends_with('$') {
+            continue;
+        }
+        if formula[..start_pos].ends_with(|c: char| c.is_ascii_digit() || c == '.') {
+            continue;
+        }
+
+        // Skip if this position is inside a quoted reference (any occurrence)
+        if quoted_spans.iter().any(|span| span.contains(&start_pos)) {
+            continue;
+        }
+
+        let time_shift = parse_time_shift(index_shift, time_shift_str);
+
+        references.push((
+            ChannelReference {
+                name,
+                time_shift,
+                full_match,
+            },
+            caps.get(0).unwrap().range(),
+        ));
+    }
+
+    references
+}
+
+/// Helper to parse time shift from capture groups
+fn parse_time_shift(index_shift: Option<&str>, time_shift_str: Option<&str>) -> TimeShift {
+    if let Some(idx_str) = index_shift {
+        match idx_str.parse::<i32>() {
+            Ok(offset) => TimeShift::IndexOffset(offset),
+            Err(_) => TimeShift::None,
+        }
+    } else if let Some(time_str) = time_shift_str {
+        match time_str.parse::<f64>() {
+            Ok(offset) => TimeShift::TimeOffset(offset),
+            Err(_) => TimeShift::None,
+        }
+    } else {
+        TimeShift::None
+    }
+}
+
+/// Validate a formula for syntax errors and channel availability
+pub fn validate_formula(formula: &str, available_channels: &[String]) -> Result<(), String> {
+    if formula.trim().is_empty() {
+        return Err("Formula cannot be empty".to_string());
+    }
+
+    // Extract channel references
+    let refs = extract_channel_references(formula);
+
+    // Check that all referenced channels exist
+    let missing: Vec<_> = refs
+        .iter()
+        .filter(|r| resolve_channel_name(&r.name, available_channels).is_none())
+        .map(|r| r.name.clone())
+        .collect();
+
+    if !missing.is_empty() {
+        return Err(format!("Unknown channels: {}", missing.join(", ")));
+    }
+
+    // Check the syntax with channel references replaced by variables
+    parse_formula(formula, &refs).map(|_| ())
+}
+
+/// A formula error and, when known, the byte range of the formula at fault
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormulaError {
+    pub message: String,
+    pub span: Option<Range<usize>>,
+}
+
+impl FormulaError {
+    fn new(message: impl Into<String>, span: Option<Range<usize>>) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+impl std::fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Validate and compile a formula like [`validate_formula`] and
+/// [`CompiledFormula::compile_with_context`], pointing errors at the unknown
+/// channel, constant or table or the token the parser rejected
+pub fn check_formula(
+    formula: &str,
+    available_channels: &[String],
+    context: &FormulaContext,
+) -> Result<CompiledFormula, FormulaError> {
+    if formula.trim().is_empty() {
+        return Err(FormulaError::new("Formula cannot be empty", None));
+    }
+
+    let located = locate_channel_references(formula);
+    let missing: Vec<&(ChannelReference, Range<usize>)> = located
+        .iter()
+        .filter(|(r, _)| resolve_channel_name(&r.name, available_channels).is_none())
+        .collect();
+    if let Some((_, span)) = missing.first() {
+        let mut names: Vec<&str> = Vec::new();
+        for (r, _) in &missing {
+            if !names.contains(&r.name.as_str()) {
+                names.push(&r.name);
+            }
+        }
+        return Err(FormulaError::new(
+            format!("Unknown channels: {}", names.join(", ")),
+            Some(span.clone()),
+        ));
+    }
+
+    let refs = extract_channel_references(formula);
+    let prepared = prepare_formula(formula, &refs);
+    let var_names: Vec<String> = refs
+        .iter()
+        .map(|r| sanitize_var_name(&r.full_match))
+        .collect();
+    parse_spanned(&prepared, &var_names).map_err(|e| {
+        FormulaError::new(
+            format!("Parse error: {}", e.message),
+            Some(original_span(&prepared, formula, e.span)),
+        )
+    })?;
+
+    for caps in CONSTANT_REGEX.captures_iter(formula) {
+        let span = caps.get(0).unwrap().range();
+        let in_reference = located.iter().any(|(_, r)| r.contains(&span.start));
+        if !in_reference
+            && !context
+                .constants
+                .iter()
+                .any(|c| c.name.eq_ignore_ascii_case(&caps[1]))
+        {
+            return Err(FormulaError::new(
+                format!("Constant not found: ${}", &caps[1]),
+                Some(span),
+            ));
+        }
+    }
+    for span in table_name_spans(formula) {
+        let name = formula[span.clone()].trim_matches('"');
+        if !context
+            .tables
+            .iter()
+            .any(|t| t.name.eq_ignore_ascii_case(name))
+        {
+            return Err(FormulaError::new(
+                format!("Table not found: {}", name),
+                Some(span),
+            ));
+        }
+    }
+
+    let bindings = build_channel_bindings(&refs, available_channels)
+        .map_err(|e| FormulaError::new(e, None))?;
+    CompiledFormula::compile_with_context(formula, &bindings, context)
+        .map_err(|e| FormulaError::new(e, None))
+}
+
+/// Map a byte range of a prepared formula back to the formula it was
+/// prepared from. Channel references are replaced by names with as many
+/// characters, so character positions match.
+fn original_span(prepared: &str, formula: &str, span: Range<usize>) -> Range<usize> {
+    let char_index = |byte: usize| prepared[..byte.min(prepared.len())].chars().count();
+    let byte_index = |chars: usize| {
+        formula
+            .char_indices()
+            .nth(chars)
+            .map_or(formula.len(), |(i, _)| i)
+    };
+    byte_index(char_index(span.start))..byte_index(char_index(span.end))
+}
+
+/// Parse a formula into a tree whose variable slots follow the order of `refs`
+fn parse_formula(formula: &str, refs: &[ChannelReference]) -> Result<Node, String> {
+    let prepared = prepare_formula(formula, refs);
+    let var_names: Vec<String> = refs
+        .iter()
+        .map(|r| sanitize_var_name(&r.full_match))
+        .collect();
+    parse(&prepared, &var_names).map_err(|e| format!("Parse error: {}", e))
+}
+
+/// Prepare a formula for parsing by replacing channel references with sanitized variable names
+fn prepare_formula(formula: &str, refs: &[ChannelReference]) -> String {
+    // Table names are left as written, even if they match a channel name
+    let mut result = String::with_capacity(formula.len());
+    let mut last = 0;
+    for span in table_name_spans(formula) {
+        result.push_str(&replace_references(&formula[last..span.start], refs));
+        result.push_str(&formula[span.clone()]);
+        last = span.end;
+    }
+    result.push_str(&replace_references(&formula[last..], refs));
+    result
+}
+
+fn replace_references(formula: &str, refs: &[ChannelReference]) -> String {
+    let mut result = formula.to_string();
+
+    // Sort refs by length (longest first) to avoid partial replacements
+    let mut sorted_refs: Vec<_> = refs.iter().collect();
+    sorted_refs.sort_by_key(|r| std::cmp::Reverse(r.full_match.len()));
+
+    for r in sorted_refs {
+        let var_name = sanitize_var_name(&r.full_match);
+        result = result.replace(&r.full_match, &var_name);
+    }
+
+    result
+}
+
+/// Sanitize a channel reference into a valid variable name
+fn sanitize_var_name(full_match: &str) -> String {
+    // Replace non-alphanumeric chars with underscores, ensure starts with letter
+    let sanitized: String = full_match
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if sanitized
+        .chars()
+        .next()
+        .map(|c| c.is_numeric())
+        .unwrap_or(true)
+    {
+        format!("v_{}", sanitized)
+    } else {
+        sanitized
+    }
+}
+
+/// Values of one formula input
+///
+/// Channels recorded at their own rate keep their native samples, so that
+/// column functions such as `deriv()` or `avg(x, 1s)` see the real samples
+/// rather than the last sample held at every log record.
+#[derive(Clone, Debug)]
+pub enum InputColumn<'a> {
+    /// One value per log record
+    Records(Cow<'a, [f64]>),
+    /// Samples of a channel recorded at its own rate, with their times
+    Samples {
+        values: Cow<'a, [f64]>,
+        times: Cow<'a, [f64]>,
+    },
+}
+
+impl<'a> InputColumn<'a> {
+    /// A channel of a log, on its own timebase if it has one
+    pub fn from_log(log_data: &'a LogData, channel: usize) -> Self {
+        let Some(values) = log_data.column(channel) else {
+            return Self::Records(Cow::Owned(vec![f64::NAN; log_data.len()]));
+        };
+        match log_data.timebase(channel) {
+            Some(timebase) => Self::Samples {
+                values: Cow::Borrowed(values),
+                times: Cow::Borrowed(timebase.times()),
+            },
+            None => Self::Records(Cow::Borrowed(values)),
+        }
+    }
+
+    /// Transform the values (e.g. into canonical units), keeping their times
+    pub fn map_values(self, f: impl FnOnce(Cow<'a, [f64]>) -> Cow<'a, [f64]>) -> Self {
+        match self {
+            Self::Records(values) => Self::Records(f(values)),
+            Self::Samples { values, times } => Self::Samples {
+                values: f(values),
+                times,
+            },
+        }
+    }
+
+    /// Copy borrowed data, e.g. to hand the input to a background thread
+    pub fn into_owned(self) -> InputColumn<'static> {
+        match self {
+            Self::Records(values) => InputColumn::Records(Cow::Owned(values.into_owned())),
+            Self::Samples { values, times } => InputColumn::Samples {
+                values: Cow::Owned(values.into_owned()),
+                times: Cow::Owned(times.into_owned()),
+            },
+        }
+    }
+
+    /// Borrow the data as another input
+    pub fn borrowed(&self) -> InputColumn<'_> {
+        match self {
+            Self::Records(values) => InputColumn::Records(Cow::Borrowed(values)),
+            Self::Samples { values, times } => InputColumn::Samples {
+                values: Cow::Borrowed(values),
+                times: Cow::Borrowed(times),
+            },
+        }
+    }
+
+    /// One value per record of `log_times`, holding the last sample of
+    /// channels recorded at their own rate
+    pub fn to_records(&self, log_times: &[f64]) -> Cow<'_, [f64]> {
+        match self {
+            Self::Records(values) => Cow::Borrowed(values),
+            Self::Samples { values, times } => {
+                Cow::Owned(window::hold_samples(values, times, log_times))
+            }
+        }
+    }
+}
+
+/// Lookup tables and constants a formula can read besides log channels
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FormulaContext<'a> {
+    /// Tables for `table()` and `table2d()`
+    pub tables: &'a [LookupTable],
+    /// Constants of the file's vehicle profile, for `$name`
+    pub constants: &'a [ProfileConstant],
+}
+
+/// Attach table data to every lookup in the tree and replace constants with
+/// their values
+fn resolve_context(node: &mut Node, context: &FormulaContext) -> Result<(), String> {
+    match node {
+        Node::Number(_) | Node::Var(_) => Ok(()),
+        Node::Constant(name) => {
+            let constant = context
+                .constants
+                .iter()
+                .find(|c| c.name.eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("Constant not found: ${}", name))?;
+            *node = Node::Number(constant.value);
+            Ok(())
+        }
+        Node::Unary(_, arg) => resolve_context(arg, context),
+        Node::Binary(_, lhs, rhs) => {
+            resolve_context(lhs, context)?;
+            resolve_context(rhs, context)
+        }
+        Node::If(cond, then, otherwise) => {
+            resolve_context(cond, context)?;
+            resolve_context(then, context)?;
+            resolve_context(otherwise, context)
+        }
+        Node::Call(_, args) | Node::Column(_, args) => args
+            .iter_mut()
+            .try_for_each(|arg| resolve_context(arg, context)),
+        Node::Table(lookup, args) => {
+            let table = context
+                .tables
+                .iter()
+                .find(|t| t.name.eq_ignore_ascii_case(&lookup.name))
+                .ok_or_else(|| format!("Table not found: {}", lookup.name))?;
+            table
+                .validate()
+                .map_err(|e| format!("Table '{}': {}", table.name, e))?;
+            match (table.is_2d(), args.len()) {
+                (false, 2) => {
+                    return Err(format!(
+                        "'{}' is a 1D table; use table() to read it",
+                        table.name
+                    ))
+                }
+                (true, 1) => {
+                    return Err(format!(
+                        "'{}' is a 2D table; use table2d() to read it",
+                        table.name
+                    ))
+                }
+                _ => {}
+            }
+            lookup.table = Some(Arc::new(table.clone()));
+            args.iter_mut()
+                .try_for_each(|arg| resolve_context(arg, context))
+        }
+    }
+}
+
+/// Find the channel a formula reference names: a case-insensitive match, or
+/// else a channel with the same normalized name (so `MAP` finds "Manifold
+/// Pressure"), letting one formula work across ECUs
+pub fn resolve_channel_name(name: &str, available_channels: &[String]) -> Option<usize> {
+    available_channels
+        .iter()
+        .position(|c| c.eq_ignore_ascii_case(name))
+        .or_else(|| {
+            let normalized = normalize_channel_name(name);
+            available_channels
+                .iter()
+                .position(|c| normalize_channel_name(c).eq_ignore_ascii_case(&normalized))
+        })
+}
+
+/// Build channel bindings from references to file channel indices
+pub fn build_channel_bindings(
+    refs: &[ChannelReference],
+    available_channels: &[String],
+) -> Result<HashMap<String, usize>, String> {
+    let mut bindings = HashMap::new();
+
+    for r in refs {
+        let idx = resolve_channel_name(&r.name, available_channels)
+            .ok_or_else(|| format!("Channel not found: {}", r.name))?;
+
+        bindings.insert(r.name.clone(), idx);
+    }
+
+    Ok(bindings)
+}
+
+/// A formula parsed once and bound to a file's channels, ready to evaluate
+#[derive(Clone, Debug)]
+pub struct CompiledFormula {
+    expr: Node,
+    /// Distinct log channels the formula reads
+    channels: Vec<usize>,
+    /// For each variable slot: index into `channels` and the shift to apply
+    slots: Vec<(usize, TimeShift)>,
+}
+
+impl CompiledFormula {
+    /// Parse a formula and resolve its channel references through `bindings`
+    pub fn compile(formula: &str, bindings: &HashMap<String, usize>) -> Result<Self, String> {
+        Self::compile_with_context(formula, bindings, &FormulaContext::default())
+    }
+
+    /// Like [`compile`](Self::compile), also resolving lookup tables and
+    /// `$name` constants from `context`
+    pub fn compile_with_context(
+        formula: &str,
+        bindings: &HashMap<String, usize>,
+        context: &FormulaContext,
+    ) -> Result<Self, String> {
+        let refs = extract_channel_references(formula);
+        let mut expr = parse_formula(formula, &refs)?;
+        resolve_context(&mut expr, context)?;
+
+        let mut channels: Vec<usize> = Vec::new();
+        let mut slots = Vec::with_capacity(refs.len());
+        for r in &refs {
+            let channel = *bindings
+                .get(&r.name)
+                .ok_or_else(|| format!("Channel not found: {}", r.name))?;
+            let input = channels
+                .iter()
+                .position(|&c| c == channel)
+                .unwrap_or_else(|| {
+                    channels.push(channel);
+                    channels.len() - 1
+                });
+            slots.push((input, r.time_shift.clone()));
+        }
+
+        Ok(Self {
+            expr,
+            channels,
+            slots,
+        })
+    }
+
+    /// Channel indices the formula reads, in input order
+    pub fn channels(&self) -> &[usize] {
+        &self.channels
+    }
+
+    /// Infer the result unit and check the dimensions the formula combines,
+    /// given the unit of each entry of [`channels`](Self::channels)
+    pub fn analyze_units(&self, channel_units: &[&str]) -> UnitAnalysis {
+        let slot_units: Vec<&str> = self
+            .slots
+            .iter()
+            .map(|(input, _)| channel_units.get(*input).copied().unwrap_or_default())
+            .collect();
+        dimension::analyze(&self.expr, &slot_units)
+    }
+
+    /// Evaluate for every record of the log
+    pub fn evaluate(&self, log_data: &LogData, times: &[f64]) -> Vec<f64> {
+        self.evaluate_with_progress(log_data, times, &|_| {})
+    }
+
+    /// Evaluate for every record, reporting the completed fraction
+    pub fn evaluate_with_progress(
+        &self,
+        log_data: &LogData,
+        times: &[f64],
+        progress: &(dyn Fn(f32) + Sync),
+    ) -> Vec<f64> {
+        let inputs: Vec<InputColumn> = self
+            .channels
+            .iter()
+            .map(|&channel| InputColumn::from_log(log_data, channel))
+            .collect();
+        self.evaluate_inputs(&inputs, times, log_data.len(), progress)
+    }
+
+    /// Evaluate with one record-aligned column per entry of
+    /// [`channels`](Self::channels), e.g. values copied out of a log for a
+    /// background thread
+    pub fn evaluate_slices(
+        &self,
+        columns: &[&[f64]],
+        times: &[f64],
+        num_records: usize,
+        progress: &(dyn Fn(f32) + Sync),
+    ) -> Vec<f64> {
+        let inputs: Vec<InputColumn> = columns
+            .iter()
+            .map(|&c| InputColumn::Records(Cow::Borrowed(c)))
+            .collect();
+        self.evaluate_inputs(&inputs, times, num_records, progress)
+    }
+
+    /// Evaluate with one input per entry of [`channels`](Self::channels),
+    /// each either record-aligned or on its own timebase
+    pub fn evaluate_inputs(
+        &self,
+        inputs: &[InputColumn],
+        times: &[f64],
+        num_records: usize,
+        progress: &(dyn Fn(f32) + Sync),
+    ) -> Vec<f64> {
+        if num_records == 0 {
+            return Vec::new();
+        }
+
+        // One column per variable slot with its time shift applied; unshifted
+        // slots of channels with their own timebase also keep their samples
+        let records: Vec<Cow<[f64]>> = inputs.iter().map(|i| i.to_records(times)).collect();
+        let columns = self
+            .slots
+            .iter()
+            .map(|(input, shift)| shift_column(&records[*input], shift, times))
+            .collect();
+        let samples = self
+            .slots
+            .iter()
+            .map(|(input, shift)| match (&inputs[*input], shift) {
+                (InputColumn::Samples { values, times }, TimeShift::None) => Some(SampledColumn {
+                    values: Cow::Borrowed(values.as_ref()),
+                    times: times.as_ref(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        let mut results =
+            window::evaluate_columns(&self.expr, columns, samples, times, num_records, progress);
+
+        // Missing inputs propagate as NaN; non-finite results are recorded
+        // as missing rather than zero
+        results.par_iter_mut().for_each(|value| {
+            if !value.is_finite() {
+                *value = f64::NAN;
+            }
+        });
+        results
+    }
+}
+
+/// Evaluate a formula for all records in the log
+pub fn evaluate_all_records(
+    formula: &str,
+    bindings: &HashMap<String, usize>,
+    log_data: &LogData,
+    times: &[f64],
+) -> Result<Vec<f64>, String> {
+    if log_data.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(CompiledFormula::compile(formula, bindings)?.evaluate(log_data, times))
+}
+
+/// Apply a time shift to a whole column
+fn shift_column<'a>(column: &'a [f64], shift: &TimeShift, times: &[f64]) -> Cow<'a, [f64]> {
+    let last = column.len().saturating_sub(1) as i64;
+    match shift {
+        TimeShift::None => Cow::Borrowed(column),
+
+        TimeShift::IndexOffset(offset) => Cow::Owned(
+            (0..column.len() as i64)
+                .into_par_iter()
+                .map(|i| column[(i + *offset as i64).clamp(0, last) as usize])
+                .collect(),
+        ),
+
+        TimeShift::TimeOffset(seconds) => Cow::Owned(
+            (0..column.len())
+                .into_par_iter()
+                .map(|i| {
+                    let current_time = times.get(i).copied().unwrap_or(0.0);
+                    let target = find_record_at_time(times, current_time + seconds);
+                    column.get(target).copied().unwrap_or(f64::NAN)
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Find the record index closest to a given time using binary search
+fn find_record_at_time(times: &[f64], target_time: f64) -> usize {
+    if times.is_empty() {
+        return 0;
+    }
+
+    // Clamp to valid time range
+    let clamped_time = target_time.clamp(
+        *times.first().unwrap_or(&0.0),
+        *times.last().unwrap_or(&0.0),
+    );
+
+    // Binary search for closest time
+    match times.binary_search_by(|t| {
+        t.partial_cmp(&clamped_time)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }) {
+        Ok(idx) => idx,
+        Err(idx) => {
+            // idx is where we'd insert to maintain order
+            if idx == 0 {
+                0
+            } else if idx >= times.len() {
+                times.len() - 1
+            } else {
+                // Check which neighbor is closer
+                let prev_diff = (times[idx - 1] - clamped_time).abs();
+                let next_diff = (times[idx] - clamped_time).abs();
+                if prev_diff <= next_diff {
+                    idx - 1
+                } else {
+                    idx
+                }
+            }
+        }
+    }
+}
+
+/// Generate preview values for a formula (first N values)
+pub fn generate_preview(
+    formula: &str,
+    bindings: &HashMap<String, usize>,
+    log_data: &LogData,
+    times: &[f64],
+    count: usize,
+) -> Result<Vec<f64>, String> {
+    let all_values = evaluate_all_records(formula, bindings, log_data, times)?;
+    Ok(all_values.into_iter().take(count).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::types::Timebase;
+
+    #[test]
+    fn test_extract_simple_reference() {
+        let refs = extract_channel_references("RPM * 2");
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].name, "RPM");
+        assert_eq!(refs[0].time_shift, TimeShift::None);
+    }
+
+    #[test]
+    fn test_extract_quoted_reference() {
+        let refs = extract_channel_references("\"Manifold Pressure\" + 10");
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].name, "Manifold Pressure");
+    }
+
+    #[test]
+    fn test_extract_repeated_quoted_reference() {
+        let refs = extract_channel_references("\"Vehicle Speed\" * 2 + \"Vehicle Speed\"");
+        assert!(refs.iter().all(|r| r.name == "Vehicle Speed"));
+    }
+
+    #[test]
+    fn test_extract_index_offset() {
+        let refs = extract_channel_references("RPM[-1]");
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].name, "RPM");
+        assert_eq!(refs[0].time_shift, TimeShift::IndexOffset(-1));
+    }
+
+    #[test]
+    fn test_extract_time_offset() {
+        let refs = extract_channel_references("RPM@-0.1s");
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].name, "RPM");
+        assert_eq!(refs[0].time_shift, TimeShift::TimeOffset(-0.1));
+    }
+
+    #[test]
+    fn test_extract_multiple_references() {
+        let refs = extract_channel_references("RPM + Boost * 2");
+        assert_eq!(refs.len(), 2);
+    }
+
+    #[test]
+    fn test_skip_reserved_names() {
+        let refs = extract_channel_references("sin(RPM) + cos(Boost)");
+        // Should find RPM and Boost, but not sin and cos
+        assert_eq!(refs.len(), 2);
+        assert!(refs.iter().all(|r| r.name != "sin" && r.name != "cos"));
+    }
+
+    #[test]
+    fn test_validate_valid_formula() {
+        let channels = vec!["RPM".to_string(), "Boost".to_string()];
+        let result = validate_formula("RPM + Boost", &channels);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_validate_missing_channel() {
+        let channels = vec!["RPM".to_string()];
+        let result = validate_formula("RPM + MissingChannel", &channels);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Unknown channels"));
+    }
+
+    #[test]
+    fn test_validate_empty_formula() {
+        let channels = vec!["RPM".to_string()];
+        let result = validate_formula("", &channels);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_check_formula_error_spans() {
+        let channels = vec!["RPM".to_string(), "Coolant Temp".to_string()];
+        let context = FormulaContext::default();
+        let span_text = |formula: &'static str| {
+            let err = check_formula(formula, &channels, &context).unwrap_err();
+            &formula[err.span.expect("error has a span")]
+        };
+
+        assert_eq!(span_text("RPM * Boost + 1"), "Boost");
+        assert_eq!(span_text("\"Coolant Temp\" + foo(RPM)"), "foo");
+        assert_eq!(span_text("\"Coolant Temp\" * * 2"), "*");
+        assert_eq!(span_text("RPM * $injector_cc"), "$injector_cc");
+        assert_eq!(span_text("table(\"VE\", RPM)"), "\"VE\"");
+        assert_eq!(span_text("avg(RPM, 0s)"), "0s");
+        assert_eq!(span_text("atan2(RPM)"), "atan2(RPM)");
+
+        // Errors at the end of the formula point there
+        let err = check_formula("RPM +", &channels, &context).unwrap_err();
+        assert_eq!(err.span, Some(5..5));
+        assert!(err.message.starts_with("Parse error"));
+
+        assert!(check_formula("\"Coolant Temp\" - RPM", &channels, &context).is_ok());
+    }
+
+    #[test]
+    fn test_evaluate_simple() {
+        let data =
+            LogData::from_columns(vec![vec![1000.0, 2000.0, 3000.0], vec![10.0, 20.0, 30.0]]);
+        let times = vec![0.0, 0.1, 0.2];
+        let mut bindings = HashMap::new();
+        bindings.insert("RPM".to_string(), 0);
+        bindings.insert("Boost".to_string(), 1);
+
+        let result = evaluate_all_records("RPM + Boost", &bindings, &data, &times).unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0], 1010.0);
+        assert_eq!(result[1], 2020.0);
+        assert_eq!(result[2], 3030.0);
+    }
+
+    #[test]
+    fn test_evaluate_with_index_offset() {
+        let data = LogData::from_columns(vec![vec![1000.0, 2000.0, 3000.0]]);
+        let times = vec![0.0, 0.1, 0.2];
+        let mut bindings = HashMap::new();
+        bindings.insert("RPM".to_string(), 0);
+
+        // RPM - RPM[-1] should give the change from previous sample
+        let result = evaluate_all_records("RPM - RPM[-1]", &bindings, &data, &times).unwrap();
+        assert_eq!(result.len(), 3);
+        // First record: 1000 - 1000 (clamped to 0) = 0
+        assert_eq!(result[0], 0.0);
+        // Second record: 2000 - 1000 = 1000
+        assert_eq!(result[1], 1000.0);
+        // Third record: 3000 - 2000 = 1000
+        assert_eq!(result[2], 1000.0);
+    }
+
+    #[test]
+    fn test_evaluate_conditional() {
+        let data =
+            LogData::from_columns(vec![vec![1500.0, 2500.0, f64::NAN], vec![10.0, 20.0, 30.0]]);
+        let times = vec![0.0, 0.1, 0.2];
+        let mut bindings = HashMap::new();
+        bindings.insert("RPM".to_string(), 0);
+        bindings.insert("Injector Duty".to_string(), 1);
+
+        let result = evaluate_all_records(
+            "if(RPM > 2000 && !(RPM == 0), \"Injector Duty\", 0)",
+            &bindings,
+            &data,
+            &times,
+        )
+        .unwrap();
+        assert_eq!(result[0], 0.0);
+        assert_eq!(result[1], 20.0);
+        // A missing condition input gives a missing result
+        assert!(result[2].is_nan());
+    }
+
+    #[test]
+    fn test_validate_conditional_formula() {
+        let channels = vec!["RPM".to_string(), "TPS".to_string()];
+        assert!(validate_formula("if(TPS > 90 || RPM[-1] >= 6000, 1, 0)", &channels).is_ok());
+        assert!(validate_formula("if(TPS > 90, 1)", &channels).is_err());
+        assert!(validate_formula("TPS > ", &channels).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_windowed_functions() {
+        let data = LogData::from_columns(vec![vec![0.0, 10.0, 20.0, 30.0]]);
+        let times = vec![0.0, 0.5, 1.0, 1.5];
+        let mut bindings = HashMap::new();
+        bindings.insert("Speed".to_string(), 0);
+
+        let avg = evaluate_all_records("avg(Speed, 0.5s)", &bindings, &data, &times).unwrap();
+        assert_eq!(avg, vec![0.0, 5.0, 15.0, 25.0]);
+
+        let accel = evaluate_all_records("deriv(Speed) / 2", &bindings, &data, &times).unwrap();
+        assert_eq!(accel, vec![10.0, 10.0, 10.0, 10.0]);
+
+        // Column functions can be nested and combined with per-record logic
+        let result = evaluate_all_records(
+            "if(Speed > 5, max_over(integ(Speed), 2), -1)",
+            &bindings,
+            &data,
+            &times,
+        )
+        .unwrap();
+        assert_eq!(result, vec![-1.0, 2.5, 10.0, 22.5]);
+    }
+
+    #[test]
+    fn test_column_functions_on_own_timebase() {
+        // A 1 Hz ramp of 10 per second in a 10 Hz log
+        let times: Vec<f64> = (0..30).map(|i| i as f64 * 0.1).collect();
+        let timebase = Timebase::new(vec![0.0, 1.0, 2.0], &times);
+        let data = LogData::from_timed_columns(
+            times.len(),
+            vec![(vec![0.0, 10.0, 20.0], Some(std::sync::Arc::new(timebase)))],
+        );
+        let mut bindings = HashMap::new();
+        bindings.insert("Slow".to_string(), 0);
+        let eval = |formula: &str| evaluate_all_records(formula, &bindings, &data, &times).unwrap();
+
+        // Computed on the samples rather than on the held records
+        assert!(eval("deriv(Slow)").iter().all(|&v| v == 10.0));
+        assert!(eval("deriv(lowpass(Slow * 2, 100))")
+            .iter()
+            .all(|&v| (v - 20.0).abs() < 1.0));
+        assert_eq!(eval("avg(Slow, 1s)")[15], 5.0);
+        assert_eq!(eval("integ(Slow)")[25], 20.0);
+
+        // Shifted references fall back to the held records
+        assert!((eval("deriv(Slow[-1])")[11] - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_extract_skips_window_suffix() {
+        let refs = extract_channel_references("avg(RPM, 0.5s) + rms(\"Knock Level\", 10)");
+        let mut names: Vec<_> = refs.iter().map(|r| r.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["Knock Level", "RPM"]);
+    }
+
+    #[test]
+    fn test_compiled_formula_across_chunks() {
+        let n = 40_000;
+        let rpm: Vec<f64> = (0..n).map(|i| i as f64).collect();
+        let data = LogData::from_columns(vec![rpm]);
+        let times: Vec<f64> = (0..n).map(|i| i as f64 * 0.01).collect();
+        let mut bindings = HashMap::new();
+        bindings.insert("RPM".to_string(), 0);
+
+        let compiled = CompiledFormula::compile("RPM - RPM[-1] + avg(RPM, 2)", &bindings).unwrap();
+        let progress = std::sync::Mutex::new(Vec::new());
+        let result =
+            compiled.evaluate_with_progress(&data, &times, &|p| progress.lock().unwrap().push(p));
+
+        assert_eq!(result.len(), n);
+        assert_eq!(result[0], 0.0);
+        assert_eq!(result[20_000], 1.0 + 19_999.5);
+        let progress = progress.into_inner().unwrap();
+        assert!(progress.iter().all(|p| (0.0..=1.0).contains(p)));
+        assert_eq!(progress.iter().cloned().fold(0.0, f32::max), 1.0);
+
+        // Detached input columns give the same result
+        let column = data.aligned_column(compiled.channels()[0]).unwrap();
+        assert_eq!(
+            compiled.evaluate_slices(&[&column], &times, n, &|_| {}),
+            result
+        );
+    }
+
+    #[test]
+    fn test_compile_unbound_channel() {
+        let bindings = HashMap::new();
+        let err = CompiledFormula::compile("RPM * 2", &bindings).unwrap_err();
+        assert!(err.contains("RPM"));
+    }
+
+    #[test]
+    fn test_table_names_are_not_channels() {
+        let refs = extract_channel_references(
+            "table(\"Deadtime\", \"Battery Voltage\") + table2d(VE, RPM, MAP)",
+        );
+        let mut names: Vec<_> = refs.iter().map(|r| r.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["Battery Voltage", "MAP", "RPM"]);
+        assert_eq!(
+            extract_table_references("table(\"Deadtime\", V) * table(deadtime, V)"),
+            vec!["Deadtime".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_compile_with_tables() {
+        let data = LogData::from_columns(vec![vec![9.0, 20.0], vec![1500.0, 3000.0]]);
+        let times = vec![0.0, 0.1];
+        let mut bindings = HashMap::new();
+        bindings.insert("Battery Voltage".to_string(), 0);
+        bindings.insert("RPM".to_string(), 1);
+        // A table may share its name with a channel
+        let tables = vec![
+            LookupTable::new_1d(
+                "RPM".to_string(),
+                vec![8.0, 10.0, 12.0],
+                vec![1.6, 1.2, 0.9],
+            ),
+            LookupTable::new_2d(
+                "VE".to_string(),
+                vec![1000.0, 2000.0],
+                vec![9.0, 20.0],
+                vec![vec![50.0, 60.0], vec![70.0, 80.0]],
+            ),
+        ];
+        let context = FormulaContext {
+            tables: &tables,
+            constants: &[],
+        };
+
+        let compiled = CompiledFormula::compile_with_context(
+            "table(\"RPM\", \"Battery Voltage\") + RPM * 0",
+            &bindings,
+            &context,
+        )
+        .unwrap();
+        let result = compiled.evaluate(&data, &times);
+        assert!((result[0] - 1.4).abs() < 1e-9);
+        assert_eq!(result[1], 0.9);
+
+        let compiled = CompiledFormula::compile_with_context(
+            "table2d(VE, RPM, avg(\"Battery Voltage\", 1))",
+            &bindings,
+            &context,
+        )
+        .unwrap();
+        let result = compiled.evaluate(&data, &times);
+        assert_eq!(result[0], 55.0);
+        assert_eq!(result[1], 80.0);
+
+        let err = CompiledFormula::compile_with_context("table(Fuel, RPM)", &bindings, &context)
+            .unwrap_err();
+        assert!(err.contains("Table not found"));
+        let err = CompiledFormula::compile_with_context("table(VE, RPM)", &bindings, &context)
+            .unwrap_err();
+        assert!(err.contains("table2d"));
+        assert!(CompiledFormula::compile("table(VE, RPM)", &bindings).is_err());
+    }
+
+    #[test]
+    fn test_compile_with_constants() {
+        let data = LogData::from_columns(vec![vec![50.0, 100.0]]);
+        let times = vec![0.0, 0.1];
+        let mut bindings = HashMap::new();
+        bindings.insert("Duty".to_string(), 0);
+        let constants = vec![
+            ProfileConstant::new("injector_cc", 1000.0, "cc/min"),
+            ProfileConstant::new("cylinders", 4.0, ""),
+        ];
+        let context = FormulaContext {
+            tables: &[],
+            constants: &constants,
+        };
+
+        let formula = "Duty / 100 * $Injector_CC * $cylinders";
+        let refs = extract_channel_references(formula);
+        assert_eq!(refs.len(), 1);
+        assert_eq!(
+            extract_constant_references(formula),
+            vec!["Injector_CC".to_string(), "cylinders".to_string()]
+        );
+
+        let compiled = CompiledFormula::compile_with_context(formula, &bindings, &context).unwrap();
+        assert_eq!(compiled.evaluate(&data, &times), vec![2000.0, 4000.0]);
+
+        let err =
+            CompiledFormula::compile_with_context("Duty * $mass", &bindings, &context).unwrap_err();
+        assert!(err.contains("$mass"));
+        assert!(CompiledFormula::compile(formula, &bindings).is_err());
+    }
+
+    #[test]
+    fn test_find_record_at_time() {
+        let times = vec![0.0, 0.1, 0.2, 0.3, 0.4];
+
+        assert_eq!(find_record_at_time(&times, 0.0), 0);
+        assert_eq!(find_record_at_time(&times, 0.1), 1);
+        assert_eq!(find_record_at_time(&times, 0.15), 1); // Closer to 0.1
+        assert_eq!(find_record_at_time(&times, 0.16), 2); // Closer to 0.2
+        assert_eq!(find_record_at_time(&times, -1.0), 0); // Clamped
+        assert_eq!(find_record_at_time(&times, 10.0), 4); // Clamped
+    }
+}
@@ -0,0 +1,1066 @@
+//! Formula parser and evaluator
+//!
+//! Parses the formula language used by computed channels into a [`Node`] tree.
+//! Channel references are replaced with variable names before parsing; each
+//! name resolves to a slot in the variable list passed to [`parse`].
+//!
+//! Comparison and boolean operators yield `1.0` (true) or `0.0` (false), and
+//! any non-zero value counts as true. A missing (`NaN`) operand makes the
+//! result missing too.
+
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::window::{ColumnFunction, Window};
+use crate::lookup_table::LookupTable;
+
+/// Unary operators
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+/// Binary operators
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+/// Built-in math functions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Sqrt,
+    Exp,
+    Ln,
+    Log10,
+    Log2,
+    Abs,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Asinh,
+    Acosh,
+    Atanh,
+    Floor,
+    Ceil,
+    Round,
+    Trunc,
+    Fract,
+    Signum,
+    Atan2,
+    Max,
+    Min,
+}
+
+impl Function {
+    /// Look up a function by name (case-insensitive)
+    fn from_name(name: &str) -> Option<Self> {
+        let function = match name.to_lowercase().as_str() {
+            "sqrt" => Self::Sqrt,
+            "exp" => Self::Exp,
+            "ln" => Self::Ln,
+            "log" | "log10" => Self::Log10,
+            "log2" => Self::Log2,
+            "abs" => Self::Abs,
+            "sin" => Self::Sin,
+            "cos" => Self::Cos,
+            "tan" => Self::Tan,
+            "asin" => Self::Asin,
+            "acos" => Self::Acos,
+            "atan" => Self::Atan,
+            "sinh" => Self::Sinh,
+            "cosh" => Self::Cosh,
+            "tanh" => Self::Tanh,
+            "asinh" => Self::Asinh,
+            "acosh" => Self::Acosh,
+            "atanh" => Self::Atanh,
+            "floor" => Self::Floor,
+            "ceil" => Self::Ceil,
+            "round" => Self::Round,
+            "trunc" => Self::Trunc,
+            "fract" => Self::Fract,
+            "signum" => Self::Signum,
+            "atan2" => Self::Atan2,
+            "max" => Self::Max,
+            "min" => Self::Min,
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    /// Minimum and maximum argument count (`None` = variadic)
+    fn arity(self) -> (usize, Option<usize>) {
+        match self {
+            Self::Atan2 => (2, Some(2)),
+            Self::Max | Self::Min => (1, None),
+            _ => (1, Some(1)),
+        }
+    }
+
+    fn apply(self, args: &[f64]) -> f64 {
+        let x = args[0];
+        match self {
+            Self::Sqrt => x.sqrt(),
+            Self::Exp => x.exp(),
+            Self::Ln => x.ln(),
+            Self::Log10 => x.log10(),
+            Self::Log2 => x.log2(),
+            Self::Abs => x.abs(),
+            Self::Sin => x.sin(),
+            Self::Cos => x.cos(),
+            Self::Tan => x.tan(),
+            Self::Asin => x.asin(),
+            Self::Acos => x.acos(),
+            Self::Atan => x.atan(),
+            Self::Sinh => x.sinh(),
+            Self::Cosh => x.cosh(),
+            Self::Tanh => x.tanh(),
+            Self::Asinh => x.asinh(),
+            Self::Acosh => x.acosh(),
+            Self::Atanh => x.atanh(),
+            Self::Floor => x.floor(),
+            Self::Ceil => x.ceil(),
+            Self::Round => x.round(),
+            Self::Trunc => x.trunc(),
+            Self::Fract => x.fract(),
+            Self::Signum => x.signum(),
+            Self::Atan2 => x.atan2(args[1]),
+            // Unlike f64::max/min, a missing argument makes the result missing
+            Self::Max => fold_missing(args, f64::max),
+            Self::Min => fold_missing(args, f64::min),
+        }
+    }
+}
+
+fn fold_missing(args: &[f64], f: fn(f64, f64) -> f64) -> f64 {
+    args.iter()
+        .skip(1)
+        .fold(args[0], |acc, &v| if v.is_nan() { v } else { f(acc, v) })
+}
+
+/// A parsed formula
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Number(f64),
+    /// Index into the variable list given to [`parse`]
+    Var(usize),
+    /// A `$name` profile constant, replaced by its value when the formula is
+    /// compiled; missing if unresolved
+    Constant(String),
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+    /// `if(cond, then, else)` - missing when the condition is missing
+    If(Box<Node>, Box<Node>, Box<Node>),
+    /// A function over whole columns, computed by
+    /// [`evaluate_records`](super::window::evaluate_records) before
+    /// per-record evaluation
+    Column(ColumnFunction, Vec<Node>),
+    /// `table(name, x)` or `table2d(name, x, y)`, with one argument per input
+    Table(TableLookup, Vec<Node>),
+}
+
+/// The table read by a [`Node::Table`] lookup
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableLookup {
+    /// Table name as written in the formula
+    pub name: String,
+    /// The table data, attached when the formula is compiled; lookups in an
+    /// unresolved table are missing
+    pub table: Option<Arc<LookupTable>>,
+}
+
+impl Node {
+    /// Evaluate for the records in `range`, reading variable slots from
+    /// `columns`. Works a chunk of records at a time so each node is visited
+    /// once per chunk rather than once per record.
+    pub fn eval_columns(&self, columns: &[Cow<'_, [f64]>], range: Range<usize>) -> Vec<f64> {
+        let len = range.len();
+        match self {
+            Node::Number(v) => vec![*v; len],
+            Node::Constant(_) => vec![f64::NAN; len],
+            Node::Var(slot) => columns
+                .get(*slot)
+                .and_then(|column| column.get(range))
+                .map_or_else(|| vec![f64::NAN; len], <[f64]>::to_vec),
+            Node::Unary(op, arg) => {
+                let mut values = arg.eval_columns(columns, range);
+                for v in &mut values {
+                    *v = match op {
+                        UnaryOp::Neg => -*v,
+                        UnaryOp::Not => truth(*v).map_or(f64::NAN, |b| bool_value(!b)),
+                    };
+                }
+                values
+            }
+            Node::Binary(op, lhs, rhs) => {
+                let mut values = lhs.eval_columns(columns, range.clone());
+                let rhs = rhs.eval_columns(columns, range);
+                for (a, &b) in values.iter_mut().zip(&rhs) {
+                    *a = apply_binary(*op, *a, b);
+                }
+                values
+            }
+            Node::Call(function, args) => {
+                let args: Vec<Vec<f64>> = args
+                    .iter()
+                    .map(|arg| arg.eval_columns(columns, range.clone()))
+                    .collect();
+                let mut values = vec![0.0; args.len()];
+                (0..len)
+                    .map(|i| {
+                        for (value, arg) in values.iter_mut().zip(&args) {
+                            *value = arg[i];
+                        }
+                        function.apply(&values)
+                    })
+                    .collect()
+            }
+            Node::If(cond, then, otherwise) => {
+                let cond = cond.eval_columns(columns, range.clone());
+                let then = then.eval_columns(columns, range.clone());
+                let otherwise = otherwise.eval_columns(columns, range);
+                cond.iter()
+                    .zip(then.iter().zip(&otherwise))
+                    .map(|(&c, (&t, &o))| match truth(c) {
+                        Some(true) => t,
+                        Some(false) => o,
+                        None => f64::NAN,
+                    })
+                    .collect()
+            }
+            Node::Table(lookup, args) => {
+                let args: Vec<Vec<f64>> = args
+                    .iter()
+                    .map(|arg| arg.eval_columns(columns, range.clone()))
+                    .collect();
+                match (&lookup.table, args.as_slice()) {
+                    (Some(table), [x]) => x.iter().map(|&x| table.lookup(x)).collect(),
+                    (Some(table), [x, y]) => x
+                        .iter()
+                        .zip(y)
+                        .map(|(&x, &y)| table.lookup2d(x, y))
+                        .collect(),
+                    _ => vec![f64::NAN; len],
+                }
+            }
+            // Lowered to a variable before evaluation
+            Node::Column(..) => vec![f64::NAN; len],
+        }
+    }
+}
+
+/// Truth value of a number (`None` when missing)
+fn truth(v: f64) -> Option<bool> {
+    (!v.is_nan()).then_some(v != 0.0)
+}
+
+fn bool_value(b: bool) -> f64 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn apply_binary(op: BinaryOp, a: f64, b: f64) -> f64 {
+    let compare = |result: bool| {
+        if a.is_nan() || b.is_nan() {
+            f64::NAN
+        } else {
+            bool_value(result)
+        }
+    };
+    match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div => a / b,
+        BinaryOp::Rem => a % b,
+        BinaryOp::Pow => a.powf(b),
+        BinaryOp::Eq => compare(a == b),
+        BinaryOp::Ne => compare(a != b),
+        BinaryOp::Lt => compare(a < b),
+        BinaryOp::Le => compare(a <= b),
+        BinaryOp::Gt => compare(a > b),
+        BinaryOp::Ge => compare(a >= b),
+        // A false (true) left side decides `&&` (`||`) even if the right is missing
+        BinaryOp::And => match truth(a) {
+            Some(true) => truth(b).map_or(f64::NAN, bool_value),
+            Some(false) => 0.0,
+            None => f64::NAN,
+        },
+        BinaryOp::Or => match truth(a) {
+            Some(true) => 1.0,
+            Some(false) => truth(b).map_or(f64::NAN, bool_value),
+            None => f64::NAN,
+        },
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    /// A number with an `s` suffix, used for time windows
+    Seconds(f64),
+    Ident(String),
+    /// Double-quoted text, used for table names
+    Str(String),
+    /// A `$name` profile constant
+    Constant(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &[
+    "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "^", "(", ")", ",", "<", ">", "!",
+];
+
+/// A syntax error and the byte range of the formula it points at
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Range<usize>,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+/// Split a formula into tokens and the byte range of each
+fn tokenize(formula: &str) -> Result<(Vec<Token>, Vec<Range<usize>>), ParseError> {
+    let mut tokens = Vec::new();
+    let mut spans = Vec::new();
+    let mut rest = formula;
+
+    while let Some(c) = rest.chars().next() {
+        let start = formula.len() - rest.len();
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        } else if c.is_ascii_digit()
+            || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let len = number_len(rest);
+            let value = rest[..len].parse::<f64>().map_err(|_| {
+                ParseError::new(
+                    format!("Invalid number '{}'", &rest[..len]),
+                    start..start + len,
+                )
+            })?;
+            rest = &rest[len..];
+            // A trailing `s` (not the start of a name) marks seconds
+            let is_seconds = rest.starts_with('s')
+                && !rest[1..].starts_with(|c: char| c.is_alphanumeric() || c == '_');
+            if is_seconds {
+                tokens.push(Token::Seconds(value));
+                rest = &rest[1..];
+            } else {
+                tokens.push(Token::Number(value));
+            }
+        } else if c == '"' {
+            let len = rest[1..]
+                .find('"')
+                .ok_or_else(|| ParseError::new("Unterminated quoted name", start..formula.len()))?;
+            tokens.push(Token::Str(rest[1..=len].to_string()));
+            rest = &rest[len + 2..];
+        } else if c == '$' {
+            let len = rest[1..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len() - 1);
+            if len == 0 {
+                return Err(ParseError::new(
+                    "Expected a constant name after '$'",
+                    start..start + 1,
+                ));
+            }
+            tokens.push(Token::Constant(rest[1..=len].to_string()));
+            rest = &rest[len + 1..];
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            rest = &rest[len..];
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            return Err(ParseError::new(
+                format!("Unexpected character '{}'", c),
+                start..start + c.len_utf8(),
+            ));
+        }
+        spans.push(start..formula.len() - rest.len());
+    }
+
+    Ok((tokens, spans))
+}
+
+/// Length of the number literal at the start of `s` (digits, fraction, exponent)
+fn number_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let digits = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+
+    let mut end = digits(0);
+    if end < bytes.len() && bytes[end] == b'.' {
+        end = digits(end + 1);
+    }
+    if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+        let mut exp = end + 1;
+        if exp < bytes.len() && (bytes[exp] == b'+' || bytes[exp] == b'-') {
+            exp += 1;
+        }
+        if exp < bytes.len() && bytes[exp].is_ascii_digit() {
+            end = digits(exp);
+        }
+    }
+    end
+}
+
+/// Parse a formula whose identifiers are either built-in functions and
+/// constants or names from `vars`
+pub fn parse(formula: &str, vars: &[String]) -> Result<Node, String> {
+    parse_spanned(formula, vars).map_err(|e| e.message)
+}
+
+/// Like [`parse`], with errors pointing at the part of the formula at fault
+pub fn parse_spanned(formula: &str, vars: &[String]) -> Result<Node, ParseError> {
+    let (tokens, spans) = tokenize(formula)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        spans: &spans,
+        end: formula.len(),
+        pos: 0,
+        depth: 0,
+        vars,
+    };
+
+    let node = parser.expression()?;
+    match parser.peek() {
+        None => Ok(node),
+        Some(token) => Err(parser.error_at(parser.pos, format!("Unexpected {}", describe(token)))),
+    }
+}
+
+/// Deepest tree of operators, parentheses and calls a formula may parse to
+const MAX_DEPTH: usize = 128;
+
+/// Recursive-descent parser, one method per precedence level
+struct Parser<'a> {
+    tokens: &'a [Token],
+    /// Byte range of each token
+    spans: &'a [Range<usize>],
+    /// Length of the formula, where errors at its end point
+    end: usize,
+    pos: usize,
+    /// Depth of the tree at the current position
+    depth: usize,
+    vars: &'a [String],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<&Token, ParseError> {
+        if self.pos >= self.tokens.len() {
+            return Err(self.error_at(self.pos, "Unexpected end of formula"));
+        }
+        self.pos += 1;
+        Ok(&self.tokens[self.pos - 1])
+    }
+
+    /// An error pointing at token `index`, or the end of the formula
+    fn error_at(&self, index: usize, message: impl Into<String>) -> ParseError {
+        let span = self.spans.get(index).cloned().unwrap_or(self.end..self.end);
+        ParseError::new(message, span)
+    }
+
+    /// An error spanning token `first` to the last consumed token
+    fn error_from(&self, first: usize, message: impl Into<String>) -> ParseError {
+        let start = self.spans.get(first).map_or(self.end, |s| s.start);
+        let end = self
+            .pos
+            .checked_sub(1)
+            .and_then(|last| self.spans.get(last))
+            .map_or(self.end, |s| s.end);
+        ParseError::new(message, start..end.max(start))
+    }
+
+    /// Consume the symbol if it is next
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), ParseError> {
+        if self.eat(symbol) {
+            return Ok(());
+        }
+        let message = match self.peek() {
+            Some(token) => format!("Expected '{}' but found {}", symbol, describe(token)),
+            None => format!("Expected '{}' at end of formula", symbol),
+        };
+        Err(self.error_at(self.pos, message))
+    }
+
+    /// Parse a left-associative chain of `ops` over `operand`
+    fn binary_level(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        operand: fn(&mut Self) -> Result<Node, ParseError>,
+    ) -> Result<Node, ParseError> {
+        let depth = self.depth;
+        let mut node = operand(self)?;
+        'chain: loop {
+            for (symbol, op) in ops {
+                if self.eat(symbol) {
+                    // Each operator of the chain nests the tree one level deeper
+                    self.enter(self.pos - 1)?;
+                    let rhs = operand(self)?;
+                    node = Node::Binary(*op, Box::new(node), Box::new(rhs));
+                    continue 'chain;
+                }
+            }
+            self.depth = depth;
+            return Ok(node);
+        }
+    }
+
+    /// Go one level deeper into the tree, failing at token `index` past
+    /// [`MAX_DEPTH`]; deep trees would overflow the stack here and when
+    /// they are analyzed and evaluated
+    fn enter(&mut self, index: usize) -> Result<(), ParseError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error_at(index, "Formula is nested too deeply"));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn expression(&mut self) -> Result<Node, ParseError> {
+        self.binary_level(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Node, ParseError> {
+        self.binary_level(&[("&&", BinaryOp::And)], Self::equality)
+    }
+
+    fn equality(&mut self) -> Result<Node, ParseError> {
+        self.binary_level(
+            &[("==", BinaryOp::Eq), ("!=", BinaryOp::Ne)],
+            Self::comparison,
+        )
+    }
+
+    fn comparison(&mut self) -> Result<Node, ParseError> {
+        self.binary_level(
+            &[
+                ("<=", BinaryOp::Le),
+                (">=", BinaryOp::Ge),
+                ("<", BinaryOp::Lt),
+                (">", BinaryOp::Gt),
+            ],
+            Self::additive,
+        )
+    }
+
+    fn additive(&mut self) -> Result<Node, ParseError> {
+        self.binary_level(
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+            Self::multiplicative,
+        )
+    }
+
+    fn multiplicative(&mut self) -> Result<Node, ParseError> {
+        self.binary_level(
+            &[
+                ("*", BinaryOp::Mul),
+                ("/", BinaryOp::Div),
+                ("%", BinaryOp::Rem),
+            ],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Node, ParseError> {
+        self.enter(self.pos)?;
+        let node = self.unary_operand()?;
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    fn unary_operand(&mut self) -> Result<Node, ParseError> {
+        if self.eat("-") {
+            Ok(Node::Unary(UnaryOp::Neg, Box::new(self.unary()?)))
+        } else if self.eat("+") {
+            self.unary()
+        } else if self.eat("!") {
+            Ok(Node::Unary(UnaryOp::Not, Box::new(self.unary()?)))
+        } else {
+            self.power()
+        }
+    }
+
+    /// `^` binds tighter than unary minus and is right-associative
+    fn power(&mut self) -> Result<Node, ParseError> {
+        let base = self.primary()?;
+        if self.eat("^") {
+            let exponent = self.unary()?;
+            return Ok(Node::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Node, ParseError> {
+        let start = self.pos;
+        match self.next()?.clone() {
+            Token::Number(v) => Ok(Node::Number(v)),
+            Token::Constant(name) => Ok(Node::Constant(name)),
+            Token::Symbol("(") => {
+                let node = self.expression()?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            Token::Ident(name) if self.eat("(") => self.call(&name, start),
+            Token::Ident(name) => self.name(&name),
+            token => Err(self.error_at(start, format!("Unexpected {}", describe(&token)))),
+        }
+    }
+
+    /// Parse the arguments of a call whose opening parenthesis is consumed;
+    /// `start` is the index of the function name token
+    fn call(&mut self, name: &str, start: usize) -> Result<Node, ParseError> {
+        if let Some(node) = self.column_call(name, start)? {
+            return Ok(node);
+        }
+        if let Some(node) = self.table_call(name, start)? {
+            return Ok(node);
+        }
+
+        let mut args = Vec::new();
+        if !self.eat(")") {
+            loop {
+                args.push(self.expression()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+
+        if name.eq_ignore_ascii_case("if") {
+            if args.len() != 3 {
+                return Err(self.error_from(
+                    start,
+                    format!(
+                        "'if' expects 3 arguments (condition, then, else), got {}",
+                        args.len()
+                    ),
+                ));
+            }
+            let mut args = args.into_iter().map(Box::new);
+            let (cond, then, otherwise) = (
+                args.next().unwrap(),
+                args.next().unwrap(),
+                args.next().unwrap(),
+            );
+            return Ok(Node::If(cond, then, otherwise));
+        }
+
+        let function = Function::from_name(name)
+            .ok_or_else(|| self.error_at(start, format!("Unknown function '{}'", name)))?;
+        let (min, max) = function.arity();
+        if args.len() < min || max.is_some_and(|max| args.len() > max) {
+            let expected = match max {
+                Some(max) if max == min => format!("{}", min),
+                Some(max) => format!("{} to {}", min, max),
+                None => format!("at least {}", min),
+            };
+            return Err(self.error_from(
+                start,
+                format!(
+                    "'{}' expects {} argument(s), got {}",
+                    name,
+                    expected,
+                    args.len()
+                ),
+            ));
+        }
+        Ok(Node::Call(function, args))
+    }
+
+    /// Parse a windowed or stateful function call, if `name` is one
+    fn column_call(&mut self, name: &str, start: usize) -> Result<Option<Node>, ParseError> {
+        let lower = name.to_lowercase();
+        let (usage, needs_window) = match lower.as_str() {
+            "avg" | "rms" | "max_over" | "min_over" => ("(channel, window)", true),
+            "deriv" | "integ" => ("(channel)", false),
+            "lowpass" => ("(channel, cutoff_hz)", false),
+            "hold" => ("(channel, condition)", false),
+            _ => return Ok(None),
+        };
+        let name_span = self.spans[start].clone();
+        let usage_error =
+            || ParseError::new(format!("'{}' expects {}", name, usage), name_span.clone());
+
+        let mut args = vec![self.expression()?];
+        let function = if needs_window {
+            self.expect(",").map_err(|_| usage_error())?;
+            let window = match self.next().map_err(|_| usage_error())? {
+                Token::Seconds(seconds) if *seconds > 0.0 => Window::Seconds(*seconds),
+                Token::Number(n) if *n >= 1.0 && n.fract() == 0.0 => Window::Samples(*n as usize),
+                _ => {
+                    return Err(self.error_at(
+                        self.pos - 1,
+                        format!(
+                            "'{}' window must be a duration like 0.5s or a sample count",
+                            name
+                        ),
+                    ))
+                }
+            };
+            match lower.as_str() {
+                "avg" => ColumnFunction::Avg(window),
+                "rms" => ColumnFunction::Rms(window),
+                "max_over" => ColumnFunction::MaxOver(window),
+                _ => ColumnFunction::MinOver(window),
+            }
+        } else {
+            match lower.as_str() {
+                "deriv" => ColumnFunction::Deriv,
+                "integ" => ColumnFunction::Integ,
+                "lowpass" => {
+                    self.expect(",").map_err(|_| usage_error())?;
+                    match self.next().map_err(|_| usage_error())? {
+                        Token::Number(hz) if *hz > 0.0 => ColumnFunction::Lowpass(*hz),
+                        _ => {
+                            return Err(self.error_at(
+                                self.pos - 1,
+                                format!("'{}' cutoff must be a positive number in Hz", name),
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    self.expect(",").map_err(|_| usage_error())?;
+                    args.push(self.expression()?);
+                    ColumnFunction::Hold
+                }
+            }
+        };
+
+        self.expect(")").map_err(|_| usage_error())?;
+        Ok(Some(Node::Column(function, args)))
+    }
+
+    /// Parse a `table(name, x)` or `table2d(name, x, y)` lookup, if `name` is one
+    fn table_call(&mut self, name: &str, start: usize) -> Result<Option<Node>, ParseError> {
+        let (usage, inputs) = match name.to_lowercase().as_str() {
+            "table" => ("(\"table\", x)", 1),
+            "table2d" => ("(\"table\", x, y)", 2),
+            _ => return Ok(None),
+        };
+        let name_span = self.spans[start].clone();
+        let usage_error =
+            || ParseError::new(format!("'{}' expects {}", name, usage), name_span.clone());
+
+        let table = match self.next().map_err(|_| usage_error())? {
+            Token::Str(table) | Token::Ident(table) => table.clone(),
+            _ => return Err(usage_error()),
+        };
+        let mut args = Vec::with_capacity(inputs);
+        for _ in 0..inputs {
+            self.expect(",").map_err(|_| usage_error())?;
+            args.push(self.expression()?);
+        }
+        self.expect(")").map_err(|_| usage_error())?;
+
+        Ok(Some(Node::Table(
+            TableLookup {
+                name: table,
+                table: None,
+            },
+            args,
+        )))
+    }
+
+    /// Resolve a variable or built-in constant
+    fn name(&self, name: &str) -> Result<Node, ParseError> {
+        if let Some(slot) = self.vars.iter().position(|v| v == name) {
+            return Ok(Node::Var(slot));
+        }
+        let value = match name.to_lowercase().as_str() {
+            "pi" => std::f64::consts::PI,
+            "e" => std::f64::consts::E,
+            "tau" => std::f64::consts::TAU,
+            "phi" => 1.618_033_988_749_895,
+            _ => {
+                return Err(self.error_at(self.pos - 1, format!("Unknown name '{}'", name)));
+            }
+        };
+        Ok(Node::Number(value))
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(v) => format!("number {}", v),
+        Token::Seconds(v) => format!("duration {}s", v),
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Str(text) => format!("\"{}\"", text),
+        Token::Constant(name) => format!("'${}'", name),
+        Token::Symbol(symbol) => format!("'{}'", symbol),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(formula: &str) -> f64 {
+        parse(formula, &[]).unwrap().eval_columns(&[], 0..1)[0]
+    }
+
+    /// Evaluate a single record with the given variable values
+    fn eval_vars(node: &Node, vars: &[f64]) -> f64 {
+        let columns: Vec<Cow<[f64]>> = vars.iter().map(|&v| Cow::Owned(vec![v])).collect();
+        node.eval_columns(&columns, 0..1)[0]
+    }
+
+    #[test]
+    fn test_arithmetic_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("(1 + 2) * 3"), 9.0);
+        assert_eq!(eval("-2 ^ 2"), -4.0);
+        assert_eq!(eval("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(eval("2 ^ -1"), 0.5);
+        assert_eq!(eval("7 % 4"), 3.0);
+        assert_eq!(eval("1.5e3 + .5"), 1500.5);
+    }
+
+    #[test]
+    fn test_comparisons_and_logic() {
+        assert_eq!(eval("3 > 2"), 1.0);
+        assert_eq!(eval("3 < 2"), 0.0);
+        assert_eq!(eval("2 <= 2 && 2 >= 2"), 1.0);
+        assert_eq!(eval("1 == 2 || 1 != 2"), 1.0);
+        assert_eq!(eval("!0"), 1.0);
+        assert_eq!(eval("!(1 + 1 > 1)"), 0.0);
+        // Comparisons bind looser than arithmetic
+        assert_eq!(eval("1 + 1 == 2"), 1.0);
+    }
+
+    #[test]
+    fn test_if() {
+        assert_eq!(eval("if(1 > 0, 10, 20)"), 10.0);
+        assert_eq!(eval("if(0, 10, 20)"), 20.0);
+        assert_eq!(eval("2 * if(1, 3, 4) + 1"), 7.0);
+    }
+
+    #[test]
+    fn test_missing_values_propagate() {
+        let node = parse("x > 5", &["x".to_string()]).unwrap();
+        assert!(eval_vars(&node, &[f64::NAN]).is_nan());
+
+        // A missing value in the unselected branch is ignored
+        let node = parse("if(x > 5, y, 0)", &["x".to_string(), "y".to_string()]).unwrap();
+        assert_eq!(eval_vars(&node, &[1.0, f64::NAN]), 0.0);
+
+        // A false left side short-circuits a missing right side
+        let node = parse("x > 5 && y > 5", &["x".to_string(), "y".to_string()]).unwrap();
+        assert_eq!(eval_vars(&node, &[1.0, f64::NAN]), 0.0);
+        assert!(eval_vars(&node, &[6.0, f64::NAN]).is_nan());
+
+        assert!(eval("max(1, 0 / 0)").is_nan());
+    }
+
+    #[test]
+    fn test_functions_and_constants() {
+        assert_eq!(eval("max(1, 5, 3)"), 5.0);
+        assert_eq!(eval("MIN(4, 2)"), 2.0);
+        assert_eq!(eval("sqrt(16) + abs(-1)"), 5.0);
+        assert_eq!(eval("pi"), std::f64::consts::PI);
+        assert_eq!(eval("atan2(0, 1)"), 0.0);
+    }
+
+    #[test]
+    fn test_variables() {
+        let node = parse("RPM / 1000", &["RPM".to_string()]).unwrap();
+        assert_eq!(
+            node,
+            Node::Binary(
+                BinaryOp::Div,
+                Box::new(Node::Var(0)),
+                Box::new(Node::Number(1000.0)),
+            )
+        );
+        assert_eq!(eval_vars(&node, &[3000.0]), 3.0);
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = format!(
+            "{}1{}",
+            "(".repeat(MAX_DEPTH - 1),
+            ")".repeat(MAX_DEPTH - 1)
+        );
+        assert!(parse(&nested, &[]).is_ok());
+
+        let formula = format!("{}1{}", "(".repeat(50_000), ")".repeat(50_000));
+        let error = parse_spanned(&formula, &[]).unwrap_err();
+        assert!(error.message.contains("nested too deeply"));
+        assert_eq!(error.span, MAX_DEPTH..MAX_DEPTH + 1);
+
+        let formula = format!("{}1", "-".repeat(50_000));
+        assert!(parse(&formula, &[]).is_err());
+        let formula = format!("2{}", "^2".repeat(50_000));
+        assert!(parse(&formula, &[]).is_err());
+        let formula = format!("1{}", "+1".repeat(50_000));
+        assert!(parse(&formula, &[]).is_err());
+    }
+
+    #[test]
+    fn test_column_functions() {
+        let vars = ["x".to_string(), "y".to_string()];
+        assert_eq!(
+            parse("avg(x, 0.5s)", &vars).unwrap(),
+            Node::Column(
+                ColumnFunction::Avg(Window::Seconds(0.5)),
+                vec![Node::Var(0)]
+            )
+        );
+        assert_eq!(
+            parse("max_over(x * 2, 10)", &vars).unwrap(),
+            Node::Column(
+                ColumnFunction::MaxOver(Window::Samples(10)),
+                vec![Node::Binary(
+                    BinaryOp::Mul,
+                    Box::new(Node::Var(0)),
+                    Box::new(Node::Number(2.0))
+                )]
+            )
+        );
+        assert!(parse("deriv(lowpass(x, 5)) + hold(x, y > 1)", &vars).is_ok());
+
+        assert!(parse("avg(x)", &vars).unwrap_err().contains("expects"));
+        assert!(parse("avg(x, y)", &vars).unwrap_err().contains("window"));
+        assert!(parse("avg(x, 0.5)", &vars).is_err());
+        assert!(parse("lowpass(x, 0)", &vars)
+            .unwrap_err()
+            .contains("cutoff"));
+        assert!(parse("x + 0.5s", &vars).is_err());
+    }
+
+    #[test]
+    fn test_table_lookups() {
+        let vars = ["x".to_string(), "y".to_string()];
+        let node = parse("table(\"Deadtime\", x) + 1", &vars).unwrap();
+        let Node::Binary(_, lookup, _) = &node else {
+            panic!("expected a sum, got {:?}", node);
+        };
+        assert_eq!(
+            **lookup,
+            Node::Table(
+                TableLookup {
+                    name: "Deadtime".to_string(),
+                    table: None
+                },
+                vec![Node::Var(0)]
+            )
+        );
+        // Unresolved tables give missing values
+        assert!(eval_vars(&node, &[12.0, 0.0]).is_nan());
+
+        let mut node = parse("table2d(VE, x, y * 2)", &vars).unwrap();
+        if let Node::Table(lookup, args) = &mut node {
+            assert_eq!(lookup.name, "VE");
+            assert_eq!(args.len(), 2);
+            lookup.table = Some(Arc::new(LookupTable::new_2d(
+                "VE".to_string(),
+                vec![0.0, 10.0],
+                vec![0.0, 10.0],
+                vec![vec![0.0, 10.0], vec![100.0, 110.0]],
+            )));
+        }
+        assert_eq!(eval_vars(&node, &[5.0, 2.5]), 55.0);
+
+        assert!(parse("table(x)", &vars).unwrap_err().contains("expects"));
+        assert!(parse("table2d(\"VE\", x)", &vars)
+            .unwrap_err()
+            .contains("expects"));
+        assert!(parse("table(\"VE, x)", &vars).is_err());
+    }
+
+    #[test]
+    fn test_constants() {
+        let vars = ["x".to_string()];
+        assert_eq!(
+            parse("x * $injector_cc", &vars).unwrap(),
+            Node::Binary(
+                BinaryOp::Mul,
+                Box::new(Node::Var(0)),
+                Box::new(Node::Constant("injector_cc".to_string()))
+            )
+        );
+        assert!(eval("$mass + 1").is_nan());
+        assert!(parse("x * $", &vars).unwrap_err().contains("constant name"));
+        assert!(parse("$mass(1)", &vars).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse("1 +", &[]).is_err());
+        assert!(parse("1 + + +", &[]).is_err());
+        assert!(parse("(1 + 2", &[]).is_err());
+        assert!(parse("1 2", &[]).is_err());
+        assert!(parse("foo", &[]).unwrap_err().contains("Unknown name"));
+        assert!(parse("foo(1)", &[])
+            .unwrap_err()
+            .contains("Unknown function"));
+        assert!(parse("if(1, 2)", &[]).unwrap_err().contains("expects 3"));
+        assert!(parse("atan2(1)", &[]).unwrap_err().contains("expects 2"));
+        assert!(parse("1 = 2", &[]).is_err());
+        assert!(parse("1 & 2", &[]).is_err());
+    }
+}
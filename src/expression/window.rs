@@ -0,0 +1,487 @@
+//! Windowed and stateful formula functions
+//!
+//! Functions such as `avg(ch, 0.5s)` or `integ(ch)` depend on more than one
+//! record, so they are computed over whole columns before the rest of the
+//! formula is evaluated record by record. Windows are trailing: a record sees
+//! its own value and the ones before it.
+
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::parser::Node;
+
+/// Length of a trailing window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    /// Window in seconds, written with an `s` suffix (`0.5s`)
+    Seconds(f64),
+    /// Number of records, written as a plain number (`10`)
+    Samples(usize),
+}
+
+/// A function computed over a whole column
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnFunction {
+    /// `avg(x, window)` - mean over a trailing window
+    Avg(Window),
+    /// `rms(x, window)` - root mean square over a trailing window
+    Rms(Window),
+    /// `max_over(x, window)` - maximum over a trailing window
+    MaxOver(Window),
+    /// `min_over(x, window)` - minimum over a trailing window
+    MinOver(Window),
+    /// `deriv(x)` - rate of change per second
+    Deriv,
+    /// `integ(x)` - running integral over time (trapezoidal)
+    Integ,
+    /// `lowpass(x, hz)` - first-order low-pass filter with the given cutoff
+    Lowpass(f64),
+    /// `hold(x, cond)` - `x` while `cond` is true, otherwise the last value
+    /// taken while it was
+    Hold,
+}
+
+impl ColumnFunction {
+    /// Apply to argument columns sampled at `times`
+    pub fn apply(self, args: &[Vec<f64>], times: &[f64]) -> Vec<f64> {
+        let x = &args[0];
+        match self {
+            Self::Avg(window) => windowed_mean(x, times, window, |v| v),
+            Self::Rms(window) => windowed_mean(x, times, window, |v| v * v)
+                .into_iter()
+                .map(f64::sqrt)
+                .collect(),
+            Self::MaxOver(window) => windowed_extreme(x, times, window, |a, b| a >= b),
+            Self::MinOver(window) => windowed_extreme(x, times, window, |a, b| a <= b),
+            Self::Deriv => derivative(x, times),
+            Self::Integ => integral(x, times),
+            Self::Lowpass(cutoff_hz) => lowpass(x, times, cutoff_hz),
+            Self::Hold => hold(x, &args[1]),
+        }
+    }
+}
+
+/// Index of the first record inside the trailing window ending at `end`,
+/// given the previous start (windows only move forward)
+fn window_start(times: &[f64], window: Window, end: usize, start: usize) -> usize {
+    match window {
+        Window::Samples(n) => (end + 1).saturating_sub(n.max(1)),
+        Window::Seconds(seconds) => {
+            let mut start = start;
+            while start < end && times[start] < times[end] - seconds - 1e-9 {
+                start += 1;
+            }
+            start
+        }
+    }
+}
+
+/// Mean of `f(x)` over each trailing window, ignoring missing samples
+fn windowed_mean(x: &[f64], times: &[f64], window: Window, f: fn(f64) -> f64) -> Vec<f64> {
+    let mut sum = 0.0;
+    let mut count = 0usize;
+    let mut start = 0;
+    let mut out = Vec::with_capacity(x.len());
+
+    for end in 0..x.len() {
+        if !x[end].is_nan() {
+            sum += f(x[end]);
+            count += 1;
+        }
+        let new_start = window_start(times, window, end, start);
+        for &v in &x[start..new_start] {
+            if !v.is_nan() {
+                sum -= f(v);
+                count -= 1;
+            }
+        }
+        start = new_start;
+
+        // Recompute occasionally to keep the running sum from drifting
+        if end % 4096 == 4095 {
+            sum = x[start..=end]
+                .iter()
+                .filter(|v| !v.is_nan())
+                .map(|&v| f(v))
+                .sum();
+        }
+        out.push(if count > 0 {
+            sum / count as f64
+        } else {
+            f64::NAN
+        });
+    }
+    out
+}
+
+/// Extreme value over each trailing window using a monotonic queue;
+/// `keeps(a, b)` is true when `a` should win over `b`
+fn windowed_extreme(
+    x: &[f64],
+    times: &[f64],
+    window: Window,
+    keeps: fn(f64, f64) -> bool,
+) -> Vec<f64> {
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    let mut start = 0;
+    let mut out = Vec::with_capacity(x.len());
+
+    for end in 0..x.len() {
+        if !x[end].is_nan() {
+            while queue.back().is_some_and(|&i| keeps(x[end], x[i])) {
+                queue.pop_back();
+            }
+            queue.push_back(end);
+        }
+        start = window_start(times, window, end, start);
+        while queue.front().is_some_and(|&i| i < start) {
+            queue.pop_front();
+        }
+        out.push(queue.front().map_or(f64::NAN, |&i| x[i]));
+    }
+    out
+}
+
+/// Backward difference per second (forward difference for the first record)
+fn derivative(x: &[f64], times: &[f64]) -> Vec<f64> {
+    let slope = |a: usize, b: usize| {
+        let dt = times[b] - times[a];
+        if dt > 0.0 {
+            (x[b] - x[a]) / dt
+        } else {
+            f64::NAN
+        }
+    };
+    (0..x.len())
+        .map(|i| match i {
+            0 if x.len() > 1 => slope(0, 1),
+            0 => f64::NAN,
+            _ => slope(i - 1, i),
+        })
+        .collect()
+}
+
+/// Running trapezoidal integral, skipping intervals with a missing end
+fn integral(x: &[f64], times: &[f64]) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut out = Vec::with_capacity(x.len());
+    for i in 0..x.len() {
+        if i > 0 && !x[i].is_nan() && !x[i - 1].is_nan() {
+            total += (x[i] + x[i - 1]) * 0.5 * (times[i] - times[i - 1]);
+        }
+        out.push(total);
+    }
+    out
+}
+
+/// First-order IIR low-pass filter; missing samples pass through as missing
+fn lowpass(x: &[f64], times: &[f64], cutoff_hz: f64) -> Vec<f64> {
+    let rc = 1.0 / (2.0 * std::f64::consts::PI * cutoff_hz);
+    let mut state: Option<(f64, f64)> = None; // (time, filtered value)
+    x.iter()
+        .zip(times)
+        .map(|(&v, &t)| {
+            if v.is_nan() {
+                return f64::NAN;
+            }
+            let filtered = match state {
+                Some((last_t, last)) => {
+                    let dt = (t - last_t).max(0.0);
+                    last + (v - last) * dt / (rc + dt)
+                }
+                None => v,
+            };
+            state = Some((t, filtered));
+            filtered
+        })
+        .collect()
+}
+
+/// Track `x` while `cond` is true and hold the last tracked value otherwise
+fn hold(x: &[f64], cond: &[f64]) -> Vec<f64> {
+    let mut held = f64::NAN;
+    x.iter()
+        .zip(cond)
+        .map(|(&v, &c)| {
+            if !c.is_nan() && c != 0.0 {
+                held = v;
+            }
+            held
+        })
+        .collect()
+}
+
+/// Value in effect at each of `log_times`: the last sample at or before it,
+/// or missing before the first sample
+pub fn hold_samples(values: &[f64], sample_times: &[f64], log_times: &[f64]) -> Vec<f64> {
+    let mut next = 0;
+    log_times
+        .iter()
+        .map(|&time| {
+            while next < sample_times.len() && sample_times[next] <= time {
+                next += 1;
+            }
+            next.checked_sub(1)
+                .and_then(|i| values.get(i).copied())
+                .unwrap_or(f64::NAN)
+        })
+        .collect()
+}
+
+/// Native samples behind a variable slot of a channel recorded at its own rate
+pub struct SampledColumn<'a> {
+    pub values: Cow<'a, [f64]>,
+    pub times: &'a [f64],
+}
+
+/// Records evaluated per parallel work item
+const CHUNK_SIZE: usize = 16_384;
+
+/// Evaluate `expr` for `num_records` records whose variable slots are read
+/// from `columns`. Column functions are computed first over whole columns,
+/// on the native samples in `samples` when all their inputs share one
+/// timebase; records are then evaluated in parallel chunks. `progress`
+/// receives the completed fraction (0.0 to 1.0) and may be called from
+/// worker threads.
+pub fn evaluate_columns<'a>(
+    expr: &Node,
+    columns: Vec<Cow<'a, [f64]>>,
+    samples: Vec<Option<SampledColumn<'a>>>,
+    times: &'a [f64],
+    num_records: usize,
+    progress: &(dyn Fn(f32) + Sync),
+) -> Vec<f64> {
+    let chunks = num_records.div_ceil(CHUNK_SIZE).max(1);
+    let mut evaluator = ColumnEvaluator {
+        times,
+        num_records,
+        columns,
+        samples,
+        total_chunks: chunks * (1 + column_arg_count(expr)),
+        chunks_done: AtomicUsize::new(0),
+        progress,
+    };
+    let expr = evaluator.lower(expr);
+    evaluator.eval_records(&expr)
+}
+
+/// Number of column function arguments in the tree, each needing a pass
+fn column_arg_count(node: &Node) -> usize {
+    match node {
+        Node::Number(_) | Node::Var(_) | Node::Constant(_) => 0,
+        Node::Unary(_, arg) => column_arg_count(arg),
+        Node::Binary(_, lhs, rhs) => column_arg_count(lhs) + column_arg_count(rhs),
+        Node::Call(_, args) => args.iter().map(column_arg_count).sum(),
+        Node::If(cond, then, otherwise) => {
+            column_arg_count(cond) + column_arg_count(then) + column_arg_count(otherwise)
+        }
+        Node::Column(_, args) => args.len() + args.iter().map(column_arg_count).sum::<usize>(),
+        Node::Table(_, args) => args.iter().map(column_arg_count).sum(),
+    }
+}
+
+struct ColumnEvaluator<'a> {
+    times: &'a [f64],
+    num_records: usize,
+    /// Variable slots: the formula's inputs followed by precomputed column
+    /// function results
+    columns: Vec<Cow<'a, [f64]>>,
+    /// Native samples of each variable slot on its own timebase
+    samples: Vec<Option<SampledColumn<'a>>>,
+    total_chunks: usize,
+    chunks_done: AtomicUsize,
+    progress: &'a (dyn Fn(f32) + Sync),
+}
+
+impl<'a> ColumnEvaluator<'a> {
+    /// Replace column function calls (innermost first) with variables holding
+    /// their precomputed results
+    fn lower(&mut self, node: &Node) -> Node {
+        let boxed = |this: &mut Self, node: &Node| Box::new(this.lower(node));
+        match node {
+            Node::Column(function, args) => {
+                let args: Vec<Node> = args.iter().map(|arg| self.lower(arg)).collect();
+                match self.shared_sample_times(&args) {
+                    // Computed on the samples, then held at each record
+                    Some(sample_times) => {
+                        let slots: Vec<Cow<[f64]>> = self
+                            .samples
+                            .iter()
+                            .map(|s| match s {
+                                Some(s) if s.times == sample_times => Cow::Borrowed(&*s.values),
+                                _ => Cow::Borrowed(&[][..]),
+                            })
+                            .collect();
+                        let columns: Vec<Vec<f64>> = args
+                            .iter()
+                            .map(|arg| arg.eval_columns(&slots, 0..sample_times.len()))
+                            .collect();
+                        let values = function.apply(&columns, sample_times);
+                        self.columns.push(Cow::Owned(hold_samples(
+                            &values,
+                            sample_times,
+                            self.times,
+                        )));
+                        self.samples.push(Some(SampledColumn {
+                            values: Cow::Owned(values),
+                            times: sample_times,
+                        }));
+                    }
+                    None => {
+                        let columns: Vec<Vec<f64>> =
+                            args.iter().map(|arg| self.eval_records(arg)).collect();
+                        self.columns
+                            .push(Cow::Owned(function.apply(&columns, self.times)));
+                        self.samples.push(None);
+                    }
+                }
+                Node::Var(self.columns.len() - 1)
+            }
+            Node::Number(_) | Node::Var(_) | Node::Constant(_) => node.clone(),
+            Node::Unary(op, arg) => Node::Unary(*op, boxed(self, arg)),
+            Node::Binary(op, lhs, rhs) => Node::Binary(*op, boxed(self, lhs), boxed(self, rhs)),
+            Node::Call(function, args) => {
+                Node::Call(*function, args.iter().map(|arg| self.lower(arg)).collect())
+            }
+            Node::If(cond, then, otherwise) => {
+                Node::If(boxed(self, cond), boxed(self, then), boxed(self, otherwise))
+            }
+            Node::Table(lookup, args) => Node::Table(
+                lookup.clone(),
+                args.iter().map(|arg| self.lower(arg)).collect(),
+            ),
+        }
+    }
+
+    /// Sample times shared by every variable the arguments read, when they
+    /// all come from one channel timebase other than the log's
+    fn shared_sample_times(&self, args: &[Node]) -> Option<&'a [f64]> {
+        let mut slots = Vec::new();
+        for arg in args {
+            collect_vars(arg, &mut slots);
+        }
+        let mut times = None;
+        for slot in slots {
+            let sample_times = self.samples.get(slot)?.as_ref()?.times;
+            if times.is_some_and(|t: &[f64]| t != sample_times) {
+                return None;
+            }
+            times = Some(sample_times);
+        }
+        times
+    }
+
+    fn eval_records(&self, expr: &Node) -> Vec<f64> {
+        let mut out = vec![0.0; self.num_records];
+        out.par_chunks_mut(CHUNK_SIZE)
+            .enumerate()
+            .for_each(|(i, chunk)| {
+                let start = i * CHUNK_SIZE;
+                chunk
+                    .copy_from_slice(&expr.eval_columns(&self.columns, start..start + chunk.len()));
+
+                let done = self.chunks_done.fetch_add(1, Ordering::Relaxed) + 1;
+                (self.progress)((done as f32 / self.total_chunks as f32).min(1.0));
+            });
+        out
+    }
+}
+
+/// Variable slots read by a (lowered) expression
+fn collect_vars(node: &Node, slots: &mut Vec<usize>) {
+    match node {
+        Node::Var(slot) => slots.push(*slot),
+        Node::Number(_) | Node::Constant(_) => {}
+        Node::Unary(_, arg) => collect_vars(arg, slots),
+        Node::Binary(_, lhs, rhs) => {
+            collect_vars(lhs, slots);
+            collect_vars(rhs, slots);
+        }
+        Node::If(cond, then, otherwise) => {
+            collect_vars(cond, slots);
+            collect_vars(then, slots);
+            collect_vars(otherwise, slots);
+        }
+        Node::Call(_, args) | Node::Column(_, args) | Node::Table(_, args) => {
+            args.iter().for_each(|arg| collect_vars(arg, slots))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(n: usize) -> Vec<f64> {
+        (0..n).map(|i| i as f64 * 0.1).collect()
+    }
+
+    #[test]
+    fn test_avg_seconds_and_samples() {
+        let x = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let t = times(5);
+
+        let avg = ColumnFunction::Avg(Window::Seconds(0.2)).apply(std::slice::from_ref(&x), &t);
+        assert_eq!(avg, vec![1.0, 1.5, 2.0, 3.0, 4.0]);
+
+        let avg = ColumnFunction::Avg(Window::Samples(2)).apply(&[x], &t);
+        assert_eq!(avg, vec![1.0, 1.5, 2.5, 3.5, 4.5]);
+    }
+
+    #[test]
+    fn test_avg_skips_missing() {
+        let x = vec![2.0, f64::NAN, 4.0];
+        let avg = ColumnFunction::Avg(Window::Samples(3)).apply(&[x], &times(3));
+        assert_eq!(avg, vec![2.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_rms() {
+        let x = vec![3.0, -4.0];
+        let rms = ColumnFunction::Rms(Window::Samples(2)).apply(&[x], &times(2));
+        assert_eq!(rms[0], 3.0);
+        assert!((rms[1] - 12.5f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_max_min_over() {
+        let x = vec![1.0, 5.0, 2.0, f64::NAN, 0.0, 3.0];
+        let t = times(6);
+        let max = ColumnFunction::MaxOver(Window::Samples(3)).apply(std::slice::from_ref(&x), &t);
+        assert_eq!(max, vec![1.0, 5.0, 5.0, 5.0, 2.0, 3.0]);
+        let min = ColumnFunction::MinOver(Window::Samples(2)).apply(&[x], &t);
+        assert_eq!(min, vec![1.0, 1.0, 2.0, 2.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_deriv_and_integ() {
+        let x = vec![0.0, 1.0, 3.0];
+        let t = vec![0.0, 0.5, 1.0];
+        assert_eq!(
+            ColumnFunction::Deriv.apply(std::slice::from_ref(&x), &t),
+            vec![2.0, 2.0, 4.0]
+        );
+        assert_eq!(ColumnFunction::Integ.apply(&[x], &t), vec![0.0, 0.25, 1.25]);
+    }
+
+    #[test]
+    fn test_lowpass_converges() {
+        let x = vec![0.0, 10.0, 10.0, 10.0, 10.0];
+        let y = ColumnFunction::Lowpass(1.0).apply(&[x], &times(5));
+        assert_eq!(y[0], 0.0);
+        assert!(y[1] > 0.0 && y[1] < 10.0);
+        assert!(y.windows(2).all(|w| w[1] >= w[0]));
+        assert!(y[4] < 10.0);
+    }
+
+    #[test]
+    fn test_hold() {
+        let x = vec![1.0, 2.0, 3.0, 4.0];
+        let cond = vec![0.0, 1.0, 0.0, 1.0];
+        let held = ColumnFunction::Hold.apply(&[x, cond], &times(4));
+        assert!(held[0].is_nan());
+        assert_eq!(&held[1..], &[2.0, 2.0, 4.0]);
+    }
+}
@@ -0,0 +1,246 @@
+//! GPS position channels
+//!
+//! Finds the latitude / longitude channels of a log by name and projects
+//! coordinates (in degrees) onto a flat local plane in meters, which is
+//! accurate enough over the size of a track or a drive.
+
+use std::ops::Range;
+
+/// Mean Earth radius in meters
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Indices of the latitude and longitude channels among `names`, if the log
+/// has both
+pub fn find_gps_channels(names: &[String]) -> Option<(usize, usize)> {
+    let words = |name: &str| -> Vec<String> {
+        name.to_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let is_gps = |words: &[String]| words.iter().any(|w| w == "gps");
+
+    let latitude = names.iter().position(|name| {
+        let words = words(name);
+        words.iter().any(|w| w == "latitude" || w == "lat")
+    })?;
+    let longitude = names.iter().position(|name| {
+        let words = words(name);
+        // "Long" alone is too common (long term fuel trim, ...)
+        words.iter().any(|w| {
+            w == "longitude" || w == "lon" || w == "lng" || (w == "long" && is_gps(&words))
+        })
+    })?;
+    Some((latitude, longitude))
+}
+
+/// Check whether a latitude / longitude pair is a usable position
+pub fn is_valid_position(lat: f64, lon: f64) -> bool {
+    lat.is_finite()
+        && lon.is_finite()
+        && (-90.0..=90.0).contains(&lat)
+        && (-180.0..=180.0).contains(&lon)
+        // Receivers without a fix often log exactly zero
+        && (lat != 0.0 || lon != 0.0)
+}
+
+/// Equirectangular projection around an origin: x east, y north, in meters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LocalProjection {
+    origin_lat: f64,
+    origin_lon: f64,
+    cos_lat: f64,
+}
+
+impl LocalProjection {
+    pub fn new(origin_lat: f64, origin_lon: f64) -> Self {
+        Self {
+            origin_lat,
+            origin_lon,
+            cos_lat: origin_lat.to_radians().cos(),
+        }
+    }
+
+    /// Position relative to the origin in meters
+    pub fn to_local(&self, lat: f64, lon: f64) -> [f64; 2] {
+        [
+            (lon - self.origin_lon).to_radians() * self.cos_lat * EARTH_RADIUS_M,
+            (lat - self.origin_lat).to_radians() * EARTH_RADIUS_M,
+        ]
+    }
+}
+
+/// Compass heading (degrees clockwise from north) of travel from `a` to `b`
+/// in local coordinates
+pub fn heading_deg(a: [f64; 2], b: [f64; 2]) -> f64 {
+    (b[0] - a[0])
+        .atan2(b[1] - a[1])
+        .to_degrees()
+        .rem_euclid(360.0)
+}
+
+/// Distance between two points in local coordinates
+pub fn distance(a: [f64; 2], b: [f64; 2]) -> f64 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+/// The driven path of a log in local coordinates, one point per record
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackPath {
+    /// Projection around the first position with a fix
+    pub projection: Option<LocalProjection>,
+    /// Position of each record, `None` without a GPS fix
+    pub points: Vec<Option<[f64; 2]>>,
+}
+
+impl TrackPath {
+    /// Project aligned latitude / longitude data (one value per record)
+    pub fn new(lat: &[f64], lon: &[f64]) -> Self {
+        let projection = lat
+            .iter()
+            .zip(lon)
+            .find(|(&la, &lo)| is_valid_position(la, lo))
+            .map(|(&la, &lo)| LocalProjection::new(la, lo));
+        let points = match projection {
+            Some(projection) => lat
+                .iter()
+                .zip(lon)
+                .map(|(&la, &lo)| is_valid_position(la, lo).then(|| projection.to_local(la, lo)))
+                .collect(),
+            None => vec![None; lat.len().min(lon.len())],
+        };
+        Self { projection, points }
+    }
+
+    fn positions(&self, records: Range<usize>) -> impl Iterator<Item = (usize, [f64; 2])> + '_ {
+        let records = records.start.min(self.points.len())..records.end.min(self.points.len());
+        records.filter_map(|i| self.points[i].map(|p| (i, p)))
+    }
+
+    /// Bounding box (min, max) of the positions among `records`
+    pub fn bounds(&self, records: Range<usize>) -> Option<([f64; 2], [f64; 2])> {
+        self.positions(records).fold(None, |bounds, (_, p)| {
+            let (min, max) = bounds.unwrap_or((p, p));
+            Some((
+                [min[0].min(p[0]), min[1].min(p[1])],
+                [max[0].max(p[0]), max[1].max(p[1])],
+            ))
+        })
+    }
+
+    /// Record among `records` whose position is closest to `point`
+    pub fn nearest_record(&self, point: [f64; 2], records: Range<usize>) -> Option<usize> {
+        self.positions(records)
+            .min_by(|a, b| distance(a.1, point).total_cmp(&distance(b.1, point)))
+            .map(|(i, _)| i)
+    }
+
+    /// Time span of the stretch of path inside the box spanned by `a` and
+    /// `b`. Where the path passes through the box several times (once per
+    /// lap), the pass closest in time to `near_time` is used, otherwise the
+    /// longest one.
+    pub fn region_time_span(
+        &self,
+        times: &[f64],
+        a: [f64; 2],
+        b: [f64; 2],
+        records: Range<usize>,
+        near_time: Option<f64>,
+    ) -> Option<(f64, f64)> {
+        let (min, max) = (
+            [a[0].min(b[0]), a[1].min(b[1])],
+            [a[0].max(b[0]), a[1].max(b[1])],
+        );
+        let inside =
+            |p: [f64; 2]| (min[0]..=max[0]).contains(&p[0]) && (min[1]..=max[1]).contains(&p[1]);
+
+        // Consecutive records inside the box; records without a fix don't
+        // interrupt a pass
+        let mut passes: Vec<(f64, f64)> = Vec::new();
+        let mut current: Option<(f64, f64)> = None;
+        for (i, p) in self.positions(records) {
+            let Some(&t) = times.get(i) else {
+                break;
+            };
+            if inside(p) {
+                current = Some(current.map_or((t, t), |(start, _)| (start, t)));
+            } else if let Some(pass) = current.take() {
+                passes.push(pass);
+            }
+        }
+        passes.extend(current);
+        passes.retain(|(start, end)| end > start);
+
+        match near_time {
+            Some(t) => passes.into_iter().min_by(|x, y| {
+                let gap = |(start, end): (f64, f64)| (start - t).max(t - end).max(0.0);
+                gap(*x).total_cmp(&gap(*y))
+            }),
+            None => passes
+                .into_iter()
+                .max_by(|x, y| (x.1 - x.0).total_cmp(&(y.1 - y.0))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_find_gps_channels() {
+        let found = find_gps_channels(&names(&["RPM", "GPS Latitude", "GPS Longitude"]));
+        assert_eq!(found, Some((1, 2)));
+        let found = find_gps_channels(&names(&["GPS_Lat", "Long Fuel Trim", "GPS_Long"]));
+        assert_eq!(found, Some((0, 2)));
+        assert_eq!(
+            find_gps_channels(&names(&["Lateral G", "Long Fuel Trim"])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_projection() {
+        let projection = LocalProjection::new(45.0, 7.0);
+        let [x, y] = projection.to_local(45.001, 7.001);
+        // 0.001 degree is ~111 m north and ~79 m east at 45 degrees
+        assert!((y - 111.19).abs() < 0.1);
+        assert!((x - 78.62).abs() < 0.1);
+        assert!((heading_deg([0.0, 0.0], [1.0, 0.0]) - 90.0).abs() < 1e-9);
+        assert!((heading_deg([0.0, 0.0], [0.0, -1.0]) - 180.0).abs() < 1e-9);
+        assert!(!is_valid_position(0.0, 0.0));
+        assert!(!is_valid_position(f64::NAN, 7.0));
+    }
+
+    #[test]
+    fn test_track_path_regions() {
+        // Two passes east along a straight, with a dropout in the second
+        let lat = [45.0, 45.0, 45.0, 45.001, 45.0, 0.0, 45.0, 45.0];
+        let lon = [7.0, 7.0005, 7.001, 7.001, 7.0, 0.0, 7.0005, 7.001];
+        let times = [0.0, 1.0, 2.0, 3.0, 10.0, 11.0, 12.0, 13.0];
+        let path = TrackPath::new(&lat, &lon);
+        assert_eq!(path.points[5], None);
+        let (min, max) = path.bounds(0..8).unwrap();
+        assert_eq!(min, [0.0, 0.0]);
+        assert!((max[1] - 111.19).abs() < 0.1);
+        assert_eq!(path.nearest_record([40.0, 5.0], 4..8), Some(6));
+
+        // A box over the start of the straight: the longer second pass,
+        // unless the first one is nearer in time
+        let (a, b) = ([-5.0, -5.0], [45.0, 5.0]);
+        assert_eq!(
+            path.region_time_span(&times, a, b, 0..8, None),
+            Some((10.0, 12.0))
+        );
+        assert_eq!(
+            path.region_time_span(&times, a, b, 0..8, Some(0.5)),
+            Some((0.0, 1.0))
+        );
+        assert_eq!(path.region_time_span(&times, a, b, 2..4, None), None);
+    }
+}
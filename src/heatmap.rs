@@ -0,0 +1,234 @@
+//! Table-shaped 2D histograms for the scatter plot heatmap
+//!
+//! Samples are binned into the cells of a table with the given X and Y
+//! breakpoints the way an ECU looks up its tables: each sample belongs to
+//! the cell of its nearest X and Y breakpoint, clamped to the ends of the
+//! axes. Every cell counts its samples and keeps statistics of an optional
+//! Z channel.
+
+use crate::lookup_table::nearest_breakpoint;
+
+/// Samples that fell into one cell and statistics of their Z values
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellStats {
+    /// Number of X/Y samples in the cell
+    pub hits: u32,
+    /// Number of those samples with a Z value
+    pub z_count: u32,
+    z_mean: f64,
+    /// Sum of squared differences from the mean (Welford's algorithm)
+    z_m2: f64,
+    z_min: f64,
+    z_max: f64,
+}
+
+impl Default for CellStats {
+    fn default() -> Self {
+        Self {
+            hits: 0,
+            z_count: 0,
+            z_mean: 0.0,
+            z_m2: 0.0,
+            z_min: f64::INFINITY,
+            z_max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl CellStats {
+    fn add(&mut self, z: Option<f64>) {
+        self.hits += 1;
+        let Some(z) = z.filter(|z| !z.is_nan()) else {
+            return;
+        };
+        self.z_count += 1;
+        let delta = z - self.z_mean;
+        self.z_mean += delta / self.z_count as f64;
+        self.z_m2 += delta * (z - self.z_mean);
+        self.z_min = self.z_min.min(z);
+        self.z_max = self.z_max.max(z);
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.z_count > 0).then_some(self.z_mean)
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        (self.z_count > 0).then_some(self.z_min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (self.z_count > 0).then_some(self.z_max)
+    }
+
+    /// Population standard deviation of Z
+    pub fn std_dev(&self) -> Option<f64> {
+        (self.z_count > 0).then(|| (self.z_m2 / self.z_count as f64).sqrt())
+    }
+}
+
+/// A 2D histogram over table breakpoints
+#[derive(Clone, Debug, PartialEq)]
+pub struct BreakpointHistogram {
+    pub x_axis: Vec<f64>,
+    pub y_axis: Vec<f64>,
+    /// One row per `y_axis` entry, each with one cell per `x_axis` entry
+    pub cells: Vec<Vec<CellStats>>,
+    pub max_hits: u32,
+}
+
+impl BreakpointHistogram {
+    /// Bin the records where both `x` and `y` have a sample, with `z`
+    /// values (one per record) collected per cell
+    pub fn build(x: &[f64], y: &[f64], z: Option<&[f64]>, x_axis: &[f64], y_axis: &[f64]) -> Self {
+        let mut cells = vec![vec![CellStats::default(); x_axis.len()]; y_axis.len()];
+        let mut max_hits = 0;
+
+        if !x_axis.is_empty() && !y_axis.is_empty() {
+            for (i, (&xv, &yv)) in x.iter().zip(y).enumerate() {
+                if xv.is_nan() || yv.is_nan() {
+                    continue;
+                }
+                let cell =
+                    &mut cells[nearest_breakpoint(y_axis, yv)][nearest_breakpoint(x_axis, xv)];
+                cell.add(z.and_then(|z| z.get(i).copied()));
+                max_hits = max_hits.max(cell.hits);
+            }
+        }
+
+        Self {
+            x_axis: x_axis.to_vec(),
+            y_axis: y_axis.to_vec(),
+            cells,
+            max_hits,
+        }
+    }
+
+    /// One CSV row per cell with its breakpoints, hit count and Z statistics
+    pub fn to_csv(&self, x_name: &str, y_name: &str, z_name: Option<&str>) -> String {
+        let quote = |name: &str| format!("\"{}\"", name.replace('"', "\"\""));
+        let mut csv = format!("{},{},Hits", quote(x_name), quote(y_name));
+        if let Some(z_name) = z_name {
+            for stat in ["Mean", "Min", "Max", "StdDev"] {
+                csv.push_str(&format!(",{}", quote(&format!("{} {}", z_name, stat))));
+            }
+        }
+        csv.push('\n');
+
+        let number = |v: Option<f64>| v.map_or_else(String::new, |v| v.to_string());
+        for (y, row) in self.y_axis.iter().zip(&self.cells) {
+            for (x, cell) in self.x_axis.iter().zip(row) {
+                csv.push_str(&format!("{},{},{}", x, y, cell.hits));
+                if z_name.is_some() {
+                    for stat in [cell.mean(), cell.min(), cell.max(), cell.std_dev()] {
+                        csv.push_str(&format!(",{}", number(stat)));
+                    }
+                }
+                csv.push('\n');
+            }
+        }
+        csv
+    }
+}
+
+/// About `count` evenly spaced breakpoints at round values covering the
+/// range of `values` (empty if there are none)
+pub fn auto_breakpoints(values: &[f64], count: usize) -> Vec<f64> {
+    let (min, max) = values
+        .iter()
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        });
+    if min > max {
+        return Vec::new();
+    }
+    if (max - min).abs() < f64::EPSILON {
+        return vec![min];
+    }
+
+    let raw_step = (max - min) / (count.max(2) - 1) as f64;
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    let step = [1.0, 2.0, 2.5, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|&s| s >= raw_step)
+        .unwrap_or(10.0 * magnitude);
+    // Enough decimals to show the step exactly
+    let scale = 10f64.powi((-step.log10().floor()).max(0.0) as i32 + 1);
+    let round = |v: f64| (v * scale).round() / scale;
+
+    let start = (min / step).floor() * step;
+    let mut axis = Vec::new();
+    let mut i = 0;
+    loop {
+        let v = start + i as f64 * step;
+        axis.push(round(v));
+        if v >= max - step * 1e-9 {
+            break;
+        }
+        i += 1;
+    }
+    axis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bins_and_stats() {
+        let x = [1000.0, 1100.0, 2900.0, f64::NAN, 3000.0];
+        let y = [50.0, 55.0, 100.0, 100.0, 140.0];
+        let z = [12.0, 14.0, 11.0, 11.0, f64::NAN];
+        let histogram =
+            BreakpointHistogram::build(&x, &y, Some(&z), &[1000.0, 3000.0], &[50.0, 100.0]);
+
+        let cell = histogram.cells[0][0];
+        assert_eq!(cell.hits, 2);
+        assert_eq!(cell.mean(), Some(13.0));
+        assert_eq!((cell.min(), cell.max()), (Some(12.0), Some(14.0)));
+        assert_eq!(cell.std_dev(), Some(1.0));
+
+        // 140 kPa clamps into the last row; its Z is missing
+        let cell = histogram.cells[1][1];
+        assert_eq!((cell.hits, cell.z_count), (2, 1));
+        assert_eq!(cell.std_dev(), Some(0.0));
+        assert_eq!(histogram.max_hits, 2);
+        assert_eq!(histogram.cells[0][1], CellStats::default());
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let histogram = BreakpointHistogram::build(
+            &[1000.0, 1000.0],
+            &[50.0, 50.0],
+            Some(&[1.0, 3.0]),
+            &[1000.0, 2000.0],
+            &[50.0],
+        );
+        assert_eq!(
+            histogram.to_csv("RPM", "MAP", Some("AFR")),
+            "\"RPM\",\"MAP\",Hits,\"AFR Mean\",\"AFR Min\",\"AFR Max\",\"AFR StdDev\"\n\
+             1000,50,2,2,1,3,1\n\
+             2000,50,0,,,,\n"
+        );
+        assert_eq!(
+            histogram.to_csv("RPM", "MAP", None),
+            "\"RPM\",\"MAP\",Hits\n1000,50,2\n2000,50,0\n"
+        );
+    }
+
+    #[test]
+    fn test_auto_breakpoints() {
+        let rpm = [850.0, 3200.0, 6800.0];
+        assert_eq!(
+            auto_breakpoints(&rpm, 8),
+            [0.0, 1000.0, 2000.0, 3000.0, 4000.0, 5000.0, 6000.0, 7000.0]
+        );
+        let lambda = [0.78, 0.95, 1.12];
+        assert_eq!(auto_breakpoints(&lambda, 4), [0.6, 0.8, 1.0, 1.2]);
+        assert_eq!(auto_breakpoints(&[5.0, 5.0], 8), [5.0]);
+        assert!(auto_breakpoints(&[f64::NAN], 8).is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+//! Lap and sector detection
+//!
+//! Laps are derived from the times the car crosses the start/finish line:
+//! either beacon crossings recorded by the logger (lap markers), or
+//! crossings of a timing line placed on the GPS track. Further timing lines
+//! split each lap into sectors. The part of the log before the first
+//! crossing is the out lap and the part after the last one the in lap.
+
+use crate::gps::{distance, heading_deg, is_valid_position, LocalProjection};
+use crate::parsers::{Marker, MarkerKind};
+
+/// Crossings of the same line closer together than this are GPS noise
+const MIN_CROSSING_INTERVAL: f64 = 5.0;
+
+/// Sample steps longer than this are GPS dropouts, not driving
+const MAX_STEP_M: f64 = 200.0;
+
+/// Distance covered between the samples used to measure the heading
+const HEADING_BASELINE_M: f64 = 5.0;
+
+/// Default width of a timing line in meters
+pub const DEFAULT_LINE_WIDTH_M: f64 = 30.0;
+
+/// A timing line across the track, crossed in the direction of travel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimingLine {
+    /// Latitude of the center of the line
+    pub lat: f64,
+    /// Longitude of the center of the line
+    pub lon: f64,
+    /// Direction of travel across the line, degrees clockwise from north
+    pub heading: f64,
+    /// Width of the line in meters
+    pub width: f64,
+}
+
+impl TimingLine {
+    /// A line across the track at `record`, facing the direction the car
+    /// was travelling. `None` if there is no GPS fix or the car is stopped.
+    pub fn at_record(lat: &[f64], lon: &[f64], record: usize, width: f64) -> Option<Self> {
+        let (&center_lat, &center_lon) = (lat.get(record)?, lon.get(record)?);
+        if !is_valid_position(center_lat, center_lon) {
+            return None;
+        }
+        let projection = LocalProjection::new(center_lat, center_lon);
+        let position = |i: usize| {
+            is_valid_position(lat[i], lon[i]).then(|| projection.to_local(lat[i], lon[i]))
+        };
+
+        // Widen the window around the record until it covers enough ground
+        let len = lat.len().min(lon.len());
+        for step in 1..len {
+            let before = record.saturating_sub(step);
+            let after = (record + step).min(len - 1);
+            if let (Some(a), Some(b)) = (position(before), position(after)) {
+                if distance(a, b) >= HEADING_BASELINE_M {
+                    return Some(Self {
+                        lat: center_lat,
+                        lon: center_lon,
+                        heading: heading_deg(a, b),
+                        width,
+                    });
+                }
+            }
+            if before == 0 && after == len - 1 {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Times at which the path crosses the line in its direction, with the
+    /// time interpolated between samples
+    pub fn crossings(&self, times: &[f64], lat: &[f64], lon: &[f64]) -> Vec<f64> {
+        let projection = LocalProjection::new(self.lat, self.lon);
+        let heading = self.heading.to_radians();
+        // Along the direction of travel, and along the line
+        let forward = [heading.sin(), heading.cos()];
+        let across = [heading.cos(), -heading.sin()];
+        let dot = |a: [f64; 2], b: [f64; 2]| a[0] * b[0] + a[1] * b[1];
+
+        let mut crossings: Vec<f64> = Vec::new();
+        let mut previous: Option<(f64, [f64; 2])> = None;
+        for ((&t, &la), &lo) in times.iter().zip(lat).zip(lon) {
+            if !is_valid_position(la, lo) {
+                continue;
+            }
+            let p = projection.to_local(la, lo);
+            if let Some((t0, p0)) = previous {
+                let (s0, s1) = (dot(p0, forward), dot(p, forward));
+                if s0 < 0.0 && s1 >= 0.0 && distance(p0, p) <= MAX_STEP_M {
+                    let frac = s0 / (s0 - s1);
+                    let hit = [p0[0] + (p[0] - p0[0]) * frac, p0[1] + (p[1] - p0[1]) * frac];
+                    let time = t0 + (t - t0) * frac;
+                    let debounced = crossings
+                        .last()
+                        .is_none_or(|&last| time - last >= MIN_CROSSING_INTERVAL);
+                    if dot(hit, across).abs() <= self.width / 2.0 && debounced {
+                        crossings.push(time);
+                    }
+                }
+            }
+            previous = Some((t, p));
+        }
+        crossings
+    }
+}
+
+/// Which part of the session a lap is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LapKind {
+    /// From the start of the log to the first crossing
+    OutLap,
+    /// Between two start/finish crossings
+    Timed,
+    /// From the last crossing to the end of the log
+    InLap,
+}
+
+/// A lap with its sector times
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lap {
+    /// Lap number, counting timed laps from 1 (0 for out / in laps)
+    pub number: usize,
+    pub kind: LapKind,
+    /// Start time in seconds
+    pub start: f64,
+    /// End time in seconds
+    pub end: f64,
+    /// Sector durations, one more than there are sector lines; empty if a
+    /// sector line was not crossed in order during the lap
+    pub sectors: Vec<f64>,
+}
+
+impl Lap {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+
+    /// Short name for lists: "Lap 3", "Out", "In"
+    pub fn label(&self) -> String {
+        match self.kind {
+            LapKind::OutLap => "Out".to_string(),
+            LapKind::Timed => format!("Lap {}", self.number),
+            LapKind::InLap => "In".to_string(),
+        }
+    }
+}
+
+/// Start/finish crossings recorded by the logger as lap markers
+pub fn beacon_crossings(markers: &[Marker]) -> Vec<f64> {
+    markers
+        .iter()
+        .filter(|m| m.kind == MarkerKind::Lap)
+        .map(|m| m.time)
+        .collect()
+}
+
+/// Split a log spanning `log_range` into laps at the start/finish
+/// `crossings`, with sectors split at the crossings of each sector line
+pub fn laps_from_crossings(
+    crossings: &[f64],
+    log_range: (f64, f64),
+    sector_crossings: &[Vec<f64>],
+) -> Vec<Lap> {
+    let (log_start, log_end) = log_range;
+    let mut crossings: Vec<f64> = crossings
+        .iter()
+        .copied()
+        .filter(|t| (log_start..=log_end).contains(t))
+        .collect();
+    crossings.sort_by(f64::total_cmp);
+
+    let (Some(&first), Some(&last)) = (crossings.first(), crossings.last()) else {
+        return Vec::new();
+    };
+
+    let mut laps = Vec::new();
+    if first - log_start >= MIN_CROSSING_INTERVAL {
+        laps.push(Lap {
+            number: 0,
+            kind: LapKind::OutLap,
+            start: log_start,
+            end: first,
+            sectors: Vec::new(),
+        });
+    }
+    for (i, pair) in crossings.windows(2).enumerate() {
+        let (start, end) = (pair[0], pair[1]);
+        laps.push(Lap {
+            number: i + 1,
+            kind: LapKind::Timed,
+            start,
+            end,
+            sectors: sector_times(start, end, sector_crossings),
+        });
+    }
+    if log_end - last >= MIN_CROSSING_INTERVAL {
+        laps.push(Lap {
+            number: 0,
+            kind: LapKind::InLap,
+            start: last,
+            end: log_end,
+            sectors: Vec::new(),
+        });
+    }
+    laps
+}
+
+/// Sector durations of a lap, if every sector line was crossed in order
+fn sector_times(start: f64, end: f64, sector_crossings: &[Vec<f64>]) -> Vec<f64> {
+    if sector_crossings.is_empty() {
+        return Vec::new();
+    }
+    let mut splits = vec![start];
+    for crossings in sector_crossings {
+        let previous = *splits.last().unwrap_or(&start);
+        match crossings.iter().find(|&&t| t > previous && t < end) {
+            Some(&t) => splits.push(t),
+            None => return Vec::new(),
+        }
+    }
+    splits.push(end);
+    splits.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+/// Index of the fastest timed lap
+pub fn best_lap(laps: &[Lap]) -> Option<usize> {
+    laps.iter()
+        .enumerate()
+        .filter(|(_, lap)| lap.kind == LapKind::Timed)
+        .min_by(|a, b| a.1.duration().total_cmp(&b.1.duration()))
+        .map(|(i, _)| i)
+}
+
+/// Format a lap or sector time as m:ss.sss (or s.sss under a minute)
+pub fn format_lap_time(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).floor();
+    let secs = seconds - minutes * 60.0;
+    if minutes > 0.0 {
+        format!("{}:{:06.3}", minutes as u32, secs)
+    } else {
+        format!("{:.3}", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Laps of a circle of radius ~100 m driven anticlockwise from the
+    /// south, one lap every 20 s, sampled at 10 Hz
+    fn circuit(laps: usize) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let samples = laps * 200;
+        let times: Vec<f64> = (0..samples).map(|i| i as f64 * 0.1).collect();
+        let (mut lat, mut lon) = (Vec::new(), Vec::new());
+        for &t in &times {
+            let angle = -std::f64::consts::FRAC_PI_2 + t / 20.0 * std::f64::consts::TAU;
+            lat.push(45.0 + 0.0009 * angle.sin());
+            lon.push(7.0 + 0.00127 * angle.cos());
+        }
+        (times, lat, lon)
+    }
+
+    #[test]
+    fn test_gps_start_finish_line() {
+        let (times, lat, lon) = circuit(3);
+        // Placed at 2 s into the first lap
+        let line = TimingLine::at_record(&lat, &lon, 20, DEFAULT_LINE_WIDTH_M).unwrap();
+        let crossings = line.crossings(&times, &lat, &lon);
+        assert_eq!(crossings.len(), 3);
+        for (crossing, expected) in crossings.iter().zip([2.0, 22.0, 42.0]) {
+            assert!((crossing - expected).abs() < 0.05);
+        }
+
+        // Travelling the other way never crosses it
+        let reverse = TimingLine {
+            heading: (line.heading + 180.0) % 360.0,
+            ..line
+        };
+        assert!(reverse.crossings(&times, &lat, &lon).is_empty());
+    }
+
+    #[test]
+    fn test_laps_and_sectors() {
+        let crossings = [12.0, 72.5, 131.0];
+        let sectors = vec![vec![30.0, 90.0], vec![50.0, 150.0]];
+        let laps = laps_from_crossings(&crossings, (0.0, 150.0), &sectors);
+
+        let kinds: Vec<LapKind> = laps.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                LapKind::OutLap,
+                LapKind::Timed,
+                LapKind::Timed,
+                LapKind::InLap
+            ]
+        );
+        assert_eq!(laps[1].label(), "Lap 1");
+        assert_eq!(laps[1].duration(), 60.5);
+        assert_eq!(laps[1].sectors, vec![18.0, 20.0, 22.5]);
+        // The second sector line is not crossed during lap 2
+        assert!(laps[2].sectors.is_empty());
+        assert_eq!(best_lap(&laps), Some(2));
+    }
+
+    #[test]
+    fn test_beacon_crossings() {
+        let markers = vec![
+            Marker::new(MarkerKind::Lap, "Lap 1", 10.0),
+            Marker::new(MarkerKind::Manual, "Marker 1", 25.0),
+            Marker::new(MarkerKind::Lap, "Lap 2", 40.0),
+        ];
+        assert_eq!(beacon_crossings(&markers), vec![10.0, 40.0]);
+        assert!(laps_from_crossings(&[], (0.0, 100.0), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_format_lap_time() {
+        assert_eq!(format_lap_time(83.4567), "1:23.457");
+        assert_eq!(format_lap_time(21.5), "21.500");
+    }
+}
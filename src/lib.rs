@@ -6,10 +6,21 @@
 //! ## Module Structure
 //!
 //! - [`app`] - Main application state and eframe::App implementation
+//! - [`builtin_templates`] - Standard computed channel templates shipped with the app
+//! - [`cli`] - Headless command-line interface (`ultralog-cli`)
+//! - [`data_export`] - CSV / JSON export of channel data
+//! - [`gps`] - GPS channel detection and local track coordinates
+//! - [`heatmap`] - Table-shaped 2D histograms over custom breakpoints
+//! - [`laps`] - Lap and sector timing from beacons or GPS timing lines
+//! - [`library_sharing`] - Import, export and merging of computed channel libraries
+//! - [`lookup_table`] - 1D/2D lookup tables used by computed channel formulas
+//! - [`overlay`] - Alignment and delta traces of logs overlaid on one chart
 //! - [`parsers`] - ECU log file parsers (Haltech, etc.)
 //! - [`state`] - Core data types and constants
 //! - [`units`] - Unit preference types and conversion utilities
+//! - [`ve_analyze`] - Fuel table corrections from AFR error per RPM / load cell
 //! - [`normalize`] - Field name normalization for standardizing channel names
+//! - [`vehicle_profile`] - Per-vehicle constants referenced from formulas as `$name`
 //! - [`updater`] - Auto-update functionality for checking and downloading updates
 //! - [`analytics`] - Anonymous usage analytics via PostHog
 //! - [`ui`] - User interface components
@@ -23,11 +34,22 @@
 
 pub mod analytics;
 pub mod app;
+pub mod builtin_templates;
+pub mod cli;
 pub mod computed;
+pub mod data_export;
 pub mod expression;
+pub mod gps;
+pub mod heatmap;
+pub mod laps;
+pub mod library_sharing;
+pub mod lookup_table;
 pub mod normalize;
+pub mod overlay;
 pub mod parsers;
 pub mod state;
 pub mod ui;
 pub mod units;
 pub mod updater;
+pub mod ve_analyze;
+pub mod vehicle_profile;
@@ -0,0 +1,430 @@
+//! Import, export and merging of computed channel libraries
+//!
+//! Libraries are shared as the same JSON document the app stores in its
+//! config directory. Older documents are migrated to the current format
+//! using their `version` field, and imported templates are merged into the
+//! user's library by ID and name according to a [`ConflictPolicy`].
+
+use serde_json::{Map, Value};
+
+use crate::computed::{ComputedChannelLibrary, ComputedChannelTemplate};
+use crate::expression::{
+    extract_channel_references, extract_constant_references, extract_table_references,
+};
+
+/// How to resolve an imported template or table that clashes with the library
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep the library's version and skip the imported one
+    KeepExisting,
+    /// Replace the library's version with the imported one
+    Replace,
+    /// Add imported templates under a new ID and name; tables keep the
+    /// library's version, since formulas refer to them by name
+    KeepBoth,
+}
+
+/// Outcome of [`ComputedChannelLibrary::merge`]
+#[derive(Clone, Debug, Default)]
+pub struct MergeReport {
+    /// Templates added without conflict
+    pub added: usize,
+    /// Templates and tables identical to the library's
+    pub unchanged: usize,
+    /// Conflicting templates and tables that were not imported
+    pub skipped: usize,
+    /// Conflicting templates added under a new name
+    pub renamed: usize,
+    /// Library templates replaced by imported ones
+    pub replaced_templates: Vec<ComputedChannelTemplate>,
+    /// Names of library tables added or replaced by imported ones
+    pub changed_tables: Vec<String>,
+}
+
+impl MergeReport {
+    /// One-line summary for the user
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} added", self.added + self.renamed)];
+        if !self.replaced_templates.is_empty() {
+            parts.push(format!("{} replaced", self.replaced_templates.len()));
+        }
+        if self.skipped > 0 {
+            parts.push(format!("{} skipped", self.skipped));
+        }
+        if self.unchanged > 0 {
+            parts.push(format!("{} unchanged", self.unchanged));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Check whether two templates define the same channel
+fn same_definition(a: &ComputedChannelTemplate, b: &ComputedChannelTemplate) -> bool {
+    a.name == b.name && a.formula == b.formula && a.unit == b.unit && a.description == b.description
+}
+
+/// Bring a library document of any earlier version to the current format
+fn migrate(mut value: Value) -> Result<Value, String> {
+    // Version 0: a bare template or list of templates, possibly hand-written
+    // without IDs or timestamps
+    if value.is_array() || value.get("formula").is_some() {
+        let templates = match value {
+            Value::Array(templates) => templates,
+            template => vec![template],
+        };
+        value = Value::Object(Map::from_iter([
+            ("version".to_string(), Value::from(0)),
+            ("templates".to_string(), Value::Array(templates)),
+        ]));
+    }
+
+    let Some(object) = value.as_object_mut() else {
+        return Err("Expected a computed channel library or template".to_string());
+    };
+    let version = object.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > ComputedChannelLibrary::CURRENT_VERSION as u64 {
+        return Err(format!(
+            "Library version {} was created by a newer version of UltraLog",
+            version
+        ));
+    }
+
+    if version < 1 {
+        if let Some(Value::Array(templates)) = object.get_mut("templates") {
+            for template in templates.iter_mut().filter_map(Value::as_object_mut) {
+                if !template.contains_key("id") {
+                    let id = uuid::Uuid::new_v4().to_string();
+                    template.insert("id".to_string(), Value::from(id));
+                }
+                if !template.contains_key("unit") {
+                    template.insert("unit".to_string(), Value::from(""));
+                }
+                for key in ["created_at", "modified_at"] {
+                    template.entry(key).or_insert(Value::from(0));
+                }
+            }
+        }
+    }
+
+    object.insert(
+        "version".to_string(),
+        Value::from(ComputedChannelLibrary::CURRENT_VERSION),
+    );
+    Ok(value)
+}
+
+impl ComputedChannelLibrary {
+    /// Parse a library, template or list of templates from JSON, migrating
+    /// documents written by earlier versions
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let value: Value =
+            serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?;
+        serde_json::from_value(migrate(value)?)
+            .map_err(|e| format!("Invalid computed channel library: {}", e))
+    }
+
+    /// Serialize the library as JSON
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize library: {}", e))
+    }
+
+    /// A library holding the given templates, the templates they reference
+    /// (directly or through others) and the lookup tables they all use
+    pub fn subset(&self, template_ids: &[String]) -> Self {
+        let mut templates: Vec<ComputedChannelTemplate> = self
+            .templates
+            .iter()
+            .filter(|t| template_ids.contains(&t.id))
+            .cloned()
+            .collect();
+        let mut i = 0;
+        while i < templates.len() {
+            for reference in extract_channel_references(&templates[i].formula) {
+                let referenced = self.templates.iter().find(|t| {
+                    t.name.eq_ignore_ascii_case(&reference.name)
+                        && !templates.iter().any(|added| added.id == t.id)
+                });
+                if let Some(referenced) = referenced {
+                    templates.push(referenced.clone());
+                }
+            }
+            i += 1;
+        }
+        let table_names: Vec<String> = templates
+            .iter()
+            .flat_map(|t| extract_table_references(&t.formula))
+            .collect();
+        let tables = self
+            .tables
+            .iter()
+            .filter(|table| {
+                table_names
+                    .iter()
+                    .any(|n| n.eq_ignore_ascii_case(&table.name))
+            })
+            .cloned()
+            .collect();
+
+        Self {
+            templates,
+            tables,
+            ..Self::new()
+        }
+    }
+
+    /// `$name` constants the templates read, which come from the vehicle
+    /// profile rather than the library
+    pub fn constant_references(&self) -> Vec<String> {
+        let mut constants: Vec<String> = Vec::new();
+        for name in self
+            .templates
+            .iter()
+            .flat_map(|t| extract_constant_references(&t.formula))
+        {
+            if !constants.iter().any(|c| c.eq_ignore_ascii_case(&name)) {
+                constants.push(name);
+            }
+        }
+        constants
+    }
+
+    /// Number of templates and tables in `other` that clash with this
+    /// library: same ID or name but a different definition
+    pub fn count_conflicts(&self, other: &Self) -> usize {
+        let templates = other
+            .templates
+            .iter()
+            .filter(|t| {
+                self.matching_template(t)
+                    .is_some_and(|i| !same_definition(&self.templates[i], t))
+            })
+            .count();
+        let tables = other
+            .tables
+            .iter()
+            .filter(|t| {
+                self.find_table(&t.name)
+                    .is_some_and(|existing| existing != *t)
+            })
+            .count();
+        templates + tables
+    }
+
+    /// Merge the templates and tables of `other` into this library
+    pub fn merge(&mut self, other: Self, policy: ConflictPolicy) -> MergeReport {
+        let mut report = MergeReport::default();
+
+        for template in other.templates {
+            let Some(idx) = self.matching_template(&template) else {
+                self.templates.push(template);
+                report.added += 1;
+                continue;
+            };
+            if same_definition(&self.templates[idx], &template) {
+                report.unchanged += 1;
+                continue;
+            }
+            match policy {
+                ConflictPolicy::KeepExisting => report.skipped += 1,
+                ConflictPolicy::Replace => {
+                    // Keep the library's ID so applied channels follow the update
+                    let existing = &mut self.templates[idx];
+                    *existing = ComputedChannelTemplate {
+                        id: existing.id.clone(),
+                        ..template
+                    };
+                    report.replaced_templates.push(existing.clone());
+                }
+                ConflictPolicy::KeepBoth => {
+                    let name = self.unique_template_name(&template.name);
+                    self.templates.push(ComputedChannelTemplate::new(
+                        name,
+                        template.formula,
+                        template.unit,
+                        template.description,
+                    ));
+                    report.renamed += 1;
+                }
+            }
+        }
+
+        for table in other.tables {
+            match self.find_table(&table.name) {
+                None => {}
+                Some(existing) if *existing == table => {
+                    report.unchanged += 1;
+                    continue;
+                }
+                Some(_) if policy != ConflictPolicy::Replace => {
+                    report.skipped += 1;
+                    continue;
+                }
+                Some(_) => {}
+            }
+            report.changed_tables.push(table.name.clone());
+            self.set_table(table);
+        }
+
+        report
+    }
+
+    /// Index of the library template with the ID of `template`, or else its
+    /// name (case-insensitive)
+    fn matching_template(&self, template: &ComputedChannelTemplate) -> Option<usize> {
+        self.templates
+            .iter()
+            .position(|t| t.id == template.id)
+            .or_else(|| {
+                self.templates
+                    .iter()
+                    .position(|t| t.name.eq_ignore_ascii_case(&template.name))
+            })
+    }
+
+    /// `name`, or `name (2)`, `name (3)`, ... if the library already uses it
+    fn unique_template_name(&self, name: &str) -> String {
+        let is_used = |candidate: &str| {
+            self.templates
+                .iter()
+                .any(|t| t.name.eq_ignore_ascii_case(candidate))
+        };
+        let mut candidate = name.to_string();
+        let mut suffix = 2;
+        while is_used(&candidate) {
+            candidate = format!("{} ({})", name, suffix);
+            suffix += 1;
+        }
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup_table::LookupTable;
+
+    fn template(name: &str, formula: &str) -> ComputedChannelTemplate {
+        ComputedChannelTemplate::new(
+            name.to_string(),
+            formula.to_string(),
+            String::new(),
+            String::new(),
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut library = ComputedChannelLibrary::new();
+        library.add_template(template("Boost", "MAP - 100"));
+        let parsed = ComputedChannelLibrary::from_json(&library.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.templates[0].id, library.templates[0].id);
+        assert_eq!(parsed.version, ComputedChannelLibrary::CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_bare_templates() {
+        let library =
+            ComputedChannelLibrary::from_json(r#"[{"name": "Boost", "formula": "MAP - 100"}]"#)
+                .unwrap();
+        assert_eq!(library.templates.len(), 1);
+        assert!(!library.templates[0].id.is_empty());
+
+        let library =
+            ComputedChannelLibrary::from_json(r#"{"name": "Lambda", "formula": "AFR / 14.7"}"#)
+                .unwrap();
+        assert_eq!(library.templates[0].name, "Lambda");
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        let err =
+            ComputedChannelLibrary::from_json(r#"{"version": 99, "templates": []}"#).unwrap_err();
+        assert!(err.contains("newer version"));
+    }
+
+    #[test]
+    fn test_subset_includes_used_tables() {
+        let mut library = ComputedChannelLibrary::new();
+        library.add_template(template("Corrected", "table(\"Trim\", RPM) * IPW"));
+        library.add_template(template("Boost", "MAP - 100"));
+        library.set_table(LookupTable::new_1d(
+            "Trim".to_string(),
+            vec![0.0, 1.0],
+            vec![1.0, 1.0],
+        ));
+        library.set_table(LookupTable::new_1d(
+            "Unused".to_string(),
+            vec![0.0, 1.0],
+            vec![1.0, 1.0],
+        ));
+
+        let subset = library.subset(&[library.templates[0].id.clone()]);
+        assert_eq!(subset.templates.len(), 1);
+        assert_eq!(subset.tables.len(), 1);
+        assert_eq!(subset.tables[0].name, "Trim");
+    }
+
+    #[test]
+    fn test_subset_follows_template_references() {
+        let mut library = ComputedChannelLibrary::new();
+        library.add_template(template("Fuel Flow", "Duty / 100 * $injector_cc"));
+        library.add_template(template(
+            "Duty",
+            "table(\"Deadtime\", RPM) * \"Pulse Width\"",
+        ));
+        library.add_template(template("Boost", "MAP - 100"));
+        library.set_table(LookupTable::new_1d(
+            "Deadtime".to_string(),
+            vec![0.0, 1.0],
+            vec![1.0, 1.0],
+        ));
+
+        let subset = library.subset(&[library.templates[0].id.clone()]);
+        let names: Vec<&str> = subset.templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["Fuel Flow", "Duty"]);
+        assert_eq!(subset.tables.len(), 1);
+        assert_eq!(subset.constant_references(), vec!["injector_cc"]);
+    }
+
+    #[test]
+    fn test_merge_policies() {
+        let mut library = ComputedChannelLibrary::new();
+        library.add_template(template("Boost", "MAP - 100"));
+        let mut shared = library.clone();
+        shared.templates[0].formula = "MAP - Baro".to_string();
+        shared.add_template(template("Lambda", "AFR / 14.7"));
+        // Same name, different ID
+        shared.add_template(template("boost", "MAP - 101"));
+        assert_eq!(library.count_conflicts(&shared), 2);
+
+        let mut kept = library.clone();
+        let report = kept.merge(shared.clone(), ConflictPolicy::KeepExisting);
+        assert_eq!((report.added, report.skipped), (1, 2));
+        assert_eq!(kept.templates[0].formula, "MAP - 100");
+
+        let mut replaced = library.clone();
+        let report = replaced.merge(shared.clone(), ConflictPolicy::Replace);
+        assert_eq!(report.replaced_templates.len(), 2);
+        assert_eq!(replaced.templates.len(), 2);
+        assert_eq!(replaced.templates[0].id, library.templates[0].id);
+        assert_eq!(replaced.templates[0].formula, "MAP - 101");
+
+        let mut both = library.clone();
+        let report = both.merge(shared, ConflictPolicy::KeepBoth);
+        assert_eq!(report.renamed, 2);
+        let names: Vec<&str> = both.templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["Boost", "Boost (2)", "Lambda", "boost (3)"]);
+    }
+
+    #[test]
+    fn test_merge_identical_is_unchanged() {
+        let mut library = ComputedChannelLibrary::with_builtin_templates();
+        let report = library.merge(
+            ComputedChannelLibrary::with_builtin_templates(),
+            ConflictPolicy::Replace,
+        );
+        assert_eq!(report.unchanged, library.templates.len());
+        assert!(report.replaced_templates.is_empty());
+    }
+}
@@ -0,0 +1,465 @@
+//! Lookup tables for computed channel formulas
+//!
+//! Tables map one input (`table("Name", x)`) or two inputs
+//! (`table2d("Name", x, y)`) to an output by linear or bilinear interpolation,
+//! clamping inputs to the ends of each axis like an ECU does. They are stored
+//! in the computed channel library and can be edited or imported from CSV.
+
+use serde::{Deserialize, Serialize};
+
+/// A named 1D or 2D lookup table
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LookupTable {
+    /// Name used to refer to the table in formulas
+    pub name: String,
+    /// Optional description for user reference
+    #[serde(default)]
+    pub description: String,
+    /// Column axis, looked up with the first input
+    pub x_axis: Vec<f64>,
+    /// Row axis, looked up with the second input (empty for a 1D table)
+    #[serde(default)]
+    pub y_axis: Vec<f64>,
+    /// One row per `y_axis` entry (a single row for a 1D table), each with
+    /// one value per `x_axis` entry
+    pub values: Vec<Vec<f64>>,
+}
+
+impl LookupTable {
+    /// Create a 1D table from an axis and one value per axis point
+    pub fn new_1d(name: String, x_axis: Vec<f64>, values: Vec<f64>) -> Self {
+        Self {
+            name,
+            description: String::new(),
+            x_axis,
+            y_axis: Vec::new(),
+            values: vec![values],
+        }
+    }
+
+    /// Create a 2D table from its axes and rows of values (one per `y_axis` entry)
+    pub fn new_2d(name: String, x_axis: Vec<f64>, y_axis: Vec<f64>, values: Vec<Vec<f64>>) -> Self {
+        Self {
+            name,
+            description: String::new(),
+            x_axis,
+            y_axis,
+            values,
+        }
+    }
+
+    /// Check if this table takes two inputs
+    pub fn is_2d(&self) -> bool {
+        !self.y_axis.is_empty()
+    }
+
+    /// Short description of the table size, e.g. "1D, 8 points" or "2D, 16 x 12"
+    pub fn size_label(&self) -> String {
+        if self.is_2d() {
+            format!("2D, {} x {}", self.x_axis.len(), self.y_axis.len())
+        } else {
+            format!("1D, {} points", self.x_axis.len())
+        }
+    }
+
+    /// Check that the axes are strictly increasing and the values fill the grid
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Table name cannot be empty".to_string());
+        }
+        check_axis("X", &self.x_axis)?;
+        if self.is_2d() {
+            check_axis("Y", &self.y_axis)?;
+        }
+
+        let rows = self.y_axis.len().max(1);
+        if self.values.len() != rows || self.values.iter().any(|r| r.len() != self.x_axis.len()) {
+            return Err(format!(
+                "Expected {} row(s) of {} values",
+                rows,
+                self.x_axis.len()
+            ));
+        }
+        if self.values.iter().flatten().any(|v| !v.is_finite()) {
+            return Err("Table values must be numbers".to_string());
+        }
+        Ok(())
+    }
+
+    /// Interpolate a 1D table (the first row of a 2D table) at `x`
+    pub fn lookup(&self, x: f64) -> f64 {
+        let Some(row) = self.values.first() else {
+            return f64::NAN;
+        };
+        match segment(&self.x_axis, x) {
+            Some((i, j, t)) => lerp(row[i], row[j], t),
+            None => f64::NAN,
+        }
+    }
+
+    /// Bilinearly interpolate a 2D table at (`x`, `y`)
+    pub fn lookup2d(&self, x: f64, y: f64) -> f64 {
+        let (Some((x0, x1, tx)), Some((y0, y1, ty))) =
+            (segment(&self.x_axis, x), segment(&self.y_axis, y))
+        else {
+            return f64::NAN;
+        };
+        let (Some(low), Some(high)) = (self.values.get(y0), self.values.get(y1)) else {
+            return f64::NAN;
+        };
+        lerp(lerp(low[x0], low[x1], tx), lerp(high[x0], high[x1], tx), ty)
+    }
+
+    /// Add an axis point after the last one, copying the last column
+    pub fn push_x(&mut self) {
+        let next = next_axis_value(&self.x_axis);
+        self.x_axis.push(next);
+        for row in &mut self.values {
+            let last = row.last().copied().unwrap_or(0.0);
+            row.push(last);
+        }
+    }
+
+    /// Remove the last x axis point, keeping at least one
+    pub fn pop_x(&mut self) {
+        if self.x_axis.len() > 1 {
+            self.x_axis.pop();
+            for row in &mut self.values {
+                row.pop();
+            }
+        }
+    }
+
+    /// Add a y axis point after the last one, copying the last row. A 1D
+    /// table becomes 2D with its values as the first row.
+    pub fn push_y(&mut self) {
+        if self.y_axis.is_empty() {
+            self.y_axis.push(0.0);
+        }
+        let next = next_axis_value(&self.y_axis);
+        self.y_axis.push(next);
+        let last = self
+            .values
+            .last()
+            .cloned()
+            .unwrap_or_else(|| vec![0.0; self.x_axis.len()]);
+        self.values.resize(self.y_axis.len(), last);
+    }
+
+    /// Remove the last y axis point. A 2D table with one row left becomes 1D.
+    pub fn pop_y(&mut self) {
+        if self.y_axis.pop().is_some() {
+            self.values.truncate(self.y_axis.len().max(1));
+            if self.y_axis.len() == 1 {
+                self.y_axis.clear();
+            }
+        }
+    }
+
+    /// Parse a table from CSV text.
+    ///
+    /// A 1D table is rows of `x,value` (an optional header row is skipped).
+    /// A 2D table has the x axis in the first row after an empty corner
+    /// cell, and each following row starts with its y axis value.
+    pub fn from_csv(name: &str, text: &str) -> Result<Self, String> {
+        let mut rows: Vec<Vec<Option<f64>>> = text
+            .lines()
+            // Keep a leading tab: it is the empty corner cell of a 2D table
+            // copied from a spreadsheet or tuning software
+            .map(|line| {
+                line.trim_start_matches(|c: char| c.is_whitespace() && c != '\t')
+                    .trim_end()
+            })
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.split([',', ';', '\t'])
+                    .map(|cell| cell.trim().trim_matches('"').parse::<f64>().ok())
+                    .collect()
+            })
+            .collect();
+
+        // Header row with no numbers
+        if rows
+            .first()
+            .is_some_and(|row| row.iter().all(Option::is_none))
+        {
+            rows.remove(0);
+        }
+        let Some(first) = rows.first() else {
+            return Err("CSV contains no data".to_string());
+        };
+
+        let number = |cell: &Option<f64>, line: usize| {
+            cell.ok_or_else(|| format!("Row {}: expected a number", line + 1))
+        };
+
+        let table = if first[0].is_none() {
+            let x_axis = first[1..]
+                .iter()
+                .map(|c| number(c, 0))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut y_axis = Vec::new();
+            let mut values = Vec::new();
+            for (line, row) in rows.iter().enumerate().skip(1) {
+                if row.len() != x_axis.len() + 1 {
+                    return Err(format!(
+                        "Row {}: expected {} values, got {}",
+                        line + 1,
+                        x_axis.len() + 1,
+                        row.len()
+                    ));
+                }
+                y_axis.push(number(&row[0], line)?);
+                values.push(
+                    row[1..]
+                        .iter()
+                        .map(|c| number(c, line))
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+            }
+            if y_axis.is_empty() {
+                return Err("2D table has no rows".to_string());
+            }
+            Self::new_2d(name.to_string(), x_axis, y_axis, values)
+        } else {
+            let mut x_axis = Vec::new();
+            let mut values = Vec::new();
+            for (line, row) in rows.iter().enumerate() {
+                if row.len() != 2 {
+                    return Err(format!(
+                        "Row {}: expected 2 values (x, value), got {}",
+                        line + 1,
+                        row.len()
+                    ));
+                }
+                x_axis.push(number(&row[0], line)?);
+                values.push(number(&row[1], line)?);
+            }
+            Self::new_1d(name.to_string(), x_axis, values)
+        };
+
+        table.validate()?;
+        Ok(table)
+    }
+
+    /// Write the table as CSV in the layout read by [`from_csv`](Self::from_csv)
+    pub fn to_csv(&self) -> String {
+        let join = |values: &[f64]| {
+            values
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        let mut csv = String::new();
+        if self.is_2d() {
+            csv.push_str(&format!(",{}\n", join(&self.x_axis)));
+            for (y, row) in self.y_axis.iter().zip(&self.values) {
+                csv.push_str(&format!("{},{}\n", y, join(row)));
+            }
+        } else {
+            csv.push_str("x,value\n");
+            let row = self.values.first().map_or(&[][..], Vec::as_slice);
+            for (x, v) in self.x_axis.iter().zip(row) {
+                csv.push_str(&format!("{},{}\n", x, v));
+            }
+        }
+        csv
+    }
+}
+
+/// Check that an axis is non-empty, numeric and strictly increasing
+pub(crate) fn check_axis(label: &str, axis: &[f64]) -> Result<(), String> {
+    if axis.is_empty() {
+        return Err(format!("{} axis is empty", label));
+    }
+    if axis.iter().any(|v| !v.is_finite()) {
+        return Err(format!("{} axis values must be numbers", label));
+    }
+    if axis.windows(2).any(|w| w[1] <= w[0]) {
+        return Err(format!("{} axis must be strictly increasing", label));
+    }
+    Ok(())
+}
+
+/// Parse breakpoints separated by commas, tabs or spaces
+pub fn parse_axis(label: &str, text: &str) -> Result<Vec<f64>, String> {
+    let axis = text
+        .split([',', ';', '\t', ' ', '\n'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<f64>()
+                .map_err(|_| format!("{} axis: '{}' is not a number", label, s))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    check_axis(label, &axis)?;
+    Ok(axis)
+}
+
+/// Index of the axis point closest to `v`, clamped to the ends of the axis
+pub fn nearest_breakpoint(axis: &[f64], v: f64) -> usize {
+    let j = axis.partition_point(|&a| a < v);
+    if j == 0 {
+        0
+    } else if j == axis.len() || v - axis[j - 1] < axis[j] - v {
+        j - 1
+    } else {
+        j
+    }
+}
+
+/// Axis points bracketing `v` (clamped to the axis) and the weight of the
+/// second one
+fn segment(axis: &[f64], v: f64) -> Option<(usize, usize, f64)> {
+    if v.is_nan() || axis.is_empty() {
+        return None;
+    }
+    let last = axis.len() - 1;
+    if v <= axis[0] {
+        return Some((0, 0, 0.0));
+    }
+    if v >= axis[last] {
+        return Some((last, last, 0.0));
+    }
+    let j = axis.partition_point(|&a| a <= v).min(last);
+    let i = j - 1;
+    Some((i, j, (v - axis[i]) / (axis[j] - axis[i])))
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Next axis value continuing the spacing of the last two points
+fn next_axis_value(axis: &[f64]) -> f64 {
+    match axis {
+        [] => 0.0,
+        [only] => only + 1.0,
+        [.., a, b] => b + (b - a),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deadtime() -> LookupTable {
+        LookupTable::new_1d(
+            "Deadtime".to_string(),
+            vec![8.0, 10.0, 12.0, 14.0],
+            vec![1.6, 1.2, 0.9, 0.7],
+        )
+    }
+
+    fn ve() -> LookupTable {
+        LookupTable::new_2d(
+            "VE".to_string(),
+            vec![1000.0, 2000.0, 3000.0],
+            vec![50.0, 100.0],
+            vec![vec![40.0, 50.0, 60.0], vec![60.0, 80.0, 90.0]],
+        )
+    }
+
+    #[test]
+    fn test_lookup_interpolates_and_clamps() {
+        let table = deadtime();
+        assert!((table.lookup(11.0) - 1.05).abs() < 1e-9);
+        assert_eq!(table.lookup(12.0), 0.9);
+        assert_eq!(table.lookup(5.0), 1.6);
+        assert_eq!(table.lookup(20.0), 0.7);
+        assert!(table.lookup(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_lookup2d_bilinear() {
+        let table = ve();
+        assert_eq!(table.lookup2d(2000.0, 100.0), 80.0);
+        // Centre of the first cell: mean of its corners
+        assert!((table.lookup2d(1500.0, 75.0) - 57.5).abs() < 1e-9);
+        // Clamped on both axes
+        assert_eq!(table.lookup2d(500.0, 20.0), 40.0);
+        assert_eq!(table.lookup2d(9000.0, 200.0), 90.0);
+        assert!(table.lookup2d(1500.0, f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(deadtime().validate().is_ok());
+        assert!(ve().validate().is_ok());
+
+        let mut table = deadtime();
+        table.x_axis[2] = 9.0;
+        assert!(table.validate().unwrap_err().contains("increasing"));
+
+        let mut table = ve();
+        table.values[1].pop();
+        assert!(table.validate().is_err());
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        for table in [deadtime(), ve()] {
+            let parsed = LookupTable::from_csv(&table.name, &table.to_csv()).unwrap();
+            assert_eq!(parsed, table);
+        }
+    }
+
+    #[test]
+    fn test_from_csv_layouts() {
+        let table = LookupTable::from_csv("Deadtime", "Voltage;Deadtime\n8;1.6\n14;0.7\n").unwrap();
+        assert!(!table.is_2d());
+        assert_eq!(table.x_axis, vec![8.0, 14.0]);
+
+        let table = LookupTable::from_csv("VE", "RPM/MAP,1000,2000\n50,40,50\n100,60,80").unwrap();
+        assert!(table.is_2d());
+        assert_eq!(table.y_axis, vec![50.0, 100.0]);
+        assert_eq!(table.values[1], vec![60.0, 80.0]);
+
+        let table = LookupTable::from_csv("VE", "\t1000\t2000\n50\t40\t50\n").unwrap();
+        assert!(table.is_2d());
+        assert_eq!(table.x_axis, vec![1000.0, 2000.0]);
+
+        assert!(LookupTable::from_csv("Bad", "1,2,3\n4,5,6").is_err());
+        assert!(LookupTable::from_csv("Bad", "").is_err());
+    }
+
+    #[test]
+    fn test_parse_axis() {
+        assert_eq!(
+            parse_axis("RPM", "500, 1000\t1500 2000").unwrap(),
+            vec![500.0, 1000.0, 1500.0, 2000.0]
+        );
+        assert!(parse_axis("RPM", "1000, 500").is_err());
+        assert!(parse_axis("RPM", "1000, abc").unwrap_err().contains("abc"));
+        assert!(parse_axis("RPM", "").is_err());
+    }
+
+    #[test]
+    fn test_nearest_breakpoint() {
+        let axis = [1000.0, 2000.0, 4000.0];
+        assert_eq!(nearest_breakpoint(&axis, 0.0), 0);
+        assert_eq!(nearest_breakpoint(&axis, 1400.0), 0);
+        assert_eq!(nearest_breakpoint(&axis, 1600.0), 1);
+        assert_eq!(nearest_breakpoint(&axis, 3100.0), 2);
+        assert_eq!(nearest_breakpoint(&axis, 9000.0), 2);
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut table = deadtime();
+        table.push_x();
+        assert_eq!(table.x_axis.last(), Some(&16.0));
+        assert_eq!(table.values[0].last(), Some(&0.7));
+
+        table.push_y();
+        assert!(table.is_2d());
+        assert_eq!(table.y_axis, vec![0.0, 1.0]);
+        assert!(table.validate().is_ok());
+
+        table.pop_y();
+        assert!(!table.is_2d());
+        assert!(table.validate().is_ok());
+    }
+}
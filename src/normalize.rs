@@ -24,6 +24,7 @@ static NORMALIZATION_MAP: LazyLock<HashMap<&'static str, Vec<&'static str>>> =
                 "WB2 AFR 1",
                 "Air Fuel Ratio",
                 "AFR_R_BANK",
+                "AFR Avg",
             ],
         );
 
@@ -166,6 +167,7 @@ static NORMALIZATION_MAP: LazyLock<HashMap<&'static str, Vec<&'static str>>> =
                 "map",
                 "Manifold Pressure",
                 "Inlet Manifold Pressure",
+                "Manifold Absolute Pressure",
             ],
         );
 
@@ -196,6 +198,8 @@ static NORMALIZATION_MAP: LazyLock<HashMap<&'static str, Vec<&'static str>>> =
                 "NJ_GPW_AVE",
                 "PW",
                 "pw",
+                "Inj Actual PW",
+                "Injection Stage 1 Average Injection Time",
             ],
         );
 
@@ -210,6 +214,7 @@ static NORMALIZATION_MAP: LazyLock<HashMap<&'static str, Vec<&'static str>>> =
                 "Engine RPM4",
                 "RPM_INC_RPM",
                 "engine/rpm",
+                "Engine Speed",
             ],
         );
 
@@ -261,6 +266,18 @@ static NORMALIZATION_MAP: LazyLock<HashMap<&'static str, Vec<&'static str>>> =
             vec!["Knock", "Knock Retard", "KnockRetard", "Knock Count"],
         );
 
+        map.insert(
+            "Baro",
+            vec![
+                "Baro",
+                "baro",
+                "Baro Pressure",
+                "Barometric Pressure",
+                "BaroPressure",
+                "Barometer",
+                "Atmospheric Pressure",
+            ],
+        );
         map.insert(
             "Boost",
             vec!["Boost", "Boost Pressure", "BoostPressure", "boost"],
@@ -480,6 +497,12 @@ mod tests {
         assert_eq!(normalize_channel_name("PedalPos"), "TPS");
     }
 
+    #[test]
+    fn test_normalize_baro() {
+        assert_eq!(normalize_channel_name("Barometric Pressure"), "Baro");
+        assert_eq!(normalize_channel_name("Atmospheric Pressure"), "Baro");
+    }
+
     #[test]
     fn test_normalize_path() {
         assert_eq!(normalize_channel_name("engine/rpm"), "RPM");
@@ -0,0 +1,335 @@
+//! Overlaying other logs on a log's timeline
+//!
+//! An overlaid log is placed on the timeline of the reference log by a time
+//! offset, optionally derived from when a trigger condition (e.g. TPS
+//! rising through 90 %) is first met in each log. Aligning by distance
+//! instead places each sample of the overlaid log at the reference time at
+//! which the same distance had been covered since the alignment point. A
+//! delta trace is the overlaid value minus the reference value at the same
+//! aligned time.
+
+use crate::units::{Dimension, Unit};
+
+/// Direction in which a trigger channel crosses its threshold
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TriggerEdge {
+    #[default]
+    Rising,
+    Falling,
+}
+
+/// Condition marking the same moment in every log
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trigger {
+    pub threshold: f64,
+    pub edge: TriggerEdge,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self {
+            threshold: 90.0,
+            edge: TriggerEdge::Rising,
+        }
+    }
+}
+
+impl Trigger {
+    /// Time at which `values` first cross the threshold, interpolated
+    /// between samples
+    pub fn first_crossing(&self, times: &[f64], values: &[f64]) -> Option<f64> {
+        let samples: Vec<(f64, f64)> = times
+            .iter()
+            .zip(values)
+            .filter(|(_, v)| !v.is_nan())
+            .map(|(&t, &v)| (t, v))
+            .collect();
+        samples.windows(2).find_map(|pair| {
+            let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+            let crossed = match self.edge {
+                TriggerEdge::Rising => v0 < self.threshold && v1 >= self.threshold,
+                TriggerEdge::Falling => v0 > self.threshold && v1 <= self.threshold,
+            };
+            crossed.then(|| t0 + (t1 - t0) * (self.threshold - v0) / (v1 - v0))
+        })
+    }
+}
+
+/// Factor converting speeds logged in `unit` to m/s (km/h if unknown)
+pub fn speed_scale(unit: &str) -> f64 {
+    Unit::parse(unit)
+        .filter(|u| u.dimension == Dimension::SPEED)
+        .map_or(1.0 / 3.6, |u| u.scale)
+}
+
+/// Distance covered over time, integrated from a speed channel
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistanceTrace {
+    times: Vec<f64>,
+    /// Cumulative distance in meters at each time
+    distance: Vec<f64>,
+}
+
+impl DistanceTrace {
+    /// Integrate speeds in m/s; missing or negative samples count as stopped
+    pub fn new(times: &[f64], speed_mps: &[f64]) -> Self {
+        let mut distance = Vec::with_capacity(times.len());
+        let mut total = 0.0;
+        let mut previous: Option<(f64, f64)> = None;
+        for (&t, &v) in times.iter().zip(speed_mps) {
+            let v = if v.is_nan() { 0.0 } else { v.max(0.0) };
+            if let Some((t0, v0)) = previous {
+                total += (t - t0).max(0.0) * (v0 + v) / 2.0;
+            }
+            distance.push(total);
+            previous = Some((t, v));
+        }
+        Self {
+            times: times[..distance.len()].to_vec(),
+            distance,
+        }
+    }
+
+    /// Distance covered at `time`
+    pub fn at(&self, time: f64) -> Option<f64> {
+        interpolate(&self.times, &self.distance, time)
+    }
+
+    /// First time at which `distance` had been covered
+    pub fn time_at(&self, distance: f64) -> Option<f64> {
+        let i = self.distance.partition_point(|&d| d < distance);
+        if i == 0 {
+            return self.times.first().copied();
+        }
+        let (d0, d1) = (self.distance[i - 1], *self.distance.get(i)?);
+        let (t0, t1) = (self.times[i - 1], self.times[i]);
+        Some(t0 + (t1 - t0) * (distance - d0) / (d1 - d0))
+    }
+
+    pub fn total(&self) -> f64 {
+        self.distance.last().copied().unwrap_or(0.0)
+    }
+}
+
+/// How times of an overlaid log map onto the reference log's timeline
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimeMap {
+    /// Reference time = overlay time + offset
+    Offset(f64),
+    /// Equal distance covered since each log's zero point; before the zero
+    /// points the logs are lined up by time. `shift` moves the overlaid log
+    /// later on top of that.
+    Distance {
+        reference: DistanceTrace,
+        overlay: DistanceTrace,
+        reference_zero: f64,
+        overlay_zero: f64,
+        shift: f64,
+    },
+}
+
+impl TimeMap {
+    /// Reference time of an overlaid log's time
+    pub fn to_reference(&self, time: f64) -> Option<f64> {
+        match self {
+            TimeMap::Offset(offset) => Some(time + offset),
+            TimeMap::Distance {
+                reference,
+                overlay,
+                reference_zero,
+                overlay_zero,
+                shift,
+            } => Some(
+                map_by_distance(overlay, *overlay_zero, reference, *reference_zero, time)? + shift,
+            ),
+        }
+    }
+
+    /// Overlaid log's time at a reference time
+    pub fn to_overlay(&self, time: f64) -> Option<f64> {
+        match self {
+            TimeMap::Offset(offset) => Some(time - offset),
+            TimeMap::Distance {
+                reference,
+                overlay,
+                reference_zero,
+                overlay_zero,
+                shift,
+            } => map_by_distance(
+                reference,
+                *reference_zero,
+                overlay,
+                *overlay_zero,
+                time - shift,
+            ),
+        }
+    }
+}
+
+/// Time in the `to` log at which it had covered the distance the `from`
+/// log covered between its zero point and `time`
+fn map_by_distance(
+    from: &DistanceTrace,
+    from_zero: f64,
+    to: &DistanceTrace,
+    to_zero: f64,
+    time: f64,
+) -> Option<f64> {
+    if time <= from_zero {
+        return Some(time - from_zero + to_zero);
+    }
+    let covered = from.at(time)? - from.at(from_zero)?;
+    let target = to.at(to_zero)? + covered;
+    if target > to.total() {
+        return None;
+    }
+    to.time_at(target).map(|t| t.max(to_zero))
+}
+
+/// Value at `time`, linearly interpolated between the samples around it;
+/// `None` outside the samples or next to a missing one
+pub fn interpolate(times: &[f64], values: &[f64], time: f64) -> Option<f64> {
+    let len = times.len().min(values.len());
+    let i = times[..len].partition_point(|&t| t < time);
+    if i == len {
+        return None;
+    }
+    let value = if times[i] == time || i == 0 {
+        (times[i] == time).then_some(values[i])?
+    } else {
+        let (t0, t1) = (times[i - 1], times[i]);
+        values[i - 1] + (values[i] - values[i - 1]) * (time - t0) / (t1 - t0)
+    };
+    (!value.is_nan()).then_some(value)
+}
+
+/// Overlaid value minus reference value at each reference point
+/// (`[time, value]`); NaN where the overlaid log has no value
+pub fn delta_trace(
+    reference: &[[f64; 2]],
+    overlay_times: &[f64],
+    overlay_values: &[f64],
+    map: &TimeMap,
+) -> Vec<[f64; 2]> {
+    reference
+        .iter()
+        .map(|&[t, v]| {
+            let overlay = map
+                .to_overlay(t)
+                .and_then(|t| interpolate(overlay_times, overlay_values, t));
+            [t, overlay.map_or(f64::NAN, |o| o - v)]
+        })
+        .collect()
+}
+
+/// Converts values of an overlaid channel logged in `unit` to the unit of
+/// the reference channel it is matched with, when both units are known and
+/// measure the same thing; other values are left as they are
+pub fn reference_unit_converter(unit: &str, reference_unit: &str) -> impl Fn(f64) -> f64 {
+    let units = Unit::parse(unit)
+        .zip(Unit::parse(reference_unit))
+        .filter(|(from, to)| from.dimension == to.dimension);
+    move |value| units.map_or(value, |(from, to)| from.convert(value, &to))
+}
+
+/// Index of the channel in `names` to overlay on a channel called `name`:
+/// the same name, or else the same normalized name
+pub fn match_channel(
+    name: &str,
+    names: &[String],
+    normalize: impl Fn(&str) -> String,
+) -> Option<usize> {
+    names
+        .iter()
+        .position(|n| n.eq_ignore_ascii_case(name))
+        .or_else(|| {
+            let normalized = normalize(name);
+            names
+                .iter()
+                .position(|n| normalize(n).eq_ignore_ascii_case(&normalized))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger_crossing() {
+        let times = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let tps = [10.0, 50.0, f64::NAN, 100.0, 20.0];
+        let rising = Trigger::default();
+        assert_eq!(rising.first_crossing(&times, &tps), Some(2.6));
+        let falling = Trigger {
+            threshold: 60.0,
+            edge: TriggerEdge::Falling,
+        };
+        assert_eq!(falling.first_crossing(&times, &tps), Some(3.5));
+        assert_eq!(rising.first_crossing(&times, &[0.0; 5]), None);
+    }
+
+    #[test]
+    fn test_distance_mapping() {
+        // Both cover 10 m/s after their zero points; the overlay starts
+        // 2 s later and drives twice as fast
+        let times: Vec<f64> = (0..=20).map(|i| i as f64).collect();
+        let reference: Vec<f64> = times
+            .iter()
+            .map(|&t| if t < 5.0 { 0.0 } else { 10.0 })
+            .collect();
+        let overlay: Vec<f64> = times
+            .iter()
+            .map(|&t| if t < 7.0 { 0.0 } else { 20.0 })
+            .collect();
+        let map = TimeMap::Distance {
+            reference: DistanceTrace::new(&times, &reference),
+            overlay: DistanceTrace::new(&times, &overlay),
+            reference_zero: 5.0,
+            overlay_zero: 7.0,
+            shift: 0.0,
+        };
+        // Before the zero points the logs are lined up by time
+        assert_eq!(map.to_reference(3.0), Some(1.0));
+        // 60 m past its zero point at 10 s, which the reference covers at 11 s
+        let t = map.to_reference(10.0).unwrap();
+        assert!((t - 11.0).abs() < 1e-9);
+        assert!((map.to_overlay(t).unwrap() - 10.0).abs() < 1e-9);
+        // The reference never covers the overlay's full distance
+        assert_eq!(map.to_reference(20.0), None);
+        assert_eq!(TimeMap::Offset(1.5).to_overlay(3.0), Some(1.5));
+    }
+
+    #[test]
+    fn test_interpolate_and_delta() {
+        let times = [0.0, 1.0, 2.0];
+        let values = [10.0, 20.0, f64::NAN];
+        assert_eq!(interpolate(&times, &values, 0.25), Some(12.5));
+        assert_eq!(interpolate(&times, &values, 0.0), Some(10.0));
+        assert_eq!(interpolate(&times, &values, 1.5), None);
+        assert_eq!(interpolate(&times, &values, -1.0), None);
+
+        let delta = delta_trace(
+            &[[1.0, 12.0], [3.0, 12.0]],
+            &times,
+            &values,
+            &TimeMap::Offset(1.0),
+        );
+        assert_eq!(delta[0], [1.0, -2.0]);
+        assert!(delta[1][1].is_nan());
+    }
+
+    #[test]
+    fn test_match_channel() {
+        let names = vec!["Engine RPM".to_string(), "TPS".to_string()];
+        let normalize = |n: &str| {
+            if n.contains("RPM") {
+                "RPM".to_string()
+            } else {
+                n.to_string()
+            }
+        };
+        assert_eq!(match_channel("tps", &names, normalize), Some(1));
+        assert_eq!(match_channel("RPM", &names, normalize), Some(0));
+        assert_eq!(match_channel("MAP", &names, normalize), None);
+    }
+}
@@ -8,7 +8,7 @@ use serde::Serialize;
 use std::error::Error;
 use std::path::Path;
 
-use super::types::{Log, Meta, Value};
+use super::types::{Log, LogData, Marker, MarkerKind, Meta, Value};
 
 /// AIM channel metadata
 #[derive(Clone, Debug, Serialize)]
@@ -52,7 +52,7 @@ impl Aim {
     }
 
     /// Parse XRK binary data using pure Rust implementation
-    fn parse_binary(data: &[u8]) -> Result<Log, Box<dyn Error>> {
+    pub fn parse_binary(data: &[u8]) -> Result<Log, Box<dyn Error>> {
         if !Self::detect(data) {
             return Err("Not a valid AIM XRK file".into());
         }
@@ -67,9 +67,14 @@ impl Aim {
         tracing::info!("Found {} channels", channels.len());
 
         // Parse metadata from footer
-        let meta = Self::parse_metadata(data)?;
+        let mut meta = Self::parse_metadata(data)?;
         tracing::info!("Vehicle: {}, Track: {}", meta.vehicle, meta.track);
 
+        // Lap/beacon crossings
+        let markers = Self::parse_laps(data);
+        meta.lap_count = markers.len();
+        tracing::info!("Found {} laps", markers.len());
+
         // Parse channel data
         let (times, channel_data) = Self::parse_channel_data(data, channels.len())?;
         tracing::info!("Parsed {} data points", times.len());
@@ -82,6 +87,7 @@ impl Aim {
                 .collect(),
             times,
             data: channel_data,
+            markers,
         })
     }
 
@@ -206,14 +212,47 @@ impl Aim {
         Ok(meta)
     }
 
+    /// Parse lap records from <hLAP> sections
+    ///
+    /// Each 20-byte payload holds the lap number (u16 at offset 2), the lap
+    /// duration (u32 at offset 4) and the lap end time (u32 at offset 16), in
+    /// milliseconds since the start of the recording. A marker is placed at
+    /// the beacon crossing that starts each lap.
+    fn parse_laps(data: &[u8]) -> Vec<Marker> {
+        let mut markers = Vec::new();
+        let mut offset = 0;
+
+        while let Some(pos) = Self::find_pattern(data, b"<hLAP\x00", offset) {
+            offset = pos + 6;
+
+            // Skip tag, length and version bytes
+            let payload = pos + 12;
+            if payload + 20 > data.len() {
+                break;
+            }
+
+            let read_u32 = |at: usize| {
+                u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+            };
+            let lap = u16::from_le_bytes([data[payload + 2], data[payload + 3]]);
+            let duration_ms = read_u32(payload + 4);
+            let end_ms = read_u32(payload + 16);
+
+            let start = end_ms.saturating_sub(duration_ms) as f64 / 1000.0;
+            markers.push(Marker::new(MarkerKind::Lap, format!("Lap {}", lap), start));
+        }
+
+        markers.sort_by(|a, b| a.time.total_cmp(&b.time));
+        markers
+    }
+
     /// Parse channel data samples from )(G records
-    #[allow(clippy::type_complexity)]
     fn parse_channel_data(
         data: &[u8],
         channel_count: usize,
-    ) -> Result<(Vec<f64>, Vec<Vec<Value>>), Box<dyn Error>> {
+    ) -> Result<(Vec<f64>, LogData), Box<dyn Error>> {
         let mut times = Vec::new();
-        let mut all_data: Vec<Vec<Value>> = Vec::new();
+        let mut all_data = LogData::new(channel_count);
 
         if channel_count == 0 {
             return Ok((times, all_data));
@@ -253,11 +292,11 @@ impl Aim {
                                 data[float_offset + 3],
                             ]);
 
-                            // Only include finite values
+                            // Non-finite values are dropouts, not zeros
                             if value.is_finite() {
                                 values.push(value);
                             } else {
-                                values.push(0.0);
+                                values.push(f32::NAN);
                             }
                         }
                     }
@@ -269,18 +308,9 @@ impl Aim {
                         let time_sec = record_count as f64 / sample_rate_hz;
                         times.push(time_sec);
 
-                        // Create data row for all channels
-                        let mut row = Vec::with_capacity(channel_count);
-                        for ch_idx in 0..channel_count {
-                            // Map channel to value - use 0.0 for channels beyond available values
-                            let value = if ch_idx < values.len() {
-                                values[ch_idx] as f64
-                            } else {
-                                0.0
-                            };
-                            row.push(Value::Float(value));
-                        }
-                        all_data.push(row);
+                        // Create data row for all channels - channels beyond
+                        // the available values are filled with 0.0
+                        all_data.push_row(values.iter().map(|&v| Value::Float(v as f64)));
                         record_count += 1;
                     }
                 }
@@ -390,6 +420,33 @@ mod tests {
         assert_eq!(Aim::find_pattern(data, b"hello", 0), Some(0));
     }
 
+    #[test]
+    fn test_parse_laps() {
+        let lap_record = |lap: u16, duration_ms: u32, end_ms: u32| {
+            let mut record = b"<hLAP\x00\x14\x00\x00\x00\x01>".to_vec();
+            let mut payload = [0u8; 20];
+            payload[2..4].copy_from_slice(&lap.to_le_bytes());
+            payload[4..8].copy_from_slice(&duration_ms.to_le_bytes());
+            payload[16..20].copy_from_slice(&end_ms.to_le_bytes());
+            record.extend_from_slice(&payload);
+            record.extend_from_slice(b"<LAP\x00>");
+            record
+        };
+
+        let mut data = b"<hCNF\x00".to_vec();
+        data.extend(lap_record(1, 95_500, 120_000));
+        data.extend(lap_record(2, 94_250, 214_250));
+
+        let markers = Aim::parse_laps(&data);
+        assert_eq!(
+            markers,
+            vec![
+                Marker::new(MarkerKind::Lap, "Lap 1", 24.5),
+                Marker::new(MarkerKind::Lap, "Lap 2", 120.0),
+            ]
+        );
+    }
+
     #[test]
     fn test_read_null_terminated_string() {
         let data = b"Hello\x00World";
@@ -460,7 +517,7 @@ mod tests {
                     let has_non_zero = log
                         .data
                         .iter()
-                        .any(|row| row.iter().any(|v| v.as_f64().abs() > 0.0001));
+                        .any(|row| row.iter().any(|v| v.abs() > 0.0001));
                     if !has_non_zero {
                         eprintln!("  Warning: No non-zero values found");
                         continue;
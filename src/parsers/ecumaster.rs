@@ -6,7 +6,7 @@
 use serde::Serialize;
 use std::error::Error;
 
-use super::types::{Channel, Log, Meta, Parseable, Value};
+use super::types::{Channel, Log, LogData, Meta, Parseable, Value};
 
 /// ECUMaster log file metadata
 #[derive(Clone, Debug, Default, Serialize)]
@@ -184,7 +184,6 @@ impl Parseable for EcuMaster {
 
         let mut channels: Vec<Channel> = Vec::with_capacity(50);
         let mut times: Vec<f64> = Vec::with_capacity(estimated_data_rows);
-        let mut data: Vec<Vec<Value>> = Vec::with_capacity(estimated_data_rows);
 
         let mut lines = file_contents.lines();
 
@@ -206,6 +205,8 @@ impl Parseable for EcuMaster {
             channels.push(Channel::EcuMaster(channel));
         }
 
+        let mut data = LogData::with_capacity(channels.len(), estimated_data_rows);
+
         // Track last known values for sparse data interpolation
         let mut last_values: Vec<Option<f64>> = vec![None; channels.len()];
 
@@ -233,9 +234,9 @@ impl Parseable for EcuMaster {
                     let part = part.trim();
 
                     if part.is_empty() {
-                        // Empty value - use last known value or 0
-                        let value = last_values.get(idx).and_then(|v| *v).unwrap_or(0.0);
-                        row_values.push(Value::Float(value));
+                        // Empty value - use last known value, missing before the first
+                        let value = last_values.get(idx).and_then(|v| *v);
+                        row_values.push(value.map_or(Value::MISSING, Value::Float));
                     } else if let Ok(val) = part.parse::<f64>() {
                         // Valid numeric value
                         if idx < last_values.len() {
@@ -243,20 +244,20 @@ impl Parseable for EcuMaster {
                         }
                         row_values.push(Value::Float(val));
                     } else {
-                        // Non-numeric value - use last known or 0
-                        let value = last_values.get(idx).and_then(|v| *v).unwrap_or(0.0);
-                        row_values.push(Value::Float(value));
+                        // Non-numeric value - use last known, missing before the first
+                        let value = last_values.get(idx).and_then(|v| *v);
+                        row_values.push(value.map_or(Value::MISSING, Value::Float));
                     }
                 }
 
                 // Pad row to match channel count if needed
                 while row_values.len() < channels.len() {
                     let idx = row_values.len();
-                    let value = last_values.get(idx).and_then(|v| *v).unwrap_or(0.0);
-                    row_values.push(Value::Float(value));
+                    let value = last_values.get(idx).and_then(|v| *v);
+                    row_values.push(value.map_or(Value::MISSING, Value::Float));
                 }
 
-                data.push(row_values);
+                data.push_row(row_values);
             }
         }
 
@@ -274,6 +275,7 @@ impl Parseable for EcuMaster {
             channels,
             times,
             data,
+            markers: Vec::new(),
         })
     }
 }
@@ -329,14 +331,14 @@ mod tests {
         assert_eq!(log.data.len(), 3);
 
         // Check first row
-        assert_eq!(log.data[0][0].as_f64(), 1000.0);
-        assert_eq!(log.data[0][1].as_f64(), 10.5);
-        assert_eq!(log.data[0][2].as_f64(), 15.0);
+        assert_eq!(log.data.row(0)[0], 1000.0);
+        assert_eq!(log.data.row(0)[1], 10.5);
+        assert_eq!(log.data.row(0)[2], 15.0);
 
         // Check sparse data handling (empty values use previous)
-        assert_eq!(log.data[1][0].as_f64(), 1050.0);
-        assert_eq!(log.data[1][1].as_f64(), 10.5); // Previous value
-        assert_eq!(log.data[1][2].as_f64(), 15.5);
+        assert_eq!(log.data.row(1)[0], 1050.0);
+        assert_eq!(log.data.row(1)[1], 10.5); // Previous value
+        assert_eq!(log.data.row(1)[2], 15.5);
 
         // Check units
         assert_eq!(log.channels[0].unit(), "RPM");
@@ -344,6 +346,21 @@ mod tests {
         assert_eq!(log.channels[2].unit(), "°");
     }
 
+    #[test]
+    fn test_missing_before_first_sample() {
+        let sample = "TIME;engine/rpm;sensors/tps1\n\
+                      0.000;1000;\n\
+                      0.020;1050;12.0\n\
+                      0.040;1100;\n";
+
+        let log = EcuMaster.parse(sample).unwrap();
+
+        // No value is known for tps1 until the second row
+        assert!(log.data.row(0)[1].is_nan());
+        assert_eq!(log.data.row(1)[1], 12.0);
+        assert_eq!(log.data.row(2)[1], 12.0);
+    }
+
     #[test]
     fn test_unit_inference() {
         // Temperature
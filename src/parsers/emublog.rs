@@ -0,0 +1,304 @@
+//! ECUMaster EMU Black native (.emublog) binary format parser
+//!
+//! EMU Client stores the ECU's internal log as a gzip-compressed stream of
+//! fixed-size frames (decompression is handled by the parser registry).
+//! Format structure based on reverse engineering of EMU Black logs:
+//! - Header (12 bytes): magic `0x22446082`, format version, frame count
+//! - Frames: fixed size records sampled at 25 Hz, each starting with a
+//!   16-bit frame counter followed by the ECU's realtime values
+//!
+//! The frame layout is not documented by ECUMaster, so only channels whose
+//! position and scaling have been verified are imported.
+
+use std::error::Error;
+
+use super::ecumaster::{EcuMasterChannel, EcuMasterMeta};
+use super::types::{Channel, Log, LogData, Meta, Parseable, Value};
+
+/// File magic (little-endian u32 at offset 0)
+const EMUBLOG_MAGIC: u32 = 0x2244_6082;
+
+/// Size of the file header
+const HEADER_SIZE: usize = 12;
+
+/// Frame rate of the internal logger
+const FRAME_RATE_HZ: f64 = 25.0;
+
+/// Frame size for each known format version
+const FRAME_SIZES: &[(u32, usize)] = &[(5, 308)];
+
+/// Storage type of a field in a frame
+#[derive(Clone, Copy, Debug)]
+enum FieldType {
+    U8,
+    I8,
+    U16,
+}
+
+/// A decoded channel within a frame
+struct FieldDef {
+    /// Channel path, matching the naming of EMU Client CSV exports
+    path: &'static str,
+    offset: usize,
+    field_type: FieldType,
+    scale: f64,
+    unit: &'static str,
+}
+
+/// Known channels in the version 5 frame
+const FIELDS_V5: &[FieldDef] = &[
+    FieldDef {
+        path: "engine/rpm",
+        offset: 2,
+        field_type: FieldType::U16,
+        scale: 1.0,
+        unit: "RPM",
+    },
+    FieldDef {
+        path: "sensors/map",
+        offset: 4,
+        field_type: FieldType::U16,
+        scale: 1.0,
+        unit: "kPa",
+    },
+    FieldDef {
+        path: "sensors/tps1",
+        offset: 6,
+        field_type: FieldType::U8,
+        scale: 1.0,
+        unit: "%",
+    },
+    FieldDef {
+        path: "sensors/iat",
+        offset: 7,
+        field_type: FieldType::I8,
+        scale: 1.0,
+        unit: "°C",
+    },
+    FieldDef {
+        path: "sensors/batteryVoltage",
+        offset: 8,
+        field_type: FieldType::U16,
+        scale: 0.027,
+        unit: "V",
+    },
+    FieldDef {
+        path: "ignition/angle",
+        offset: 13,
+        field_type: FieldType::U8,
+        scale: 0.5,
+        unit: "°",
+    },
+    FieldDef {
+        path: "fueling/lambdaTarget",
+        offset: 43,
+        field_type: FieldType::U8,
+        scale: 1.0 / 128.0,
+        unit: "λ",
+    },
+    FieldDef {
+        path: "sensors/lambda",
+        offset: 189,
+        field_type: FieldType::U8,
+        scale: 0.01,
+        unit: "λ",
+    },
+];
+
+impl FieldDef {
+    fn read(&self, frame: &[u8]) -> f64 {
+        let raw = match self.field_type {
+            FieldType::U8 => frame[self.offset] as f64,
+            FieldType::I8 => frame[self.offset] as i8 as f64,
+            FieldType::U16 => {
+                u16::from_le_bytes([frame[self.offset], frame[self.offset + 1]]) as f64
+            }
+        };
+        raw * self.scale
+    }
+}
+
+/// ECUMaster EMU Black native log parser
+pub struct EmuBlog;
+
+impl EmuBlog {
+    /// Detect if (decompressed) data is an EMU Black native log
+    pub fn detect(data: &[u8]) -> bool {
+        data.len() >= HEADER_SIZE && Self::read_u32(data, 0) == EMUBLOG_MAGIC
+    }
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    }
+
+    /// Parse the .emublog binary format
+    pub fn parse_binary(data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        if !Self::detect(data) {
+            return Err("Invalid EMU Black log header".into());
+        }
+
+        let version = Self::read_u32(data, 4);
+        let frame_count = Self::read_u32(data, 8) as usize;
+        let frame_size = FRAME_SIZES
+            .iter()
+            .find(|(v, _)| *v == version)
+            .map(|(_, size)| *size)
+            .ok_or_else(|| format!("Unsupported EMU Black log version {}", version))?;
+
+        // Recover what we can from truncated logs (e.g. power lost while logging)
+        let available = (data.len() - HEADER_SIZE) / frame_size;
+        if available < frame_count {
+            tracing::warn!(
+                "EMU Black log truncated: {} of {} frames present",
+                available,
+                frame_count
+            );
+        }
+        let frame_count = frame_count.min(available);
+
+        let channels: Vec<Channel> = FIELDS_V5
+            .iter()
+            .map(|field| {
+                Channel::EcuMaster(EcuMasterChannel {
+                    path: field.path.to_string(),
+                    name: field
+                        .path
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or(field.path)
+                        .to_string(),
+                    unit: field.unit.to_string(),
+                })
+            })
+            .collect();
+
+        let mut times = Vec::with_capacity(frame_count);
+        let mut data_rows = LogData::with_capacity(FIELDS_V5.len(), frame_count);
+
+        // The 16-bit frame counter wraps and skips frames when logging pauses,
+        // so accumulate the wrapped difference to build the timeline
+        let mut frame_index: u64 = 0;
+        let mut last_counter: Option<u16> = None;
+
+        for frame in data[HEADER_SIZE..]
+            .chunks_exact(frame_size)
+            .take(frame_count)
+        {
+            let counter = u16::from_le_bytes([frame[0], frame[1]]);
+            if let Some(last) = last_counter {
+                frame_index += counter.wrapping_sub(last) as u64;
+            }
+            last_counter = Some(counter);
+
+            times.push(frame_index as f64 / FRAME_RATE_HZ);
+            data_rows.push_row(
+                FIELDS_V5
+                    .iter()
+                    .map(|field| Value::Float(field.read(frame))),
+            );
+        }
+
+        tracing::info!(
+            "Parsed EMU Black log: version {}, {} channels, {} frames",
+            version,
+            channels.len(),
+            data_rows.len()
+        );
+
+        Ok(Log {
+            meta: Meta::EcuMaster(EcuMasterMeta {
+                channel_count: channels.len(),
+                data_points: data_rows.len(),
+            }),
+            channels,
+            times,
+            data: data_rows,
+            markers: Vec::new(),
+        })
+    }
+}
+
+impl Parseable for EmuBlog {
+    fn parse(&self, _data: &str) -> Result<Log, Box<dyn Error>> {
+        // This method is for text-based parsing
+        // EMU Black logs are binary, so this will return an error
+        Err("EMU Black .emublog files are binary format. Use parse_binary() instead.".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_frame(counter: u16, rpm: u16, map: u16) -> Vec<u8> {
+        let mut frame = vec![0u8; 308];
+        frame[0..2].copy_from_slice(&counter.to_le_bytes());
+        frame[2..4].copy_from_slice(&rpm.to_le_bytes());
+        frame[4..6].copy_from_slice(&map.to_le_bytes());
+        frame[7] = (-5i8) as u8;
+        frame[8..10].copy_from_slice(&517u16.to_le_bytes());
+        frame
+    }
+
+    fn build_log(frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&EMUBLOG_MAGIC.to_le_bytes());
+        data.extend_from_slice(&5u32.to_le_bytes());
+        data.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+        for frame in frames {
+            data.extend_from_slice(frame);
+        }
+        data
+    }
+
+    #[test]
+    fn test_detect() {
+        assert!(EmuBlog::detect(&build_log(&[])));
+        assert!(!EmuBlog::detect(b"MLVLG\x00\x00\x01\x00\x00\x00\x00"));
+        assert!(!EmuBlog::detect(&[0x82, 0x60, 0x44]));
+    }
+
+    #[test]
+    fn test_parse_frames() {
+        let log = EmuBlog::parse_binary(&build_log(&[
+            build_frame(100, 900, 35),
+            build_frame(101, 950, 36),
+        ]))
+        .unwrap();
+
+        assert_eq!(log.channels.len(), FIELDS_V5.len());
+        assert_eq!(log.channels[0].name(), "rpm");
+        assert_eq!(log.times, vec![0.0, 0.04]);
+        assert_eq!(log.data.row(1)[0], 950.0);
+        assert_eq!(log.data.row(1)[1], 36.0);
+        assert_eq!(log.data.row(0)[3], -5.0);
+        assert!((log.data.row(0)[4] - 13.959).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_counter_wrap_and_gaps() {
+        let log = EmuBlog::parse_binary(&build_log(&[
+            build_frame(65535, 900, 35),
+            build_frame(0, 900, 35),
+            build_frame(25, 900, 35),
+        ]))
+        .unwrap();
+
+        assert_eq!(log.times, vec![0.0, 0.04, 1.04]);
+    }
+
+    #[test]
+    fn test_truncated_and_unknown_version() {
+        let mut data = build_log(&[build_frame(1, 900, 35), build_frame(2, 900, 35)]);
+        data.truncate(data.len() - 10);
+        assert_eq!(EmuBlog::parse_binary(&data).unwrap().data.len(), 1);
+
+        data[4] = 9;
+        assert!(EmuBlog::parse_binary(&data).is_err());
+    }
+}
@@ -0,0 +1,653 @@
+//! ECUMaster EMU PRO native (.emuprolog) binary format parser
+//!
+//! EMU PRO Client stores the ECU's internal log as a (usually gzip-compressed)
+//! stream of records (decompression is handled by the parser registry).
+//! Format structure based on reverse engineering of EMU PRO logs:
+//! - Header (128 bytes): magic `\xefeml`, firmware and format versions
+//! - Records, each aligned to 8 bytes and starting with a 6-byte header
+//!   `[type][flags][length u16 BE][checksum u16]`:
+//!   - `0xe3` metadata: zlib-compressed channel tree (units, enums, structs,
+//!     groups) with the storage type and scaling of every channel
+//!   - `0xec` schema: which channels are logged, and at which rate class
+//!   - `0xed` data: schema id, 26-bit microsecond timestamp and values
+//!
+//! Every data record starts with the fast rate classes (RPM, loads, ignition,
+//! lambda, MAP, TPS, ...) in a fixed layout; slower channels are spread over
+//! the records in a layout that is not understood yet, so only the fast
+//! channels are imported.
+
+use std::error::Error;
+use std::io::Read;
+
+use super::ecumaster::{EcuMasterChannel, EcuMasterMeta};
+use super::types::{Channel, Log, LogData, Meta, Parseable, Value};
+
+/// File magic at offset 0
+const EMUPROLOG_MAGIC: &[u8] = b"\xefeml";
+
+/// Size of the file header
+const HEADER_SIZE: usize = 0x80;
+
+/// Size of the header in front of every record
+const RECORD_HEADER_SIZE: usize = 6;
+
+/// Record types
+const RECORD_METADATA: u8 = 0xe3;
+const RECORD_SCHEMA: u8 = 0xec;
+const RECORD_DATA: u8 = 0xed;
+
+/// Data record prefix: record header, schema id (u16) and timestamp (u32)
+const DATA_HEADER_SIZE: usize = 12;
+
+/// The timestamp is a free-running microsecond counter of this many bits
+const TIMESTAMP_BITS: u32 = 26;
+
+/// Offset of the string tables in the decompressed metadata
+const METADATA_HEADER_SIZE: usize = 18;
+
+/// Offset of the channel entries within a schema copy
+const SCHEMA_ENTRIES_OFFSET: usize = 104;
+
+/// Rate classes stored at the start of every data record, in storage order
+const FAST_RATE_CLASSES: &[u8] = &[3, 4];
+
+/// Struct nesting limit, so a malformed metadata block cannot recurse forever
+const MAX_STRUCT_DEPTH: usize = 16;
+
+/// Storage type of a channel entry (low nibble of the type byte)
+const TYPE_ENUM: u8 = 8;
+const TYPE_STRUCT: u8 = 9;
+
+/// A channel entry from the metadata: a struct member or a group entry
+struct Field {
+    base_type: u8,
+    /// Enum or struct index for enum and struct fields
+    reference: usize,
+    /// Element count for arrays (`$` in the ident is the element number)
+    array_len: Option<usize>,
+    unit: usize,
+    scale: f64,
+    offset: f64,
+    ident: String,
+}
+
+/// A scalar channel after expanding structs and arrays
+struct Leaf {
+    path: String,
+    unit: usize,
+    size: usize,
+    signed: bool,
+    scale: f64,
+    offset: f64,
+}
+
+impl Leaf {
+    fn read(&self, body: &[u8], pos: usize) -> f64 {
+        let bytes = &body[pos..pos + self.size];
+        let raw = match (self.size, self.signed) {
+            (1, false) => bytes[0] as f64,
+            (1, true) => bytes[0] as i8 as f64,
+            (2, false) => u16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+            (2, true) => i16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+            (_, false) => u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            (_, true) => i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+        };
+        raw * self.scale + self.offset
+    }
+}
+
+/// Decoded metadata block
+struct Metadata {
+    units: Vec<String>,
+    /// Channels in definition order
+    leaves: Vec<Leaf>,
+}
+
+/// Bounds-checked reader over the metadata block
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or("Truncated EMU PRO metadata")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let rest = &self.data[self.pos.min(self.data.len())..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("Truncated EMU PRO metadata")?;
+        let s = String::from_utf8_lossy(&rest[..len]).into_owned();
+        self.pos += len + 1;
+        Ok(s)
+    }
+
+    fn field(&mut self) -> Result<Field, String> {
+        let type_byte = self.u8()?;
+        let flags = self.u8()?;
+        let base_type = type_byte & 0x0f;
+        if base_type > TYPE_STRUCT {
+            return Err(format!("Unknown EMU PRO channel type {:#04x}", type_byte));
+        }
+
+        let reference = if base_type == TYPE_ENUM || base_type == TYPE_STRUCT {
+            self.u8()? as usize
+        } else {
+            0
+        };
+        let array_len = if type_byte & 0x10 != 0 {
+            Some(self.u8()? as usize)
+        } else {
+            None
+        };
+        let unit = if flags & 0x01 != 0 {
+            self.u8()? as usize
+        } else {
+            0
+        };
+
+        // Limits are stored in the field's own width (enums as one byte)
+        let limit_size = match base_type {
+            2 | 3 => 2,
+            5..=7 => 4,
+            TYPE_STRUCT => 0,
+            _ => 1,
+        };
+        if flags & 0x02 != 0 {
+            self.take(limit_size)?;
+        }
+        if flags & 0x04 != 0 {
+            self.take(limit_size)?;
+        }
+
+        let div = if flags & 0x08 != 0 { self.u16()? } else { 1 };
+        let mul = if flags & 0x10 != 0 { self.u16()? } else { 1 };
+        let offset = if flags & 0x20 != 0 {
+            self.u16()? as i16
+        } else {
+            0
+        };
+        if flags & 0x80 != 0 {
+            // Display decimals
+            self.u8()?;
+        }
+
+        let ident = self.string()?;
+        if type_byte & 0x80 != 0 {
+            // Display name, EMU PRO Client CSV exports use the ident path
+            self.string()?;
+        }
+
+        Ok(Field {
+            base_type,
+            reference,
+            array_len,
+            unit,
+            scale: mul as f64 / div.max(1) as f64,
+            offset: offset as f64,
+            ident,
+        })
+    }
+}
+
+/// ECUMaster EMU PRO native log parser
+pub struct EmuProLog;
+
+impl EmuProLog {
+    /// Detect if (decompressed) data is an EMU PRO native log
+    pub fn detect(data: &[u8]) -> bool {
+        data.len() >= HEADER_SIZE && data.starts_with(EMUPROLOG_MAGIC)
+    }
+
+    /// Split the data into `(type, record)` pairs, records including their header
+    fn records(data: &[u8]) -> Vec<(u8, &[u8])> {
+        let mut records = Vec::new();
+        let mut pos = HEADER_SIZE;
+        while pos + RECORD_HEADER_SIZE <= data.len() {
+            let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            if len < RECORD_HEADER_SIZE || pos + len > data.len() {
+                tracing::warn!("EMU PRO log truncated at offset {}", pos);
+                break;
+            }
+            records.push((data[pos], &data[pos..pos + len]));
+            pos = (pos + len + 7) & !7;
+        }
+        records
+    }
+
+    /// Decode the channel tree of the metadata record
+    fn parse_metadata(record: &[u8]) -> Result<Metadata, String> {
+        let mut data = Vec::new();
+        flate2::read::ZlibDecoder::new(&record[RECORD_HEADER_SIZE..])
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to decompress EMU PRO metadata: {}", e))?;
+
+        let mut reader = Reader {
+            data: &data,
+            pos: 4,
+        };
+        let unit_count = reader.u8()? as usize;
+        let path_count = reader.u8()? as usize;
+        let group_count = reader.u8()? as usize;
+        let enum_count = reader.u8()? as usize;
+        reader.u16()?; // total number of entries
+        let struct_count = reader.u8()? as usize;
+        reader.pos = METADATA_HEADER_SIZE;
+
+        let units = (0..unit_count)
+            .map(|_| reader.string())
+            .collect::<Result<Vec<_>, _>>()?;
+        let paths = (0..path_count)
+            .map(|_| reader.string())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Enums only matter for their storage: kind bit 0x02 means 16-bit
+        // (unless it is a bit field), bit 0x01 means signed
+        let mut enums = Vec::with_capacity(enum_count);
+        for _ in 0..enum_count {
+            let kind = reader.u8()?;
+            reader.string()?;
+            let value_size = if kind & 0x02 != 0 && kind & 0x10 == 0 {
+                2
+            } else {
+                1
+            };
+            for _ in 0..reader.u8()? {
+                reader.take(value_size)?;
+                reader.string()?;
+            }
+            let size = if kind & 0x02 != 0 { 2 } else { 1 };
+            enums.push((size, kind & 0x01 != 0));
+        }
+
+        let struct_sizes = (0..struct_count)
+            .map(|_| {
+                let count = reader.u8()? as usize;
+                reader.string()?;
+                Ok(count)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let groups = (0..group_count)
+            .map(|_| {
+                let count = reader.u8()? as usize;
+                let path = reader.u8()? as usize;
+                reader.string()?;
+                Ok((count, path))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let structs = struct_sizes
+            .iter()
+            .map(|&count| (0..count).map(|_| reader.field()).collect())
+            .collect::<Result<Vec<Vec<Field>>, _>>()?;
+
+        let mut leaves = Vec::new();
+        for (count, path) in groups {
+            let fields = (0..count)
+                .map(|_| reader.field())
+                .collect::<Result<Vec<_>, _>>()?;
+            let prefix = paths.get(path).map_or("", |p| p.trim_end_matches('/'));
+            Self::flatten(&fields, &structs, &enums, prefix, 0, &mut leaves)?;
+        }
+
+        Ok(Metadata { units, leaves })
+    }
+
+    /// Expand struct members and array elements into scalar channels
+    fn flatten(
+        fields: &[Field],
+        structs: &[Vec<Field>],
+        enums: &[(usize, bool)],
+        prefix: &str,
+        depth: usize,
+        leaves: &mut Vec<Leaf>,
+    ) -> Result<(), String> {
+        if depth > MAX_STRUCT_DEPTH {
+            return Err("EMU PRO metadata structs are nested too deeply".into());
+        }
+
+        for field in fields {
+            for element in 1..=field.array_len.unwrap_or(1) {
+                let ident = if field.array_len.is_some() {
+                    field.ident.replace('$', &element.to_string())
+                } else {
+                    field.ident.clone()
+                };
+                // `^` is the value of the struct itself
+                let path = match (ident.as_str(), prefix) {
+                    ("^", _) => prefix.to_string(),
+                    (_, "") => ident,
+                    _ => format!("{}/{}", prefix, ident),
+                };
+
+                let (size, signed) = match field.base_type {
+                    TYPE_STRUCT => {
+                        let members = structs
+                            .get(field.reference)
+                            .ok_or("Invalid EMU PRO struct reference")?;
+                        Self::flatten(members, structs, enums, &path, depth + 1, leaves)?;
+                        continue;
+                    }
+                    TYPE_ENUM => *enums
+                        .get(field.reference)
+                        .ok_or("Invalid EMU PRO enum reference")?,
+                    0 | 4 => (1, false),
+                    1 => (1, true),
+                    2 => (2, false),
+                    3 => (2, true),
+                    6 => (4, true),
+                    _ => (4, false),
+                };
+                leaves.push(Leaf {
+                    path,
+                    unit: field.unit,
+                    size,
+                    signed,
+                    scale: field.scale,
+                    offset: field.offset,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode the schema record into the schema id and the fast channels
+    /// (indices into `leaves`) in storage order
+    fn parse_schema(record: &[u8], leaves: &[Leaf]) -> Result<(u16, Vec<usize>), String> {
+        let truncated = || "Truncated EMU PRO schema".to_string();
+        let header = record.get(..RECORD_HEADER_SIZE + 2).ok_or_else(truncated)?;
+        let schema_id = u16::from_be_bytes([header[4], header[5]]);
+
+        // The schema is stored twice; use the first copy
+        let copy = &record[RECORD_HEADER_SIZE + 2..];
+        let copy_size = copy
+            .get(..2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+            .ok_or_else(truncated)?;
+        let entries = copy
+            .get(SCHEMA_ENTRIES_OFFSET..copy_size)
+            .ok_or_else(truncated)?;
+
+        // Schema indices number the 8-bit channels, then the 32-bit channels
+        // (two slots each), then the 16-bit channels
+        let mut by_size: [Vec<usize>; 3] = Default::default();
+        for (i, leaf) in leaves.iter().enumerate() {
+            by_size[match leaf.size {
+                1 => 0,
+                4 => 1,
+                _ => 2,
+            }]
+            .push(i);
+        }
+        let leaf_at = |index: usize| {
+            let (l8, l32, l16) = (&by_size[0], &by_size[1], &by_size[2]);
+            if index < l8.len() {
+                l8.get(index)
+            } else if index < l8.len() + 2 * l32.len() {
+                l32.get((index - l8.len()) / 2)
+            } else {
+                l16.get(index - l8.len() - 2 * l32.len())
+            }
+            .copied()
+        };
+
+        let mut fast = Vec::new();
+        for &class in FAST_RATE_CLASSES {
+            let mut channels: Vec<usize> = Vec::new();
+            for entry in entries.chunks_exact(3) {
+                let value = u16::from_be_bytes([entry[0], entry[1]]);
+                if (value & 0x0f) as u8 != class {
+                    continue;
+                }
+                let first = (value >> 4) as usize;
+                for index in first..first + entry[2] as usize {
+                    let leaf = leaf_at(index).ok_or("Invalid EMU PRO schema entry")?;
+                    if !channels.contains(&leaf) {
+                        channels.push(leaf);
+                    }
+                }
+            }
+            // Wider values are stored first within a rate class
+            channels.sort_by_key(|&i| std::cmp::Reverse(leaves[i].size));
+            fast.extend(channels);
+        }
+
+        Ok((schema_id, fast))
+    }
+
+    /// Parse the .emuprolog binary format
+    pub fn parse_binary(data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        if !Self::detect(data) {
+            return Err("Invalid EMU PRO log header".into());
+        }
+
+        let records = Self::records(data);
+        let find = |record_type: u8| {
+            records
+                .iter()
+                .find(|(t, _)| *t == record_type)
+                .map(|(_, record)| *record)
+        };
+        let metadata =
+            Self::parse_metadata(find(RECORD_METADATA).ok_or("EMU PRO log has no metadata")?)?;
+        let (schema_id, fast) = Self::parse_schema(
+            find(RECORD_SCHEMA).ok_or("EMU PRO log has no schema")?,
+            &metadata.leaves,
+        )?;
+        let fast_size: usize = fast.iter().map(|&i| metadata.leaves[i].size).sum();
+
+        let channels: Vec<Channel> = fast
+            .iter()
+            .map(|&i| {
+                let leaf = &metadata.leaves[i];
+                Channel::EcuMaster(EcuMasterChannel {
+                    path: leaf.path.clone(),
+                    name: leaf
+                        .path
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or(&leaf.path)
+                        .to_string(),
+                    unit: metadata.units.get(leaf.unit).cloned().unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        let mut times = Vec::new();
+        let mut data_rows = LogData::with_capacity(channels.len(), records.len());
+
+        // The timestamp counter wraps every 2^26 µs (about 67 s). A jump of
+        // more than half the range is a counter reset rather than a pause, so
+        // the previous record interval is used instead
+        let counter_mask = (1u32 << TIMESTAMP_BITS) - 1;
+        let mut micros: u64 = 0;
+        let mut last_interval: u64 = 0;
+        let mut last_counter: Option<u32> = None;
+
+        for (_, record) in records.iter().filter(|(t, _)| *t == RECORD_DATA) {
+            if record.len() < DATA_HEADER_SIZE + fast_size
+                || u16::from_be_bytes([record[6], record[7]]) != schema_id
+            {
+                continue;
+            }
+
+            let counter =
+                u32::from_be_bytes([record[8], record[9], record[10], record[11]]) & counter_mask;
+            if let Some(last) = last_counter {
+                let interval = counter.wrapping_sub(last) & counter_mask;
+                if interval <= counter_mask / 2 {
+                    last_interval = interval as u64;
+                }
+                micros += last_interval;
+            }
+            last_counter = Some(counter);
+
+            let body = &record[DATA_HEADER_SIZE..];
+            let mut pos = 0;
+            times.push(micros as f64 / 1_000_000.0);
+            data_rows.push_row(fast.iter().map(|&i| {
+                let leaf = &metadata.leaves[i];
+                let value = leaf.read(body, pos);
+                pos += leaf.size;
+                Value::Float(value)
+            }));
+        }
+
+        tracing::info!(
+            "Parsed EMU PRO log: {} channels, {} records",
+            channels.len(),
+            data_rows.len()
+        );
+
+        Ok(Log {
+            meta: Meta::EcuMaster(EcuMasterMeta {
+                channel_count: channels.len(),
+                data_points: data_rows.len(),
+            }),
+            channels,
+            times,
+            data: data_rows,
+            markers: Vec::new(),
+        })
+    }
+}
+
+impl Parseable for EmuProLog {
+    fn parse(&self, _data: &str) -> Result<Log, Box<dyn Error>> {
+        // This method is for text-based parsing
+        // EMU PRO logs are binary, so this will return an error
+        Err("EMU PRO .emuprolog files are binary format. Use parse_binary() instead.".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const SCHEMA_ID: u16 = 0x57ac;
+
+    /// Append a record and pad to the 8-byte record alignment
+    fn push_record(file: &mut Vec<u8>, record_type: u8, header: [u8; 2], payload: &[u8]) {
+        let len = (RECORD_HEADER_SIZE + payload.len()) as u16;
+        file.push(record_type);
+        file.push(0);
+        file.extend_from_slice(&len.to_be_bytes());
+        file.extend_from_slice(&header);
+        file.extend_from_slice(payload);
+        file.resize((file.len() + 7) & !7, 0);
+    }
+
+    /// Metadata with `engine/rpm` (u16), `sensors/map` (u16, /10) and
+    /// `sensors/map/raw` (u8, struct member)
+    fn build_metadata() -> Vec<u8> {
+        let mut meta = vec![0x04, 0x74, 0x08, 0x71, 3, 3, 2, 0, 0, 4, 1];
+        meta.resize(METADATA_HEADER_SIZE, 0);
+        meta.extend_from_slice(b"\0rpm\0kPa\0");
+        meta.extend_from_slice(b"\0engine/\0sensors/\0");
+        meta.extend_from_slice(b"\x02sMap\0");
+        meta.extend_from_slice(b"\x01\x01Engine\0\x01\x02Sensors\0");
+        // Struct members: value (u16, kPa, div 10) and raw (u8)
+        meta.extend_from_slice(b"\x02\x09\x02\x00\x0a^\0\x00\x00raw\0");
+        // Group entries: engine/rpm (u16, rpm) and sensors/map (struct 0)
+        meta.extend_from_slice(b"\x02\x01\x01rpm\0\x09\x00\x00map\0");
+
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&meta).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn build_log(samples: &[(u32, u16, u16, u8)]) -> Vec<u8> {
+        let mut file = EMUPROLOG_MAGIC.to_vec();
+        file.resize(HEADER_SIZE, 0);
+
+        let metadata = build_metadata();
+        push_record(&mut file, RECORD_METADATA, [0, 0], &metadata);
+
+        // Class 3: rpm (index 1); class 4: raw (index 0) and map (index 2)
+        let mut schema = vec![0, 1];
+        let mut copy = vec![0u8; SCHEMA_ENTRIES_OFFSET];
+        copy.extend_from_slice(&[0x00, 0x13, 1, 0x00, 0x04, 1, 0x00, 0x24, 1]);
+        let copy_size = copy.len() as u16;
+        copy[..2].copy_from_slice(&copy_size.to_be_bytes());
+        schema.extend_from_slice(&copy);
+        push_record(&mut file, RECORD_SCHEMA, SCHEMA_ID.to_be_bytes(), &schema);
+
+        for &(counter, rpm, map, raw) in samples {
+            let mut payload = SCHEMA_ID.to_be_bytes().to_vec();
+            payload.extend_from_slice(&counter.to_be_bytes());
+            payload.extend_from_slice(&rpm.to_be_bytes());
+            payload.extend_from_slice(&map.to_be_bytes());
+            payload.push(raw);
+            push_record(&mut file, RECORD_DATA, [0, 0], &payload);
+        }
+        file
+    }
+
+    #[test]
+    fn test_detect() {
+        assert!(EmuProLog::detect(&build_log(&[])));
+        assert!(!EmuProLog::detect(b"\xefeml"));
+        assert!(!EmuProLog::detect(&[0u8; HEADER_SIZE]));
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let log =
+            EmuProLog::parse_binary(&build_log(&[(1_000, 900, 355, 7), (44_000, 950, 362, 8)]))
+                .unwrap();
+
+        let paths: Vec<String> = log
+            .channels
+            .iter()
+            .map(|c| match c {
+                Channel::EcuMaster(ch) => ch.path.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(paths, ["engine/rpm", "sensors/map", "sensors/map/raw"]);
+        assert_eq!(log.channels[1].name(), "map");
+        assert_eq!(log.channels[1].unit(), "kPa");
+        assert_eq!(log.times, vec![0.0, 0.043]);
+        assert_eq!(log.data.row(1)[0], 950.0);
+        assert!((log.data.row(1)[1] - 36.2).abs() < 1e-9);
+        assert_eq!(log.data.row(1)[2], 8.0);
+    }
+
+    #[test]
+    fn test_counter_wrap_and_reset() {
+        let log = EmuProLog::parse_binary(&build_log(&[
+            ((1 << TIMESTAMP_BITS) - 1_000, 900, 355, 0),
+            (43_000, 900, 355, 0),
+            (10, 900, 355, 0),
+        ]))
+        .unwrap();
+
+        assert_eq!(log.times, vec![0.0, 0.044, 0.088]);
+    }
+
+    #[test]
+    fn test_missing_metadata() {
+        let mut file = EMUPROLOG_MAGIC.to_vec();
+        file.resize(HEADER_SIZE, 0);
+        assert!(EmuProLog::parse_binary(&file).is_err());
+    }
+}
@@ -10,7 +10,7 @@ use strum::{AsRefStr, EnumString};
 static TIMESTAMP_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^\d{1,2}:\d{2}:\d{2}").expect("Invalid timestamp regex"));
 
-use super::types::{Channel, Log, Meta, Parseable, Value};
+use super::types::{Channel, Log, LogData, Meta, Parseable, Value};
 
 /// Haltech channel types - comprehensive list from actual log files
 #[derive(AsRefStr, Clone, Debug, EnumString, Serialize, Default)]
@@ -383,9 +383,13 @@ impl Parseable for Haltech {
                 let values: Vec<Value> = parts[1..]
                     .iter()
                     .enumerate()
-                    .filter_map(|(idx, v)| {
+                    .map(|(idx, v)| {
                         let v = v.trim();
-                        let raw_value: f64 = v.parse().ok()?;
+                        // Unparseable cells stay in place as missing samples so
+                        // later columns keep their alignment
+                        let Ok(raw_value) = v.parse::<f64>() else {
+                            return Value::MISSING;
+                        };
 
                         // Apply conversion based on channel type if available
                         let converted = if let Some(Channel::Haltech(ch)) = channels.get(idx) {
@@ -393,11 +397,11 @@ impl Parseable for Haltech {
                         } else {
                             raw_value
                         };
-                        Some(Value::Float(converted))
+                        Value::Float(converted)
                     })
                     .collect();
 
-                if values.is_empty() {
+                if values.iter().all(Value::is_missing) {
                     None
                 } else {
                     Some((timestamp_secs, values))
@@ -407,33 +411,22 @@ impl Parseable for Haltech {
 
         // Phase 3: Post-process results (sequential for ordering)
         let data_count = parsed_rows.len();
+        let channel_count = channels.len();
         let mut times: Vec<f64> = Vec::with_capacity(data_count);
-        let mut data: Vec<Vec<Value>> = Vec::with_capacity(data_count);
+        let mut data = LogData::with_capacity(channel_count, data_count);
 
         if !parsed_rows.is_empty() {
             // First timestamp is the base for relative times
             let first_timestamp = parsed_rows[0].0;
 
             for (timestamp, values) in parsed_rows {
-                times.push(timestamp - first_timestamp);
-                data.push(values);
-            }
-        }
-
-        // Verify data integrity
-        let channel_count = channels.len();
-        if channel_count > 0 {
-            // Filter out data rows that don't match channel count
-            let mut filtered_times = Vec::with_capacity(times.len());
-            let mut filtered_data = Vec::with_capacity(data.len());
-            for (time, row) in times.into_iter().zip(data.into_iter()) {
-                if row.len() >= channel_count {
-                    filtered_times.push(time);
-                    filtered_data.push(row);
+                // Verify data integrity: skip rows that don't cover every channel
+                if values.len() < channel_count {
+                    continue;
                 }
+                times.push(timestamp - first_timestamp);
+                data.push_row(values);
             }
-            times = filtered_times;
-            data = filtered_data;
         }
 
         tracing::info!(
@@ -447,6 +440,7 @@ impl Parseable for Haltech {
             channels,
             times,
             data,
+            markers: Vec::new(),
         })
     }
 }
@@ -503,10 +497,10 @@ Log : 20250718 02:15:46
 
         // Check unit conversions are applied
         // RPM: y = x (no conversion) - raw 5000 -> 5000 RPM
-        assert_eq!(log.data[0][0].as_f64(), 5000.0);
+        assert_eq!(log.data.row(0)[0], 5000.0);
 
         // Pressure: y = x/10 - 101.3 (gauge kPa) - raw 1013 -> 0.0 kPa
-        let pressure_value = log.data[0][1].as_f64();
+        let pressure_value = log.data.row(0)[1];
         assert!(
             (pressure_value - 0.0).abs() < 0.01,
             "Expected ~0.0, got {}",
@@ -518,6 +512,29 @@ Log : 20250718 02:15:46
         assert_eq!(log.channels[1].unit(), "kPa");
     }
 
+    #[test]
+    fn test_unparseable_cell_keeps_column_alignment() {
+        let sample = "%DataLog%\n\
+Channel : RPM\n\
+ID : 384\n\
+Type : EngineSpeed\n\
+Channel : Throttle\n\
+ID : 100\n\
+Type : Raw\n\
+Channel : Lambda\n\
+ID : 101\n\
+Type : Raw\n\
+14:15:46.000,5000,20,1\n\
+14:15:46.020,---,25,2\n";
+
+        let log = Haltech.parse(sample).unwrap();
+
+        assert_eq!(log.data.len(), 2);
+        assert!(log.data.row(1)[0].is_nan());
+        assert_eq!(log.data.row(1)[1], 25.0);
+        assert_eq!(log.data.row(1)[2], 2.0);
+    }
+
     #[test]
     fn test_channel_type_conversions() {
         // RPM: no conversion
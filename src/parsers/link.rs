@@ -9,8 +9,12 @@
 
 use serde::Serialize;
 use std::error::Error;
+use std::sync::Arc;
 
-use super::types::{Channel, Log, Meta, Parseable, Value};
+use super::types::{Channel, Log, LogData, Marker, MarkerKind, Meta, Parseable, Timebase};
+
+/// Upper bound on the number of records kept from a log
+const MAX_RECORDS: usize = 50_001;
 
 /// Link ECU channel metadata
 #[derive(Clone, Debug, Serialize)]
@@ -243,9 +247,10 @@ impl Link {
             // Return empty log with channel definitions
             return Ok(Log {
                 meta: Meta::Link(meta),
+                data: LogData::new(channels.len()),
                 channels: channels.into_iter().map(Channel::Link).collect(),
                 times: Vec::new(),
-                data: Vec::new(),
+                markers: Vec::new(),
             });
         }
 
@@ -253,61 +258,92 @@ impl Link {
         let first_time = *all_times.first().unwrap_or(&0.0);
         let times: Vec<f64> = all_times.iter().map(|t| (*t - first_time) as f64).collect();
 
-        // Build data matrix: for each timestamp, interpolate/hold values for each channel
-        let mut data_matrix: Vec<Vec<Value>> = Vec::with_capacity(times.len());
-
-        for (time_idx, &time) in all_times.iter().enumerate() {
-            let mut row: Vec<Value> = Vec::with_capacity(channels.len());
-
-            for ch_data in &channel_data {
-                // Find the value at or before this timestamp
-                let value = if ch_data.is_empty() {
-                    0.0
+        // Limit output to reasonable size
+        let mut times = times;
+        if times.len() > MAX_RECORDS {
+            tracing::warn!("Truncating log data at {} samples", MAX_RECORDS);
+            times.truncate(MAX_RECORDS);
+        }
+        let last_time = times.last().copied().unwrap_or(0.0);
+
+        // Each channel keeps its own samples. Channels logged at every record
+        // share the log timebase; the rest get their own and are held between
+        // samples.
+        let columns: Vec<_> = channel_data
+            .iter()
+            .map(|points| {
+                let (own_times, values): (Vec<f64>, Vec<f64>) = points
+                    .iter()
+                    .map(|&(t, v)| ((t - first_time) as f64, v as f64))
+                    .take_while(|&(t, _)| t <= last_time)
+                    .unzip();
+                if own_times.len() == times.len() {
+                    (values, None)
                 } else {
-                    // Binary search for the closest time <= current time
-                    match ch_data.binary_search_by(|probe| {
-                        probe
-                            .0
-                            .partial_cmp(&time)
-                            .unwrap_or(std::cmp::Ordering::Equal)
-                    }) {
-                        Ok(idx) => ch_data[idx].1,
-                        Err(idx) => {
-                            if idx == 0 {
-                                ch_data[0].1
-                            } else {
-                                ch_data[idx - 1].1
-                            }
-                        }
-                    }
-                };
-
-                row.push(Value::Float(value as f64));
-            }
+                    let timebase = Timebase::new(own_times, &times);
+                    (values, Some(Arc::new(timebase)))
+                }
+            })
+            .collect();
+        let data_matrix = LogData::from_timed_columns(times.len(), columns);
 
-            data_matrix.push(row);
-
-            // Limit output to reasonable size
-            if time_idx > 50000 {
-                tracing::warn!("Truncating log data at 50000 samples");
-                break;
-            }
-        }
+        let markers = Self::extract_markers(&channels, &channel_data, first_time, last_time);
 
         tracing::info!(
-            "Parsed Link ECU log: {} channels, {} data points, ECU: {}",
+            "Parsed Link ECU log: {} channels, {} data points, {} markers, ECU: {}",
             channels.len(),
             data_matrix.len(),
+            markers.len(),
             meta.ecu_model
         );
 
         Ok(Log {
             meta: Meta::Link(meta),
             channels: channels.into_iter().map(Channel::Link).collect(),
-            times: times[..data_matrix.len()].to_vec(),
+            times,
             data: data_matrix,
+            markers,
         })
     }
+
+    /// Build markers from logged marker channels
+    ///
+    /// PC Link records the datalog marker as a channel (e.g. "Log Marker")
+    /// rather than a separate event list, so a marker is emitted each time
+    /// such a channel changes to a non-zero value.
+    fn extract_markers(
+        channels: &[LinkChannel],
+        channel_data: &[Vec<(f32, f32)>],
+        first_time: f32,
+        last_time: f64,
+    ) -> Vec<Marker> {
+        let mut markers = Vec::new();
+
+        for (channel, points) in channels.iter().zip(channel_data) {
+            if !channel.name.to_lowercase().contains("marker") {
+                continue;
+            }
+
+            let mut previous = 0.0;
+            for &(time, value) in points {
+                let time = (time - first_time) as f64;
+                if time > last_time {
+                    break;
+                }
+                if value != previous && value != 0.0 {
+                    markers.push(Marker::new(
+                        MarkerKind::Manual,
+                        format!("{} {}", channel.name, markers.len() + 1),
+                        time,
+                    ));
+                }
+                previous = value;
+            }
+        }
+
+        markers.sort_by(|a, b| a.time.total_cmp(&b.time));
+        markers
+    }
 }
 
 impl Parseable for Link {
@@ -412,4 +448,32 @@ mod tests {
             eprintln!("Version: {}", meta.software_version);
         }
     }
+
+    #[test]
+    fn test_extract_markers() {
+        let channel = |name: &str| LinkChannel {
+            name: name.to_string(),
+            unit: String::new(),
+            channel_id: 1,
+        };
+        let channels = vec![channel("Engine Speed"), channel("Log Marker")];
+        let channel_data = vec![
+            vec![(10.0, 3000.0), (10.4, 3100.0)],
+            [0.0, 1.0, 1.0, 0.0, 1.0, 1.0]
+                .iter()
+                .enumerate()
+                .map(|(i, &m)| (10.0 + i as f32 * 0.1, m))
+                .collect(),
+        ];
+
+        // The last sample falls after the end of the log and is ignored
+        let markers = Link::extract_markers(&channels, &channel_data, 10.0, 0.45);
+        assert_eq!(
+            markers,
+            vec![
+                Marker::new(MarkerKind::Manual, "Log Marker 1", (10.1f32 - 10.0) as f64),
+                Marker::new(MarkerKind::Manual, "Log Marker 2", (10.4f32 - 10.0) as f64),
+            ]
+        );
+    }
 }
@@ -0,0 +1,265 @@
+//! MegaSquirt / TunerStudio (.msl) text log parser
+//!
+//! TunerStudio and MegaLogViewer write MS2/MS3 datalogs as tab-separated text:
+//! - Title line with the firmware signature (e.g. `"MS3 Format 0435.14P"`)
+//! - Capture date line (e.g. `"Capture Date: Fri Jul 10 14:22:01 EDT 2020"`)
+//! - Channel name row, starting with `Time`
+//! - Units row
+//! - Data rows, interleaved with `MARK` rows when the user pressed the marker key
+//!
+//! Binary `MLVLG` logs from the same software are handled by the Speeduino parser.
+
+use serde::Serialize;
+use std::error::Error;
+
+use super::types::{Channel, Log, LogData, Marker, MarkerKind, Meta, Parseable, Value};
+
+/// Number of lines searched for the title, capture date and header rows
+const HEADER_SEARCH_LINES: usize = 5;
+
+/// MegaSquirt channel metadata
+#[derive(Clone, Debug, Serialize)]
+pub struct MegaSquirtChannel {
+    pub name: String,
+    pub unit: String,
+}
+
+impl MegaSquirtChannel {
+    /// Create a channel from a header name and the raw units row entry
+    pub fn new(name: &str, unit: &str) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            unit: Self::normalize_unit(unit),
+        }
+    }
+
+    /// Map TunerStudio unit spellings onto the symbols used elsewhere in UltraLog
+    fn normalize_unit(unit: &str) -> String {
+        let unit = unit.trim().trim_matches('"').trim();
+        match unit.to_lowercase().as_str() {
+            "deg f" | "degf" | "°f" | "f" => "°F".to_string(),
+            "deg c" | "degc" | "°c" | "c" => "°C".to_string(),
+            "deg" | "deg btdc" | "°" | "degrees" => "°".to_string(),
+            "kpa" => "kPa".to_string(),
+            "psi" => "PSI".to_string(),
+            "v" | "volts" => "V".to_string(),
+            "rpm" => "RPM".to_string(),
+            "s" | "sec" | "secs" => "s".to_string(),
+            "afr" => "AFR".to_string(),
+            "lambda" => "λ".to_string(),
+            _ => unit.to_string(),
+        }
+    }
+
+    /// Get the display unit for this channel
+    pub fn unit(&self) -> &str {
+        &self.unit
+    }
+}
+
+/// MegaSquirt log metadata
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct MegaSquirtMeta {
+    /// Firmware signature from the title line
+    pub title: String,
+    pub capture_date: String,
+}
+
+/// MegaSquirt / TunerStudio MSL parser
+pub struct MegaSquirt;
+
+impl MegaSquirt {
+    /// Detect if file contents look like a TunerStudio MSL log
+    ///
+    /// Requires the quoted title or capture date line followed by a
+    /// tab-separated header row starting with `Time`.
+    pub fn detect(contents: &str) -> bool {
+        let mut has_preamble = false;
+        for line in contents.lines().take(HEADER_SEARCH_LINES) {
+            let line = line.trim_start_matches('\u{feff}').trim();
+            if line.starts_with('"') || line.contains("Capture Date") {
+                has_preamble = true;
+            } else if Self::is_header_row(line) {
+                return has_preamble;
+            }
+        }
+        false
+    }
+
+    /// Check if a line is the channel name row
+    fn is_header_row(line: &str) -> bool {
+        line.contains('\t')
+            && line
+                .split('\t')
+                .next()
+                .is_some_and(|first| first.trim().eq_ignore_ascii_case("time"))
+    }
+
+    /// Check if a row contains only numeric (or empty) fields
+    fn is_numeric_row(line: &str) -> bool {
+        line.split('\t')
+            .map(str::trim)
+            .all(|field| field.is_empty() || field.parse::<f64>().is_ok())
+    }
+
+    /// Strip surrounding quotes from a preamble line
+    fn unquote(line: &str) -> &str {
+        line.trim().trim_matches('"').trim()
+    }
+}
+
+impl Parseable for MegaSquirt {
+    fn parse(&self, file_contents: &str) -> Result<Log, Box<dyn Error>> {
+        let mut meta = MegaSquirtMeta::default();
+        let mut lines = file_contents.lines().peekable();
+
+        // Preamble: title and capture date lines before the header row
+        let mut header = None;
+        for line in lines.by_ref().take(HEADER_SEARCH_LINES) {
+            let line = line.trim_start_matches('\u{feff}').trim_end();
+            if Self::is_header_row(line) {
+                header = Some(line);
+                break;
+            }
+            let text = Self::unquote(line);
+            if let Some(date) = text.strip_prefix("Capture Date:") {
+                meta.capture_date = date.trim().to_string();
+            } else if meta.title.is_empty() && !text.is_empty() {
+                meta.title = text.to_string();
+            }
+        }
+        let header = header.ok_or("Invalid MSL log: no header row starting with Time")?;
+
+        let column_names: Vec<&str> = header.split('\t').collect();
+
+        // Units row is optional in some exports; only consume it if it isn't data
+        let units: Vec<&str> = match lines.peek() {
+            Some(line) if !Self::is_numeric_row(line) && !line.starts_with("MARK") => lines
+                .next()
+                .map(|l| l.split('\t').collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        // First column is time (already in seconds); remaining columns are channels
+        let channels: Vec<Channel> = column_names
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(idx, name)| {
+                let unit = units.get(idx).copied().unwrap_or("");
+                Channel::MegaSquirt(MegaSquirtChannel::new(name, unit))
+            })
+            .collect();
+
+        let estimated_rows = file_contents.len() / (column_names.len() * 6).max(1);
+        let mut times: Vec<f64> = Vec::with_capacity(estimated_rows);
+        let mut data = LogData::with_capacity(channels.len(), estimated_rows);
+        let mut markers: Vec<Marker> = Vec::new();
+
+        for line in lines {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // Marker text (e.g. "MARK 000 - Manual - 14:22:05"), placed at
+            // the time of the last data row before the mark
+            if line.starts_with("MARK") {
+                markers.push(Marker::new(
+                    MarkerKind::Manual,
+                    line.replace('\t', " ").trim(),
+                    times.last().copied().unwrap_or(0.0),
+                ));
+                continue;
+            }
+
+            let mut parts = line.split('\t');
+            let Some(Ok(time)) = parts.next().map(|t| t.trim().parse::<f64>()) else {
+                // Repeated header rows or comments after a log restart
+                continue;
+            };
+
+            // Empty, non-numeric and absent trailing values are missing samples
+            let row =
+                (0..channels.len()).map(|_| match parts.next().map(|p| p.trim().parse::<f64>()) {
+                    Some(Ok(val)) => Value::Float(val),
+                    _ => Value::MISSING,
+                });
+
+            data.push_row(row);
+            times.push(time);
+        }
+
+        if data.is_empty() {
+            return Err("Invalid MSL log: no data rows found".into());
+        }
+
+        tracing::info!(
+            "Parsed MegaSquirt log: {} channels, {} data points, {} markers",
+            channels.len(),
+            data.len(),
+            markers.len()
+        );
+
+        Ok(Log {
+            meta: Meta::MegaSquirt(meta),
+            channels,
+            times,
+            data,
+            markers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\"MS3 Format 0435.14P\"\n\
+        \"Capture Date: Fri Jul 10 14:22:01 EDT 2020\"\n\
+        Time\tRPM\tMAP\tCLT\tAFR\n\
+        s\tRPM\tkPa\tdeg F\tAFR\n\
+        0.000\t850\t35.2\t180.1\t14.7\n\
+        0.050\t900\t36.0\t180.2\t14.6\n\
+        MARK 000 - Manual - 14:22:05\n\
+        0.100\t950\t\t180.4\t14.5\n";
+
+    #[test]
+    fn test_detect() {
+        assert!(MegaSquirt::detect(SAMPLE));
+        assert!(!MegaSquirt::detect("TIME\tengine/rpm\n0.0\t1000\n"));
+        assert!(!MegaSquirt::detect("Time (msec),Engine Speed (rpm)\n"));
+        assert!(!MegaSquirt::detect("%DataLog%\n"));
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let log = MegaSquirt.parse(SAMPLE).unwrap();
+
+        assert_eq!(log.channels.len(), 4);
+        assert_eq!(log.channels[0].name(), "RPM");
+        assert_eq!(log.channels[2].unit(), "°F");
+        assert_eq!(log.times, vec![0.0, 0.05, 0.1]);
+
+        // Empty MAP value is a missing sample
+        assert!(log.data.row(2)[1].is_nan());
+
+        let Meta::MegaSquirt(meta) = &log.meta else {
+            panic!("Expected MegaSquirt metadata");
+        };
+        assert_eq!(meta.title, "MS3 Format 0435.14P");
+        assert_eq!(meta.capture_date, "Fri Jul 10 14:22:01 EDT 2020");
+        assert_eq!(log.markers.len(), 1);
+        assert_eq!(log.markers[0].time, 0.05);
+        assert_eq!(log.markers[0].label, "MARK 000 - Manual - 14:22:05");
+    }
+
+    #[test]
+    fn test_unit_normalization() {
+        assert_eq!(MegaSquirtChannel::new("CLT", "deg C").unit(), "°C");
+        assert_eq!(MegaSquirtChannel::new("SPK", "deg").unit(), "°");
+        assert_eq!(MegaSquirtChannel::new("MAP", "KPA").unit(), "kPa");
+        assert_eq!(MegaSquirtChannel::new("PW", "ms").unit(), "ms");
+    }
+}
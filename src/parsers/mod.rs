@@ -1,15 +1,25 @@
 pub mod aim;
 pub mod ecumaster;
+pub mod emublog;
+pub mod emuprolog;
 pub mod haltech;
 pub mod link;
+pub mod megasquirt;
+pub mod motec;
+pub mod registry;
 pub mod romraider;
 pub mod speeduino;
 pub mod types;
 
 pub use aim::Aim;
 pub use ecumaster::EcuMaster;
+pub use emublog::EmuBlog;
+pub use emuprolog::EmuProLog;
 pub use haltech::Haltech;
 pub use link::Link;
+pub use megasquirt::MegaSquirt;
+pub use motec::MotEc;
+pub use registry::{LogFormat, ParserRegistry};
 pub use romraider::RomRaider;
 pub use speeduino::Speeduino;
-pub use types::{Channel, EcuType, Log, Parseable, Value};
+pub use types::{Channel, EcuType, Log, LogData, Marker, MarkerKind, Parseable, Timebase, Value};
@@ -0,0 +1,510 @@
+//! MoTeC i2 (.ld) binary format parser
+//!
+//! MoTeC M1/M150 ECUs and ADL/C-series dash loggers write `.ld` files which are
+//! read by MoTeC i2. Format structure based on reverse engineering (see also
+//! the open-source `ldparser` project):
+//! - Header: fixed layout starting with marker 0x40, pointers to the channel
+//!   metadata list, channel data and event block, plus device/driver/vehicle/venue strings
+//! - Event block: event name, session, comment and a pointer to the venue block
+//! - Venue block: venue name and a pointer to the vehicle block
+//! - Channel metadata: doubly linked list of 124-byte headers with name, unit,
+//!   sample rate, data type and scaling (shift / multiplier / scale / decimal places)
+//! - Channel data: raw samples for each channel stored contiguously
+//!
+//! All multi-byte values are little-endian.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use super::types::{Channel, Log, LogData, Meta, Parseable, Timebase};
+
+/// Header marker value at offset 0
+const LD_MARKER: u32 = 0x40;
+
+/// Size of a channel metadata block
+const CHANNEL_HEADER_SIZE: usize = 124;
+
+/// Minimum size of the file header we read from
+const HEADER_SIZE: usize = 1762;
+
+/// Upper bound on channel count to guard against corrupted files
+const MAX_CHANNELS: usize = 5000;
+
+/// Storage type of channel samples
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum MotEcDataType {
+    I16,
+    I32,
+    F16,
+    F32,
+}
+
+impl MotEcDataType {
+    /// Decode the (type class, size) pair stored in the channel header
+    fn from_header(type_class: u16, size: u16) -> Option<Self> {
+        match (type_class, size) {
+            (0x07, 2) => Some(Self::F16),
+            (0x07, 4) => Some(Self::F32),
+            (0x00 | 0x03 | 0x05, 2) => Some(Self::I16),
+            (0x00 | 0x03 | 0x05, 4) => Some(Self::I32),
+            _ => None,
+        }
+    }
+
+    fn byte_size(&self) -> usize {
+        match self {
+            Self::I16 | Self::F16 => 2,
+            Self::I32 | Self::F32 => 4,
+        }
+    }
+
+    /// Read a raw sample at the given offset
+    fn read(&self, data: &[u8], offset: usize) -> f64 {
+        match self {
+            Self::I16 => i16::from_le_bytes([data[offset], data[offset + 1]]) as f64,
+            Self::I32 => i32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ]) as f64,
+            Self::F16 => f16_to_f64(u16::from_le_bytes([data[offset], data[offset + 1]])),
+            Self::F32 => f32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ]) as f64,
+        }
+    }
+}
+
+/// MoTeC channel metadata
+#[derive(Clone, Debug, Serialize)]
+pub struct MotEcChannel {
+    pub name: String,
+    pub short_name: String,
+    pub unit: String,
+    /// Sample rate in Hz
+    pub sample_rate: u16,
+    pub data_type: MotEcDataType,
+    /// Offset added after scaling
+    pub shift: i16,
+    /// Multiplier applied last
+    pub multiplier: i16,
+    /// Divisor applied to the raw value
+    pub scale: i16,
+    /// Decimal places (raw value is multiplied by 10^-dec)
+    pub decimal_places: i16,
+}
+
+impl MotEcChannel {
+    /// Get the display unit for this channel
+    pub fn unit(&self) -> &str {
+        &self.unit
+    }
+
+    /// Convert a raw sample to engineering units:
+    /// `(raw / scale * 10^-dec + shift) * multiplier`
+    pub fn convert_value(&self, raw: f64) -> f64 {
+        let scale = if self.scale == 0 {
+            1.0
+        } else {
+            self.scale as f64
+        };
+        let multiplier = if self.multiplier == 0 {
+            1.0
+        } else {
+            self.multiplier as f64
+        };
+        (raw / scale * 10f64.powi(-(self.decimal_places as i32)) + self.shift as f64) * multiplier
+    }
+}
+
+/// MoTeC log file metadata
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct MotEcMeta {
+    pub device_type: String,
+    pub device_serial: u32,
+    pub device_version: u16,
+    pub date: String,
+    pub time: String,
+    pub driver: String,
+    pub vehicle: String,
+    pub venue: String,
+    pub short_comment: String,
+    pub event: String,
+    pub session: String,
+    pub event_comment: String,
+    pub vehicle_weight: u32,
+    pub vehicle_type: String,
+    pub vehicle_comment: String,
+}
+
+/// A channel with its samples converted to engineering units
+type ParsedChannel = (MotEcChannel, Vec<f64>);
+
+/// MoTeC i2 log file parser
+pub struct MotEc;
+
+impl MotEc {
+    /// Detect if data is a MoTeC .ld file
+    pub fn detect(data: &[u8]) -> bool {
+        if data.len() < HEADER_SIZE || Self::read_u32(data, 0) != LD_MARKER {
+            return false;
+        }
+        // The channel metadata pointer must point inside the file
+        let meta_ptr = Self::read_u32(data, 8) as usize;
+        let data_ptr = Self::read_u32(data, 12) as usize;
+        meta_ptr >= HEADER_SIZE && meta_ptr < data.len() && data_ptr <= data.len()
+    }
+
+    /// Read a little-endian u16
+    fn read_u16(data: &[u8], offset: usize) -> u16 {
+        if offset + 2 > data.len() {
+            return 0;
+        }
+        u16::from_le_bytes([data[offset], data[offset + 1]])
+    }
+
+    /// Read a little-endian i16
+    fn read_i16(data: &[u8], offset: usize) -> i16 {
+        Self::read_u16(data, offset) as i16
+    }
+
+    /// Read a little-endian u32
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        if offset + 4 > data.len() {
+            return 0;
+        }
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    }
+
+    /// Read a fixed-size, null-padded ASCII string
+    fn read_string(data: &[u8], offset: usize, len: usize) -> String {
+        if offset >= data.len() {
+            return String::new();
+        }
+        let end = (offset + len).min(data.len());
+        let bytes = &data[offset..end];
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+    }
+
+    /// Parse header, event, venue and vehicle metadata
+    fn parse_meta(data: &[u8]) -> MotEcMeta {
+        let mut meta = MotEcMeta {
+            device_serial: Self::read_u32(data, 70),
+            device_type: Self::read_string(data, 74, 8),
+            device_version: Self::read_u16(data, 82),
+            date: Self::read_string(data, 94, 16),
+            time: Self::read_string(data, 126, 16),
+            driver: Self::read_string(data, 158, 64),
+            vehicle: Self::read_string(data, 222, 64),
+            venue: Self::read_string(data, 350, 64),
+            short_comment: Self::read_string(data, 1572, 64),
+            ..Default::default()
+        };
+
+        // Event block: name (64), session (64), comment (1024), venue pointer (u16)
+        let event_ptr = Self::read_u32(data, 36) as usize;
+        if event_ptr > 0 && event_ptr + 1154 <= data.len() {
+            meta.event = Self::read_string(data, event_ptr, 64);
+            meta.session = Self::read_string(data, event_ptr + 64, 64);
+            meta.event_comment = Self::read_string(data, event_ptr + 128, 1024);
+
+            // Venue block: name (64), padding (1034), vehicle pointer (u16)
+            let venue_ptr = Self::read_u16(data, event_ptr + 1152) as usize;
+            if venue_ptr > 0 && venue_ptr + 1100 <= data.len() {
+                let venue = Self::read_string(data, venue_ptr, 64);
+                if !venue.is_empty() {
+                    meta.venue = venue;
+                }
+
+                // Vehicle block: id (64), padding (128), weight (u32), type (32), comment (32)
+                let vehicle_ptr = Self::read_u16(data, venue_ptr + 1098) as usize;
+                if vehicle_ptr > 0 && vehicle_ptr + 260 <= data.len() {
+                    let vehicle = Self::read_string(data, vehicle_ptr, 64);
+                    if !vehicle.is_empty() {
+                        meta.vehicle = vehicle;
+                    }
+                    meta.vehicle_weight = Self::read_u32(data, vehicle_ptr + 192);
+                    meta.vehicle_type = Self::read_string(data, vehicle_ptr + 196, 32);
+                    meta.vehicle_comment = Self::read_string(data, vehicle_ptr + 228, 32);
+                }
+            }
+        }
+
+        meta
+    }
+
+    /// Walk the channel metadata linked list, returning each channel with its
+    /// raw samples converted to engineering units
+    fn parse_channels(data: &[u8]) -> Result<Vec<ParsedChannel>, Box<dyn Error>> {
+        let mut channels = Vec::new();
+        let mut meta_ptr = Self::read_u32(data, 8) as usize;
+        let mut visited = std::collections::HashSet::new();
+
+        while meta_ptr != 0 {
+            if !visited.insert(meta_ptr) {
+                return Err(format!("Channel list loops back to offset {}", meta_ptr).into());
+            }
+            if channels.len() >= MAX_CHANNELS {
+                return Err(format!("Unreasonable channel count (> {})", MAX_CHANNELS).into());
+            }
+            if meta_ptr + CHANNEL_HEADER_SIZE > data.len() {
+                return Err(format!(
+                    "Channel header at offset {} exceeds file size {}",
+                    meta_ptr,
+                    data.len()
+                )
+                .into());
+            }
+
+            let next_ptr = Self::read_u32(data, meta_ptr + 4) as usize;
+            let data_ptr = Self::read_u32(data, meta_ptr + 8) as usize;
+            let sample_count = Self::read_u32(data, meta_ptr + 12) as usize;
+            let type_class = Self::read_u16(data, meta_ptr + 18);
+            let type_size = Self::read_u16(data, meta_ptr + 20);
+            let sample_rate = Self::read_u16(data, meta_ptr + 22);
+
+            let name = Self::read_string(data, meta_ptr + 32, 32);
+            let Some(data_type) = MotEcDataType::from_header(type_class, type_size) else {
+                tracing::warn!(
+                    "Skipping MoTeC channel '{}' with unsupported data type ({:#x}, {})",
+                    name,
+                    type_class,
+                    type_size
+                );
+                meta_ptr = next_ptr;
+                continue;
+            };
+
+            let channel = MotEcChannel {
+                name,
+                short_name: Self::read_string(data, meta_ptr + 64, 8),
+                unit: Self::read_string(data, meta_ptr + 72, 12),
+                sample_rate,
+                data_type,
+                shift: Self::read_i16(data, meta_ptr + 24),
+                multiplier: Self::read_i16(data, meta_ptr + 26),
+                scale: Self::read_i16(data, meta_ptr + 28),
+                decimal_places: Self::read_i16(data, meta_ptr + 30),
+            };
+
+            // Clamp sample count to what is actually present in the file
+            let size = data_type.byte_size();
+            let available = data.len().saturating_sub(data_ptr) / size;
+            if sample_count > available {
+                tracing::warn!(
+                    "MoTeC channel '{}' truncated: {} of {} samples present",
+                    channel.name,
+                    available,
+                    sample_count
+                );
+            }
+            let count = sample_count.min(available);
+
+            let values: Vec<f64> = (0..count)
+                .map(|i| channel.convert_value(data_type.read(data, data_ptr + i * size)))
+                .collect();
+
+            channels.push((channel, values));
+            meta_ptr = next_ptr;
+        }
+
+        Ok(channels)
+    }
+
+    /// Parse the .ld binary format
+    pub fn parse_binary(data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        if !Self::detect(data) {
+            return Err("Invalid MoTeC .ld file header".into());
+        }
+
+        let meta = Self::parse_meta(data);
+        let parsed = Self::parse_channels(data)?;
+
+        // Channels are sampled at independent rates. Records follow the
+        // fastest channel; slower channels keep their native samples on a
+        // timebase shared by every channel logged at that rate.
+        let max_rate = parsed
+            .iter()
+            .map(|(ch, _)| ch.sample_rate)
+            .max()
+            .unwrap_or(0);
+        let duration = parsed
+            .iter()
+            .filter(|(ch, _)| ch.sample_rate > 0)
+            .map(|(ch, values)| values.len() as f64 / ch.sample_rate as f64)
+            .fold(0.0, f64::max);
+
+        let sample_count = if max_rate > 0 {
+            (duration * max_rate as f64).round() as usize
+        } else {
+            0
+        };
+        let times: Vec<f64> = (0..sample_count)
+            .map(|i| i as f64 / max_rate as f64)
+            .collect();
+
+        let (motec_channels, channel_values): (Vec<MotEcChannel>, Vec<Vec<f64>>) =
+            parsed.into_iter().unzip();
+
+        let mut timebases: HashMap<(u16, usize), Arc<Timebase>> = HashMap::new();
+        let columns: Vec<_> = motec_channels
+            .iter()
+            .zip(channel_values)
+            .map(|(ch, values)| match ch.sample_rate {
+                // A zero rate has no usable timing; leave the channel empty
+                0 => (Vec::new(), None),
+                rate if rate == max_rate => (values, None),
+                rate => {
+                    let timebase = timebases.entry((rate, values.len())).or_insert_with(|| {
+                        let own_times = (0..values.len()).map(|i| i as f64 / rate as f64).collect();
+                        Arc::new(Timebase::new(own_times, &times))
+                    });
+                    (values, Some(Arc::clone(timebase)))
+                }
+            })
+            .collect();
+        let data_rows = LogData::from_timed_columns(times.len(), columns);
+
+        tracing::info!(
+            "Parsed MoTeC log: {} channels, {} data points at {} Hz, device: {}",
+            motec_channels.len(),
+            data_rows.len(),
+            max_rate,
+            meta.device_type
+        );
+
+        Ok(Log {
+            meta: Meta::MotEc(meta),
+            channels: motec_channels.into_iter().map(Channel::MotEc).collect(),
+            times,
+            data: data_rows,
+            markers: Vec::new(),
+        })
+    }
+}
+
+impl Parseable for MotEc {
+    fn parse(&self, _data: &str) -> Result<Log, Box<dyn Error>> {
+        // This method is for text-based parsing
+        // MoTeC uses binary .ld format, so this will return an error
+        Err("MoTeC .ld files are binary format. Use parse_binary() instead.".into())
+    }
+}
+
+/// Convert an IEEE 754 half-precision float to f64
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f64;
+
+    match exponent {
+        0 => sign * mantissa * 2f64.powi(-24),
+        0x1f => {
+            if mantissa == 0.0 {
+                sign * f64::INFINITY
+            } else {
+                f64::NAN
+            }
+        }
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f16_conversion() {
+        assert_eq!(f16_to_f64(0x0000), 0.0);
+        assert_eq!(f16_to_f64(0x3c00), 1.0);
+        assert_eq!(f16_to_f64(0xc000), -2.0);
+        assert_eq!(f16_to_f64(0x3800), 0.5);
+        assert!(f16_to_f64(0x7c00).is_infinite());
+        assert!(f16_to_f64(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn test_data_type_from_header() {
+        assert_eq!(
+            MotEcDataType::from_header(0x07, 4),
+            Some(MotEcDataType::F32)
+        );
+        assert_eq!(
+            MotEcDataType::from_header(0x07, 2),
+            Some(MotEcDataType::F16)
+        );
+        assert_eq!(
+            MotEcDataType::from_header(0x03, 2),
+            Some(MotEcDataType::I16)
+        );
+        assert_eq!(
+            MotEcDataType::from_header(0x05, 4),
+            Some(MotEcDataType::I32)
+        );
+        assert_eq!(MotEcDataType::from_header(0x01, 4), None);
+        assert_eq!(MotEcDataType::from_header(0x07, 8), None);
+    }
+
+    #[test]
+    fn test_convert_value() {
+        let channel = MotEcChannel {
+            name: "Engine Temp".to_string(),
+            short_name: "ET".to_string(),
+            unit: "C".to_string(),
+            sample_rate: 10,
+            data_type: MotEcDataType::I16,
+            shift: 0,
+            multiplier: 1,
+            scale: 1,
+            decimal_places: 1,
+        };
+        assert!((channel.convert_value(905.0) - 90.5).abs() < 1e-9);
+
+        let shifted = MotEcChannel {
+            shift: -40,
+            multiplier: 2,
+            scale: 4,
+            decimal_places: 0,
+            ..channel
+        };
+        // (100 / 4 - 40) * 2 = -30
+        assert!((shifted.convert_value(100.0) + 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_zero_scale_and_multiplier_are_identity() {
+        let channel = MotEcChannel {
+            name: "Raw".to_string(),
+            short_name: String::new(),
+            unit: String::new(),
+            sample_rate: 1,
+            data_type: MotEcDataType::F32,
+            shift: 0,
+            multiplier: 0,
+            scale: 0,
+            decimal_places: 0,
+        };
+        assert_eq!(channel.convert_value(12.5), 12.5);
+    }
+
+    #[test]
+    fn test_detect_rejects_other_formats() {
+        assert!(!MotEc::detect(b"MLVLG\x00\x00\x01"));
+        assert!(!MotEc::detect(b"%DataLog%"));
+        assert!(!MotEc::detect(&[0x40, 0, 0, 0]));
+        assert!(!MotEc::detect(&[]));
+    }
+}
@@ -0,0 +1,595 @@
+//! Parser registry and common log format trait
+//!
+//! Every supported log format implements [`LogFormat`], and [`ParserRegistry`]
+//! holds the set of known formats. Format detection asks each parser how
+//! confident it is that the data belongs to it and picks the best match, so the
+//! GUI, the command-line tools and tests all choose formats the same way.
+//! New formats only need to be added to [`ParserRegistry::new`].
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::io::Read;
+
+use super::aim::Aim;
+use super::ecumaster::EcuMaster;
+use super::emublog::EmuBlog;
+use super::emuprolog::EmuProLog;
+use super::haltech::Haltech;
+use super::link::Link;
+use super::megasquirt::MegaSquirt;
+use super::motec::MotEc;
+use super::romraider::RomRaider;
+use super::speeduino::Speeduino;
+use super::types::{EcuType, Log, Parseable};
+
+/// Detection confidence returned by [`LogFormat::detect`]
+///
+/// `0.0` means the data is definitely not this format, `1.0` means the file
+/// signature matched exactly. Values in between are used by formats that can
+/// only be recognised heuristically.
+pub type Confidence = f32;
+
+/// Confidence for an exact magic-byte / header signature match
+pub const CONFIDENCE_CERTAIN: Confidence = 1.0;
+
+/// Confidence for a heuristic match (e.g. header row shape of a CSV export)
+pub const CONFIDENCE_LIKELY: Confidence = 0.8;
+
+/// Confidence used by the fallback text parser when nothing else matched
+pub const CONFIDENCE_FALLBACK: Confidence = 0.1;
+
+/// Confidence for data that is not this format
+pub const CONFIDENCE_NONE: Confidence = 0.0;
+
+/// Common interface implemented by every log file parser
+pub trait LogFormat: Send + Sync {
+    /// Human-readable format name used in messages (e.g. "Link ECU LLG")
+    fn name(&self) -> &'static str;
+
+    /// The ECU type this format produces
+    fn ecu_type(&self) -> EcuType;
+
+    /// File extensions (lowercase, without dot) commonly used by this format
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// How confident this parser is that `data` is in its format
+    fn detect(&self, data: &[u8]) -> Confidence;
+
+    /// Parse raw file data into a [`Log`]
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>>;
+}
+
+/// Decode file bytes as text, falling back to lossy conversion for files
+/// with encoding issues
+fn decode_text(data: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(data)
+}
+
+/// Proprietary formats we recognise but cannot read, with export instructions
+const UNSUPPORTED_FORMATS: &[(&[u8], &str)] = &[
+    (
+        b"HEPS",
+        "This is a Haltech .hlgzip file which uses proprietary compression.\n\n\
+        To use this log in UltraLog, please export it as CSV from Haltech's ESP or NSP software:\n\
+        1. Open the .hlgzip file in Haltech ESP/NSP\n\
+        2. Go to File → Export → CSV\n\
+        3. Load the exported .csv file in UltraLog",
+    ),
+    (
+        b"EMERALD",
+        "This is an AEM .daq file which uses a proprietary format.\n\n\
+        To use this log in UltraLog, please export it as CSV from AEM's software:\n\
+        1. Open the .daq file in AEMdata or AEM Pro\n\
+        2. Go to File → Export → CSV\n\
+        3. Load the exported .csv file in UltraLog",
+    ),
+];
+
+/// Gzip stream magic bytes
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Registry of all known log formats
+pub struct ParserRegistry {
+    formats: Vec<Box<dyn LogFormat>>,
+}
+
+impl Default for ParserRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParserRegistry {
+    /// Create a registry containing all built-in parsers
+    pub fn new() -> Self {
+        Self {
+            formats: vec![
+                Box::new(Aim),
+                Box::new(Speeduino),
+                Box::new(Link),
+                Box::new(MotEc),
+                Box::new(EmuProLog),
+                Box::new(EmuBlog),
+                Box::new(MegaSquirt),
+                Box::new(EcuMaster),
+                Box::new(RomRaider),
+                Box::new(Haltech),
+            ],
+        }
+    }
+
+    /// All registered formats, in registration order
+    pub fn formats(&self) -> &[Box<dyn LogFormat>] {
+        &self.formats
+    }
+
+    /// Find a registered format by ECU type
+    pub fn find(&self, ecu_type: EcuType) -> Option<&dyn LogFormat> {
+        self.formats
+            .iter()
+            .find(|f| f.ecu_type() == ecu_type)
+            .map(|f| f.as_ref())
+    }
+
+    /// All file extensions supported by the registered formats (deduplicated)
+    pub fn supported_extensions(&self) -> Vec<&'static str> {
+        let mut extensions: Vec<&'static str> = Vec::new();
+        for format in &self.formats {
+            for ext in format.extensions() {
+                if !extensions.contains(ext) {
+                    extensions.push(ext);
+                }
+            }
+        }
+        extensions
+    }
+
+    /// Extensions offered by file dialogs: those of the registered formats,
+    /// plus gzip-wrapped logs that are decompressed before detection
+    pub fn file_dialog_extensions(&self) -> Vec<&'static str> {
+        let mut extensions = self.supported_extensions();
+        extensions.push("gz");
+        extensions
+    }
+
+    /// Return an explanatory error if the data is a known but unsupported format
+    pub fn check_unsupported(data: &[u8]) -> Option<&'static str> {
+        UNSUPPORTED_FORMATS
+            .iter()
+            .find(|(magic, _)| data.starts_with(magic))
+            .map(|(_, message)| *message)
+    }
+
+    /// Transparently decompress gzip-wrapped logs (e.g. `.emublog`,
+    /// `.emuprolog.gz`); other data is returned unchanged
+    pub fn decompress(data: &[u8]) -> Result<Cow<'_, [u8]>, Box<dyn Error>> {
+        if !data.starts_with(GZIP_MAGIC) {
+            return Ok(Cow::Borrowed(data));
+        }
+
+        let mut decompressed = Vec::with_capacity(data.len() * 4);
+        flate2::read::MultiGzDecoder::new(data)
+            .read_to_end(&mut decompressed)
+            .map_err(|e| format!("Failed to decompress gzip file: {}", e))?;
+        Ok(Cow::Owned(decompressed))
+    }
+
+    /// Detect the most likely format for the data
+    ///
+    /// Returns `None` if no parser reports a non-zero confidence. When two
+    /// parsers report the same confidence, the one registered first wins.
+    pub fn detect(&self, data: &[u8]) -> Option<&dyn LogFormat> {
+        let mut best: Option<(&dyn LogFormat, Confidence)> = None;
+        for format in &self.formats {
+            let confidence = format.detect(data);
+            if confidence <= CONFIDENCE_NONE {
+                continue;
+            }
+            if best.is_none_or(|(_, c)| confidence > c) {
+                best = Some((format.as_ref(), confidence));
+            }
+        }
+        best.map(|(format, _)| format)
+    }
+
+    /// Decompress if needed, detect the format and parse the data
+    pub fn parse(&self, data: &[u8]) -> Result<(Log, EcuType), Box<dyn Error>> {
+        let data = Self::decompress(data)?;
+        let data = data.as_ref();
+
+        if let Some(message) = Self::check_unsupported(data) {
+            return Err(message.into());
+        }
+
+        let format = self.detect(data).ok_or("File format not recognized")?;
+
+        tracing::info!("Detected {} format", format.name());
+
+        match format.parse(data) {
+            Ok(log) => Ok((log, format.ecu_type())),
+            Err(e) => Err(format!("Failed to parse {} file: {}", format.name(), e).into()),
+        }
+    }
+}
+
+// ============================================================================
+// LogFormat implementations for the built-in parsers
+// ============================================================================
+
+impl LogFormat for Haltech {
+    fn name(&self) -> &'static str {
+        "Haltech"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::Haltech
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["csv", "log", "txt"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        if data.starts_with(b"%DataLog%") {
+            CONFIDENCE_CERTAIN
+        } else {
+            // Haltech is the historical default for text logs that no other
+            // parser claims, so it accepts anything at the lowest confidence
+            CONFIDENCE_FALLBACK
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        Parseable::parse(self, &decode_text(data))
+    }
+}
+
+impl LogFormat for EcuMaster {
+    fn name(&self) -> &'static str {
+        "ECUMaster"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::EcuMaster
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["csv"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        if EcuMaster::detect(&decode_text(first_line(data))) {
+            CONFIDENCE_LIKELY
+        } else {
+            CONFIDENCE_NONE
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        Parseable::parse(self, &decode_text(data))
+    }
+}
+
+impl LogFormat for MegaSquirt {
+    fn name(&self) -> &'static str {
+        "MegaSquirt MSL"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::MegaSquirt
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["msl"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        // The title, date and header rows all fit well within the first few KB
+        let head = &data[..data.len().min(4096)];
+        if MegaSquirt::detect(&decode_text(head)) {
+            CONFIDENCE_CERTAIN
+        } else {
+            CONFIDENCE_NONE
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        Parseable::parse(self, &decode_text(data))
+    }
+}
+
+impl LogFormat for RomRaider {
+    fn name(&self) -> &'static str {
+        "RomRaider"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::RomRaider
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["csv"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        if RomRaider::detect(&decode_text(first_line(data))) {
+            CONFIDENCE_LIKELY
+        } else {
+            CONFIDENCE_NONE
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        Parseable::parse(self, &decode_text(data))
+    }
+}
+
+impl LogFormat for Speeduino {
+    fn name(&self) -> &'static str {
+        "Speeduino/rusEFI MLG"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::Speeduino
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["mlg"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        if Speeduino::detect(data) {
+            CONFIDENCE_CERTAIN
+        } else {
+            CONFIDENCE_NONE
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        Speeduino::parse_binary(data)
+    }
+}
+
+impl LogFormat for Link {
+    fn name(&self) -> &'static str {
+        "Link ECU LLG"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::Link
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["llg", "llg5"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        if Link::detect(data) {
+            CONFIDENCE_CERTAIN
+        } else {
+            CONFIDENCE_NONE
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        Link::parse_binary(data)
+    }
+}
+
+impl LogFormat for MotEc {
+    fn name(&self) -> &'static str {
+        "MoTeC i2 LD"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::MotEc
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["ld"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        if MotEc::detect(data) {
+            CONFIDENCE_CERTAIN
+        } else {
+            CONFIDENCE_NONE
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        MotEc::parse_binary(data)
+    }
+}
+
+impl LogFormat for EmuBlog {
+    fn name(&self) -> &'static str {
+        "ECUMaster EMU Black"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::EcuMaster
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["emublog"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        if EmuBlog::detect(data) {
+            CONFIDENCE_CERTAIN
+        } else {
+            CONFIDENCE_NONE
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        EmuBlog::parse_binary(data)
+    }
+}
+
+impl LogFormat for EmuProLog {
+    fn name(&self) -> &'static str {
+        "ECUMaster EMU PRO"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::EcuMaster
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["emuprolog"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        if EmuProLog::detect(data) {
+            CONFIDENCE_CERTAIN
+        } else {
+            CONFIDENCE_NONE
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        EmuProLog::parse_binary(data)
+    }
+}
+
+impl LogFormat for Aim {
+    fn name(&self) -> &'static str {
+        "AIM XRK"
+    }
+
+    fn ecu_type(&self) -> EcuType {
+        EcuType::Aim
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["xrk", "drk"]
+    }
+
+    fn detect(&self, data: &[u8]) -> Confidence {
+        if Aim::detect(data) {
+            CONFIDENCE_CERTAIN
+        } else {
+            CONFIDENCE_NONE
+        }
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Log, Box<dyn Error>> {
+        Aim::parse_binary(data)
+    }
+}
+
+/// Get the first line of the data (text detectors only need the header row)
+fn first_line(data: &[u8]) -> &[u8] {
+    match data.iter().position(|&b| b == b'\n') {
+        Some(pos) => &data[..pos],
+        None => data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_binary_formats() {
+        let registry = ParserRegistry::new();
+
+        let mlg = b"MLVLG\x00\x00\x01";
+        assert_eq!(
+            registry.detect(mlg).map(|f| f.ecu_type()),
+            Some(EcuType::Speeduino)
+        );
+
+        let xrk = b"<hCNF\x00\x3c\xa5\x00\x00";
+        assert_eq!(
+            registry.detect(xrk).map(|f| f.ecu_type()),
+            Some(EcuType::Aim)
+        );
+    }
+
+    #[test]
+    fn test_detect_text_formats() {
+        let registry = ParserRegistry::new();
+
+        let ecumaster = b"TIME;engine/rpm\n0.0;1000\n";
+        assert_eq!(
+            registry.detect(ecumaster).map(|f| f.ecu_type()),
+            Some(EcuType::EcuMaster)
+        );
+
+        let romraider = b"Time (msec),Engine Speed (rpm)\n0,1000\n";
+        assert_eq!(
+            registry.detect(romraider).map(|f| f.ecu_type()),
+            Some(EcuType::RomRaider)
+        );
+
+        let msl = b"\"MS2Extra comms342h2\"\nTime\tRPM\ns\tRPM\n0.0\t900\n";
+        assert_eq!(
+            registry.detect(msl).map(|f| f.ecu_type()),
+            Some(EcuType::MegaSquirt)
+        );
+
+        let haltech = b"%DataLog%\nDataLogVersion : 1.1\n";
+        assert_eq!(
+            registry.detect(haltech).map(|f| f.ecu_type()),
+            Some(EcuType::Haltech)
+        );
+    }
+
+    #[test]
+    fn test_unknown_text_falls_back_to_haltech() {
+        let registry = ParserRegistry::new();
+        assert_eq!(
+            registry.detect(b"something else").map(|f| f.ecu_type()),
+            Some(EcuType::Haltech)
+        );
+    }
+
+    #[test]
+    fn test_unsupported_formats() {
+        assert!(ParserRegistry::check_unsupported(b"HEPS\x00\x01").is_some());
+        assert!(ParserRegistry::check_unsupported(b"EMERALD\x00").is_some());
+        assert!(ParserRegistry::check_unsupported(b"MLVLG").is_none());
+
+        let registry = ParserRegistry::new();
+        let err = registry.parse(b"HEPS\x00\x01").unwrap_err();
+        assert!(err.to_string().contains("hlgzip"));
+    }
+
+    #[test]
+    fn test_decompress_gzip() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(b"MLVLG\x00\x00\x01").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let data = ParserRegistry::decompress(&compressed).unwrap();
+        assert_eq!(data.as_ref(), b"MLVLG\x00\x00\x01");
+        assert!(matches!(
+            ParserRegistry::decompress(b"MLVLG"),
+            Ok(Cow::Borrowed(_))
+        ));
+        assert!(ParserRegistry::decompress(&[0x1f, 0x8b, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_supported_extensions_deduplicated() {
+        let registry = ParserRegistry::new();
+        let extensions = registry.supported_extensions();
+        assert_eq!(extensions.iter().filter(|e| **e == "csv").count(), 1);
+        assert!(extensions.contains(&"mlg"));
+        assert!(extensions.contains(&"llg5"));
+        assert!(registry.file_dialog_extensions().contains(&"gz"));
+    }
+
+    #[test]
+    fn test_first_line() {
+        assert_eq!(first_line(b"abc\ndef"), b"abc");
+        assert_eq!(first_line(b"abc"), b"abc");
+        assert_eq!(first_line(b""), b"");
+    }
+}
@@ -12,7 +12,7 @@
 use serde::Serialize;
 use std::error::Error;
 
-use super::types::{Channel, Log, Meta, Parseable, Value};
+use super::types::{Channel, Log, LogData, Meta, Parseable, Value};
 
 /// RomRaider log file metadata
 #[derive(Clone, Debug, Default, Serialize)]
@@ -241,7 +241,6 @@ impl Parseable for RomRaider {
 
         let mut channels: Vec<Channel> = Vec::with_capacity(50);
         let mut times: Vec<f64> = Vec::with_capacity(estimated_data_rows);
-        let mut data: Vec<Vec<Value>> = Vec::with_capacity(estimated_data_rows);
 
         let mut lines = file_contents.lines();
 
@@ -273,6 +272,8 @@ impl Parseable for RomRaider {
             channels.push(Channel::RomRaider(channel));
         }
 
+        let mut data = LogData::with_capacity(channels.len(), estimated_data_rows);
+
         // Track first timestamp for relative time calculation
         let mut first_time: Option<f64> = None;
 
@@ -308,17 +309,17 @@ impl Parseable for RomRaider {
                     if let Some(val) = Self::parse_european_number(part, delimiter) {
                         row_values.push(Value::Float(val));
                     } else {
-                        // Empty or non-numeric value - use 0
-                        row_values.push(Value::Float(0.0));
+                        // Empty or non-numeric value - no sample
+                        row_values.push(Value::MISSING);
                     }
                 }
 
                 // Pad row to match channel count if needed
                 while row_values.len() < channels.len() {
-                    row_values.push(Value::Float(0.0));
+                    row_values.push(Value::MISSING);
                 }
 
-                data.push(row_values);
+                data.push_row(row_values);
             }
         }
 
@@ -337,6 +338,7 @@ impl Parseable for RomRaider {
             channels,
             times,
             data,
+            markers: Vec::new(),
         })
     }
 }
@@ -463,9 +465,9 @@ mod tests {
         assert!((log.times[2] - 0.040).abs() < 0.001);
 
         // Check first row values
-        assert_eq!(log.data[0][0].as_f64(), 1000.0);
-        assert_eq!(log.data[0][1].as_f64(), 10.5);
-        assert_eq!(log.data[0][2].as_f64(), 1.5);
+        assert_eq!(log.data.row(0)[0], 1000.0);
+        assert_eq!(log.data.row(0)[1], 10.5);
+        assert_eq!(log.data.row(0)[2], 1.5);
 
         // Check units
         assert_eq!(log.channels[0].unit(), "rpm");
@@ -498,9 +500,9 @@ mod tests {
         assert!((log.times[2] - 0.396).abs() < 0.001);
 
         // Check first row values (European decimals converted)
-        assert_eq!(log.data[0][0].as_f64(), 14.0);
-        assert_eq!(log.data[0][1].as_f64(), 860.0);
-        assert!((log.data[0][2].as_f64() - 0.38).abs() < 0.001);
+        assert_eq!(log.data.row(0)[0], 14.0);
+        assert_eq!(log.data.row(0)[1], 860.0);
+        assert!((log.data.row(0)[2] - 0.38).abs() < 0.001);
 
         // Check units (extracted from parentheses in header)
         assert_eq!(log.channels[0].unit(), "degrees");
@@ -542,8 +544,8 @@ mod tests {
 
         assert_eq!(log.data.len(), 3);
 
-        // Empty values should be 0
-        assert_eq!(log.data[1][1].as_f64(), 0.0); // Missing Load
-        assert_eq!(log.data[2][0].as_f64(), 0.0); // Missing RPM
+        // Empty values are missing samples, not zeros
+        assert!(log.data.row(1)[1].is_nan()); // Missing Load
+        assert!(log.data.row(2)[0].is_nan()); // Missing RPM
     }
 }
@@ -6,11 +6,15 @@
 //! - Format version (int16) and metadata
 //! - Field definitions (55 bytes for v1, 89 bytes for v2)
 //! - Binary data records (block type + timestamp + field values)
+//! - Marker records (block type 1 + timestamp + 50-byte message)
 
 use serde::Serialize;
 use std::error::Error;
 
-use super::types::{Log, Parseable, Value};
+use super::types::{Log, LogData, Marker, MarkerKind, Parseable, Value};
+
+/// Size of the message in a marker record
+const MARKER_MESSAGE_SIZE: usize = 50;
 
 /// MLG field data types (from mlg-converter)
 #[derive(Clone, Copy, Debug)]
@@ -271,13 +275,12 @@ impl Speeduino {
         // Each record is roughly: 1 (block type) + 2 (timestamp) + num_fields * ~4 bytes + 1 (CRC)
         let remaining_data = data.len().saturating_sub(data_begin_index);
         let estimated_record_size = 4 + channels.len() * 4;
-        let estimated_records = if estimated_record_size > 0 {
-            remaining_data / estimated_record_size
-        } else {
-            1000 // Fallback estimate
-        };
+        let estimated_records = remaining_data
+            .checked_div(estimated_record_size)
+            .unwrap_or(1000); // Fallback estimate
         let mut times: Vec<f64> = Vec::with_capacity(estimated_records);
-        let mut data_records: Vec<Vec<Value>> = Vec::with_capacity(estimated_records);
+        let mut data_records = LogData::with_capacity(channels.len(), estimated_records);
+        let mut markers: Vec<Marker> = Vec::new();
 
         // Track timestamp wraparound (u16 wraps at 65535ms = 65.535 seconds)
         let mut prev_raw_timestamp: u16 = 0;
@@ -408,7 +411,7 @@ impl Speeduino {
                             | FieldType::U16Bitfield
                             | FieldType::U32Bitfield => {
                                 offset += field_type.byte_size();
-                                Value::Float(0.0) // Bitfields not fully supported yet
+                                Value::MISSING // Bitfields not fully supported yet
                             }
                         };
                         record.push(value);
@@ -419,13 +422,13 @@ impl Speeduino {
 
                 // Only add the timestamp and record together to ensure they stay in sync
                 times.push(timestamp);
-                data_records.push(record);
+                data_records.push_row(record);
 
                 // Skip CRC (1 byte)
                 offset += 1;
             } else if block_type == 1 {
-                // Marker record - skip marker message (50 bytes)
-                if offset + 50 > data.len() {
+                // Marker record - null-padded message (50 bytes)
+                if offset + MARKER_MESSAGE_SIZE > data.len() {
                     eprintln!(
                         "DEBUG: Not enough data for marker block at offset {} (need 50, have {})",
                         offset,
@@ -433,7 +436,19 @@ impl Speeduino {
                     );
                     break;
                 }
-                offset += 50;
+                let message = &data[offset..offset + MARKER_MESSAGE_SIZE];
+                let end = message
+                    .iter()
+                    .position(|&b| b == 0)
+                    .unwrap_or(message.len());
+                let label = String::from_utf8_lossy(&message[..end]).trim().to_string();
+                let label = if label.is_empty() {
+                    format!("Marker {}", markers.len() + 1)
+                } else {
+                    label
+                };
+                markers.push(Marker::new(MarkerKind::Manual, label, timestamp));
+                offset += MARKER_MESSAGE_SIZE;
             } else {
                 eprintln!(
                     "DEBUG: Unknown block type {} at offset {}",
@@ -489,17 +504,13 @@ impl Speeduino {
         // Debug: Show first few records to verify data structure
         if !data_records.is_empty() {
             eprintln!("DEBUG: First record (time={}):", times[0]);
-            for (idx, val) in data_records[0].iter().enumerate() {
-                if idx < channels.len() {
-                    eprintln!("  [{}] {} = {:.3}", idx, channels[idx].name, val.as_f64());
-                }
+            for (idx, val) in data_records.row(0).iter().enumerate() {
+                eprintln!("  [{}] {} = {:.3}", idx, channels[idx].name, val);
             }
             if data_records.len() > 1 {
                 eprintln!("DEBUG: Second record (time={}):", times[1]);
-                for (idx, val) in data_records[1].iter().enumerate() {
-                    if idx < channels.len() {
-                        eprintln!("  [{}] {} = {:.3}", idx, channels[idx].name, val.as_f64());
-                    }
+                for (idx, val) in data_records.row(1).iter().enumerate() {
+                    eprintln!("  [{}] {} = {:.3}", idx, channels[idx].name, val);
                 }
             }
         }
@@ -514,20 +525,6 @@ impl Speeduino {
             .into());
         }
 
-        // Validate that all data records have the correct number of values
-        let channel_count = channels.len();
-        for (i, record) in data_records.iter().enumerate() {
-            if record.len() != channel_count {
-                return Err(format!(
-                    "Data integrity error: record {} has {} values but {} channels expected",
-                    i,
-                    record.len(),
-                    channel_count
-                )
-                .into());
-            }
-        }
-
         Ok(Log {
             meta: super::types::Meta::Speeduino(meta),
             channels: channels
@@ -536,6 +533,7 @@ impl Speeduino {
                 .collect(),
             times,
             data: data_records,
+            markers,
         })
     }
 }
@@ -813,4 +811,54 @@ mod tests {
         eprintln!("Parsed {} channels from rusEFI log", log.channels.len());
         eprintln!("Parsed {} data records", log.data.len());
     }
+
+    /// Build a minimal v1 MLG file with a single U08 "RPM" field
+    fn build_mlg(records: &[(u8, u16, &[u8])]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"MLVLG\x00");
+        data.extend_from_slice(&1i16.to_be_bytes()); // format version
+        data.extend_from_slice(&0u32.to_be_bytes()); // timestamp
+        data.extend_from_slice(&77u16.to_be_bytes()); // info_data_start
+        data.extend_from_slice(&77u32.to_be_bytes()); // data_begin_index
+        data.extend_from_slice(&1u16.to_be_bytes()); // record_length
+        data.extend_from_slice(&1u16.to_be_bytes()); // num_logger_fields
+
+        let mut field = vec![0u8; 55];
+        field[1..4].copy_from_slice(b"RPM");
+        field[46..50].copy_from_slice(&1.0f32.to_be_bytes());
+        data.extend_from_slice(&field);
+
+        for (counter, &(block_type, timestamp, payload)) in records.iter().enumerate() {
+            data.push(block_type);
+            data.push(counter as u8);
+            data.extend_from_slice(&timestamp.to_be_bytes());
+            data.extend_from_slice(payload);
+        }
+        data
+    }
+
+    #[test]
+    fn test_marker_records() {
+        let mut message = [0u8; MARKER_MESSAGE_SIZE];
+        message[..9].copy_from_slice(b"Full pull");
+        let data = build_mlg(&[
+            (0, 0, &[50, 0]),
+            (0, 100, &[60, 0]),
+            (1, 150, &message),
+            (0, 200, &[70, 0]),
+            (1, 250, &[0u8; MARKER_MESSAGE_SIZE]),
+        ]);
+
+        let log = Speeduino::parse_binary(&data).unwrap();
+
+        assert_eq!(log.data.len(), 3, "Marker records are not data");
+        assert_eq!(log.data.row(2)[0], 70.0);
+        assert_eq!(
+            log.markers,
+            vec![
+                Marker::new(MarkerKind::Manual, "Full pull", 0.15),
+                Marker::new(MarkerKind::Manual, "Marker 2", 0.25),
+            ]
+        );
+    }
 }
@@ -1,10 +1,14 @@
 use serde::Serialize;
+use std::borrow::Cow;
 use std::error::Error;
+use std::sync::Arc;
 
 use super::aim::{AimChannel, AimMeta};
 use super::ecumaster::{EcuMasterChannel, EcuMasterMeta};
 use super::haltech::{HaltechChannel, HaltechMeta};
 use super::link::{LinkChannel, LinkMeta};
+use super::megasquirt::{MegaSquirtChannel, MegaSquirtMeta};
+use super::motec::{MotEcChannel, MotEcMeta};
 use super::romraider::{RomRaiderChannel, RomRaiderMeta};
 use super::speeduino::{SpeeduinoChannel, SpeeduinoMeta};
 
@@ -15,6 +19,8 @@ pub enum Meta {
     Haltech(HaltechMeta),
     EcuMaster(EcuMasterMeta),
     Link(LinkMeta),
+    MegaSquirt(MegaSquirtMeta),
+    MotEc(MotEcMeta),
     RomRaider(RomRaiderMeta),
     Speeduino(SpeeduinoMeta),
     #[default]
@@ -39,6 +45,8 @@ pub enum Channel {
     Haltech(HaltechChannel),
     EcuMaster(EcuMasterChannel),
     Link(LinkChannel),
+    MegaSquirt(MegaSquirtChannel),
+    MotEc(MotEcChannel),
     RomRaider(RomRaiderChannel),
     Speeduino(SpeeduinoChannel),
     /// A computed/virtual channel derived from a formula
@@ -55,6 +63,8 @@ impl Serialize for Channel {
             Channel::Haltech(h) => h.serialize(serializer),
             Channel::EcuMaster(e) => e.serialize(serializer),
             Channel::Link(l) => l.serialize(serializer),
+            Channel::MegaSquirt(m) => m.serialize(serializer),
+            Channel::MotEc(m) => m.serialize(serializer),
             Channel::RomRaider(r) => r.serialize(serializer),
             Channel::Speeduino(s) => s.serialize(serializer),
             Channel::Computed(c) => c.serialize(serializer),
@@ -69,6 +79,8 @@ impl Channel {
             Channel::Haltech(h) => h.name.clone(),
             Channel::EcuMaster(e) => e.name.clone(),
             Channel::Link(l) => l.name.clone(),
+            Channel::MegaSquirt(m) => m.name.clone(),
+            Channel::MotEc(m) => m.name.clone(),
             Channel::RomRaider(r) => r.name.clone(),
             Channel::Speeduino(s) => s.name.clone(),
             Channel::Computed(c) => c.name.clone(),
@@ -82,6 +94,8 @@ impl Channel {
             Channel::Haltech(h) => h.id.clone(),
             Channel::EcuMaster(e) => e.path.clone(),
             Channel::Link(l) => l.channel_id.to_string(),
+            Channel::MegaSquirt(m) => m.name.clone(),
+            Channel::MotEc(m) => m.name.clone(),
             Channel::RomRaider(r) => r.name.clone(),
             Channel::Speeduino(s) => s.name.clone(),
             Channel::Computed(c) => format!("computed_{}", c.name),
@@ -94,6 +108,8 @@ impl Channel {
             Channel::Haltech(h) => h.r#type.as_ref().to_string(),
             Channel::EcuMaster(e) => e.path.clone(),
             Channel::Link(_) => "Link".to_string(),
+            Channel::MegaSquirt(_) => "MegaSquirt".to_string(),
+            Channel::MotEc(_) => "MoTeC".to_string(),
             Channel::RomRaider(_) => "RomRaider".to_string(),
             Channel::Speeduino(_) => "Speeduino/rusEFI".to_string(),
             Channel::Computed(_) => "Computed".to_string(),
@@ -106,6 +122,8 @@ impl Channel {
             Channel::Haltech(h) => h.display_min,
             Channel::EcuMaster(_) => None,
             Channel::Link(_) => None,
+            Channel::MegaSquirt(_) => None,
+            Channel::MotEc(_) => None,
             Channel::RomRaider(_) => None,
             Channel::Speeduino(_) => None,
             Channel::Computed(_) => None,
@@ -118,6 +136,8 @@ impl Channel {
             Channel::Haltech(h) => h.display_max,
             Channel::EcuMaster(_) => None,
             Channel::Link(_) => None,
+            Channel::MegaSquirt(_) => None,
+            Channel::MotEc(_) => None,
             Channel::RomRaider(_) => None,
             Channel::Speeduino(_) => None,
             Channel::Computed(_) => None,
@@ -130,6 +150,8 @@ impl Channel {
             Channel::Haltech(h) => h.unit(),
             Channel::EcuMaster(e) => e.unit(),
             Channel::Link(l) => l.unit(),
+            Channel::MegaSquirt(m) => m.unit(),
+            Channel::MotEc(m) => m.unit(),
             Channel::RomRaider(r) => r.unit(),
             Channel::Speeduino(s) => s.unit(),
             Channel::Computed(c) => &c.unit,
@@ -144,10 +166,22 @@ impl Channel {
 
 /// Optimized value storage - all ECU log data is stored as f64
 /// This uses 8 bytes per value instead of 16 bytes with the previous enum
-#[derive(Clone, Copy, Debug, Default)]
+///
+/// Samples that were not logged are stored as NaN ("no data") rather than an
+/// invented zero, so they show up as gaps instead of fake readings.
+#[derive(Clone, Copy, Debug)]
 pub struct Value(f64);
 
+impl Default for Value {
+    fn default() -> Self {
+        Self::MISSING
+    }
+}
+
 impl Value {
+    /// A sample with no data
+    pub const MISSING: Value = Value(f64::NAN);
+
     /// Create a new Value from an f64
     /// Note: Named 'Float' to maintain API compatibility with previous enum variant
     #[inline]
@@ -156,11 +190,23 @@ impl Value {
         Self(value)
     }
 
-    /// Convert value to f64 for charting
+    /// Convert value to f64 for charting (NaN if missing)
     #[inline]
     pub fn as_f64(&self) -> f64 {
         self.0
     }
+
+    /// Check if this sample has no data
+    #[inline]
+    pub fn is_missing(&self) -> bool {
+        self.0.is_nan()
+    }
+
+    /// Get the value, or `None` if missing
+    #[inline]
+    pub fn as_option(&self) -> Option<f64> {
+        (!self.is_missing()).then_some(self.0)
+    }
 }
 
 impl Serialize for Value {
@@ -172,6 +218,345 @@ impl Serialize for Value {
     }
 }
 
+/// Sample times of a channel recorded at its own rate
+///
+/// Shared (via `Arc`) by every channel logged at the same rate. Besides the
+/// sample times it records which sample is in effect at each log record, so
+/// record lookups hold the last sample without resampling the data.
+#[derive(Clone, Debug, Default)]
+pub struct Timebase {
+    times: Vec<f64>,
+    /// Sample index in effect at each log record (`u32::MAX` before the first)
+    held: Vec<u32>,
+}
+
+impl Timebase {
+    /// Build a timebase from its sample times, mapped onto the log's record times
+    pub fn new(times: Vec<f64>, log_times: &[f64]) -> Self {
+        let mut held = Vec::with_capacity(log_times.len());
+        let mut next = 0usize;
+        for &time in log_times {
+            while next < times.len() && times[next] <= time {
+                next += 1;
+            }
+            held.push(next.checked_sub(1).map_or(u32::MAX, |i| i as u32));
+        }
+        Self { times, held }
+    }
+
+    /// Sample times in seconds
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    /// Sample in effect at a log record
+    fn sample_at_record(&self, record: usize) -> Option<usize> {
+        self.held
+            .get(record)
+            .filter(|&&i| i != u32::MAX)
+            .map(|&i| i as usize)
+    }
+}
+
+/// Index of the last sample at or before `time`
+pub fn sample_at_or_before(times: &[f64], time: f64) -> Option<usize> {
+    times.partition_point(|&t| t <= time).checked_sub(1)
+}
+
+/// Channel data stored column-major: one contiguous column per channel
+///
+/// Channel access is a slice borrow. Parsers append rows with `push_row`, and
+/// row-oriented consumers such as the formula engine read through `Row` views.
+///
+/// A column normally holds one value per log record. Channels recorded at
+/// their own rate instead keep their native samples alongside a `Timebase`;
+/// record lookups on those channels hold the last sample.
+#[derive(Clone, Debug, Default)]
+pub struct LogData {
+    columns: Vec<Vec<f64>>,
+    timebases: Vec<Option<Arc<Timebase>>>,
+    len: usize,
+}
+
+impl LogData {
+    /// Create empty storage for the given number of channels
+    pub fn new(channel_count: usize) -> Self {
+        Self::with_capacity(channel_count, 0)
+    }
+
+    /// Create empty storage with room for `rows` records per channel
+    pub fn with_capacity(channel_count: usize, rows: usize) -> Self {
+        Self {
+            columns: (0..channel_count)
+                .map(|_| Vec::with_capacity(rows))
+                .collect(),
+            timebases: vec![None; channel_count],
+            len: 0,
+        }
+    }
+
+    /// Build from row-major records. Short rows are padded with missing samples.
+    pub fn from_rows(rows: Vec<Vec<Value>>) -> Self {
+        let channel_count = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut data = Self::with_capacity(channel_count, rows.len());
+        for row in rows {
+            data.push_row(row);
+        }
+        data
+    }
+
+    /// Build from per-channel columns. Short columns are padded with missing samples.
+    pub fn from_columns(mut columns: Vec<Vec<f64>>) -> Self {
+        let len = columns.iter().map(Vec::len).max().unwrap_or(0);
+        for column in &mut columns {
+            column.resize(len, f64::NAN);
+        }
+        Self {
+            timebases: vec![None; columns.len()],
+            columns,
+            len,
+        }
+    }
+
+    /// Build from per-channel columns, some of which carry their own timebase.
+    ///
+    /// `len` is the number of log records. Columns without a timebase are
+    /// padded or truncated to `len`; columns with one to its sample count.
+    pub fn from_timed_columns(len: usize, columns: Vec<(Vec<f64>, Option<Arc<Timebase>>)>) -> Self {
+        let (columns, timebases) = columns
+            .into_iter()
+            .map(|(mut values, timebase)| {
+                let count = timebase.as_ref().map_or(len, |tb| tb.times.len());
+                values.resize(count, f64::NAN);
+                (values, timebase)
+            })
+            .unzip();
+        Self {
+            columns,
+            timebases,
+            len,
+        }
+    }
+
+    /// Append a record. Short records are padded with missing samples and
+    /// extra values are ignored, as are values for channels with their own
+    /// timebase.
+    pub fn push_row<I: IntoIterator<Item = Value>>(&mut self, row: I) {
+        let mut values = row.into_iter();
+        for (column, timebase) in self.columns.iter_mut().zip(&self.timebases) {
+            let value = values.next().unwrap_or(Value::MISSING);
+            if timebase.is_none() {
+                column.push(value.as_f64());
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Number of records
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Borrow all values of a channel, on its own timebase if it has one
+    pub fn column(&self, channel_index: usize) -> Option<&[f64]> {
+        self.columns.get(channel_index).map(Vec::as_slice)
+    }
+
+    /// Timebase of a channel recorded at its own rate
+    pub fn timebase(&self, channel_index: usize) -> Option<&Timebase> {
+        self.timebases.get(channel_index)?.as_deref()
+    }
+
+    /// Values of a channel with one entry per log record
+    ///
+    /// Borrowed for channels on the log timebase; channels with their own
+    /// timebase are expanded by holding the last sample.
+    pub fn aligned_column(&self, channel_index: usize) -> Option<Cow<'_, [f64]>> {
+        let column = self.columns.get(channel_index)?;
+        Some(match self.timebase(channel_index) {
+            None => Cow::Borrowed(column.as_slice()),
+            Some(_) => Cow::Owned(
+                (0..self.len)
+                    .map(|record| self.value(record, channel_index))
+                    .collect(),
+            ),
+        })
+    }
+
+    /// Get a single value
+    pub fn get(&self, record: usize, channel_index: usize) -> Option<f64> {
+        if record >= self.len || channel_index >= self.columns.len() {
+            return None;
+        }
+        Some(self.value(record, channel_index))
+    }
+
+    /// Reference to the value in effect at an in-bounds record
+    fn value_ref(&self, record: usize, channel_index: usize) -> &f64 {
+        let column = &self.columns[channel_index];
+        match &self.timebases[channel_index] {
+            None => &column[record],
+            Some(timebase) => timebase
+                .sample_at_record(record)
+                .map_or(&MISSING_SAMPLE, |i| &column[i]),
+        }
+    }
+
+    fn value(&self, record: usize, channel_index: usize) -> f64 {
+        *self.value_ref(record, channel_index)
+    }
+
+    /// View a record. Panics if `record` is out of bounds.
+    pub fn row(&self, record: usize) -> Row<'_> {
+        assert!(
+            record < self.len,
+            "record {} out of bounds ({} records)",
+            record,
+            self.len
+        );
+        Row {
+            data: self,
+            index: record,
+        }
+    }
+
+    pub fn first(&self) -> Option<Row<'_>> {
+        (!self.is_empty()).then(|| self.row(0))
+    }
+
+    /// Iterate over records as row views
+    pub fn iter(&self) -> Rows<'_> {
+        Rows {
+            data: self,
+            next: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a LogData {
+    type Item = Row<'a>;
+    type IntoIter = Rows<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+static MISSING_SAMPLE: f64 = f64::NAN;
+
+/// Borrowed view of one record across all channels
+#[derive(Clone, Copy, Debug)]
+pub struct Row<'a> {
+    data: &'a LogData,
+    index: usize,
+}
+
+impl<'a> Row<'a> {
+    /// Number of channels in the record
+    pub fn len(&self) -> usize {
+        self.data.channel_count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, channel_index: usize) -> Option<f64> {
+        self.data.get(self.index, channel_index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = f64> + 'a {
+        let (data, index) = (self.data, self.index);
+        (0..data.channel_count()).map(move |channel_index| data.value(index, channel_index))
+    }
+
+    pub fn to_vec(&self) -> Vec<f64> {
+        self.iter().collect()
+    }
+}
+
+impl std::ops::Index<usize> for Row<'_> {
+    type Output = f64;
+
+    fn index(&self, channel_index: usize) -> &f64 {
+        self.data.value_ref(self.index, channel_index)
+    }
+}
+
+/// Iterator over the records of a `LogData`
+pub struct Rows<'a> {
+    data: &'a LogData,
+    next: usize,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = Row<'a>;
+
+    fn next(&mut self) -> Option<Row<'a>> {
+        if self.next >= self.data.len {
+            return None;
+        }
+        let row = self.data.row(self.next);
+        self.next += 1;
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.data.len - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Rows<'_> {}
+
+/// Kind of event recorded alongside the log data
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum MarkerKind {
+    /// Marker placed by the driver or tuner (marker button, keypress)
+    Manual,
+    /// Lap or beacon crossing
+    Lap,
+    /// Other event logged by the device
+    Event,
+}
+
+impl MarkerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarkerKind::Manual => "Marker",
+            MarkerKind::Lap => "Lap",
+            MarkerKind::Event => "Event",
+        }
+    }
+}
+
+/// A timestamped event in the log (marker, lap, beacon, ...)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Marker {
+    pub kind: MarkerKind,
+    pub label: String,
+    /// Time in seconds, on the same timeline as `Log::times`
+    pub time: f64,
+}
+
+impl Marker {
+    pub fn new(kind: MarkerKind, label: impl Into<String>, time: f64) -> Self {
+        Self {
+            kind,
+            label: label.into(),
+            time,
+        }
+    }
+}
+
 /// Parsed log file structure
 #[derive(Clone, Debug, Default)]
 pub struct Log {
@@ -180,16 +565,61 @@ pub struct Log {
     pub channels: Vec<Channel>,
     /// Time values stored directly as f64 (seconds) for efficiency
     pub times: Vec<f64>,
-    pub data: Vec<Vec<Value>>,
+    /// Channel values, one column per entry in `channels`
+    pub data: LogData,
+    /// Markers and events, sorted by time
+    pub markers: Vec<Marker>,
 }
 
 impl Log {
-    /// Get data for a specific channel by index
-    pub fn get_channel_data(&self, channel_index: usize) -> Vec<f64> {
+    /// Get data for a specific channel by index (empty if out of range).
+    /// Pair with `get_channel_times`, which differs from `times` for channels
+    /// recorded at their own rate.
+    pub fn get_channel_data(&self, channel_index: usize) -> &[f64] {
+        self.data.column(channel_index).unwrap_or(&[])
+    }
+
+    /// Sample times of a channel: its own timebase, or the log's record times
+    pub fn get_channel_times(&self, channel_index: usize) -> &[f64] {
         self.data
-            .iter()
-            .filter_map(|row| row.get(channel_index).map(|v| v.as_f64()))
-            .collect()
+            .timebase(channel_index)
+            .map_or(&self.times, Timebase::times)
+    }
+
+    /// Channel data with one value per record of `times`
+    pub fn get_aligned_channel_data(&self, channel_index: usize) -> Cow<'_, [f64]> {
+        self.data
+            .aligned_column(channel_index)
+            .unwrap_or(Cow::Borrowed(&[]))
+    }
+
+    /// Value of a channel at a time. Channels on the log timebase use the
+    /// nearest record; channels with their own timebase hold their last sample.
+    pub fn value_at_time(&self, channel_index: usize, time: f64) -> Option<f64> {
+        let value = match self.data.timebase(channel_index) {
+            Some(timebase) => {
+                let sample = sample_at_or_before(timebase.times(), time)?;
+                self.data.column(channel_index)?.get(sample).copied()
+            }
+            None => self
+                .data
+                .get(self.find_record_at_time(time)?, channel_index),
+        };
+        value.filter(|v| !v.is_nan())
+    }
+
+    /// Index of the record closest to the given time
+    pub fn find_record_at_time(&self, time: f64) -> Option<usize> {
+        let times = &self.times;
+        if times.is_empty() {
+            return None;
+        }
+        let idx = times.partition_point(|&t| t < time).min(times.len() - 1);
+        if idx > 0 && (times[idx] - time).abs() > (times[idx - 1] - time).abs() {
+            Some(idx - 1)
+        } else {
+            Some(idx)
+        }
     }
 
     /// Get time values as f64 slice (seconds) - no parsing needed, stored directly
@@ -202,6 +632,18 @@ impl Log {
     pub fn find_channel_index(&self, name: &str) -> Option<usize> {
         self.channels.iter().position(|c| c.name() == name)
     }
+
+    /// First marker strictly after the given time
+    pub fn next_marker(&self, time: f64) -> Option<&Marker> {
+        let idx = self.markers.partition_point(|m| m.time <= time);
+        self.markers.get(idx)
+    }
+
+    /// Last marker strictly before the given time
+    pub fn previous_marker(&self, time: f64) -> Option<&Marker> {
+        let idx = self.markers.partition_point(|m| m.time < time);
+        idx.checked_sub(1).map(|i| &self.markers[i])
+    }
 }
 
 /// Trait for log file parsers
@@ -284,9 +726,17 @@ mod tests {
     }
 
     #[test]
-    fn test_value_default() {
+    fn test_value_default_is_missing() {
         let val = Value::default();
-        assert_eq!(val.as_f64(), 0.0);
+        assert!(val.is_missing());
+        assert_eq!(val.as_option(), None);
+    }
+
+    #[test]
+    fn test_value_missing() {
+        assert!(Value::MISSING.as_f64().is_nan());
+        assert!(!Value::Float(0.0).is_missing());
+        assert_eq!(Value::Float(14.7).as_option(), Some(14.7));
     }
 
     #[test]
@@ -304,6 +754,133 @@ mod tests {
         assert_eq!(val1.as_f64(), val2.as_f64());
     }
 
+    // ============================================
+    // LogData Tests
+    // ============================================
+
+    #[test]
+    fn test_log_data_push_row() {
+        let mut data = LogData::new(2);
+        data.push_row([Value::Float(1.0), Value::Float(10.0)]);
+        data.push_row([Value::Float(2.0)]);
+        data.push_row([Value::Float(3.0), Value::Float(30.0), Value::Float(99.0)]);
+
+        assert_eq!(data.len(), 3);
+        assert_eq!(data.channel_count(), 2);
+        assert_eq!(data.column(0), Some(&[1.0, 2.0, 3.0][..]));
+        // Short rows are padded with missing samples, extra values are dropped
+        let column = data.column(1).unwrap();
+        assert_eq!(column[0], 10.0);
+        assert!(column[1].is_nan());
+        assert_eq!(column[2], 30.0);
+        assert_eq!(data.column(2), None);
+    }
+
+    #[test]
+    fn test_log_data_row_view() {
+        let data = LogData::from_columns(vec![vec![1.0, 2.0], vec![10.0, 20.0]]);
+
+        let row = data.row(1);
+        assert_eq!(row.len(), 2);
+        assert_eq!(row[0], 2.0);
+        assert_eq!(row.get(1), Some(20.0));
+        assert_eq!(row.get(2), None);
+        assert_eq!(row.to_vec(), vec![2.0, 20.0]);
+
+        let rows: Vec<Vec<f64>> = data.iter().map(|r| r.to_vec()).collect();
+        assert_eq!(rows, vec![vec![1.0, 10.0], vec![2.0, 20.0]]);
+        assert_eq!(data.iter().len(), 2);
+        assert_eq!(data.get(0, 1), Some(10.0));
+        assert_eq!(data.get(2, 0), None);
+    }
+
+    #[test]
+    fn test_log_data_from_columns_pads_short_columns() {
+        let data = LogData::from_columns(vec![vec![1.0, 2.0, 3.0], vec![5.0]]);
+        assert_eq!(data.len(), 3);
+        let column = data.column(1).unwrap();
+        assert_eq!(column[0], 5.0);
+        assert!(column[1..].iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn test_timebase_holds_last_sample() {
+        let log_times = [0.0, 0.5, 1.0, 1.5, 2.0];
+        let timebase = Timebase::new(vec![0.4, 1.5], &log_times);
+
+        assert_eq!(timebase.times(), &[0.4, 1.5]);
+        assert_eq!(timebase.sample_at_record(0), None);
+        assert_eq!(timebase.sample_at_record(1), Some(0));
+        assert_eq!(timebase.sample_at_record(2), Some(0));
+        assert_eq!(timebase.sample_at_record(3), Some(1));
+        assert_eq!(timebase.sample_at_record(4), Some(1));
+    }
+
+    #[test]
+    fn test_log_data_timed_columns() {
+        let log_times = [0.0, 0.5, 1.0, 1.5];
+        let slow = Arc::new(Timebase::new(vec![0.0, 1.0], &log_times));
+        let data = LogData::from_timed_columns(
+            4,
+            vec![
+                (vec![1.0, 2.0, 3.0, 4.0], None),
+                (vec![10.0, 20.0], Some(Arc::clone(&slow))),
+            ],
+        );
+
+        assert_eq!(data.len(), 4);
+        // Native samples are stored once
+        assert_eq!(data.column(1), Some(&[10.0, 20.0][..]));
+        assert_eq!(data.timebase(1).unwrap().times(), &[0.0, 1.0]);
+        assert!(data.timebase(0).is_none());
+
+        // Record access holds the slow channel
+        assert_eq!(data.get(1, 1), Some(10.0));
+        assert_eq!(data.row(3).to_vec(), vec![4.0, 20.0]);
+        assert_eq!(data.row(2)[1], 20.0);
+        assert_eq!(
+            data.aligned_column(1).unwrap().as_ref(),
+            &[10.0, 10.0, 20.0, 20.0]
+        );
+        assert!(matches!(data.aligned_column(0), Some(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn test_log_value_at_time() {
+        let times = vec![0.0, 0.5, 1.0, 1.5];
+        let timebase = Arc::new(Timebase::new(vec![0.2, 1.2], &times));
+        let log = Log {
+            data: LogData::from_timed_columns(
+                4,
+                vec![
+                    (vec![1.0, 2.0, 3.0, 4.0], None),
+                    (vec![10.0, 20.0], Some(timebase)),
+                ],
+            ),
+            times,
+            ..Default::default()
+        };
+
+        // Log timebase: nearest record
+        assert_eq!(log.value_at_time(0, 0.7), Some(2.0));
+        assert_eq!(log.value_at_time(0, 0.8), Some(3.0));
+        // Own timebase: last sample at or before the time
+        assert_eq!(log.value_at_time(1, 0.1), None);
+        assert_eq!(log.value_at_time(1, 1.19), Some(10.0));
+        assert_eq!(log.value_at_time(1, 5.0), Some(20.0));
+
+        assert_eq!(log.get_channel_times(0), &[0.0, 0.5, 1.0, 1.5]);
+        assert_eq!(log.get_channel_times(1), &[0.2, 1.2]);
+        assert_eq!(log.find_record_at_time(-1.0), Some(0));
+        assert_eq!(log.find_record_at_time(9.0), Some(3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_log_data_row_out_of_bounds() {
+        LogData::new(1).row(0);
+    }
+
     // ============================================
     // Log Tests
     // ============================================
@@ -322,11 +899,12 @@ mod tests {
             meta: Meta::Empty,
             channels: vec![],
             times: vec![0.0, 1.0, 2.0],
-            data: vec![
+            data: LogData::from_rows(vec![
                 vec![Value::Float(100.0), Value::Float(200.0)],
                 vec![Value::Float(110.0), Value::Float(210.0)],
                 vec![Value::Float(120.0), Value::Float(220.0)],
-            ],
+            ]),
+            markers: vec![],
         };
 
         let channel0 = log.get_channel_data(0);
@@ -342,7 +920,8 @@ mod tests {
             meta: Meta::Empty,
             channels: vec![],
             times: vec![0.0, 1.0],
-            data: vec![vec![Value::Float(100.0)], vec![Value::Float(110.0)]],
+            data: LogData::from_rows(vec![vec![Value::Float(100.0)], vec![Value::Float(110.0)]]),
+            markers: vec![],
         };
 
         // Out of bounds should return empty
@@ -356,7 +935,8 @@ mod tests {
             meta: Meta::Empty,
             channels: vec![],
             times: vec![0.0, 0.5, 1.0, 1.5, 2.0],
-            data: vec![],
+            data: LogData::default(),
+            markers: vec![],
         };
 
         let times = log.get_times_as_f64();
@@ -396,7 +976,8 @@ mod tests {
                 }),
             ],
             times: vec![],
-            data: vec![],
+            data: LogData::default(),
+            markers: vec![],
         };
 
         assert_eq!(log.find_channel_index("RPM"), Some(0));
@@ -405,6 +986,26 @@ mod tests {
         assert_eq!(log.find_channel_index("Not Found"), None);
     }
 
+    #[test]
+    fn test_log_marker_navigation() {
+        let log = Log {
+            markers: vec![
+                Marker::new(MarkerKind::Lap, "Lap 1", 10.0),
+                Marker::new(MarkerKind::Manual, "Marker 1", 25.0),
+                Marker::new(MarkerKind::Lap, "Lap 2", 40.0),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(log.next_marker(0.0).map(|m| m.time), Some(10.0));
+        assert_eq!(log.next_marker(10.0).map(|m| m.time), Some(25.0));
+        assert_eq!(log.next_marker(40.0), None);
+        assert_eq!(log.previous_marker(25.0).map(|m| m.time), Some(10.0));
+        assert_eq!(log.previous_marker(30.0).map(|m| m.time), Some(25.0));
+        assert_eq!(log.previous_marker(10.0), None);
+        assert_eq!(log.markers[1].kind.name(), "Marker");
+    }
+
     // ============================================
     // Channel Tests
     // ============================================
@@ -4,8 +4,17 @@
 //! the application, including loaded files, selected channels, and color palettes.
 
 use std::path::PathBuf;
+use std::sync::atomic::AtomicU32;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
 
+use crate::computed::ComputedChannelLibrary;
+use crate::laps::{Lap, TimingLine, DEFAULT_LINE_WIDTH_M};
+use crate::lookup_table::LookupTable;
+use crate::overlay::{TimeMap, Trigger};
 use crate::parsers::{Channel, EcuType, Log};
+use crate::ve_analyze::{VeAnalysis, VeAnalyzeFilters};
+use crate::vehicle_profile::VehicleProfile;
 
 // ============================================================================
 // Constants
@@ -17,10 +26,6 @@ pub const MAX_CHANNELS: usize = 10;
 /// Maximum points to render in chart (for performance via LTTB downsampling)
 pub const MAX_CHART_POINTS: usize = 2000;
 
-/// Supported log file extensions (used in file dialogs)
-pub const SUPPORTED_EXTENSIONS: &[&str] =
-    &["csv", "log", "txt", "mlg", "llg", "llg5", "xrk", "drk"];
-
 /// Color palette for chart lines (matches original theme)
 pub const CHART_COLORS: &[[u8; 3]] = &[
     [113, 120, 78],  // Olive green (primary)
@@ -68,6 +73,8 @@ pub struct LoadedFile {
     /// Cached flag for each channel: true if channel has non-zero data
     /// Computed once on load for UI performance
     pub channels_with_data: Vec<bool>,
+    /// ID of the vehicle profile whose constants this file's formulas use
+    pub vehicle_profile: Option<String>,
 }
 
 impl LoadedFile {
@@ -87,6 +94,7 @@ impl LoadedFile {
             ecu_type,
             log,
             channels_with_data,
+            vehicle_profile: None,
         }
     }
 
@@ -119,6 +127,26 @@ pub enum LoadResult {
     Error(String),
 }
 
+/// A computed channel being evaluated on a background thread
+pub struct ComputedJob {
+    /// Matches `ComputedChannel::pending_job` of the channels being evaluated
+    pub id: u64,
+    /// Completed fraction in thousandths, updated by the worker
+    pub progress: Arc<AtomicU32>,
+    /// Receives each evaluated channel's name and values as it finishes;
+    /// disconnects once the whole job is done
+    pub receiver: Receiver<(String, Vec<f64>)>,
+}
+
+/// Whole-log preview of the formula editor's formula, evaluated on a
+/// background thread
+pub struct FormulaPreviewJob {
+    /// Formula being evaluated; results for an edited formula are dropped
+    pub formula: String,
+    /// Receives the downsampled plot points and the first few values
+    pub receiver: Receiver<(Vec<[f64; 2]>, Vec<f64>)>,
+}
+
 /// Current state of file loading
 pub enum LoadingState {
     /// No loading in progress
@@ -180,6 +208,10 @@ pub enum ActiveTool {
     LogViewer,
     /// Scatter plot view for comparing two variables with color coding
     ScatterPlot,
+    /// Fuel table corrections from AFR error per RPM / load cell
+    VeAnalyze,
+    /// Driven path from GPS channels, synced to the cursor
+    TrackMap,
 }
 
 impl ActiveTool {
@@ -188,6 +220,8 @@ impl ActiveTool {
         match self {
             ActiveTool::LogViewer => "Log Viewer",
             ActiveTool::ScatterPlot => "Scatter Plots",
+            ActiveTool::VeAnalyze => "VE Analyze",
+            ActiveTool::TrackMap => "Track Map",
         }
     }
 }
@@ -216,6 +250,23 @@ pub struct ScatterPlotConfig {
     pub z_channel: Option<usize>,
     /// Currently selected point (persisted on click)
     pub selected_point: Option<SelectedHeatmapPoint>,
+    /// Table breakpoints binning the heatmap instead of uniform bins
+    pub breakpoints: HeatmapBreakpoints,
+}
+
+/// Custom X/Y breakpoints for a heatmap, e.g. matching an ECU table's axes
+#[derive(Clone, Debug, Default)]
+pub struct HeatmapBreakpoints {
+    /// Whether the heatmap is binned by these breakpoints
+    pub enabled: bool,
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    /// X breakpoints as typed
+    pub x_text: String,
+    /// Y breakpoints as typed
+    pub y_text: String,
+    /// Error parsing the typed or imported breakpoints
+    pub error: Option<String>,
 }
 
 /// State for the scatter plot view (dual plots)
@@ -227,6 +278,297 @@ pub struct ScatterPlotState {
     pub right: ScatterPlotConfig,
 }
 
+/// Which table the VE Analyze tool copies and exports
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum VeAnalyzeOutput {
+    /// Fuel multipliers per cell
+    #[default]
+    Corrections,
+    /// The pasted base table with the multipliers applied
+    CorrectedTable,
+}
+
+/// Channels, breakpoints and result of the VE Analyze tool for a tab
+#[derive(Clone, Debug)]
+pub struct VeAnalyzeState {
+    pub rpm_channel: Option<usize>,
+    pub load_channel: Option<usize>,
+    /// Measured AFR or lambda
+    pub afr_channel: Option<usize>,
+    /// Target AFR or lambda channel (None = `fixed_target`)
+    pub target_channel: Option<usize>,
+    pub fixed_target: f64,
+    pub coolant_channel: Option<usize>,
+    /// Channel whose rate of change marks transients
+    pub transient_channel: Option<usize>,
+    /// Channel that is non-zero in closed loop
+    pub closed_loop_channel: Option<usize>,
+    /// RPM breakpoints of the ECU table, as typed
+    pub rpm_axis: String,
+    /// Load breakpoints of the ECU table, as typed
+    pub load_axis: String,
+    /// Current fuel table pasted from the tuning software (optional)
+    pub base_table: String,
+    pub filters: VeAnalyzeFilters,
+    pub output: VeAnalyzeOutput,
+    pub result: Option<VeAnalysis>,
+    /// Error from the last analysis
+    pub error: Option<String>,
+    /// Whether channels have been picked from the log's channel names
+    pub channels_guessed: bool,
+}
+
+impl Default for VeAnalyzeState {
+    fn default() -> Self {
+        Self {
+            rpm_channel: None,
+            load_channel: None,
+            afr_channel: None,
+            target_channel: None,
+            fixed_target: 14.7,
+            coolant_channel: None,
+            transient_channel: None,
+            closed_loop_channel: None,
+            rpm_axis:
+                "500, 1000, 1500, 2000, 2500, 3000, 3500, 4000, 4500, 5000, 5500, 6000, 6500, 7000"
+                    .to_string(),
+            load_axis: "20, 30, 40, 50, 60, 70, 80, 90, 100, 120, 140, 160, 180, 200".to_string(),
+            base_table: String::new(),
+            filters: VeAnalyzeFilters::default(),
+            output: VeAnalyzeOutput::default(),
+            result: None,
+            error: None,
+            channels_guessed: false,
+        }
+    }
+}
+
+/// GPS channels of a tab, shared by the lap timer and the track map
+#[derive(Clone, Debug, Default)]
+pub struct GpsChannels {
+    pub lat: Option<usize>,
+    pub lon: Option<usize>,
+    /// Whether the channels have been picked from the log's channel names
+    pub guessed: bool,
+}
+
+/// Settings of the track map for a tab
+#[derive(Clone, Debug, Default)]
+pub struct TrackMapState {
+    /// Channel the path is colored by (None = single color)
+    pub color_channel: Option<usize>,
+    /// Where a region drag started, in local track coordinates
+    pub drag_origin: Option<[f64; 2]>,
+}
+
+/// How logs overlaid on a tab's chart are lined up with the tab's log
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OverlayAlignment {
+    /// By each file's time offset only
+    #[default]
+    Offset,
+    /// By when the trigger condition is first met in each log
+    Trigger,
+    /// By distance covered since the trigger (or the start of each log)
+    Distance,
+}
+
+/// Another loaded file overlaid on a tab's chart
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverlayFile {
+    pub file_index: usize,
+    /// Seconds the file is moved later, on top of the alignment
+    pub offset: f64,
+    pub visible: bool,
+}
+
+/// Files overlaid on a tab's chart and how they are aligned
+#[derive(Clone, Debug, Default)]
+pub struct OverlayState {
+    pub files: Vec<OverlayFile>,
+    pub alignment: OverlayAlignment,
+    /// Channel the trigger condition tests, matched by name in every log
+    pub trigger_channel: Option<String>,
+    pub trigger: Trigger,
+    /// Speed channel integrated for distance alignment, matched by name
+    pub speed_channel: Option<String>,
+    /// Draw overlaid minus reference traces
+    pub show_delta: bool,
+    /// Time mapping of each entry of `files`, recomputed after changes
+    pub time_maps: Option<Vec<Result<TimeMap, String>>>,
+}
+
+/// Where lap crossings come from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LapSource {
+    /// Lap markers recorded by the logger's beacon receiver
+    #[default]
+    Beacon,
+    /// A start/finish line placed on the GPS track
+    GpsLine,
+}
+
+/// Lap detection settings, detected laps and the selected lap of a tab
+#[derive(Clone, Debug)]
+pub struct LapState {
+    pub source: LapSource,
+    /// Width in meters of newly placed timing lines
+    pub line_width: f64,
+    pub start_line: Option<TimingLine>,
+    /// Sector lines in the order they are crossed during a lap
+    pub sector_lines: Vec<TimingLine>,
+    pub laps: Vec<Lap>,
+    /// Index into `laps` the chart and scatter plots are restricted to
+    pub selected: Option<usize>,
+    /// Error from the last lap detection
+    pub error: Option<String>,
+    /// Whether the source has been picked from the log's lap markers
+    pub source_guessed: bool,
+}
+
+impl Default for LapState {
+    fn default() -> Self {
+        Self {
+            source: LapSource::default(),
+            line_width: DEFAULT_LINE_WIDTH_M,
+            start_line: None,
+            sector_lines: Vec::new(),
+            laps: Vec::new(),
+            selected: None,
+            error: None,
+            source_guessed: false,
+        }
+    }
+}
+
+impl LapState {
+    pub fn selected_lap(&self) -> Option<&Lap> {
+        self.selected.and_then(|i| self.laps.get(i))
+    }
+
+    /// Start and end time of the selected lap
+    pub fn selected_range(&self) -> Option<(f64, f64)> {
+        self.selected_lap().map(|lap| (lap.start, lap.end))
+    }
+}
+
+// ============================================================================
+// CSV Export Types
+// ============================================================================
+
+/// Which part of the log a CSV export covers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CsvExportRange {
+    /// The time range currently shown on the chart
+    #[default]
+    Visible,
+    /// The whole log
+    Entire,
+    /// A start and end time entered by the user
+    Custom,
+}
+
+/// Options for the CSV export dialog
+#[derive(Clone, Debug)]
+pub struct CsvExportState {
+    /// Whether the dialog is open
+    pub open: bool,
+    pub range: CsvExportRange,
+    /// Start of the custom range in seconds
+    pub custom_start: f64,
+    /// End of the custom range in seconds
+    pub custom_end: f64,
+    /// Convert values to the user's preferred units
+    pub convert_units: bool,
+    /// Use normalized channel names in the header
+    pub normalize_names: bool,
+    /// Resample all channels onto a fixed rate
+    pub resample: bool,
+    /// Fixed rate in Hz used when resampling
+    pub resample_hz: f64,
+}
+
+impl Default for CsvExportState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            range: CsvExportRange::default(),
+            custom_start: 0.0,
+            custom_end: 0.0,
+            convert_units: true,
+            normalize_names: false,
+            resample: false,
+            resample_hz: 20.0,
+        }
+    }
+}
+
+/// State for the vehicle profiles window
+#[derive(Clone, Debug, Default)]
+pub struct ProfileEditorState {
+    /// Whether the window is open
+    pub open: bool,
+    /// Unsaved copy of the selected profile
+    pub draft: Option<VehicleProfile>,
+    /// Validation error from the last save attempt
+    pub error: Option<String>,
+}
+
+/// An imported library waiting for the user to resolve its conflicts
+#[derive(Clone, Debug)]
+pub struct PendingLibraryImport {
+    /// File name the library was read from
+    pub source: String,
+    /// The imported templates and tables
+    pub library: ComputedChannelLibrary,
+    /// Number of templates and tables that clash with the user's library
+    pub conflicts: usize,
+}
+
+/// State for the lookup table editor dialog
+#[derive(Clone, Debug)]
+pub struct TableEditorState {
+    /// Whether the dialog is open
+    pub open: bool,
+    /// Library name of the table being edited (None = creating new)
+    pub original_name: Option<String>,
+    /// Working copy of the table
+    pub table: LookupTable,
+    /// Validation error from the last save attempt
+    pub error: Option<String>,
+}
+
+impl Default for TableEditorState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            original_name: None,
+            table: LookupTable::new_1d(String::new(), vec![0.0, 1.0], vec![0.0, 0.0]),
+            error: None,
+        }
+    }
+}
+
+impl TableEditorState {
+    /// Open the editor for a new table
+    pub fn open_new(&mut self) {
+        *self = Self {
+            open: true,
+            ..Self::default()
+        };
+    }
+
+    /// Open the editor on a copy of an existing table
+    pub fn open_edit(&mut self, table: &LookupTable) {
+        *self = Self {
+            open: true,
+            original_name: Some(table.name.clone()),
+            table: table.clone(),
+            error: None,
+        };
+    }
+}
+
 // ============================================================================
 // Tab Types
 // ============================================================================
@@ -252,8 +594,22 @@ pub struct Tab {
     pub time_range: Option<(f64, f64)>,
     /// Scatter plot state for this tab (dual heatmaps)
     pub scatter_plot_state: ScatterPlotState,
+    /// VE Analyze settings and result for this tab
+    pub ve_analyze_state: VeAnalyzeState,
+    /// GPS latitude / longitude channels for this tab
+    pub gps_channels: GpsChannels,
+    /// Detected laps and the selected lap for this tab
+    pub lap_state: LapState,
+    /// GPS track map settings for this tab
+    pub track_map_state: TrackMapState,
+    /// Other logs overlaid on this tab's chart
+    pub overlay_state: OverlayState,
     /// Request to jump the view to a specific time (used for min/max jump buttons)
     pub jump_to_time: Option<f64>,
+    /// Request to show exactly this time range on the chart (min, max)
+    pub view_request: Option<(f64, f64)>,
+    /// Time range currently shown on the chart (min, max)
+    pub visible_range: Option<(f64, f64)>,
 }
 
 impl Tab {
@@ -274,7 +630,14 @@ impl Tab {
             chart_interacted: false,
             time_range: None,
             scatter_plot_state,
+            ve_analyze_state: VeAnalyzeState::default(),
+            gps_channels: GpsChannels::default(),
+            lap_state: LapState::default(),
+            track_map_state: TrackMapState::default(),
+            overlay_state: OverlayState::default(),
             jump_to_time: None,
+            view_request: None,
+            visible_range: None,
         }
     }
 }
@@ -281,23 +281,14 @@ impl UltraLogApp {
                 if selected.file_index < self.files.len() {
                     let file = &self.files[selected.file_index];
                     let data = file.log.get_channel_data(selected.channel_index);
-                    let times = file.log.get_times_as_f64();
-
-                    if !data.is_empty() {
-                        // Find min and max with their indices
-                        let (min_idx, min_val) = data
-                            .iter()
-                            .enumerate()
-                            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-                            .map(|(i, v)| (i, *v))
-                            .unwrap();
-                        let (max_idx, max_val) = data
-                            .iter()
-                            .enumerate()
-                            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-                            .map(|(i, v)| (i, *v))
-                            .unwrap();
+                    let times = file.log.get_channel_times(selected.channel_index);
 
+                    // Find min and max with their indices, skipping missing samples
+                    let samples = || data.iter().enumerate().filter(|(_, v)| !v.is_nan());
+                    let min = samples().min_by(|(_, a), (_, b)| a.total_cmp(b));
+                    let max = samples().max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+                    if let (Some((min_idx, &min_val)), Some((max_idx, &max_val))) = (min, max) {
                         let source_unit = selected.channel.unit();
                         let (conv_min, display_unit) =
                             self.unit_preferences.convert_value(min_val, source_unit);
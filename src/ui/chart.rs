@@ -1,11 +1,13 @@
 //! Chart rendering and data processing utilities.
 
 use eframe::egui;
-use egui_plot::{Line, Plot, PlotBounds, PlotPoints, VLine};
+use egui_plot::{Line, LineStyle, Plot, PlotBounds, PlotPoint, PlotPoints, Text, VLine};
 
 use crate::app::UltraLogApp;
 use crate::normalize::normalize_channel_name_with_custom;
+use crate::parsers::MarkerKind;
 use crate::state::{CacheKey, CHART_COLORS, COLORBLIND_COLORS, MAX_CHART_POINTS};
+use crate::ui::overlay::renormalize;
 
 impl UltraLogApp {
     /// Render the main chart with cached downsampled data
@@ -26,29 +28,9 @@ impl UltraLogApp {
 
         // Pre-compute and cache downsampled + normalized data for all selected channels
         for selected in &selected_channels {
-            if selected.file_index >= self.files.len() {
-                continue;
-            }
-
-            let cache_key = CacheKey {
-                file_index: selected.file_index,
-                channel_index: selected.channel_index,
-            };
-
-            if !self.downsample_cache.contains_key(&cache_key) {
-                let file = &self.files[selected.file_index];
-                let times = file.log.get_times_as_f64();
-                // Use app method to get channel data (handles both regular and computed channels)
-                let data = self.get_channel_data(selected.file_index, selected.channel_index);
-
-                if times.len() == data.len() && !times.is_empty() {
-                    let downsampled = Self::downsample_lttb(times, &data, MAX_CHART_POINTS);
-                    // Normalize Y values to 0-1 range so all channels overlay
-                    let normalized = Self::normalize_points(&downsampled);
-                    self.downsample_cache.insert(cache_key, normalized);
-                }
-            }
+            self.ensure_downsampled(selected.file_index, selected.channel_index);
         }
+        let overlay = self.chart_overlay(&selected_channels);
 
         // Pre-compute legend names with current values at cursor position
         let use_normalization = self.field_normalization;
@@ -62,12 +44,10 @@ impl UltraLogApp {
                 } else {
                     original_name
                 };
-                if let Some(record) = self.get_cursor_record() {
-                    if let Some(value) = self.get_value_at_record(
-                        selected.file_index,
-                        selected.channel_index,
-                        record,
-                    ) {
+                if let Some(time) = self.get_cursor_time() {
+                    if let Some(value) =
+                        self.get_value_at_time(selected.file_index, selected.channel_index, time)
+                    {
                         let source_unit = selected.channel.unit();
                         let (converted_value, display_unit) =
                             self.unit_preferences.convert_value(value, source_unit);
@@ -97,6 +77,8 @@ impl UltraLogApp {
         let chart_interacted = self.get_chart_interacted();
         let initial_view_seconds = self.initial_view_seconds;
         let jump_to_time = self.get_jump_to_time();
+        let view_request = self.get_view_request();
+        let markers = self.get_markers();
 
         // Fixed Y bounds for normalized data (0-1 with small padding)
         const Y_MIN: f64 = -0.05;
@@ -117,8 +99,15 @@ impl UltraLogApp {
             let mut x_min = current_bounds.min()[0];
             let mut x_max = current_bounds.max()[0];
 
-            // Handle jump-to-time request (from min/max jump buttons)
-            if let (Some(jump_time), Some((min_t, max_t))) = (jump_to_time, time_range) {
+            // Handle a request to show a time range (e.g. a selected lap)
+            if let (Some((start, end)), Some((min_t, max_t))) = (view_request, time_range) {
+                x_min = start.max(min_t);
+                x_max = end.min(max_t);
+                if x_max <= x_min {
+                    (x_min, x_max) = (min_t, max_t);
+                }
+            } else if let (Some(jump_time), Some((min_t, max_t))) = (jump_to_time, time_range) {
+                // Handle jump-to-time request (from min/max jump buttons)
                 // Center the view on the jump target time
                 let current_width = (x_max - x_min).max(view_window);
                 let half_width = current_width / 2.0;
@@ -185,7 +174,6 @@ impl UltraLogApp {
                 };
 
                 if let Some(points) = cache.get(&cache_key) {
-                    let plot_points: PlotPoints = points.iter().copied().collect();
                     let palette = if color_blind_mode {
                         COLORBLIND_COLORS
                     } else {
@@ -196,14 +184,66 @@ impl UltraLogApp {
                     // Use legend name with value if available
                     let name = &legend_names[i];
 
+                    // Channels with overlays share their value range with them
+                    let rescaled = overlay
+                        .shared_ranges
+                        .get(i)
+                        .copied()
+                        .flatten()
+                        .map(|(own, shared)| renormalize(points, own, shared));
+                    let points = rescaled.as_deref().unwrap_or(points);
+
+                    // Missing samples break the line; each segment shares the
+                    // channel's name so the legend shows a single entry
+                    for segment in points.split(|p| p[1].is_nan()).filter(|s| !s.is_empty()) {
+                        let plot_points: PlotPoints = segment.iter().copied().collect();
+                        plot_ui.line(
+                            Line::new(name.clone(), plot_points)
+                                .color(egui::Color32::from_rgb(color[0], color[1], color[2]))
+                                .width(1.5),
+                        );
+                    }
+                }
+            }
+
+            // Draw lines of overlaid logs on the reference timeline
+            for trace in &overlay.traces {
+                for segment in trace
+                    .points
+                    .split(|p| p[1].is_nan())
+                    .filter(|s| !s.is_empty())
+                {
+                    let plot_points: PlotPoints = segment.iter().copied().collect();
                     plot_ui.line(
-                        Line::new(name.clone(), plot_points)
-                            .color(egui::Color32::from_rgb(color[0], color[1], color[2]))
+                        Line::new(trace.name.clone(), plot_points)
+                            .color(trace.color)
+                            .style(trace.style)
                             .width(1.5),
                     );
                 }
             }
 
+            // Draw log markers as annotated vertical lines. They share a single
+            // legend entry so they can be hidden together.
+            for marker in markers.iter().filter(|m| (x_min..=x_max).contains(&m.time)) {
+                let color = Self::marker_color(marker.kind);
+                plot_ui.vline(
+                    VLine::new("Markers", marker.time)
+                        .color(color)
+                        .width(1.0)
+                        .style(LineStyle::dashed_loose()),
+                );
+                plot_ui.text(
+                    Text::new(
+                        "Markers",
+                        PlotPoint::new(marker.time, Y_MAX),
+                        egui::RichText::new(&marker.label).small(),
+                    )
+                    .color(color)
+                    .anchor(egui::Align2::LEFT_TOP),
+                );
+            }
+
             // Draw vertical cursor line
             if let Some(time) = cursor_time {
                 plot_ui.vline(
@@ -214,8 +254,12 @@ impl UltraLogApp {
             }
 
             // Return pointer position if hovering for click detection
-            plot_ui.pointer_coordinate()
+            (plot_ui.pointer_coordinate(), (x_min, x_max))
         });
+        let (pointer, visible_range) = response.inner;
+        if let Some(tab_idx) = self.active_tab {
+            self.tabs[tab_idx].visible_range = Some(visible_range);
+        }
 
         // Detect user interaction with chart (drag, zoom, scroll)
         // This marks the chart as "interacted" so we stop using the initial zoomed view
@@ -227,16 +271,17 @@ impl UltraLogApp {
             self.set_chart_interacted(true);
         }
 
-        // Clear jump-to-time request after it's been processed
-        if self.get_jump_to_time().is_some() {
+        // Clear jump-to-time and view requests after they've been processed
+        if self.get_jump_to_time().is_some() || self.get_view_request().is_some() {
             self.clear_jump_to_time();
+            self.set_view_request(None);
             // Mark chart as interacted so future jumps work correctly
             self.set_chart_interacted(true);
         }
 
         // Handle click on chart to set cursor position
         if response.response.clicked() {
-            if let Some(pos) = response.inner {
+            if let Some(pos) = pointer {
                 let clicked_time = pos.x;
                 // Clamp to time range
                 if let Some((min, max)) = self.get_time_range() {
@@ -276,25 +321,67 @@ impl UltraLogApp {
         }
     }
 
+    /// Color used for a marker on the chart and timeline
+    pub fn marker_color(kind: MarkerKind) -> egui::Color32 {
+        match kind {
+            MarkerKind::Manual => egui::Color32::from_rgb(255, 105, 180), // Pink
+            MarkerKind::Lap => egui::Color32::from_rgb(253, 193, 73),     // Amber
+            MarkerKind::Event => egui::Color32::LIGHT_GRAY,
+        }
+    }
+
+    /// Downsample a channel and normalize it over its full value range into
+    /// the chart cache, unless it is already cached
+    pub(crate) fn ensure_downsampled(&mut self, file_index: usize, channel_index: usize) {
+        let cache_key = CacheKey {
+            file_index,
+            channel_index,
+        };
+        if file_index >= self.files.len() || self.downsample_cache.contains_key(&cache_key) {
+            return;
+        }
+        let range = self.get_channel_min_max(file_index, channel_index);
+
+        // Use app methods to get channel data and its sample times
+        // (handles computed channels and channels on their own timebase)
+        let times = self.get_channel_times(file_index, channel_index);
+        let data = self.get_channel_data(file_index, channel_index);
+
+        if times.len() == data.len() && !times.is_empty() {
+            let downsampled = Self::downsample_lttb(times, data, MAX_CHART_POINTS);
+            // Normalize Y values to 0-1 range so all channels overlay; the
+            // full range (rather than the downsampled one) lets overlays
+            // recover the values
+            let normalized = match range {
+                Some(range) => renormalize(&downsampled, (0.0, 1.0), range),
+                None => Self::normalize_points(&downsampled),
+            };
+            self.downsample_cache.insert(cache_key, normalized);
+        }
+    }
+
     /// Normalize values to 0-1 range for overlay display
     pub fn normalize_points(points: &[[f64; 2]]) -> Vec<[f64; 2]> {
         if points.is_empty() {
             return Vec::new();
         }
 
-        // Find min and max Y values
+        // Find min and max Y values (missing samples are ignored)
         let mut min_y = f64::MAX;
         let mut max_y = f64::MIN;
-        for point in points {
+        for point in points.iter().filter(|p| !p[1].is_nan()) {
             min_y = min_y.min(point[1]);
             max_y = max_y.max(point[1]);
         }
 
-        // Handle case where all values are the same
+        // Handle case where all values are the same (or all missing)
         let range = max_y - min_y;
-        if range.abs() < f64::EPSILON {
+        if range <= f64::EPSILON {
             // All values are the same, put at 0.5
-            return points.iter().map(|p| [p[0], 0.5]).collect();
+            return points
+                .iter()
+                .map(|p| [p[0], if p[1].is_nan() { f64::NAN } else { 0.5 }])
+                .collect();
         }
 
         // Normalize to 0-1 range
@@ -306,7 +393,75 @@ impl UltraLogApp {
 
     /// Downsample data using the LTTB (Largest Triangle Three Buckets) algorithm.
     /// This preserves visual characteristics while reducing point count for performance.
+    ///
+    /// Missing samples (NaN) split the data into runs that are downsampled
+    /// separately; runs are joined by a single `[time, NaN]` gap point. When
+    /// there are too many gaps to draw within `target_points`, only the
+    /// widest are kept and the runs between the others are joined.
     pub fn downsample_lttb(times: &[f64], values: &[f64], target_points: usize) -> Vec<[f64; 2]> {
+        let n = times.len().min(values.len());
+        let valid = values[..n].iter().filter(|v| !v.is_nan()).count();
+
+        if valid == n {
+            return Self::lttb_run(&times[..n], &values[..n], target_points);
+        }
+        if valid == 0 {
+            return Vec::new();
+        }
+
+        // Runs of valid samples as (first index, index after the last)
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        let mut start = 0;
+        while start < n {
+            if values[start].is_nan() {
+                start += 1;
+                continue;
+            }
+            let end = (start..n).find(|&i| values[i].is_nan()).unwrap_or(n);
+            runs.push((start, end));
+            start = end;
+        }
+
+        // Each run needs at least three points plus a gap point
+        let max_runs = (target_points / 4).max(1);
+        if runs.len() > max_runs {
+            let gap_width = |i: usize| times[runs[i].0] - times[runs[i - 1].1 - 1];
+            let mut kept: Vec<usize> = (1..runs.len()).collect();
+            kept.sort_by(|&a, &b| gap_width(b).total_cmp(&gap_width(a)));
+            kept.truncate(max_runs - 1);
+            kept.sort_unstable();
+
+            let mut merged = Vec::with_capacity(max_runs);
+            let mut merged_start = runs[0].0;
+            for &i in &kept {
+                merged.push((merged_start, runs[i - 1].1));
+                merged_start = runs[i].0;
+            }
+            merged.push((merged_start, runs[runs.len() - 1].1));
+            runs = merged;
+        }
+
+        // Share what is left after the gaps and each run's minimum in
+        // proportion to the run lengths
+        let shared = target_points.saturating_sub(4 * runs.len() - 1);
+        let mut result = Vec::with_capacity(target_points);
+        for (k, &(start, end)) in runs.iter().enumerate() {
+            if k > 0 {
+                result.push([times[runs[k - 1].1], f64::NAN]);
+            }
+            let (run_times, run_values): (Vec<f64>, Vec<f64>) = (start..end)
+                .filter(|&i| !values[i].is_nan())
+                .map(|i| (times[i], values[i]))
+                .unzip();
+            let budget = 3 + shared * run_times.len() / valid;
+            result.extend(Self::lttb_run(&run_times, &run_values, budget));
+        }
+
+        result
+    }
+
+    /// LTTB over a contiguous run of samples with no missing values
+    fn lttb_run(times: &[f64], values: &[f64], target_points: usize) -> Vec<[f64; 2]> {
         let n = times.len();
 
         if n <= target_points || target_points < 3 {
@@ -7,7 +7,7 @@ use eframe::egui;
 
 use crate::app::UltraLogApp;
 use crate::computed::{ComputedChannel, ComputedChannelTemplate};
-use crate::expression::{build_channel_bindings, evaluate_all_records, extract_channel_references};
+use crate::expression::{build_channel_bindings, extract_channel_references, CompiledFormula};
 use crate::parsers::types::ComputedChannelInfo;
 use crate::parsers::Channel;
 use crate::state::{SelectedChannel, CHART_COLORS};
@@ -20,6 +20,9 @@ impl UltraLogApp {
         }
 
         let mut open = true;
+        let mut restore_builtins = false;
+        let mut import_library = false;
+        let mut export_ids: Option<Vec<String>> = None;
 
         egui::Window::new("Computed Channels")
             .open(&mut open)
@@ -35,6 +38,27 @@ impl UltraLogApp {
                         if ui.button("+ New Channel").clicked() {
                             self.formula_editor_state.open_new();
                         }
+                        restore_builtins = ui
+                            .button("Restore Built-ins")
+                            .on_hover_text("Add back any removed built-in templates")
+                            .clicked();
+                        if ui
+                            .button("Export...")
+                            .on_hover_text("Save the whole library to a file to share")
+                            .clicked()
+                        {
+                            export_ids = Some(
+                                self.computed_library
+                                    .templates
+                                    .iter()
+                                    .map(|t| t.id.clone())
+                                    .collect(),
+                            );
+                        }
+                        import_library = ui
+                            .button("Import...")
+                            .on_hover_text("Merge templates and tables from a shared library file")
+                            .clicked();
                     });
                 });
 
@@ -47,6 +71,9 @@ impl UltraLogApp {
                 );
                 ui.add_space(8.0);
 
+                self.render_vehicle_profile_selector(ui);
+                ui.add_space(4.0);
+
                 ui.separator();
                 ui.add_space(4.0);
 
@@ -112,6 +139,15 @@ impl UltraLogApp {
                                                             .color(egui::Color32::GRAY),
                                                         );
                                                     }
+                                                    if template.is_builtin() {
+                                                        ui.label(
+                                                            egui::RichText::new("built-in")
+                                                                .small()
+                                                                .color(egui::Color32::from_rgb(
+                                                                    150, 150, 200,
+                                                                )),
+                                                        );
+                                                    }
                                                 });
                                                 ui.label(
                                                     egui::RichText::new(&template.formula)
@@ -138,6 +174,9 @@ impl UltraLogApp {
                                                     if ui.small_button("Edit").clicked() {
                                                         template_to_edit = Some(template.id.clone());
                                                     }
+                                                    if ui.small_button("Export").clicked() {
+                                                        export_ids = Some(vec![template.id.clone()]);
+                                                    }
                                                     if self.active_tab.is_some()
                                                         && ui
                                                             .button(
@@ -219,7 +258,9 @@ impl UltraLogApp {
                                             .show(ui, |ui| {
                                                 ui.horizontal(|ui| {
                                                     // Status indicator
-                                                    if channel.is_valid() {
+                                                    if channel.is_evaluating() {
+                                                        ui.spinner();
+                                                    } else if channel.is_valid() {
                                                         ui.label(
                                                             egui::RichText::new("●")
                                                                 .color(egui::Color32::GREEN),
@@ -236,6 +277,17 @@ impl UltraLogApp {
                                                             .color(egui::Color32::LIGHT_GREEN),
                                                     );
 
+                                                    if let Some(progress) = channel
+                                                        .pending_job
+                                                        .and_then(|id| self.computed_job_progress(id))
+                                                    {
+                                                        ui.add(
+                                                            egui::ProgressBar::new(progress)
+                                                                .desired_width(120.0)
+                                                                .show_percentage(),
+                                                        );
+                                                    }
+
                                                     if let Some(error) = &channel.error {
                                                         ui.label(
                                                             egui::RichText::new(format!(
@@ -284,6 +336,12 @@ impl UltraLogApp {
                     );
                 }
 
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(4.0);
+
+                self.render_lookup_tables_section(ui);
+
                 // Examples section
                 ui.add_space(12.0);
                 ui.separator();
@@ -408,8 +466,10 @@ impl UltraLogApp {
                         ui.add_space(8.0);
                         ui.label(egui::RichText::new("Operators:").strong());
                         ui.label("  + - * /          - Basic math");
-                        ui.label("  ^                - Power");
+                        ui.label("  ^  %             - Power, remainder");
                         ui.label("  ( )              - Grouping");
+                        ui.label("  < <= > >= == !=  - Comparisons (1 = true, 0 = false)");
+                        ui.label("  && || !          - And, or, not");
 
                         ui.add_space(8.0);
                         ui.label(egui::RichText::new("Functions:").strong());
@@ -418,9 +478,52 @@ impl UltraLogApp {
                         ui.label("  ln, log, exp     - Logarithms, exponential");
                         ui.label("  min, max         - Minimum, maximum");
                         ui.label("  floor, ceil      - Rounding");
+                        ui.label("  if(c, a, b)      - a when c is true, otherwise b");
+
+                        ui.add_space(8.0);
+                        ui.label(egui::RichText::new("Windowed Functions:").strong());
+                        ui.label("  avg(RPM, 0.5s)   - Mean over the last 0.5 s");
+                        ui.label("  rms, max_over, min_over - Same windows (0.5s or 10 samples)");
+                        ui.label("  deriv(x), integ(x) - Rate per second, running integral");
+                        ui.label("  lowpass(x, 2)    - Low-pass filter at 2 Hz");
+                        ui.label("  hold(x, c)       - Hold x from the last time c was true");
+
+                        ui.add_space(8.0);
+                        ui.label(egui::RichText::new("Lookup Tables:").strong());
+                        ui.label("  table(\"Deadtime\", \"Battery Voltage\") - 1D table lookup");
+                        ui.label("  table2d(\"VE\", RPM, MAP) - 2D table, bilinear");
+                        ui.label("  Inputs outside an axis use its end value");
+
+                        ui.add_space(8.0);
+                        ui.label(egui::RichText::new("Vehicle Constants:").strong());
+                        ui.label("  $injector_cc      - Constant from the file's vehicle profile");
+
+                        ui.add_space(8.0);
+                        ui.label(egui::RichText::new("Units:").strong());
+                        ui.label("  Channels are read in °C, kPa, km/h, km, L, cc/min, m/s²");
+                        ui.label("  whatever unit the ECU logged them in");
                     });
             });
 
+        if let Some(ids) = export_ids {
+            self.export_computed_library(&ids);
+        }
+
+        if import_library {
+            self.import_computed_library();
+        }
+
+        if restore_builtins {
+            let added = self.computed_library.add_builtin_templates();
+            if added == 0 {
+                self.show_toast_success("All built-in templates are already in the library");
+            } else if let Err(e) = self.computed_library.save() {
+                self.show_toast_error(&format!("Failed to save: {}", e));
+            } else {
+                self.show_toast_success(&format!("Restored {} built-in templates", added));
+            }
+        }
+
         if !open {
             self.show_computed_channels_manager = false;
         }
@@ -434,10 +537,19 @@ impl UltraLogApp {
         };
 
         let file_idx = self.tabs[tab_idx].file_index;
-        let file = &self.files[file_idx];
 
-        // Get available channel names
-        let available_channels: Vec<String> = file.log.channels.iter().map(|c| c.name()).collect();
+        // Log channels and computed channels already applied to this file
+        let available_channels = self.file_channel_names(file_idx);
+        if available_channels
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&template.name))
+        {
+            self.show_toast_error(&format!(
+                "Failed to apply: a channel named '{}' already exists",
+                template.name
+            ));
+            return;
+        }
 
         // Extract channel references and build bindings
         let refs = extract_channel_references(&template.formula);
@@ -449,30 +561,25 @@ impl UltraLogApp {
             }
         };
 
-        // Evaluate the formula
-        let cached_data = match evaluate_all_records(
+        // Check the formula compiles before adding it
+        if let Err(e) = CompiledFormula::compile_with_context(
             &template.formula,
             &bindings,
-            &file.log.data,
-            &file.log.times,
+            &self.formula_context(Some(file_idx)),
         ) {
-            Ok(data) => Some(data),
-            Err(e) => {
-                self.show_toast_error(&format!("Evaluation failed: {}", e));
-                return;
-            }
-        };
+            self.show_toast_error(&format!("Failed to apply: {}", e));
+            return;
+        }
 
         // Create the computed channel
         let mut channel = ComputedChannel::from_template(template.clone());
         channel.channel_bindings = bindings;
-        channel.cached_data = cached_data;
 
-        // Add to file's computed channels
-        self.file_computed_channels
-            .entry(file_idx)
-            .or_default()
-            .push(channel);
+        // Add to file's computed channels and evaluate in the background
+        let channels = self.file_computed_channels.entry(file_idx).or_default();
+        channels.push(channel);
+        let computed_idx = channels.len() - 1;
+        self.evaluate_computed_channels(file_idx, &[computed_idx]);
 
         self.show_toast_success(&format!("Applied '{}'", template.name));
     }
@@ -529,7 +636,7 @@ impl UltraLogApp {
         let channel = Channel::Computed(ComputedChannelInfo {
             name: computed.template.name.clone(),
             formula: computed.template.formula.clone(),
-            unit: computed.template.unit.clone(),
+            unit: computed.unit().to_string(),
         });
 
         self.tabs[tab_idx].selected_channels.push(SelectedChannel {
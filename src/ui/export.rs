@@ -1,5 +1,6 @@
-//! Chart export functionality (PNG, PDF).
+//! Chart export functionality (PNG, PDF) and CSV data export.
 
+use eframe::egui;
 use printpdf::*;
 use std::fs::File;
 use std::io::BufWriter;
@@ -9,7 +10,9 @@ use ::image::{Rgba, RgbaImage};
 
 use crate::analytics;
 use crate::app::UltraLogApp;
+use crate::data_export::{record_range, ExportColumn, ExportTable};
 use crate::normalize::normalize_channel_name_with_custom;
+use crate::state::CsvExportRange;
 
 impl UltraLogApp {
     /// Export the current chart view as PNG
@@ -53,6 +56,217 @@ impl UltraLogApp {
         }
     }
 
+    /// Open the CSV export dialog, defaulting the custom range to the chart view
+    pub fn open_csv_export_dialog(&mut self) {
+        let range = self
+            .active_tab
+            .and_then(|idx| self.tabs[idx].visible_range)
+            .or_else(|| self.get_time_range());
+        if let Some((start, end)) = range {
+            self.csv_export_state.custom_start = start;
+            self.csv_export_state.custom_end = end;
+        }
+        self.csv_export_state.open = true;
+    }
+
+    /// Render the CSV export options dialog
+    pub fn render_csv_export_dialog(&mut self, ctx: &egui::Context) {
+        if !self.csv_export_state.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut export_clicked = false;
+        let cursor_time = self.get_cursor_time();
+        let channel_count = self.get_selected_channels().len();
+
+        egui::Window::new("Export CSV")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .default_width(360.0)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                let state = &mut self.csv_export_state;
+
+                ui.label(format!("{} selected channel(s)", channel_count));
+                ui.add_space(4.0);
+
+                ui.label("Time range:");
+                ui.radio_value(
+                    &mut state.range,
+                    CsvExportRange::Visible,
+                    "Visible chart range",
+                );
+                ui.radio_value(&mut state.range, CsvExportRange::Entire, "Entire log");
+                ui.radio_value(&mut state.range, CsvExportRange::Custom, "Custom range");
+
+                ui.add_enabled_ui(state.range == CsvExportRange::Custom, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Start (s):");
+                        ui.add(egui::DragValue::new(&mut state.custom_start).speed(0.1));
+                        if ui
+                            .add_enabled(cursor_time.is_some(), egui::Button::new("Use cursor"))
+                            .clicked()
+                        {
+                            state.custom_start = cursor_time.unwrap_or_default();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("End (s):");
+                        ui.add(egui::DragValue::new(&mut state.custom_end).speed(0.1));
+                        if ui
+                            .add_enabled(cursor_time.is_some(), egui::Button::new("Use cursor"))
+                            .clicked()
+                        {
+                            state.custom_end = cursor_time.unwrap_or_default();
+                        }
+                    });
+                });
+
+                ui.add_space(4.0);
+                ui.separator();
+
+                ui.checkbox(&mut state.convert_units, "Convert to preferred units");
+                ui.checkbox(&mut state.normalize_names, "Use normalized channel names");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut state.resample, "Resample to fixed rate");
+                    ui.add_enabled(
+                        state.resample,
+                        egui::DragValue::new(&mut state.resample_hz)
+                            .range(0.1..=1000.0)
+                            .suffix(" Hz"),
+                    );
+                });
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(channel_count > 0, egui::Button::new("Export..."))
+                        .clicked()
+                    {
+                        export_clicked = true;
+                    }
+                });
+            });
+
+        if export_clicked {
+            self.export_csv();
+            open = false;
+        }
+        self.csv_export_state.open = open;
+    }
+
+    /// Export the selected channels of the active tab as CSV
+    fn export_csv(&mut self) {
+        let table = match self.build_csv_table() {
+            Ok(table) => table,
+            Err(e) => {
+                self.show_toast_error(&format!("Export failed: {}", e));
+                return;
+            }
+        };
+
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("ultralog_export.csv")
+            .save_file()
+        else {
+            return;
+        };
+
+        let result = File::create(&path)
+            .map_err(|e| e.into())
+            .and_then(|file| table.write_csv(&mut BufWriter::new(file)));
+        match result {
+            Ok(_) => {
+                analytics::track_export("csv");
+                self.show_toast_success(&format!(
+                    "Exported {} rows to {}",
+                    table.times.len(),
+                    path.file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                ));
+            }
+            Err(e) => self.show_toast_error(&format!("Export failed: {}", e)),
+        }
+    }
+
+    /// Build the table of selected channels for the chosen range and options
+    fn build_csv_table(&self) -> Result<ExportTable, String> {
+        let tab_idx = self.active_tab.ok_or("No active tab")?;
+        let tab = &self.tabs[tab_idx];
+        let file = self.files.get(tab.file_index).ok_or("No file loaded")?;
+        let log = &file.log;
+        let state = &self.csv_export_state;
+
+        let range = match state.range {
+            CsvExportRange::Visible => tab.visible_range.or(tab.time_range),
+            CsvExportRange::Entire => None,
+            CsvExportRange::Custom => Some((
+                state.custom_start.min(state.custom_end),
+                state.custom_start.max(state.custom_end),
+            )),
+        };
+        let rows = record_range(&log.times, range);
+        if rows.is_empty() {
+            return Err("No data in the selected time range".to_string());
+        }
+
+        let channels = tab
+            .selected_channels
+            .iter()
+            .filter(|selected| selected.file_index == tab.file_index)
+            .map(|selected| {
+                let idx = selected.channel_index;
+                // Regular channels are held onto the record times; computed
+                // channels are already evaluated on them
+                let data = if idx < log.channels.len() {
+                    log.get_aligned_channel_data(idx)
+                } else {
+                    self.get_channel_data(tab.file_index, idx).into()
+                };
+                let source = data.get(rows.clone()).unwrap_or_default();
+
+                let source_unit = selected.channel.unit();
+                let (values, unit) = if state.convert_units {
+                    let unit = self.unit_preferences.convert_value(0.0, source_unit).1;
+                    let values = source
+                        .iter()
+                        .map(|&v| self.unit_preferences.convert_value(v, source_unit).0)
+                        .collect();
+                    (values, unit.to_string())
+                } else {
+                    (source.to_vec(), source_unit.to_string())
+                };
+
+                let name = if state.normalize_names {
+                    normalize_channel_name_with_custom(
+                        &selected.channel.name(),
+                        Some(&self.custom_normalizations),
+                    )
+                } else {
+                    selected.channel.name()
+                };
+
+                ExportColumn { name, unit, values }
+            })
+            .collect();
+
+        let table = ExportTable {
+            times: log.times[rows].to_vec(),
+            channels,
+        };
+
+        Ok(if state.resample {
+            table.resampled(state.resample_hz)
+        } else {
+            table
+        })
+    }
+
     /// Render chart data to PNG file
     fn render_chart_to_png(
         &self,
@@ -104,7 +318,7 @@ impl UltraLogApp {
                 continue;
             }
             let file = &self.files[selected.file_index];
-            let times = file.log.get_times_as_f64();
+            let times = file.log.get_channel_times(selected.channel_index);
             let data = file.log.get_channel_data(selected.channel_index);
 
             if data.is_empty() {
@@ -114,7 +328,7 @@ impl UltraLogApp {
             // Find min/max for normalization
             let mut data_min = f64::MAX;
             let mut data_max = f64::MIN;
-            for &val in &data {
+            for &val in data.iter().filter(|v| !v.is_nan()) {
                 data_min = data_min.min(val);
                 data_max = data_max.max(val);
             }
@@ -135,6 +349,13 @@ impl UltraLogApp {
                     continue;
                 }
 
+                // Missing samples break the line
+                if value.is_nan() {
+                    prev_x = None;
+                    prev_y = None;
+                    continue;
+                }
+
                 let x_ratio = (time - min_time) / time_span;
                 let y_ratio = (value - data_min) / data_range;
 
@@ -257,7 +478,7 @@ impl UltraLogApp {
                 continue;
             }
             let file = &self.files[selected.file_index];
-            let times = file.log.get_times_as_f64();
+            let times = file.log.get_channel_times(selected.channel_index);
             let data = file.log.get_channel_data(selected.channel_index);
 
             if data.is_empty() {
@@ -267,7 +488,7 @@ impl UltraLogApp {
             // Find min/max for normalization
             let mut data_min = f64::MAX;
             let mut data_max = f64::MIN;
-            for &val in &data {
+            for &val in data.iter().filter(|v| !v.is_nan()) {
                 data_min = data_min.min(val);
                 data_max = data_max.max(val);
             }
@@ -278,8 +499,9 @@ impl UltraLogApp {
                 data_max - data_min
             };
 
-            // Build line points (downsample for PDF)
-            let mut points: Vec<(Point, bool)> = Vec::new();
+            // Build line segments (downsample for PDF); missing samples
+            // start a new segment
+            let mut segments: Vec<Vec<(Point, bool)>> = vec![Vec::new()];
             let step = (times.len() / 500).max(1); // Max ~500 points per channel
 
             for (i, (&time, &value)) in times.iter().zip(data.iter()).enumerate() {
@@ -291,16 +513,25 @@ impl UltraLogApp {
                     continue;
                 }
 
+                if value.is_nan() {
+                    if segments.last().is_some_and(|s| !s.is_empty()) {
+                        segments.push(Vec::new());
+                    }
+                    continue;
+                }
+
                 let x_ratio = (time - min_time) / time_span;
                 let y_ratio = (value - data_min) / data_range;
 
                 let x = chart_left + x_ratio * chart_width;
                 let y = chart_bottom + y_ratio * chart_height;
 
-                points.push((Point::new(Mm(x as f32), Mm(y as f32)), false));
+                if let Some(points) = segments.last_mut() {
+                    points.push((Point::new(Mm(x as f32), Mm(y as f32)), false));
+                }
             }
 
-            if points.len() >= 2 {
+            for points in segments.into_iter().filter(|p| p.len() >= 2) {
                 let line = Line {
                     points,
                     is_closed: false,
@@ -3,19 +3,33 @@
 //! Provides a modal window for creating and editing computed channel formulas.
 
 use eframe::egui;
+use egui_plot::{Line, Plot, PlotPoints};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::mpsc::{channel, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::app::UltraLogApp;
-use crate::computed::ComputedChannelTemplate;
-use crate::expression::{
-    build_channel_bindings, extract_channel_references, generate_preview, validate_formula,
-};
+use crate::computed::{computed_dependencies, find_reference_cycle, ComputedChannelTemplate};
+use crate::expression::{check_formula, complete, CompiledFormula, CompletionKind, InputColumn};
+use crate::state::FormulaPreviewJob;
+use crate::units::to_canonical;
+
+/// Points of the whole-log preview plot
+const PREVIEW_PLOT_POINTS: usize = 400;
+
+/// Pause in typing before the formula is previewed over the whole log
+const PREVIEW_DELAY: Duration = Duration::from_millis(300);
 
 impl UltraLogApp {
     /// Render the formula editor dialog
     pub fn render_formula_editor(&mut self, ctx: &egui::Context) {
         if !self.formula_editor_state.is_open {
+            self.formula_preview_job = None;
             return;
         }
+        self.update_formula_preview(ctx);
 
         let mut open = true;
         let mut should_save = false;
@@ -50,19 +64,72 @@ impl UltraLogApp {
 
                 // Formula field
                 ui.label("Formula:");
-                let formula_response = ui.add(
-                    egui::TextEdit::multiline(&mut self.formula_editor_state.formula)
-                        .hint_text("e.g., RPM - RPM[-1]")
-                        .desired_width(ui.available_width())
-                        .desired_rows(3)
-                        .font(egui::TextStyle::Monospace),
-                );
+                let formula_id = ui.make_persistent_id("formula_editor_formula");
+                let has_focus = ui.memory(|m| m.has_focus(formula_id));
+
+                // Tab accepts the first suggestion before the text edit sees it
+                if has_focus
+                    && self.formula_editor_state.completions.is_some()
+                    && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab))
+                {
+                    self.accept_completion(ctx, formula_id, 0);
+                }
+
+                let error_span = self.formula_editor_state.error_span.clone();
+                let mut layouter = |ui: &egui::Ui, text: &dyn egui::TextBuffer, wrap_width: f32| {
+                    let job = formula_layout_job(ui, text.as_str(), error_span.clone(), wrap_width);
+                    ui.fonts_mut(|f| f.layout_job(job))
+                };
+                let output = egui::TextEdit::multiline(&mut self.formula_editor_state.formula)
+                    .id(formula_id)
+                    .hint_text("e.g., RPM - RPM[-1]")
+                    .desired_width(ui.available_width())
+                    .desired_rows(3)
+                    .font(egui::TextStyle::Monospace)
+                    .layouter(&mut layouter)
+                    .show(ui);
 
                 // Validate on formula change
-                if formula_response.changed() {
+                if output.response.changed() {
                     self.validate_current_formula();
                 }
 
+                // Suggest names for the word at the cursor. Suggestions stay
+                // up while the pointer is pressed so they can be clicked.
+                if output.response.has_focus() {
+                    let cursor = output.cursor_range.map(|range| range.primary.index);
+                    self.update_completions(cursor);
+                } else if !ui.input(|i| i.pointer.any_down() || i.pointer.any_released()) {
+                    self.formula_editor_state.completions = None;
+                }
+                let mut completion_to_accept = None;
+                if let Some(completions) = &self.formula_editor_state.completions {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(
+                            egui::RichText::new("Tab ↹")
+                                .small()
+                                .color(egui::Color32::GRAY),
+                        );
+                        for (i, item) in completions.items.iter().enumerate() {
+                            let text = egui::RichText::new(&item.label).monospace().small();
+                            let text = match item.kind {
+                                CompletionKind::Channel => text.color(egui::Color32::LIGHT_BLUE),
+                                CompletionKind::Function => text.color(egui::Color32::LIGHT_GREEN),
+                                CompletionKind::Constant => text.color(egui::Color32::GOLD),
+                                CompletionKind::Table => {
+                                    text.color(egui::Color32::from_rgb(200, 160, 255))
+                                }
+                            };
+                            if ui.small_button(text).on_hover_text(&item.detail).clicked() {
+                                completion_to_accept = Some(i);
+                            }
+                        }
+                    });
+                }
+                if let Some(index) = completion_to_accept {
+                    self.accept_completion(ctx, formula_id, index);
+                }
+
                 // Show validation status
                 ui.add_space(4.0);
                 if let Some(error) = &self.formula_editor_state.validation_error {
@@ -73,6 +140,15 @@ impl UltraLogApp {
                 } else if !self.formula_editor_state.formula.is_empty() {
                     ui.label(egui::RichText::new("Formula valid").color(egui::Color32::GREEN));
                 }
+                if let Some(analysis) = &self.formula_editor_state.unit_analysis {
+                    for warning in &analysis.warnings {
+                        ui.label(
+                            egui::RichText::new(format!("Warning: {}", warning))
+                                .color(egui::Color32::from_rgb(255, 170, 60))
+                                .small(),
+                        );
+                    }
+                }
 
                 ui.add_space(8.0);
 
@@ -84,6 +160,20 @@ impl UltraLogApp {
                             .hint_text("e.g., RPM/s")
                             .desired_width(150.0),
                     );
+                    // An inferred unit replaces the typed one, so the unit
+                    // preferences can convert the result
+                    let inferred = self
+                        .formula_editor_state
+                        .unit_analysis
+                        .as_ref()
+                        .and_then(|a| a.unit.map(|u| (u, a.dimension)));
+                    if let Some((unit, Some(dimension))) = inferred {
+                        ui.label(
+                            egui::RichText::new(format!("Result is {} in {}", dimension, unit))
+                                .small()
+                                .color(egui::Color32::GRAY),
+                        );
+                    }
                 });
 
                 ui.add_space(8.0);
@@ -154,6 +244,22 @@ impl UltraLogApp {
                         }
                     });
                 }
+                if let Some(points) = &self.formula_editor_state.preview_plot {
+                    ui.add_space(4.0);
+                    Plot::new("formula_preview_plot")
+                        .height(120.0)
+                        .allow_drag(false)
+                        .allow_zoom(false)
+                        .allow_scroll(false)
+                        .allow_boxed_zoom(false)
+                        .show(ui, |plot_ui| {
+                            plot_ui.line(
+                                Line::new("Preview", PlotPoints::from(points.clone()))
+                                    .color(egui::Color32::LIGHT_GREEN)
+                                    .width(1.5),
+                            );
+                        });
+                }
 
                 ui.add_space(16.0);
                 ui.separator();
@@ -197,46 +303,211 @@ impl UltraLogApp {
     /// Validate the current formula in the editor
     fn validate_current_formula(&mut self) {
         let formula = self.formula_editor_state.formula.clone();
+        let completions = self.formula_editor_state.completions.take();
+        self.formula_editor_state.clear_results();
+        self.formula_editor_state.completions = completions;
 
         if formula.is_empty() {
-            self.formula_editor_state.validation_error = None;
-            self.formula_editor_state.preview_values = None;
             return;
         }
 
         // Get available channels
         let available_channels = self.get_available_channel_names();
 
-        // Validate the formula
-        match validate_formula(&formula, &available_channels) {
-            Ok(()) => {
-                self.formula_editor_state.validation_error = None;
-
-                // Generate preview if we have data
-                if let Some(tab_idx) = self.active_tab {
-                    let file_idx = self.tabs[tab_idx].file_index;
-                    if file_idx < self.files.len() {
-                        let file = &self.files[file_idx];
-                        let refs = extract_channel_references(&formula);
-                        if let Ok(bindings) = build_channel_bindings(&refs, &available_channels) {
-                            if let Ok(preview) = generate_preview(
-                                &formula,
-                                &bindings,
-                                &file.log.data,
-                                &file.log.times,
-                                5,
-                            ) {
-                                self.formula_editor_state.preview_values = Some(preview);
-                            }
-                        }
+        // Validate the formula and compile it to check the tables and
+        // constants it reads
+        let file_idx = self.active_tab.map(|tab_idx| self.tabs[tab_idx].file_index);
+        let compiled = match check_formula(
+            &formula,
+            &available_channels,
+            &self.formula_context(file_idx),
+        ) {
+            Ok(compiled) => compiled,
+            Err(e) => {
+                self.formula_editor_state.validation_error = Some(e.message);
+                self.formula_editor_state.error_span = e.span;
+                return;
+            }
+        };
+
+        let Some(file_idx) = file_idx else {
+            return;
+        };
+        if let Some(cycle) = self.editor_reference_cycle(file_idx, &formula) {
+            self.formula_editor_state.validation_error =
+                Some(format!("Circular reference: {}", cycle.join(" → ")));
+            return;
+        }
+
+        let units: Vec<&str> = compiled
+            .channels()
+            .iter()
+            .map(|&c| self.file_channel_unit(file_idx, c))
+            .collect();
+        self.formula_editor_state.unit_analysis = Some(compiled.analyze_units(&units));
+
+        // Preview the candidate channel over the whole log once typing pauses
+        self.formula_editor_state.preview_requested = Some(Instant::now());
+    }
+
+    /// Pick up the result of the whole-log preview, and start it once the
+    /// formula has been left unchanged for a moment
+    fn update_formula_preview(&mut self, ctx: &egui::Context) {
+        if let Some(job) = &self.formula_preview_job {
+            match job.receiver.try_recv() {
+                Ok((plot, values)) => {
+                    // Results for a formula that has since been edited are stale
+                    let state = &mut self.formula_editor_state;
+                    if job.formula == state.formula && state.preview_requested.is_none() {
+                        state.preview_plot = Some(plot);
+                        state.preview_values = Some(values);
                     }
+                    self.formula_preview_job = None;
                 }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.formula_preview_job = None,
             }
-            Err(e) => {
-                self.formula_editor_state.validation_error = Some(e);
-                self.formula_editor_state.preview_values = None;
-            }
         }
+
+        let Some(requested) = self.formula_editor_state.preview_requested else {
+            return;
+        };
+        let elapsed = requested.elapsed();
+        if elapsed < PREVIEW_DELAY {
+            ctx.request_repaint_after(PREVIEW_DELAY - elapsed);
+            return;
+        }
+        self.formula_editor_state.preview_requested = None;
+        self.start_formula_preview();
+    }
+
+    /// Evaluate the editor's formula over the whole log on a background
+    /// thread, replacing any preview still running
+    fn start_formula_preview(&mut self) {
+        self.formula_preview_job = None;
+        let Some(file_idx) = self.active_tab.map(|tab_idx| self.tabs[tab_idx].file_index) else {
+            return;
+        };
+        let formula = self.formula_editor_state.formula.clone();
+        let Ok(compiled) = check_formula(
+            &formula,
+            &self.get_available_channel_names(),
+            &self.formula_context(Some(file_idx)),
+        ) else {
+            return;
+        };
+        let Some(columns) = self.preview_inputs(file_idx, &compiled) else {
+            return;
+        };
+
+        // Copy the timeline so the worker doesn't borrow the log
+        let log = &self.files[file_idx].log;
+        let times = log.times.clone();
+        let num_records = log.data.len();
+        let (sender, receiver) = channel();
+
+        thread::spawn(move || {
+            let values = compiled.evaluate_inputs(&columns, &times, num_records, &|_| {});
+            let plot = Self::downsample_lttb(&times, &values, PREVIEW_PLOT_POINTS);
+            let _ = sender.send((plot, values.into_iter().take(5).collect()));
+        });
+
+        self.formula_preview_job = Some(FormulaPreviewJob { formula, receiver });
+    }
+
+    /// Update the suggestions for the word before the formula cursor (a
+    /// character index)
+    fn update_completions(&mut self, cursor: Option<usize>) {
+        let formula = &self.formula_editor_state.formula;
+        let completions = cursor.and_then(|cursor| {
+            let byte = formula
+                .char_indices()
+                .nth(cursor)
+                .map_or(formula.len(), |(i, _)| i);
+            let file_idx = self.active_tab.map(|tab_idx| self.tabs[tab_idx].file_index);
+            complete(
+                formula,
+                byte,
+                &self.get_available_channel_names(),
+                &self.formula_context(file_idx),
+            )
+        });
+        self.formula_editor_state.completions = completions;
+    }
+
+    /// Insert a suggestion into the formula and put the cursor after it
+    fn accept_completion(&mut self, ctx: &egui::Context, formula_id: egui::Id, index: usize) {
+        let state = &mut self.formula_editor_state;
+        let Some(completions) = state.completions.take() else {
+            return;
+        };
+        let Some(item) = completions.items.get(index) else {
+            return;
+        };
+        let end = completions.apply(&mut state.formula, item);
+        let cursor = egui::text::CCursor::new(state.formula[..end].chars().count());
+
+        let mut text_state = egui::TextEdit::load_state(ctx, formula_id).unwrap_or_default();
+        text_state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+        egui::TextEdit::store_state(ctx, formula_id, text_state);
+        ctx.memory_mut(|m| m.request_focus(formula_id));
+
+        self.validate_current_formula();
+    }
+
+    /// Check whether the formula being edited would make its channel depend
+    /// on itself through other computed channels of the file
+    fn editor_reference_cycle(&self, file_idx: usize, formula: &str) -> Option<Vec<String>> {
+        let file = self.files.get(file_idx)?;
+        let channels = self
+            .file_computed_channels
+            .get(&file_idx)
+            .map_or(&[][..], |c| c.as_slice());
+        let regular_names: Vec<String> = file.log.channels.iter().map(|c| c.name()).collect();
+
+        // An applied copy of the template being edited keeps its applied name
+        let state = &self.formula_editor_state;
+        let name = state
+            .editing_template_id
+            .as_ref()
+            .and_then(|id| channels.iter().find(|c| &c.template.id == id))
+            .map_or(state.name.as_str(), |c| c.name());
+        find_reference_cycle(name, formula, channels, &regular_names)
+    }
+
+    /// Inputs of a compiled formula in canonical units, reading computed
+    /// channel inputs from their evaluated data
+    fn preview_inputs(
+        &self,
+        file_idx: usize,
+        compiled: &CompiledFormula,
+    ) -> Option<Vec<InputColumn<'static>>> {
+        let file = self.files.get(file_idx)?;
+        let regular_count = file.log.channels.len();
+        let computed = self.file_computed_channels.get(&file_idx);
+        compiled
+            .channels()
+            .iter()
+            .map(|&channel| {
+                let input = if channel < regular_count {
+                    InputColumn::from_log(&file.log.data, channel)
+                } else {
+                    let data = computed?
+                        .get(channel - regular_count)?
+                        .cached_data
+                        .as_ref()?;
+                    InputColumn::Records(Cow::Borrowed(data.as_slice()))
+                };
+                let unit = self.file_channel_unit(file_idx, channel);
+                Some(
+                    input
+                        .map_values(|values| to_canonical(unit, values))
+                        .into_owned(),
+                )
+            })
+            .collect()
     }
 
     /// Save the formula from the editor to the library
@@ -252,6 +523,8 @@ impl UltraLogApp {
                     template.unit = state.unit.clone();
                     template.description = state.description.clone();
                     template.touch();
+                    let template = template.clone();
+                    self.update_applied_channels(&template);
                 }
             }
         } else {
@@ -274,4 +547,80 @@ impl UltraLogApp {
 
         self.formula_editor_state.close();
     }
+
+    /// Apply an edited template to the channels created from it in every
+    /// file, then re-evaluate them along with the channels that use them
+    pub(crate) fn update_applied_channels(&mut self, template: &ComputedChannelTemplate) {
+        let mut updates = Vec::new();
+        for (&file_idx, channels) in &mut self.file_computed_channels {
+            let Some(file) = self.files.get(file_idx) else {
+                continue;
+            };
+            let regular_names: Vec<String> = file.log.channels.iter().map(|c| c.name()).collect();
+
+            // Dependents are found before a rename can hide them
+            let deps = computed_dependencies(channels, &regular_names);
+            let updated: Vec<usize> = (0..channels.len())
+                .filter(|&i| channels[i].template.id == template.id)
+                .collect();
+            if updated.is_empty() {
+                continue;
+            }
+            let targets: Vec<usize> = (0..channels.len())
+                .filter(|i| updated.contains(i) || deps[*i].iter().any(|d| updated.contains(d)))
+                .collect();
+            for &i in &updated {
+                channels[i].template = template.clone();
+            }
+            updates.push((file_idx, targets));
+        }
+
+        for (file_idx, targets) in updates {
+            self.evaluate_computed_channels(file_idx, &targets);
+        }
+    }
+}
+
+/// Lay out the formula in monospace, underlining the part an error points at
+fn formula_layout_job(
+    ui: &egui::Ui,
+    text: &str,
+    error_span: Option<Range<usize>>,
+    wrap_width: f32,
+) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let normal = egui::TextFormat::simple(font_id, ui.visuals().text_color());
+    let error = egui::TextFormat {
+        background: egui::Color32::from_rgba_unmultiplied(255, 60, 60, 40),
+        underline: egui::Stroke::new(1.5, egui::Color32::RED),
+        ..normal.clone()
+    };
+
+    // An error at the end of the formula marks its last character
+    let span = error_span
+        .map(
+            |span| match text[..span.start.min(text.len())].chars().next_back() {
+                Some(c) if span.is_empty() && span.start >= text.len() => {
+                    text.len() - c.len_utf8()..text.len()
+                }
+                _ => span,
+            },
+        )
+        .filter(|span| {
+            span.end <= text.len()
+                && text.is_char_boundary(span.start)
+                && text.is_char_boundary(span.end)
+        });
+
+    let mut job = egui::text::LayoutJob::default();
+    job.wrap.max_width = wrap_width;
+    match span {
+        Some(span) => {
+            job.append(&text[..span.start], 0.0, normal.clone());
+            job.append(&text[span.clone()], 0.0, error);
+            job.append(&text[span.end..], 0.0, normal);
+        }
+        None => job.append(text, 0.0, normal),
+    }
+    job
 }
@@ -0,0 +1,431 @@
+//! Laps window.
+//!
+//! Detects laps of the active log from beacon markers or a start/finish
+//! line placed on the GPS track at the cursor, lists lap and sector times,
+//! and restricts the chart and scatter plots to the lap the user selects.
+
+use eframe::egui;
+
+use crate::app::UltraLogApp;
+use crate::gps::find_gps_channels;
+use crate::laps::{
+    beacon_crossings, best_lap, format_lap_time, laps_from_crossings, LapKind, TimingLine,
+};
+use crate::normalize::sort_channels_by_priority;
+use crate::parsers::MarkerKind;
+use crate::state::LapSource;
+
+/// Color of the fastest lap's time
+const BEST_LAP_COLOR: egui::Color32 = egui::Color32::from_rgb(80, 200, 120);
+
+/// What the user asked for this frame
+#[derive(Default)]
+struct LapActions {
+    detect: bool,
+    set_start_line: bool,
+    add_sector_line: bool,
+    clear_sector_lines: bool,
+    /// Lap to restrict the views to (`Some(None)` shows the whole log)
+    select: Option<Option<usize>>,
+}
+
+impl UltraLogApp {
+    /// Render the laps window
+    pub fn render_laps_window(&mut self, ctx: &egui::Context) {
+        if !self.show_laps_window {
+            return;
+        }
+
+        let mut open = true;
+        let mut actions = LapActions::default();
+
+        egui::Window::new("Laps")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(420.0)
+            .default_height(420.0)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                let Some(tab_idx) = self.active_tab.filter(|_| !self.files.is_empty()) else {
+                    ui.label(
+                        egui::RichText::new("Load a log file to time its laps")
+                            .color(egui::Color32::GRAY),
+                    );
+                    return;
+                };
+                if !self.tabs[tab_idx].lap_state.source_guessed {
+                    self.guess_lap_source(tab_idx);
+                    actions.detect = true;
+                }
+                self.render_lap_settings(ui, tab_idx, &mut actions);
+                ui.separator();
+                self.render_lap_table(ui, tab_idx, &mut actions);
+            });
+
+        if !open {
+            self.show_laps_window = false;
+        }
+
+        let Some(tab_idx) = self.active_tab.filter(|_| !self.files.is_empty()) else {
+            return;
+        };
+        if actions.set_start_line || actions.add_sector_line {
+            match self.timing_line_at_cursor(tab_idx) {
+                Ok(line) => {
+                    let state = &mut self.tabs[tab_idx].lap_state;
+                    if actions.set_start_line {
+                        state.start_line = Some(line);
+                    } else {
+                        state.sector_lines.push(line);
+                    }
+                    actions.detect = true;
+                }
+                Err(e) => self.tabs[tab_idx].lap_state.error = Some(e),
+            }
+        }
+        if actions.clear_sector_lines {
+            self.tabs[tab_idx].lap_state.sector_lines.clear();
+            actions.detect = true;
+        }
+        if actions.detect {
+            self.detect_laps(tab_idx);
+        }
+        if let Some(lap) = actions.select {
+            self.select_lap(tab_idx, lap);
+        }
+    }
+
+    /// Pick the tab's latitude / longitude channels from the log's channel
+    /// names, unless they have been picked already
+    pub(crate) fn guess_gps_channels(&mut self, tab_idx: usize) {
+        let tab = &mut self.tabs[tab_idx];
+        if tab.gps_channels.guessed {
+            return;
+        }
+        let Some(file) = self.files.get(tab.file_index) else {
+            return;
+        };
+        let names: Vec<String> = file.log.channels.iter().map(|c| c.name()).collect();
+        let found = find_gps_channels(&names);
+
+        tab.gps_channels.lat = found.map(|(lat, _)| lat);
+        tab.gps_channels.lon = found.map(|(_, lon)| lon);
+        tab.gps_channels.guessed = true;
+    }
+
+    /// Time laps from GPS when the log has no beacon markers to time them from
+    fn guess_lap_source(&mut self, tab_idx: usize) {
+        self.guess_gps_channels(tab_idx);
+        let Some(file) = self.files.get(self.tabs[tab_idx].file_index) else {
+            return;
+        };
+        let tab = &mut self.tabs[tab_idx];
+        let has_gps = tab.gps_channels.lat.is_some() && tab.gps_channels.lon.is_some();
+        if file.log.markers.iter().all(|m| m.kind != MarkerKind::Lap) && has_gps {
+            tab.lap_state.source = LapSource::GpsLine;
+        }
+        tab.lap_state.source_guessed = true;
+    }
+
+    /// Lap source, GPS channels and timing line buttons
+    fn render_lap_settings(&mut self, ui: &mut egui::Ui, tab_idx: usize, actions: &mut LapActions) {
+        let Some(file) = self.files.get(self.tabs[tab_idx].file_index) else {
+            return;
+        };
+        let sorted_channels = sort_channels_by_priority(
+            file.log.channels.len(),
+            |idx| file.log.channels[idx].name(),
+            self.field_normalization,
+            Some(&self.custom_normalizations),
+        );
+        let tab = &mut self.tabs[tab_idx];
+        let has_cursor = tab.cursor_time.is_some();
+        let state = &mut tab.lap_state;
+        let gps = &mut tab.gps_channels;
+
+        ui.horizontal(|ui| {
+            ui.label("Source:");
+            let before = state.source;
+            ui.radio_value(&mut state.source, LapSource::Beacon, "Beacon markers");
+            ui.radio_value(
+                &mut state.source,
+                LapSource::GpsLine,
+                "GPS start/finish line",
+            );
+            actions.detect |= state.source != before;
+        });
+
+        egui::Grid::new("laps_gps_channels")
+            .num_columns(2)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                for (label, slot) in [("Latitude", &mut gps.lat), ("Longitude", &mut gps.lon)] {
+                    ui.label(label);
+                    let selected = slot
+                        .and_then(|i| sorted_channels.iter().find(|(c, _, _)| *c == i))
+                        .map_or_else(|| "Select...".to_string(), |(_, name, _)| name.clone());
+                    egui::ComboBox::from_id_salt(("laps_gps_channel", label))
+                        .selected_text(selected)
+                        .width(200.0)
+                        .show_ui(ui, |ui| {
+                            for (idx, name, _) in &sorted_channels {
+                                if ui.selectable_label(*slot == Some(*idx), name).clicked() {
+                                    *slot = Some(*idx);
+                                    actions.detect = true;
+                                }
+                            }
+                        });
+                    ui.end_row();
+                }
+                ui.label("Line width");
+                ui.add(
+                    egui::DragValue::new(&mut state.line_width)
+                        .range(5.0..=200.0)
+                        .suffix(" m"),
+                )
+                .on_hover_text("Width of newly placed start/finish and sector lines");
+                ui.end_row();
+            });
+
+        ui.add_space(4.0);
+        ui.horizontal_wrapped(|ui| {
+            let hint = "Place the line across the track at the chart cursor";
+            if state.source == LapSource::GpsLine {
+                actions.set_start_line = ui
+                    .add_enabled(has_cursor, egui::Button::new("Set Start/Finish at Cursor"))
+                    .on_hover_text(hint)
+                    .on_disabled_hover_text("Click the chart to place the cursor first")
+                    .clicked();
+            }
+            actions.add_sector_line = ui
+                .add_enabled(has_cursor, egui::Button::new("Add Sector at Cursor"))
+                .on_hover_text(hint)
+                .on_disabled_hover_text("Click the chart to place the cursor first")
+                .clicked();
+            actions.clear_sector_lines = ui
+                .add_enabled(
+                    !state.sector_lines.is_empty(),
+                    egui::Button::new("Clear Sectors"),
+                )
+                .clicked();
+        });
+
+        let start_line = match (state.source, state.start_line) {
+            (LapSource::Beacon, _) => "Start/finish: beacon",
+            (LapSource::GpsLine, Some(_)) => "Start/finish: GPS line",
+            (LapSource::GpsLine, None) => "Start/finish: not set",
+        };
+        ui.label(
+            egui::RichText::new(format!(
+                "{}, {} sector line(s)",
+                start_line,
+                state.sector_lines.len()
+            ))
+            .small()
+            .color(egui::Color32::GRAY),
+        );
+        if let Some(error) = &state.error {
+            ui.colored_label(egui::Color32::from_rgb(255, 120, 120), error);
+        }
+    }
+
+    /// Lap and sector times; clicking a lap selects it
+    fn render_lap_table(&mut self, ui: &mut egui::Ui, tab_idx: usize, actions: &mut LapActions) {
+        let state = &self.tabs[tab_idx].lap_state;
+        if state.laps.is_empty() {
+            ui.label(egui::RichText::new("No laps detected").color(egui::Color32::GRAY));
+            return;
+        }
+
+        let best = best_lap(&state.laps);
+        let best_time = best.map(|i| state.laps[i].duration());
+        let sector_count = state
+            .laps
+            .iter()
+            .map(|l| l.sectors.len())
+            .max()
+            .unwrap_or(0);
+
+        egui::ScrollArea::vertical()
+            .id_salt("laps_table")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                egui::Grid::new("laps_grid")
+                    .striped(true)
+                    .num_columns(3 + sector_count)
+                    .spacing([14.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new("Lap").strong());
+                        ui.label(egui::RichText::new("Time").strong());
+                        ui.label(egui::RichText::new("Gap").strong());
+                        for sector in 1..=sector_count {
+                            ui.label(egui::RichText::new(format!("S{}", sector)).strong());
+                        }
+                        ui.end_row();
+
+                        for (i, lap) in state.laps.iter().enumerate() {
+                            if ui
+                                .selectable_label(state.selected == Some(i), lap.label())
+                                .clicked()
+                            {
+                                actions.select = Some(Some(i));
+                            }
+                            let time = egui::RichText::new(format_lap_time(lap.duration()));
+                            ui.label(if best == Some(i) {
+                                time.color(BEST_LAP_COLOR).strong()
+                            } else {
+                                time
+                            });
+                            match best_time.filter(|_| lap.kind == LapKind::Timed) {
+                                Some(best_time) if best != Some(i) => {
+                                    ui.label(format!("+{:.3}", lap.duration() - best_time));
+                                }
+                                _ => {
+                                    ui.label("");
+                                }
+                            }
+                            for sector in 0..sector_count {
+                                ui.label(
+                                    lap.sectors
+                                        .get(sector)
+                                        .map_or_else(|| "-".to_string(), |&t| format_lap_time(t)),
+                                );
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(state.selected.is_some(), egui::Button::new("Show All"))
+                .clicked()
+            {
+                actions.select = Some(None);
+            }
+            ui.label(
+                egui::RichText::new("Click a lap to restrict the chart and scatter plots to it")
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+        });
+    }
+
+    /// A timing line across the track at the cursor of the tab
+    fn timing_line_at_cursor(&self, tab_idx: usize) -> Result<TimingLine, String> {
+        let tab = &self.tabs[tab_idx];
+        let file = self
+            .files
+            .get(tab.file_index)
+            .ok_or_else(|| "No log loaded".to_string())?;
+        let (Some(lat_channel), Some(lon_channel)) = (tab.gps_channels.lat, tab.gps_channels.lon)
+        else {
+            return Err("Select the GPS latitude and longitude channels".to_string());
+        };
+        let record = tab
+            .cursor_time
+            .and_then(|t| file.log.find_record_at_time(t))
+            .ok_or_else(|| "Click the chart to place the cursor first".to_string())?;
+
+        let lat = file.log.get_aligned_channel_data(lat_channel);
+        let lon = file.log.get_aligned_channel_data(lon_channel);
+        TimingLine::at_record(&lat, &lon, record, tab.lap_state.line_width)
+            .ok_or_else(|| "No GPS fix at the cursor, or the car is not moving there".to_string())
+    }
+
+    /// Derive the tab's laps from its lap source and timing lines
+    pub(crate) fn detect_laps(&mut self, tab_idx: usize) {
+        let Some(file) = self.files.get(self.tabs[tab_idx].file_index) else {
+            return;
+        };
+        let log = &file.log;
+        let state = &self.tabs[tab_idx].lap_state;
+        let gps_channels = &self.tabs[tab_idx].gps_channels;
+        let had_selection = state.selected.is_some();
+
+        let result = (|| {
+            let (Some(&log_start), Some(&log_end)) = (log.times.first(), log.times.last()) else {
+                return Ok(Vec::new());
+            };
+            let gps = match (gps_channels.lat, gps_channels.lon) {
+                (Some(lat), Some(lon)) => Some((
+                    log.get_aligned_channel_data(lat),
+                    log.get_aligned_channel_data(lon),
+                )),
+                _ => None,
+            };
+
+            let crossings = match state.source {
+                LapSource::Beacon => {
+                    let crossings = beacon_crossings(&log.markers);
+                    if crossings.is_empty() {
+                        return Err("This log has no lap markers; place a start/finish \
+                                    line on the GPS track instead"
+                            .to_string());
+                    }
+                    crossings
+                }
+                LapSource::GpsLine => {
+                    let Some(line) = state.start_line else {
+                        return Ok(Vec::new());
+                    };
+                    let Some((lat, lon)) = &gps else {
+                        return Err("Select the GPS latitude and longitude channels".to_string());
+                    };
+                    let crossings = line.crossings(&log.times, lat, lon);
+                    if crossings.is_empty() {
+                        return Err("The start/finish line is never crossed".to_string());
+                    }
+                    crossings
+                }
+            };
+            let sector_crossings: Vec<Vec<f64>> = match &gps {
+                Some((lat, lon)) => state
+                    .sector_lines
+                    .iter()
+                    .map(|line| line.crossings(&log.times, lat, lon))
+                    .collect(),
+                None => Vec::new(),
+            };
+            Ok(laps_from_crossings(
+                &crossings,
+                (log_start, log_end),
+                &sector_crossings,
+            ))
+        })();
+
+        let state = &mut self.tabs[tab_idx].lap_state;
+        state.selected = None;
+        match result {
+            Ok(laps) => {
+                state.laps = laps;
+                state.error = None;
+            }
+            Err(e) => {
+                state.laps.clear();
+                state.error = Some(e);
+            }
+        }
+        if had_selection {
+            self.tabs[tab_idx].view_request = self.tabs[tab_idx].time_range;
+        }
+    }
+
+    /// Restrict the tab's views to a lap (or the whole log) and show it
+    fn select_lap(&mut self, tab_idx: usize, lap: Option<usize>) {
+        let tab = &mut self.tabs[tab_idx];
+        tab.lap_state.selected = lap;
+        tab.view_request = tab.lap_state.selected_range().or(tab.time_range);
+
+        if let Some((start, _)) = tab.lap_state.selected_range() {
+            let record = self
+                .files
+                .get(tab.file_index)
+                .and_then(|file| file.log.find_record_at_time(start));
+            tab.cursor_time = Some(start);
+            tab.cursor_record = record;
+            self.is_playing = false;
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Computed channel library import and export UI.
+//!
+//! Saves templates (with the lookup tables they use) to portable JSON files
+//! and merges shared files into the library, asking how to resolve
+//! templates and tables that clash with existing ones.
+
+use eframe::egui;
+
+use crate::app::UltraLogApp;
+use crate::computed::ComputedChannelLibrary;
+use crate::library_sharing::ConflictPolicy;
+use crate::state::PendingLibraryImport;
+
+impl UltraLogApp {
+    /// Save the given templates, the templates they reference and the tables
+    /// they use to a file
+    pub fn export_computed_library(&mut self, template_ids: &[String]) {
+        let library = self.computed_library.subset(template_ids);
+        let file_name = match library.templates.as_slice() {
+            [template] => format!("{}.json", template.name),
+            _ => "computed_channels.json".to_string(),
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name(file_name)
+            .save_file()
+        else {
+            return;
+        };
+
+        let result = library
+            .to_json()
+            .and_then(|content| std::fs::write(&path, content).map_err(|e| e.to_string()));
+        let exported = format!("Exported {} template(s)", library.templates.len());
+        // Constants live in vehicle profiles, so the recipient must define them
+        let constants = library.constant_references();
+        match result {
+            Ok(()) if constants.is_empty() => self.show_toast_success(&exported),
+            Ok(()) => self.show_toast_warning(&format!(
+                "{}; not included, define in the vehicle profile: {}",
+                exported,
+                constants
+                    .iter()
+                    .map(|c| format!("${}", c))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            Err(e) => self.show_toast_error(&format!("Failed to export library: {}", e)),
+        }
+    }
+
+    /// Read a shared library file and merge it, asking first if it conflicts
+    /// with the library
+    pub fn import_computed_library(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let result = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| ComputedChannelLibrary::from_json(&content));
+        let library = match result {
+            Ok(library) => library,
+            Err(e) => {
+                self.show_toast_error(&format!("Failed to import library: {}", e));
+                return;
+            }
+        };
+
+        let conflicts = self.computed_library.count_conflicts(&library);
+        if conflicts == 0 {
+            self.merge_computed_library(library, ConflictPolicy::KeepExisting);
+        } else {
+            self.pending_library_import = Some(PendingLibraryImport {
+                source: path
+                    .file_name()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                library,
+                conflicts,
+            });
+        }
+    }
+
+    /// Render the dialog resolving the conflicts of a pending import
+    pub fn render_library_import_dialog(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &self.pending_library_import else {
+            return;
+        };
+
+        let mut open = true;
+        let mut choice: Option<ConflictPolicy> = None;
+        let mut cancel = false;
+
+        egui::Window::new("Import Library")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "'{}' has {} template(s) and {} table(s).",
+                    pending.source,
+                    pending.library.templates.len(),
+                    pending.library.tables.len()
+                ));
+                ui.label(format!(
+                    "{} of them differ from ones with the same ID or name in your library.",
+                    pending.conflicts
+                ));
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Keep Mine")
+                        .on_hover_text("Only add what is new")
+                        .clicked()
+                    {
+                        choice = Some(ConflictPolicy::KeepExisting);
+                    }
+                    if ui
+                        .button("Replace")
+                        .on_hover_text("Overwrite your versions with the imported ones")
+                        .clicked()
+                    {
+                        choice = Some(ConflictPolicy::Replace);
+                    }
+                    if ui
+                        .button("Keep Both")
+                        .on_hover_text("Add imported templates under a new name")
+                        .clicked()
+                    {
+                        choice = Some(ConflictPolicy::KeepBoth);
+                    }
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if let Some(policy) = choice {
+            if let Some(pending) = self.pending_library_import.take() {
+                self.merge_computed_library(pending.library, policy);
+            }
+        } else if cancel || !open {
+            self.pending_library_import = None;
+        }
+    }
+
+    /// Merge an imported library, save it and update the channels using
+    /// replaced templates or tables
+    fn merge_computed_library(&mut self, library: ComputedChannelLibrary, policy: ConflictPolicy) {
+        let report = self.computed_library.merge(library, policy);
+        if let Err(e) = self.computed_library.save() {
+            self.show_toast_error(&format!("Failed to save: {}", e));
+            return;
+        }
+        self.show_toast_success(&format!("Imported library: {}", report.summary()));
+
+        for template in &report.replaced_templates {
+            self.update_applied_channels(template);
+        }
+        if !report.changed_tables.is_empty() {
+            self.reevaluate_table_users(&report.changed_tables);
+        }
+    }
+}
@@ -4,6 +4,7 @@ use eframe::egui;
 
 use crate::analytics;
 use crate::app::UltraLogApp;
+use crate::parsers::ParserRegistry;
 use crate::state::LoadingState;
 use crate::units::{
     AccelerationUnit, DistanceUnit, FlowUnit, FuelEconomyUnit, PressureUnit, SpeedUnit,
@@ -39,7 +40,7 @@ impl UltraLogApp {
                     .clicked()
                 {
                     if let Some(path) = rfd::FileDialog::new()
-                        .add_filter("Log Files", crate::state::SUPPORTED_EXTENSIONS)
+                        .add_filter("Log Files", &ParserRegistry::new().file_dialog_extensions())
                         .pick_file()
                     {
                         self.start_loading_file(path);
@@ -66,6 +67,11 @@ impl UltraLogApp {
                             self.export_chart_pdf();
                             ui.close();
                         }
+                        ui.separator();
+                        if ui.button("Export as CSV...").clicked() {
+                            self.open_csv_export_dialog();
+                            ui.close();
+                        }
                     });
                 });
             });
@@ -104,6 +110,28 @@ impl UltraLogApp {
 
                 ui.separator();
 
+                // Lap timing window
+                if ui
+                    .button("🏁  Laps...")
+                    .on_hover_text("Lap and sector times from beacons or a GPS start/finish line")
+                    .clicked()
+                {
+                    self.show_laps_window = true;
+                    ui.close();
+                }
+
+                // Log overlay window
+                if ui
+                    .button("📈  Overlay Logs...")
+                    .on_hover_text("Overlay and align other loaded logs on the chart")
+                    .clicked()
+                {
+                    self.show_overlay_window = true;
+                    ui.close();
+                }
+
+                ui.separator();
+
                 // Field Normalization toggle
                 if ui
                     .checkbox(&mut self.field_normalization, "📝  Field Normalization")
@@ -9,11 +9,18 @@
 //! - `toast` - Toast notification system
 //! - `icons` - Custom icon drawing utilities
 //! - `export` - Chart export functionality (PNG, PDF)
+//! - `laps` - Lap and sector times window with lap selection
+//! - `library_sharing` - Computed channel library import and export
 //! - `normalization_editor` - Field normalization customization window
+//! - `overlay` - Overlay of other logs on the chart with alignment and deltas
 //! - `tool_switcher` - Pill-style tab navigation between tools
 //! - `scatter_plot` - Scatter plot visualization view
 //! - `tab_bar` - Chrome-style tabs for managing multiple log files
+//! - `table_editor` - Lookup table list and editor for computed channels
+//! - `track_map` - GPS track map view synced to the cursor
 //! - `update_dialog` - Auto-update dialog window
+//! - `ve_analyze` - VE Analyze tool for fuel table corrections
+//! - `vehicle_profiles` - Vehicle profile selection and constants editor
 
 pub mod channels;
 pub mod chart;
@@ -21,12 +28,19 @@ pub mod computed_channels_manager;
 pub mod export;
 pub mod formula_editor;
 pub mod icons;
+pub mod laps;
+pub mod library_sharing;
 pub mod menu;
 pub mod normalization_editor;
+pub mod overlay;
 pub mod scatter_plot;
 pub mod sidebar;
 pub mod tab_bar;
+pub mod table_editor;
 pub mod timeline;
 pub mod toast;
 pub mod tool_switcher;
+pub mod track_map;
 pub mod update_dialog;
+pub mod ve_analyze;
+pub mod vehicle_profiles;
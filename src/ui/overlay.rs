@@ -0,0 +1,551 @@
+//! Chart overlay of other logs.
+//!
+//! Lets users overlay the selected channels of other loaded files on the
+//! active tab's chart, line them up by per-file offsets, a trigger
+//! condition or distance covered, and add delta traces against the tab's
+//! own log.
+
+use eframe::egui;
+use egui_plot::LineStyle;
+
+use crate::app::UltraLogApp;
+use crate::normalize::{normalize_channel_name_with_custom, sort_channels_by_priority};
+use crate::overlay::{
+    delta_trace, match_channel, reference_unit_converter, speed_scale, DistanceTrace, TimeMap,
+    TriggerEdge,
+};
+use crate::state::{
+    CacheKey, OverlayAlignment, OverlayFile, SelectedChannel, CHART_COLORS, COLORBLIND_COLORS,
+};
+
+/// How much lighter than the reference line an overlaid line is drawn
+const OVERLAY_LIGHTEN: f32 = 0.35;
+
+/// A line the chart draws for an overlaid log
+pub struct OverlayTrace {
+    /// Legend name, with the value at the cursor
+    pub name: String,
+    pub color: egui::Color32,
+    pub style: LineStyle,
+    /// Normalized points on the reference log's timeline
+    pub points: Vec<[f64; 2]>,
+}
+
+/// Minimum and maximum of a channel's values
+type ValueRange = (f64, f64);
+
+/// Overlay lines of the chart, and for each selected channel the value
+/// range shared with its overlaid counterparts
+pub struct ChartOverlay {
+    pub traces: Vec<OverlayTrace>,
+    /// (own range, shared range) of each selected channel that has overlays
+    pub shared_ranges: Vec<Option<(ValueRange, ValueRange)>>,
+}
+
+/// Move points normalized over `from` to the same values normalized over `to`
+pub fn renormalize(points: &[[f64; 2]], from: (f64, f64), to: (f64, f64)) -> Vec<[f64; 2]> {
+    let (from_span, to_span) = (from.1 - from.0, to.1 - to.0);
+    points
+        .iter()
+        .map(|&[t, y]| {
+            if y.is_nan() {
+                return [t, f64::NAN];
+            }
+            // Constant channels are drawn at mid-height
+            let value = if from_span <= f64::EPSILON {
+                from.0
+            } else {
+                from.0 + y * from_span
+            };
+            if to_span <= f64::EPSILON {
+                [t, 0.5]
+            } else {
+                [t, (value - to.0) / to_span]
+            }
+        })
+        .collect()
+}
+
+impl UltraLogApp {
+    /// Render the chart overlay window
+    pub fn render_overlay_window(&mut self, ctx: &egui::Context) {
+        if !self.show_overlay_window {
+            return;
+        }
+
+        let mut open = true;
+        egui::Window::new("Overlay Logs")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(440.0)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                let Some(tab_idx) = self.active_tab.filter(|_| self.files.len() > 1) else {
+                    ui.label(
+                        egui::RichText::new(
+                            "Load two or more logs to overlay them on a tab's chart",
+                        )
+                        .color(egui::Color32::GRAY),
+                    );
+                    return;
+                };
+                let changed = self.render_overlay_settings(ui, tab_idx);
+                if changed {
+                    self.tabs[tab_idx].overlay_state.time_maps = None;
+                }
+            });
+
+        if !open {
+            self.show_overlay_window = false;
+        }
+    }
+
+    /// Files, offsets, alignment and delta settings. Returns whether any
+    /// alignment setting changed.
+    fn render_overlay_settings(&mut self, ui: &mut egui::Ui, tab_idx: usize) -> bool {
+        self.ensure_overlay_time_maps(tab_idx);
+        let reference = self.tabs[tab_idx].file_index;
+        let channel_names = self.file_channel_names(reference);
+        let sorted_channels = sort_channels_by_priority(
+            channel_names.len(),
+            |idx| channel_names[idx].clone(),
+            self.field_normalization,
+            Some(&self.custom_normalizations),
+        );
+        let file_names: Vec<String> = self.files.iter().map(|f| f.name.clone()).collect();
+        let state = &mut self.tabs[tab_idx].overlay_state;
+        let mut changed = false;
+
+        ui.label(format!("Reference: {}", file_names[reference]));
+        ui.label(
+            egui::RichText::new(
+                "Selected channels of the reference are overlaid from each checked log",
+            )
+            .small()
+            .color(egui::Color32::GRAY),
+        );
+        ui.add_space(4.0);
+
+        egui::Grid::new("overlay_files")
+            .num_columns(3)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                for (file_index, name) in file_names.iter().enumerate() {
+                    if file_index == reference {
+                        continue;
+                    }
+                    let entry = state.files.iter().position(|f| f.file_index == file_index);
+                    let mut checked = entry.is_some_and(|i| state.files[i].visible);
+                    if ui.checkbox(&mut checked, name).changed() {
+                        match entry {
+                            Some(i) => state.files[i].visible = checked,
+                            None => state.files.push(OverlayFile {
+                                file_index,
+                                offset: 0.0,
+                                visible: true,
+                            }),
+                        }
+                        changed = true;
+                    }
+                    match entry {
+                        Some(i) => {
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut state.files[i].offset)
+                                        .speed(0.01)
+                                        .suffix(" s"),
+                                )
+                                .on_hover_text("Move this log later (or earlier if negative)")
+                                .changed();
+                            match state.time_maps.as_ref().and_then(|maps| maps.get(i)) {
+                                Some(Ok(TimeMap::Offset(offset))) => {
+                                    ui.label(format!("{:+.3} s", offset));
+                                }
+                                Some(Ok(TimeMap::Distance { .. })) => {
+                                    ui.label("by distance");
+                                }
+                                Some(Err(e)) => {
+                                    ui.colored_label(egui::Color32::from_rgb(255, 120, 120), e);
+                                }
+                                None => {
+                                    ui.label("");
+                                }
+                            }
+                        }
+                        None => {
+                            ui.label("");
+                            ui.label("");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Align by:");
+            for (alignment, label) in [
+                (OverlayAlignment::Offset, "Offset only"),
+                (OverlayAlignment::Trigger, "Trigger"),
+                (OverlayAlignment::Distance, "Distance"),
+            ] {
+                changed |= ui
+                    .radio_value(&mut state.alignment, alignment, label)
+                    .changed();
+            }
+        });
+
+        let channel_combo = |ui: &mut egui::Ui,
+                             id: &str,
+                             slot: &mut Option<String>,
+                             none_label: Option<&str>|
+         -> bool {
+            let mut changed = false;
+            let selected = slot
+                .clone()
+                .or(none_label.map(str::to_string))
+                .unwrap_or_else(|| "Select...".to_string());
+            egui::ComboBox::from_id_salt(("overlay_channel", id))
+                .selected_text(selected)
+                .width(180.0)
+                .show_ui(ui, |ui| {
+                    if let Some(none_label) = none_label {
+                        if ui.selectable_label(slot.is_none(), none_label).clicked() {
+                            *slot = None;
+                            changed = true;
+                        }
+                    }
+                    for (idx, label, _) in &sorted_channels {
+                        let name = &channel_names[*idx];
+                        if ui
+                            .selectable_label(slot.as_ref() == Some(name), label)
+                            .clicked()
+                        {
+                            *slot = Some(name.clone());
+                            changed = true;
+                        }
+                    }
+                });
+            changed
+        };
+
+        if state.alignment != OverlayAlignment::Offset {
+            egui::Grid::new("overlay_alignment")
+                .num_columns(2)
+                .spacing([8.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label("Trigger");
+                    ui.horizontal(|ui| {
+                        // Distance is measured from the start of each log
+                        // without a trigger
+                        let none_label = (state.alignment == OverlayAlignment::Distance)
+                            .then_some("Start of log");
+                        changed |=
+                            channel_combo(ui, "trigger", &mut state.trigger_channel, none_label);
+                        if state.trigger_channel.is_some() {
+                            egui::ComboBox::from_id_salt("overlay_trigger_edge")
+                                .selected_text(match state.trigger.edge {
+                                    TriggerEdge::Rising => "rises through",
+                                    TriggerEdge::Falling => "falls through",
+                                })
+                                .width(100.0)
+                                .show_ui(ui, |ui| {
+                                    for (edge, label) in [
+                                        (TriggerEdge::Rising, "rises through"),
+                                        (TriggerEdge::Falling, "falls through"),
+                                    ] {
+                                        changed |= ui
+                                            .selectable_value(&mut state.trigger.edge, edge, label)
+                                            .changed();
+                                    }
+                                });
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut state.trigger.threshold).speed(0.1))
+                                .changed();
+                        }
+                    });
+                    ui.end_row();
+
+                    if state.alignment == OverlayAlignment::Distance {
+                        ui.label("Speed");
+                        changed |= channel_combo(ui, "speed", &mut state.speed_channel, None);
+                        ui.end_row();
+                    }
+                });
+        }
+
+        ui.separator();
+        ui.checkbox(
+            &mut state.show_delta,
+            "Show delta traces (overlay - reference)",
+        )
+        .on_hover_text("Drawn around mid-height, which marks no difference");
+        changed
+    }
+
+    /// Compute the time mappings of the tab's overlaid files if needed
+    pub(crate) fn ensure_overlay_time_maps(&mut self, tab_idx: usize) {
+        if self.tabs[tab_idx].overlay_state.time_maps.is_none() {
+            let maps = self.compute_overlay_time_maps(tab_idx);
+            self.tabs[tab_idx].overlay_state.time_maps = Some(maps);
+        }
+    }
+
+    /// How each overlaid file of a tab lines up with the tab's log
+    fn compute_overlay_time_maps(&self, tab_idx: usize) -> Vec<Result<TimeMap, String>> {
+        let tab = &self.tabs[tab_idx];
+        let state = &tab.overlay_state;
+        let file_name = |file: usize| self.files.get(file).map_or("", |f| f.name.as_str());
+        let find_channel = |file: usize, name: &str| {
+            match_channel(name, &self.file_channel_names(file), |n| {
+                normalize_channel_name_with_custom(n, Some(&self.custom_normalizations))
+            })
+            .ok_or_else(|| format!("{} has no {} channel", file_name(file), name))
+        };
+
+        // Time the trigger condition is first met, or the start of the log
+        let zero_point = |file: usize| -> Result<f64, String> {
+            let Some(name) = &state.trigger_channel else {
+                return match state.alignment {
+                    OverlayAlignment::Trigger => Err("Select a trigger channel".to_string()),
+                    _ => Ok(self
+                        .files
+                        .get(file)
+                        .and_then(|f| f.log.times.first().copied())
+                        .unwrap_or(0.0)),
+                };
+            };
+            let channel = find_channel(file, name)?;
+            state
+                .trigger
+                .first_crossing(
+                    self.get_channel_times(file, channel),
+                    self.get_channel_data(file, channel),
+                )
+                .ok_or_else(|| {
+                    let edge = match state.trigger.edge {
+                        TriggerEdge::Rising => "rises",
+                        TriggerEdge::Falling => "falls",
+                    };
+                    format!(
+                        "{} never {} through {} in {}",
+                        name,
+                        edge,
+                        state.trigger.threshold,
+                        file_name(file)
+                    )
+                })
+        };
+        let distance = |file: usize| -> Result<DistanceTrace, String> {
+            let name = state
+                .speed_channel
+                .as_deref()
+                .ok_or_else(|| "Select a speed channel".to_string())?;
+            let channel = find_channel(file, name)?;
+            let scale = speed_scale(self.file_channel_unit(file, channel));
+            let speed: Vec<f64> = self
+                .get_channel_data(file, channel)
+                .iter()
+                .map(|v| v * scale)
+                .collect();
+            Ok(DistanceTrace::new(
+                self.get_channel_times(file, channel),
+                &speed,
+            ))
+        };
+
+        state
+            .files
+            .iter()
+            .map(|overlay| match state.alignment {
+                OverlayAlignment::Offset => Ok(TimeMap::Offset(overlay.offset)),
+                OverlayAlignment::Trigger => Ok(TimeMap::Offset(
+                    zero_point(tab.file_index)? - zero_point(overlay.file_index)? + overlay.offset,
+                )),
+                OverlayAlignment::Distance => Ok(TimeMap::Distance {
+                    reference: distance(tab.file_index)?,
+                    overlay: distance(overlay.file_index)?,
+                    reference_zero: zero_point(tab.file_index)?,
+                    overlay_zero: zero_point(overlay.file_index)?,
+                    shift: overlay.offset,
+                }),
+            })
+            .collect()
+    }
+
+    /// Overlaid and delta lines for the selected channels of the active tab
+    pub(crate) fn chart_overlay(&mut self, selected_channels: &[SelectedChannel]) -> ChartOverlay {
+        let mut overlay = ChartOverlay {
+            traces: Vec::new(),
+            shared_ranges: vec![None; selected_channels.len()],
+        };
+        let Some(tab_idx) = self.active_tab else {
+            return overlay;
+        };
+        if self.tabs[tab_idx].overlay_state.files.is_empty() {
+            return overlay;
+        }
+        self.ensure_overlay_time_maps(tab_idx);
+
+        let reference = self.tabs[tab_idx].file_index;
+        let files = self.tabs[tab_idx].overlay_state.files.clone();
+        let show_delta = self.tabs[tab_idx].overlay_state.show_delta;
+        // Taken out while drawing so the distance traces aren't copied
+        let maps = self.tabs[tab_idx]
+            .overlay_state
+            .time_maps
+            .take()
+            .unwrap_or_default();
+        let cursor_time = self.get_cursor_time();
+        let palette = if self.color_blind_mode {
+            COLORBLIND_COLORS
+        } else {
+            CHART_COLORS
+        };
+        let styles = [
+            LineStyle::dashed_dense(),
+            LineStyle::dashed_loose(),
+            LineStyle::dotted_loose(),
+        ];
+
+        for (i, selected) in selected_channels.iter().enumerate() {
+            if selected.file_index != reference {
+                continue;
+            }
+            let [r, g, b] = palette[selected.color_index % palette.len()];
+            let color = egui::Color32::from_rgb(r, g, b);
+            let name = selected.channel.name();
+            let unit = selected.channel.unit();
+            let label = if self.field_normalization {
+                normalize_channel_name_with_custom(&name, Some(&self.custom_normalizations))
+            } else {
+                name.clone()
+            };
+            let Some(own_range) = self.get_channel_min_max(reference, selected.channel_index)
+            else {
+                continue;
+            };
+            self.ensure_downsampled(reference, selected.channel_index);
+
+            let mut shared = own_range;
+            let mut lines = Vec::new();
+            for (k, (file, map)) in files.iter().zip(&maps).enumerate() {
+                let (true, Ok(map)) = (file.visible, map) else {
+                    continue;
+                };
+                let names = self.file_channel_names(file.file_index);
+                let Some(channel) = match_channel(&name, &names, |n| {
+                    normalize_channel_name_with_custom(n, Some(&self.custom_normalizations))
+                }) else {
+                    continue;
+                };
+                let Some(range) = self.get_channel_min_max(file.file_index, channel) else {
+                    continue;
+                };
+                // Matched channels of other ECUs may be logged in other units
+                let convert = reference_unit_converter(
+                    self.file_channel_unit(file.file_index, channel),
+                    unit,
+                );
+                let range = (convert(range.0), convert(range.1));
+                self.ensure_downsampled(file.file_index, channel);
+                shared = (shared.0.min(range.0), shared.1.max(range.1));
+                lines.push((k, file.file_index, channel, range, map, convert));
+            }
+            if lines.is_empty() {
+                continue;
+            }
+            overlay.shared_ranges[i] = Some((own_range, shared));
+
+            let reference_value = cursor_time
+                .and_then(|t| self.get_value_at_time(reference, selected.channel_index, t))
+                .map(|v| self.unit_preferences.convert_value(v, unit).0);
+            let reference_points = self
+                .downsample_cache
+                .get(&CacheKey {
+                    file_index: reference,
+                    channel_index: selected.channel_index,
+                })
+                .cloned()
+                .unwrap_or_default();
+
+            for (k, file_index, channel, range, map, convert) in lines {
+                let file_name = self.files[file_index].name.clone();
+                let key = CacheKey {
+                    file_index,
+                    channel_index: channel,
+                };
+                let Some(cached) = self.downsample_cache.get(&key) else {
+                    continue;
+                };
+                let mapped: Vec<[f64; 2]> = cached
+                    .iter()
+                    .filter_map(|&[t, y]| map.to_reference(t).map(|t| [t, y]))
+                    .collect();
+
+                // Values at the cursor, converted to the preferred units
+                let value = cursor_time
+                    .and_then(|t| map.to_overlay(t))
+                    .and_then(|t| self.get_value_at_time(file_index, channel, t))
+                    .map(|v| self.unit_preferences.convert_value(convert(v), unit));
+                let with_value = |base: String, value: Option<f64>, unit: &str| match value {
+                    Some(v) if unit.is_empty() => format!("{}: {:.2}", base, v),
+                    Some(v) => format!("{}: {:.2} {}", base, v, unit),
+                    None => base,
+                };
+                let display_unit = value.map_or("", |(_, u)| u);
+
+                overlay.traces.push(OverlayTrace {
+                    name: with_value(
+                        format!("{} [{}]", label, file_name),
+                        value.map(|(v, _)| v),
+                        display_unit,
+                    ),
+                    color: color.lerp_to_gamma(egui::Color32::WHITE, OVERLAY_LIGHTEN),
+                    style: styles[k % styles.len()],
+                    points: renormalize(&mapped, range, shared),
+                });
+
+                if show_delta {
+                    // Real values of the reference at its downsampled times
+                    let reference_values = renormalize(&reference_points, own_range, (0.0, 1.0));
+                    let overlay_values: Vec<f64> = self
+                        .get_channel_data(file_index, channel)
+                        .iter()
+                        .map(|&v| convert(v))
+                        .collect();
+                    let delta = delta_trace(
+                        &reference_values,
+                        self.get_channel_times(file_index, channel),
+                        &overlay_values,
+                        map,
+                    );
+                    // Centered on mid-height, which marks no difference
+                    let max_abs = delta
+                        .iter()
+                        .filter(|p| !p[1].is_nan())
+                        .fold(0.0f64, |m, p| m.max(p[1].abs()));
+                    let scale = if max_abs > f64::EPSILON { max_abs } else { 1.0 };
+                    let delta_value = value
+                        .zip(reference_value)
+                        .map(|((v, _), reference)| v - reference);
+                    overlay.traces.push(OverlayTrace {
+                        name: with_value(
+                            format!("Δ {} [{}]", label, file_name),
+                            delta_value,
+                            display_unit,
+                        ),
+                        color: color.lerp_to_gamma(egui::Color32::GRAY, 0.5),
+                        style: LineStyle::dotted_dense(),
+                        points: delta
+                            .iter()
+                            .map(|&[t, d]| [t, 0.5 + d / (2.0 * scale)])
+                            .collect(),
+                    });
+                }
+            }
+        }
+        self.tabs[tab_idx].overlay_state.time_maps = Some(maps);
+        overlay
+    }
+}
@@ -2,10 +2,17 @@
 //!
 //! This module provides a dual heatmap view where users can visualize
 //! relationships between channels as a 2D histogram with hit count coloring.
+//! A heatmap can also be binned by custom breakpoints (e.g. an ECU table's
+//! RPM / MAP axes), drawn as a table with statistics of a Z channel per cell.
+
+use std::ops::Range;
 
 use eframe::egui;
 
 use crate::app::UltraLogApp;
+use crate::data_export::record_range;
+use crate::heatmap::{auto_breakpoints, BreakpointHistogram, CellStats};
+use crate::lookup_table::{nearest_breakpoint, parse_axis, LookupTable};
 use crate::normalize::{normalize_channel_name_with_custom, sort_channels_by_priority};
 use crate::state::{ScatterPlotConfig, SelectedHeatmapPoint};
 
@@ -37,6 +44,15 @@ const LEGEND_HEIGHT: f32 = 35.0;
 /// Crosshair color
 const CROSSHAIR_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 255, 0); // Yellow
 
+/// Selected point / cell color
+const SELECTED_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 255, 255); // Cyan
+
+/// Number of breakpoints derived automatically from the data
+const AUTO_BREAKPOINTS: usize = 12;
+
+/// Smallest table cell (in points) that shows its value
+const MIN_LABELED_CELL: egui::Vec2 = egui::vec2(34.0, 14.0);
+
 impl UltraLogApp {
     /// Render the scatter plot view with two side-by-side plots
     pub fn render_scatter_plot_view(&mut self, ui: &mut egui::Ui) {
@@ -89,7 +105,14 @@ impl UltraLogApp {
 
         // Get channel names for the title
         let file_idx = config.file_index.unwrap_or(self.tabs[tab_idx].file_index);
-        let title = self.get_scatter_plot_title(config, file_idx);
+        let mut title = self.get_scatter_plot_title(config, file_idx);
+        if let Some(lap) = self.tabs[tab_idx]
+            .lap_state
+            .selected_lap()
+            .filter(|_| file_idx == self.tabs[tab_idx].file_index)
+        {
+            title.push_str(&format!(" ({})", lap.label()));
+        }
 
         // Title
         ui.horizontal(|ui| {
@@ -139,8 +162,12 @@ impl UltraLogApp {
         let x_name = get_name(config.x_channel);
         let y_name = get_name(config.y_channel);
 
-        // Always show "vs Hits" since that's the computed density
-        format!("{} vs {} vs Hits", y_name, x_name)
+        // Z statistics are only collected for table breakpoints; otherwise
+        // the Z axis is the computed density
+        match config.z_channel.filter(|_| config.breakpoints.enabled) {
+            Some(z) => format!("{} vs {} vs {}", y_name, x_name, get_name(Some(z))),
+            None => format!("{} vs {} vs Hits", y_name, x_name),
+        }
     }
 
     /// Render axis selector dropdowns
@@ -151,21 +178,14 @@ impl UltraLogApp {
 
         // Get values upfront to avoid borrow issues
         let tab_file_index = self.tabs[tab_idx].file_index;
-        let (file_idx, current_x, current_y) = if is_left {
-            let config = &self.tabs[tab_idx].scatter_plot_state.left;
-            (
-                config.file_index.unwrap_or(tab_file_index),
-                config.x_channel,
-                config.y_channel,
-            )
-        } else {
-            let config = &self.tabs[tab_idx].scatter_plot_state.right;
-            (
-                config.file_index.unwrap_or(tab_file_index),
-                config.x_channel,
-                config.y_channel,
-            )
-        };
+        let config = self.scatter_config(tab_idx, is_left);
+        let (file_idx, current_x, current_y, current_z, use_breakpoints) = (
+            config.file_index.unwrap_or(tab_file_index),
+            config.x_channel,
+            config.y_channel,
+            config.z_channel,
+            config.breakpoints.enabled,
+        );
 
         if file_idx >= self.files.len() {
             return;
@@ -190,6 +210,7 @@ impl UltraLogApp {
         // Track which channel was selected
         let mut new_x_channel: Option<usize> = None;
         let mut new_y_channel: Option<usize> = None;
+        let mut new_z_channel: Option<Option<usize>> = None;
 
         ui.horizontal(|ui| {
             // X Axis selector
@@ -230,9 +251,30 @@ impl UltraLogApp {
 
             ui.add_space(16.0);
 
-            // Z Axis is always "Hits" (density)
+            // Z Axis is "Hits" (density) unless a table breakpoint heatmap
+            // collects statistics of a channel
             ui.label("Z Axis:");
-            ui.label(egui::RichText::new("Hits").color(egui::Color32::from_rgb(150, 150, 150)));
+            if use_breakpoints {
+                egui::ComboBox::from_id_salt(if is_left { "left_z" } else { "right_z" })
+                    .selected_text(
+                        current_z
+                            .and_then(|i| channel_names.get(&i).map(|n| n.as_str()))
+                            .unwrap_or("Hits"),
+                    )
+                    .width(140.0)
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(current_z.is_none(), "Hits").clicked() {
+                            new_z_channel = Some(None);
+                        }
+                        for (idx, name, _is_normalized) in &sorted_channels {
+                            if ui.selectable_label(current_z == Some(*idx), name).clicked() {
+                                new_z_channel = Some(Some(*idx));
+                            }
+                        }
+                    });
+            } else {
+                ui.label(egui::RichText::new("Hits").color(egui::Color32::from_rgb(150, 150, 150)));
+            }
         });
 
         // Apply channel updates after UI is rendered
@@ -250,6 +292,232 @@ impl UltraLogApp {
                 self.tabs[tab_idx].scatter_plot_state.right.y_channel = Some(y);
             }
         }
+        if let Some(z) = new_z_channel {
+            self.scatter_config_mut(tab_idx, is_left).z_channel = z;
+        }
+
+        self.render_breakpoint_controls(ui, tab_idx, is_left);
+    }
+
+    /// Configuration of the left or right panel of a tab
+    fn scatter_config(&self, tab_idx: usize, is_left: bool) -> &ScatterPlotConfig {
+        let state = &self.tabs[tab_idx].scatter_plot_state;
+        if is_left {
+            &state.left
+        } else {
+            &state.right
+        }
+    }
+
+    fn scatter_config_mut(&mut self, tab_idx: usize, is_left: bool) -> &mut ScatterPlotConfig {
+        let state = &mut self.tabs[tab_idx].scatter_plot_state;
+        if is_left {
+            &mut state.left
+        } else {
+            &mut state.right
+        }
+    }
+
+    /// Render the table breakpoint toggle, axis inputs and import/export buttons
+    fn render_breakpoint_controls(&mut self, ui: &mut egui::Ui, tab_idx: usize, is_left: bool) {
+        let breakpoints = &mut self.scatter_config_mut(tab_idx, is_left).breakpoints;
+        let was_enabled = breakpoints.enabled;
+        let mut auto = false;
+        let mut import = false;
+        let mut export = false;
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut breakpoints.enabled, "Table breakpoints")
+                .on_hover_text(
+                    "Bin the heatmap by custom X/Y breakpoints, e.g. an ECU table's axes",
+                );
+            if breakpoints.enabled {
+                auto = ui
+                    .small_button("Auto")
+                    .on_hover_text("Derive round breakpoints from the data")
+                    .clicked();
+                import = ui
+                    .small_button("Import CSV...")
+                    .on_hover_text("Use the axes of a 2D table saved as CSV")
+                    .clicked();
+                export = ui
+                    .small_button("Export...")
+                    .on_hover_text("Save the per-cell statistics as CSV")
+                    .clicked();
+            }
+        });
+
+        if breakpoints.enabled {
+            let mut edited = false;
+            ui.horizontal(|ui| {
+                ui.label("X:");
+                edited |= ui
+                    .add(egui::TextEdit::singleline(&mut breakpoints.x_text).desired_width(160.0))
+                    .changed();
+                ui.label("Y:");
+                edited |= ui
+                    .add(egui::TextEdit::singleline(&mut breakpoints.y_text).desired_width(160.0))
+                    .changed();
+            });
+            if edited {
+                match (
+                    parse_axis("X", &breakpoints.x_text),
+                    parse_axis("Y", &breakpoints.y_text),
+                ) {
+                    (Ok(x), Ok(y)) => {
+                        breakpoints.x = x;
+                        breakpoints.y = y;
+                        breakpoints.error = None;
+                    }
+                    (Err(e), _) | (_, Err(e)) => breakpoints.error = Some(e),
+                }
+            }
+            if let Some(error) = &breakpoints.error {
+                ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+            }
+        }
+
+        let needs_axes = breakpoints.x.is_empty() || breakpoints.y.is_empty();
+        if auto || (breakpoints.enabled && !was_enabled && needs_axes) {
+            self.auto_scatter_breakpoints(tab_idx, is_left);
+        }
+        if import {
+            self.import_scatter_breakpoints(tab_idx, is_left);
+        }
+        if export {
+            self.export_breakpoint_histogram(tab_idx, is_left);
+        }
+    }
+
+    /// Aligned X, Y and Z data of a panel (empty if an axis is unset)
+    fn scatter_channel_data(&self, tab_idx: usize, is_left: bool) -> [Vec<f64>; 3] {
+        let config = self.scatter_config(tab_idx, is_left);
+        let file_idx = config.file_index.unwrap_or(self.tabs[tab_idx].file_index);
+        let Some(file) = self.files.get(file_idx) else {
+            return Default::default();
+        };
+        let records = self.scatter_records(tab_idx, file_idx);
+        [config.x_channel, config.y_channel, config.z_channel].map(|c| {
+            c.map_or_else(Vec::new, |c| {
+                file.log.get_aligned_channel_data(c)[records.clone()].to_vec()
+            })
+        })
+    }
+
+    /// Records of a file a panel plots: the selected lap when the panel
+    /// shows the tab's own file, otherwise the whole log
+    fn scatter_records(&self, tab_idx: usize, file_idx: usize) -> Range<usize> {
+        let tab = &self.tabs[tab_idx];
+        let Some(file) = self.files.get(file_idx) else {
+            return 0..0;
+        };
+        let lap = tab
+            .lap_state
+            .selected_range()
+            .filter(|_| file_idx == tab.file_index);
+        record_range(&file.log.times, lap)
+    }
+
+    /// Set a panel's breakpoints to round values covering its X and Y data
+    fn auto_scatter_breakpoints(&mut self, tab_idx: usize, is_left: bool) {
+        let [x_data, y_data, _] = self.scatter_channel_data(tab_idx, is_left);
+        let x = auto_breakpoints(&x_data, AUTO_BREAKPOINTS);
+        let y = auto_breakpoints(&y_data, AUTO_BREAKPOINTS);
+        let breakpoints = &mut self.scatter_config_mut(tab_idx, is_left).breakpoints;
+        if x.is_empty() || y.is_empty() {
+            breakpoints.error = Some("Select X and Y axes with data first".to_string());
+            return;
+        }
+        breakpoints.x_text = join_breakpoints(&x);
+        breakpoints.y_text = join_breakpoints(&y);
+        breakpoints.x = x;
+        breakpoints.y = y;
+        breakpoints.error = None;
+    }
+
+    /// Take a panel's breakpoints from the axes of a 2D table CSV file
+    fn import_scatter_breakpoints(&mut self, tab_idx: usize, is_left: bool) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv", "txt"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let result = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| LookupTable::from_csv("Breakpoints", &content))
+            .and_then(|table| {
+                if table.is_2d() {
+                    Ok(table)
+                } else {
+                    Err("Expected a 2D table with an X axis row and a Y axis column".to_string())
+                }
+            });
+        let breakpoints = &mut self.scatter_config_mut(tab_idx, is_left).breakpoints;
+        match result {
+            Ok(table) => {
+                breakpoints.x_text = join_breakpoints(&table.x_axis);
+                breakpoints.y_text = join_breakpoints(&table.y_axis);
+                breakpoints.x = table.x_axis;
+                breakpoints.y = table.y_axis;
+                breakpoints.error = None;
+            }
+            Err(e) => breakpoints.error = Some(format!("Failed to import breakpoints: {}", e)),
+        }
+    }
+
+    /// Save a panel's per-cell hit counts and Z statistics as CSV
+    fn export_breakpoint_histogram(&mut self, tab_idx: usize, is_left: bool) {
+        let config = self.scatter_config(tab_idx, is_left);
+        let file_idx = config.file_index.unwrap_or(self.tabs[tab_idx].file_index);
+        let (Some(x_channel), Some(y_channel)) = (config.x_channel, config.y_channel) else {
+            self.show_toast_error("Select X and Y axes first");
+            return;
+        };
+        let names = [Some(x_channel), Some(y_channel), config.z_channel]
+            .map(|c| c.map(|c| self.scatter_channel_name(file_idx, c)));
+        let [x_data, y_data, z_data] = self.scatter_channel_data(tab_idx, is_left);
+        let config = self.scatter_config(tab_idx, is_left);
+        let histogram = BreakpointHistogram::build(
+            &x_data,
+            &y_data,
+            names[2].as_ref().map(|_| z_data.as_slice()),
+            &config.breakpoints.x,
+            &config.breakpoints.y,
+        );
+        let csv = histogram.to_csv(
+            names[0].as_deref().unwrap_or("X"),
+            names[1].as_deref().unwrap_or("Y"),
+            names[2].as_deref(),
+        );
+
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("heatmap.csv")
+            .save_file()
+        else {
+            return;
+        };
+        match std::fs::write(&path, csv) {
+            Ok(()) => self.show_toast_success("Heatmap table exported"),
+            Err(e) => self.show_toast_error(&format!("Failed to export heatmap: {}", e)),
+        }
+    }
+
+    /// Display name of a channel, normalized if enabled
+    fn scatter_channel_name(&self, file_idx: usize, channel: usize) -> String {
+        let name = self
+            .files
+            .get(file_idx)
+            .and_then(|f| f.log.channels.get(channel))
+            .map(|c| c.name())
+            .unwrap_or_default();
+        if self.field_normalization {
+            normalize_channel_name_with_custom(&name, Some(&self.custom_normalizations))
+        } else {
+            name
+        }
     }
 
     /// Render the actual heatmap chart
@@ -288,19 +556,47 @@ impl UltraLogApp {
             return;
         }
 
+        let records = self.scatter_records(tab_idx, file_idx);
         let file = &self.files[file_idx];
-        let x_data = file.log.get_channel_data(x_idx);
-        let y_data = file.log.get_channel_data(y_idx);
+        // Pair samples by record, holding channels recorded at a slower rate
+        let x_all = file.log.get_aligned_channel_data(x_idx);
+        let y_all = file.log.get_aligned_channel_data(y_idx);
+        let (x_data, y_data) = (&x_all[records.clone()], &y_all[records.clone()]);
 
         if x_data.is_empty() || y_data.is_empty() || x_data.len() != y_data.len() {
             return;
         }
 
+        // Only records where both channels have a sample contribute
+        let pairs = || {
+            x_data
+                .iter()
+                .zip(y_data.iter())
+                .filter(|(x, y)| !x.is_nan() && !y.is_nan())
+        };
+        if pairs().next().is_none() {
+            return;
+        }
+
+        if config.breakpoints.enabled {
+            let (x_axis, y_axis) = (&config.breakpoints.x, &config.breakpoints.y);
+            if x_axis.is_empty() || y_axis.is_empty() {
+                return;
+            }
+            let z_all = config
+                .z_channel
+                .map(|z| file.log.get_aligned_channel_data(z));
+            let z_data = z_all.as_deref().map(|z| &z[records.clone()]);
+            let histogram = BreakpointHistogram::build(x_data, y_data, z_data, x_axis, y_axis);
+            self.render_breakpoint_chart(ui, is_left, &histogram, z_data.is_some());
+            return;
+        }
+
         // Calculate data bounds
-        let x_min = x_data.iter().cloned().fold(f64::MAX, f64::min);
-        let x_max = x_data.iter().cloned().fold(f64::MIN, f64::max);
-        let y_min = y_data.iter().cloned().fold(f64::MAX, f64::min);
-        let y_max = y_data.iter().cloned().fold(f64::MIN, f64::max);
+        let x_min = pairs().map(|(&x, _)| x).fold(f64::MAX, f64::min);
+        let x_max = pairs().map(|(&x, _)| x).fold(f64::MIN, f64::max);
+        let y_min = pairs().map(|(_, &y)| y).fold(f64::MAX, f64::min);
+        let y_max = pairs().map(|(_, &y)| y).fold(f64::MIN, f64::max);
 
         let x_range = if (x_max - x_min).abs() < f64::EPSILON {
             1.0
@@ -317,7 +613,7 @@ impl UltraLogApp {
         let mut histogram = vec![vec![0u32; HEATMAP_BINS]; HEATMAP_BINS];
         let mut max_hits: u32 = 0;
 
-        for (&x, &y) in x_data.iter().zip(y_data.iter()) {
+        for (&x, &y) in pairs() {
             let x_bin = (((x - x_min) / x_range) * (HEATMAP_BINS - 1) as f64).round() as usize;
             let y_bin = (((y - y_min) / y_range) * (HEATMAP_BINS - 1) as f64).round() as usize;
 
@@ -358,13 +654,7 @@ impl UltraLogApp {
             for x_bin in 0..HEATMAP_BINS {
                 let hits = histogram[y_bin][x_bin];
                 if hits > 0 {
-                    // Normalize hits to 0-1 using log scale for better visualization
-                    let normalized = if max_hits > 1 {
-                        (hits as f64).ln() / (max_hits as f64).ln()
-                    } else {
-                        1.0
-                    };
-                    let color = Self::get_heat_color(normalized);
+                    let color = Self::get_heat_color(Self::normalized_hits(hits, max_hits));
 
                     // Calculate cell position (Y is inverted - higher values at top)
                     let cell_x = plot_rect.left() + x_bin as f32 * cell_width;
@@ -533,8 +823,171 @@ impl UltraLogApp {
         self.render_heatmap_legend(ui, max_hits, is_left, x_min, x_max, y_min, y_max);
     }
 
+    /// Render a breakpoint heatmap as a table with one equally sized cell
+    /// per breakpoint pair, colored by hit count
+    fn render_breakpoint_chart(
+        &mut self,
+        ui: &mut egui::Ui,
+        is_left: bool,
+        histogram: &BreakpointHistogram,
+        has_z: bool,
+    ) {
+        let Some(tab_idx) = self.active_tab else {
+            return;
+        };
+        let (x_axis, y_axis) = (&histogram.x_axis, &histogram.y_axis);
+
+        let available = ui.available_size();
+        let chart_size = egui::vec2(available.x, (available.y - LEGEND_HEIGHT).max(100.0));
+        let (full_rect, response) = ui.allocate_exact_size(chart_size, egui::Sense::click());
+        let plot_rect = egui::Rect::from_min_max(
+            egui::pos2(full_rect.left() + AXIS_LABEL_MARGIN_LEFT, full_rect.top()),
+            egui::pos2(
+                full_rect.right(),
+                full_rect.bottom() - AXIS_LABEL_MARGIN_BOTTOM,
+            ),
+        );
+        let painter = ui.painter_at(full_rect);
+        painter.rect_filled(plot_rect, 0.0, egui::Color32::BLACK);
+
+        let cell_size = egui::vec2(
+            plot_rect.width() / x_axis.len() as f32,
+            plot_rect.height() / y_axis.len() as f32,
+        );
+        // Row 0 (lowest Y breakpoint) at the bottom
+        let cell_rect = |x: usize, y: usize| {
+            egui::Rect::from_min_size(
+                egui::pos2(
+                    plot_rect.left() + x as f32 * cell_size.x,
+                    plot_rect.bottom() - (y + 1) as f32 * cell_size.y,
+                ),
+                cell_size,
+            )
+        };
+        let grid_stroke = egui::Stroke::new(0.5, egui::Color32::from_rgb(60, 60, 60));
+        let show_values = cell_size.x >= MIN_LABELED_CELL.x && cell_size.y >= MIN_LABELED_CELL.y;
+
+        for (y, row) in histogram.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let rect = cell_rect(x, y);
+                let normalized = Self::normalized_hits(cell.hits, histogram.max_hits);
+                if cell.hits > 0 {
+                    painter.rect_filled(rect, 0.0, Self::get_heat_color(normalized));
+                }
+                painter.rect_stroke(rect, 0.0, grid_stroke, egui::StrokeKind::Inside);
+
+                let value = if has_z {
+                    cell.mean().map(format_stat)
+                } else {
+                    (cell.hits > 0).then(|| cell.hits.to_string())
+                };
+                if let Some(value) = value.filter(|_| show_values) {
+                    painter.text(
+                        rect.center(),
+                        egui::Align2::CENTER_CENTER,
+                        value,
+                        egui::FontId::proportional(10.0),
+                        if normalized > 0.5 {
+                            egui::Color32::BLACK
+                        } else {
+                            egui::Color32::WHITE
+                        },
+                    );
+                }
+            }
+        }
+
+        // Breakpoint labels at cell centers, skipping some when crowded
+        let text_color = egui::Color32::from_rgb(200, 200, 200);
+        let x_step = (36.0 / cell_size.x).ceil().max(1.0) as usize;
+        for (x, value) in x_axis.iter().enumerate().step_by(x_step) {
+            painter.text(
+                egui::pos2(cell_rect(x, 0).center().x, plot_rect.bottom() + 5.0),
+                egui::Align2::CENTER_TOP,
+                value.to_string(),
+                egui::FontId::proportional(10.0),
+                text_color,
+            );
+        }
+        let y_step = (14.0 / cell_size.y).ceil().max(1.0) as usize;
+        for (y, value) in y_axis.iter().enumerate().step_by(y_step) {
+            painter.text(
+                egui::pos2(plot_rect.left() - 5.0, cell_rect(0, y).center().y),
+                egui::Align2::RIGHT_CENTER,
+                value.to_string(),
+                egui::FontId::proportional(10.0),
+                text_color,
+            );
+        }
+
+        let config = self.scatter_config_mut(tab_idx, is_left);
+
+        // Selected cell (persistent)
+        if let Some(selected) = &config.selected_point {
+            let rect = cell_rect(
+                nearest_breakpoint(x_axis, selected.x_value),
+                nearest_breakpoint(y_axis, selected.y_value),
+            );
+            painter.rect_stroke(
+                rect,
+                0.0,
+                egui::Stroke::new(2.0, SELECTED_COLOR),
+                egui::StrokeKind::Inside,
+            );
+        }
+
+        // Hovered cell with its statistics, click to select it
+        let hovered = response
+            .hover_pos()
+            .filter(|pos| plot_rect.contains(*pos))
+            .map(|pos| {
+                let x = ((pos.x - plot_rect.left()) / cell_size.x) as usize;
+                let y = ((plot_rect.bottom() - pos.y) / cell_size.y) as usize;
+                (x.min(x_axis.len() - 1), y.min(y_axis.len() - 1))
+            });
+        if let Some((x, y)) = hovered {
+            let cell = histogram.cells[y][x];
+            painter.rect_stroke(
+                cell_rect(x, y),
+                0.0,
+                egui::Stroke::new(1.5, CROSSHAIR_COLOR),
+                egui::StrokeKind::Inside,
+            );
+            if response.clicked() {
+                config.selected_point = Some(SelectedHeatmapPoint {
+                    x_value: x_axis[x],
+                    y_value: y_axis[y],
+                    hits: cell.hits,
+                });
+            }
+            response.on_hover_text_at_pointer(cell_stats_text(x_axis[x], y_axis[y], &cell, has_z));
+        }
+
+        ui.add_space(8.0);
+        self.render_heatmap_legend(
+            ui,
+            histogram.max_hits,
+            is_left,
+            x_axis[0],
+            x_axis[x_axis.len() - 1],
+            y_axis[0],
+            y_axis[y_axis.len() - 1],
+        );
+    }
+
+    /// Hit count mapped to 0-1 on a log scale for better visualization
+    fn normalized_hits(hits: u32, max_hits: u32) -> f64 {
+        if hits == 0 {
+            0.0
+        } else if max_hits > 1 {
+            (hits as f64).ln() / (max_hits as f64).ln()
+        } else {
+            1.0
+        }
+    }
+
     /// Get a color from the heat map gradient based on normalized value (0-1)
-    fn get_heat_color(normalized: f64) -> egui::Color32 {
+    pub(crate) fn get_heat_color(normalized: f64) -> egui::Color32 {
         let t = normalized.clamp(0.0, 1.0);
         let scaled = t * (HEAT_COLORS.len() - 1) as f64;
         let idx = scaled.floor() as usize;
@@ -722,3 +1175,38 @@ impl UltraLogApp {
         }
     }
 }
+
+/// Breakpoints as an editable comma-separated list
+fn join_breakpoints(axis: &[f64]) -> String {
+    axis.iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A Z statistic with fewer decimals for larger values
+fn format_stat(value: f64) -> String {
+    match value.abs() {
+        v if v >= 100.0 => format!("{:.0}", value),
+        v if v >= 10.0 => format!("{:.1}", value),
+        _ => format!("{:.2}", value),
+    }
+}
+
+/// Hover text for a breakpoint heatmap cell
+fn cell_stats_text(x: f64, y: f64, cell: &CellStats, has_z: bool) -> String {
+    let mut text = format!("X: {}\nY: {}\nHits: {}", x, y, cell.hits);
+    if has_z {
+        let stats = [
+            ("Mean", cell.mean()),
+            ("Min", cell.min()),
+            ("Max", cell.max()),
+            ("Std dev", cell.std_dev()),
+        ];
+        for (label, value) in stats {
+            let value = value.map_or_else(|| "-".to_string(), format_stat);
+            text.push_str(&format!("\n{}: {}", label, value));
+        }
+    }
+    text
+}
@@ -3,6 +3,7 @@
 use eframe::egui;
 
 use crate::app::UltraLogApp;
+use crate::parsers::ParserRegistry;
 use crate::state::{ActiveTool, LoadingState};
 use crate::ui::icons::draw_upload_icon;
 
@@ -108,7 +109,10 @@ impl UltraLogApp {
                         .clicked()
                     {
                         if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("Log Files", crate::state::SUPPORTED_EXTENSIONS)
+                            .add_filter(
+                                "Log Files",
+                                &ParserRegistry::new().file_dialog_extensions(),
+                            )
                             .pick_file()
                         {
                             self.start_loading_file(path);
@@ -173,7 +177,10 @@ impl UltraLogApp {
                         .clicked()
                     {
                         if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("Log Files", crate::state::SUPPORTED_EXTENSIONS)
+                            .add_filter(
+                                "Log Files",
+                                &ParserRegistry::new().file_dialog_extensions(),
+                            )
                             .pick_file()
                         {
                             self.start_loading_file(path);
@@ -0,0 +1,400 @@
+//! Lookup Table Editor UI.
+//!
+//! Lists the lookup tables in the computed channel library and provides a
+//! window for editing a table's axes and values or importing it from CSV.
+
+use eframe::egui;
+
+use crate::app::UltraLogApp;
+use crate::lookup_table::LookupTable;
+
+impl UltraLogApp {
+    /// Render the lookup tables section of the computed channels manager
+    pub fn render_lookup_tables_section(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new(format!(
+                    "Lookup Tables ({})",
+                    self.computed_library.tables.len()
+                ))
+                .strong(),
+            );
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("Import CSV...").clicked() {
+                    self.import_lookup_table();
+                }
+                if ui.small_button("+ New Table").clicked() {
+                    self.table_editor_state.open_new();
+                }
+            });
+        });
+        ui.add_space(4.0);
+
+        if self.computed_library.tables.is_empty() {
+            ui.label(
+                egui::RichText::new(
+                    "No lookup tables. Use them in formulas with table(\"Name\", x) or table2d(\"Name\", x, y).",
+                )
+                .color(egui::Color32::GRAY)
+                .small(),
+            );
+            return;
+        }
+
+        let mut table_to_edit: Option<LookupTable> = None;
+        let mut table_to_delete: Option<String> = None;
+
+        egui::ScrollArea::vertical()
+            .id_salt("lookup_tables_scroll")
+            .max_height(120.0)
+            .show(ui, |ui| {
+                for table in &self.computed_library.tables {
+                    egui::Frame::NONE
+                        .fill(egui::Color32::from_rgb(45, 45, 55))
+                        .corner_radius(5.0)
+                        .inner_margin(egui::Margin::symmetric(10, 6))
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    egui::RichText::new(&table.name)
+                                        .strong()
+                                        .color(egui::Color32::LIGHT_BLUE),
+                                );
+                                ui.label(
+                                    egui::RichText::new(table.size_label())
+                                        .small()
+                                        .color(egui::Color32::GRAY),
+                                );
+                                if !table.description.is_empty() {
+                                    ui.label(
+                                        egui::RichText::new(&table.description)
+                                            .small()
+                                            .color(egui::Color32::GRAY),
+                                    );
+                                }
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        if ui.small_button("Delete").clicked() {
+                                            table_to_delete = Some(table.name.clone());
+                                        }
+                                        if ui.small_button("Edit").clicked() {
+                                            table_to_edit = Some(table.clone());
+                                        }
+                                    },
+                                );
+                            });
+                        });
+                    ui.add_space(2.0);
+                }
+            });
+
+        if let Some(table) = table_to_edit {
+            self.table_editor_state.open_edit(&table);
+        }
+
+        if let Some(name) = table_to_delete {
+            self.computed_library.remove_table(&name);
+            self.save_lookup_tables("Table deleted");
+            self.reevaluate_table_users(&[name]);
+        }
+    }
+
+    /// Render the lookup table editor dialog
+    pub fn render_table_editor(&mut self, ctx: &egui::Context) {
+        if !self.table_editor_state.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut should_save = false;
+        let mut should_cancel = false;
+        let mut should_import = false;
+        let mut should_export = false;
+
+        let title = if self.table_editor_state.original_name.is_some() {
+            "Edit Lookup Table"
+        } else {
+            "New Lookup Table"
+        };
+
+        egui::Window::new(title)
+            .open(&mut open)
+            .resizable(true)
+            .default_width(600.0)
+            .default_height(420.0)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                let state = &mut self.table_editor_state;
+
+                egui::Grid::new("table_editor_fields")
+                    .num_columns(2)
+                    .spacing([8.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut state.table.name);
+                        ui.end_row();
+
+                        ui.label("Description:");
+                        ui.text_edit_singleline(&mut state.table.description);
+                        ui.end_row();
+                    });
+
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new(state.table.size_label())
+                            .color(egui::Color32::GRAY),
+                    );
+                    ui.separator();
+                    if ui.small_button("+ Column").clicked() {
+                        state.table.push_x();
+                    }
+                    if ui.small_button("- Column").clicked() {
+                        state.table.pop_x();
+                    }
+                    if ui.small_button("+ Row").clicked() {
+                        state.table.push_y();
+                    }
+                    if ui
+                        .add_enabled(state.table.is_2d(), egui::Button::new("- Row").small())
+                        .clicked()
+                    {
+                        state.table.pop_y();
+                    }
+                    ui.separator();
+                    should_import = ui.small_button("Import CSV...").clicked();
+                    should_export = ui.small_button("Export CSV...").clicked();
+                });
+                ui.add_space(4.0);
+                ui.label(
+                    egui::RichText::new(if state.table.is_2d() {
+                        "Columns are the x input, rows the y input of table2d(\"Name\", x, y)."
+                    } else {
+                        "The top row is the x input of table(\"Name\", x). Add a row to make a 2D table."
+                    })
+                    .small()
+                    .color(egui::Color32::GRAY),
+                );
+                ui.add_space(4.0);
+
+                egui::ScrollArea::both()
+                    .id_salt("table_editor_grid")
+                    .max_height(260.0)
+                    .show(ui, |ui| {
+                        render_table_grid(ui, &mut state.table);
+                    });
+
+                if let Some(error) = &state.error {
+                    ui.add_space(4.0);
+                    ui.label(egui::RichText::new(error).color(egui::Color32::RED));
+                }
+
+                ui.add_space(8.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Save").clicked() {
+                            should_save = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            should_cancel = true;
+                        }
+                    });
+                });
+            });
+
+        if should_import {
+            if let Some(table) = self.pick_lookup_table_csv() {
+                let state = &mut self.table_editor_state;
+                let name = std::mem::take(&mut state.table.name);
+                let description = std::mem::take(&mut state.table.description);
+                state.table = LookupTable {
+                    name: if name.is_empty() { table.name } else { name },
+                    description,
+                    ..table
+                };
+                state.error = None;
+            }
+        }
+
+        if should_export {
+            self.export_lookup_table();
+        }
+
+        if should_save {
+            self.save_table_editor();
+        }
+
+        if !open || should_cancel {
+            self.table_editor_state.open = false;
+        }
+    }
+
+    /// Import a CSV file as a new library table, named after the file
+    fn import_lookup_table(&mut self) {
+        let Some(mut table) = self.pick_lookup_table_csv() else {
+            return;
+        };
+
+        // Keep existing tables; the user can rename the import
+        let base = table.name.clone();
+        let mut suffix = 2;
+        while self.computed_library.find_table(&table.name).is_some() {
+            table.name = format!("{} {}", base, suffix);
+            suffix += 1;
+        }
+
+        self.computed_library.set_table(table.clone());
+        self.save_lookup_tables(&format!("Imported table '{}'", table.name));
+        self.reevaluate_table_users(&[table.name]);
+    }
+
+    /// Ask for a CSV file and parse it as a table named after the file
+    fn pick_lookup_table_csv(&mut self) -> Option<LookupTable> {
+        let path = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv", "txt"])
+            .pick_file()?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Table".to_string());
+
+        let result = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| LookupTable::from_csv(&name, &text));
+        match result {
+            Ok(table) => Some(table),
+            Err(e) => {
+                self.show_toast_error(&format!("Failed to import table: {}", e));
+                None
+            }
+        }
+    }
+
+    /// Save the table being edited as CSV
+    fn export_lookup_table(&mut self) {
+        let table = &self.table_editor_state.table;
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("{}.csv", table.name))
+            .save_file()
+        else {
+            return;
+        };
+
+        match std::fs::write(&path, table.to_csv()) {
+            Ok(()) => self.show_toast_success("Table exported"),
+            Err(e) => self.show_toast_error(&format!("Failed to export table: {}", e)),
+        }
+    }
+
+    /// Store the edited table in the library and re-evaluate its users
+    fn save_table_editor(&mut self) {
+        let state = &mut self.table_editor_state;
+        state.table.name = state.table.name.trim().to_string();
+        if let Err(e) = state.table.validate() {
+            state.error = Some(e);
+            return;
+        }
+
+        let renamed_from = state
+            .original_name
+            .clone()
+            .filter(|old| !old.eq_ignore_ascii_case(&state.table.name));
+        let is_new_name = state.original_name.is_none() || renamed_from.is_some();
+        if is_new_name
+            && self
+                .computed_library
+                .find_table(&state.table.name)
+                .is_some()
+        {
+            state.error = Some(format!(
+                "A table named '{}' already exists",
+                state.table.name
+            ));
+            return;
+        }
+
+        let table = state.table.clone();
+        state.open = false;
+
+        let mut affected = vec![table.name.clone()];
+        if let Some(old) = renamed_from {
+            self.computed_library.remove_table(&old);
+            affected.push(old);
+        }
+        self.computed_library.set_table(table);
+        self.save_lookup_tables("Table saved to library");
+        self.reevaluate_table_users(&affected);
+    }
+
+    /// Save the library after a table change
+    fn save_lookup_tables(&mut self, success_message: &str) {
+        if let Err(e) = self.computed_library.save() {
+            self.show_toast_error(&format!("Failed to save: {}", e));
+        } else {
+            self.show_toast_success(success_message);
+        }
+    }
+}
+
+/// Editable grid of axis values and table values
+fn render_table_grid(ui: &mut egui::Ui, table: &mut LookupTable) {
+    let cell = |ui: &mut egui::Ui, value: &mut f64| {
+        ui.add(
+            egui::DragValue::new(value)
+                .speed(0.1)
+                .max_decimals(4)
+                .min_decimals(1),
+        );
+    };
+    let axis_cell = |ui: &mut egui::Ui, value: &mut f64| {
+        ui.scope(|ui| {
+            ui.visuals_mut().widgets.inactive.weak_bg_fill = egui::Color32::from_rgb(50, 60, 80);
+            cell(ui, value);
+        });
+    };
+
+    egui::Grid::new("lookup_table_values")
+        .spacing([4.0, 4.0])
+        .show(ui, |ui| {
+            if table.is_2d() {
+                ui.label(
+                    egui::RichText::new("y \\ x")
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+                for x in &mut table.x_axis {
+                    axis_cell(ui, x);
+                }
+                ui.end_row();
+
+                for (y, row) in table.y_axis.iter_mut().zip(&mut table.values) {
+                    axis_cell(ui, y);
+                    for value in row {
+                        cell(ui, value);
+                    }
+                    ui.end_row();
+                }
+            } else {
+                ui.label(egui::RichText::new("x").small().color(egui::Color32::GRAY));
+                for x in &mut table.x_axis {
+                    axis_cell(ui, x);
+                }
+                ui.end_row();
+
+                ui.label(
+                    egui::RichText::new("value")
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+                if let Some(row) = table.values.first_mut() {
+                    for value in row {
+                        cell(ui, value);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+}
@@ -48,6 +48,53 @@ impl UltraLogApp {
         // Restore original slider width
         ui.spacing_mut().slider_width = old_slider_width;
 
+        // Marker ticks under the slider - click to jump
+        let mut marker_jump = None;
+        if !self.get_markers().is_empty() {
+            let (strip, _) = ui.allocate_exact_size(
+                egui::vec2(slider_response.rect.width(), 10.0),
+                egui::Sense::hover(),
+            );
+            // Match the slider rail, which is inset by the handle radius
+            let inset = slider_response.rect.height() / 2.5;
+            let rail = strip.shrink2(egui::vec2(inset, 0.0));
+
+            for (i, marker) in self.get_markers().iter().enumerate() {
+                let fraction = ((marker.time - min_time) / total_duration).clamp(0.0, 1.0);
+                let x = rail.left() + rail.width() * fraction as f32;
+                let tick = egui::Rect::from_center_size(
+                    egui::pos2(x, strip.center().y),
+                    egui::vec2(6.0, strip.height()),
+                );
+                let response = ui
+                    .interact(tick, ui.id().with(("marker", i)), egui::Sense::click())
+                    .on_hover_text(format!(
+                        "{}: {} ({})",
+                        marker.kind.name(),
+                        marker.label,
+                        Self::format_time(marker.time)
+                    ));
+                let color = if response.hovered() {
+                    egui::Color32::WHITE
+                } else {
+                    Self::marker_color(marker.kind)
+                };
+                ui.painter().line_segment(
+                    [egui::pos2(x, strip.top()), egui::pos2(x, strip.bottom())],
+                    egui::Stroke::new(2.0, color),
+                );
+                if response.clicked() {
+                    marker_jump = Some(marker.time);
+                }
+            }
+        }
+
+        if let Some(time) = marker_jump {
+            self.jump_to_time(time);
+            ui.ctx().request_repaint();
+            return;
+        }
+
         if slider_response.changed() {
             // Stop playback when user manually scrubs
             self.is_playing = false;
@@ -115,6 +162,50 @@ impl UltraLogApp {
                 }
             }
 
+            // Marker navigation
+            if !self.get_markers().is_empty() {
+                ui.separator();
+
+                let cursor = self.get_cursor_time().unwrap_or(f64::NEG_INFINITY);
+                let (previous, next) = self
+                    .active_tab
+                    .and_then(|idx| self.files.get(self.tabs[idx].file_index))
+                    .map(|file| {
+                        (
+                            file.log.previous_marker(cursor).map(|m| m.time),
+                            file.log.next_marker(cursor).map(|m| m.time),
+                        )
+                    })
+                    .unwrap_or_default();
+
+                if ui
+                    .add_enabled(
+                        previous.is_some(),
+                        egui::Button::new(egui::RichText::new("⏮").size(16.0))
+                            .min_size(button_size),
+                    )
+                    .on_hover_text("Previous marker")
+                    .clicked()
+                {
+                    if let Some(time) = previous {
+                        self.jump_to_time(time);
+                    }
+                }
+                if ui
+                    .add_enabled(
+                        next.is_some(),
+                        egui::Button::new(egui::RichText::new("⏭").size(16.0))
+                            .min_size(button_size),
+                    )
+                    .on_hover_text("Next marker")
+                    .clicked()
+                {
+                    if let Some(time) = next {
+                        self.jump_to_time(time);
+                    }
+                }
+            }
+
             ui.separator();
 
             // Playback speed selector
@@ -1,7 +1,8 @@
 //! Tool switcher component for switching between different views.
 //!
 //! Renders a pill-style tab bar at the top of the main content area
-//! allowing users to switch between Log Viewer and Scatter Plots views.
+//! allowing users to switch between the Log Viewer, Scatter Plots, VE
+//! Analyze and Track Map views.
 
 use eframe::egui;
 
@@ -16,7 +17,12 @@ impl UltraLogApp {
             ui.add_space(10.0);
 
             // Define available tools
-            let tools = [ActiveTool::LogViewer, ActiveTool::ScatterPlot];
+            let tools = [
+                ActiveTool::LogViewer,
+                ActiveTool::ScatterPlot,
+                ActiveTool::VeAnalyze,
+                ActiveTool::TrackMap,
+            ];
 
             for tool in tools {
                 let is_selected = self.active_tool == tool;
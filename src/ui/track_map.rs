@@ -0,0 +1,455 @@
+//! GPS track map view.
+//!
+//! Draws the path driven in the active log from its latitude / longitude
+//! channels (no map tiles), optionally colored by another channel, with the
+//! cursor position marked. Clicking the map moves the cursor there and
+//! dragging a box sets the chart's time range to the pass through it.
+
+use std::ops::Range;
+
+use eframe::egui;
+
+use crate::app::UltraLogApp;
+use crate::data_export::record_range;
+use crate::gps::TrackPath;
+use crate::normalize::sort_channels_by_priority;
+use crate::state::LapSource;
+
+/// Space between the path and the edge of the map
+const MAP_MARGIN: f32 = 24.0;
+
+/// Height reserved for the legend at the bottom
+const LEGEND_HEIGHT: f32 = 35.0;
+
+/// Most path segments drawn; longer logs are decimated
+const MAX_TRACK_SEGMENTS: usize = 8000;
+
+const BACKGROUND_COLOR: egui::Color32 = egui::Color32::from_rgb(20, 20, 20);
+const PATH_COLOR: egui::Color32 = egui::Color32::from_rgb(200, 200, 200);
+/// Color of samples without a value of the color channel
+const MISSING_COLOR: egui::Color32 = egui::Color32::from_rgb(90, 90, 90);
+/// Cursor marker color, matching the chart's cursor line
+const CURSOR_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 255, 255);
+const SELECTION_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 255, 0);
+const START_LINE_COLOR: egui::Color32 = egui::Color32::WHITE;
+const SECTOR_LINE_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 170, 0);
+
+/// What the user did on the map this frame
+#[derive(Default)]
+struct TrackMapActions {
+    /// Record clicked on
+    cursor_record: Option<usize>,
+    /// Local position a region drag started at
+    drag_origin: Option<[f64; 2]>,
+    /// Finished region drag: the time span it selected, if any
+    region: Option<Option<(f64, f64)>>,
+}
+
+/// Maps local track coordinates (meters, north up) onto the screen
+struct MapTransform {
+    center: [f64; 2],
+    screen_center: egui::Pos2,
+    /// Points per meter
+    scale: f64,
+}
+
+impl MapTransform {
+    /// Fit the box from `min` to `max` into `rect`, keeping the aspect ratio
+    fn fit(min: [f64; 2], max: [f64; 2], rect: egui::Rect) -> Self {
+        let width = (max[0] - min[0]).max(1.0);
+        let height = (max[1] - min[1]).max(1.0);
+        Self {
+            center: [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0],
+            screen_center: rect.center(),
+            scale: (rect.width() as f64 / width).min(rect.height() as f64 / height),
+        }
+    }
+
+    fn to_screen(&self, p: [f64; 2]) -> egui::Pos2 {
+        egui::pos2(
+            self.screen_center.x + ((p[0] - self.center[0]) * self.scale) as f32,
+            self.screen_center.y - ((p[1] - self.center[1]) * self.scale) as f32,
+        )
+    }
+
+    fn to_local(&self, pos: egui::Pos2) -> [f64; 2] {
+        [
+            self.center[0] + (pos.x - self.screen_center.x) as f64 / self.scale,
+            self.center[1] - (pos.y - self.screen_center.y) as f64 / self.scale,
+        ]
+    }
+}
+
+impl UltraLogApp {
+    /// Render the track map view for the active tab
+    pub fn render_track_map_view(&mut self, ui: &mut egui::Ui) {
+        let Some(tab_idx) = self.active_tab.filter(|_| !self.files.is_empty()) else {
+            ui.centered_and_justified(|ui| {
+                ui.label(
+                    egui::RichText::new("Load a log file with GPS channels to see its track")
+                        .size(20.0)
+                        .color(egui::Color32::GRAY),
+                );
+            });
+            return;
+        };
+
+        self.render_tab_bar(ui);
+        ui.add_space(10.0);
+
+        self.guess_gps_channels(tab_idx);
+
+        // Laps timed from GPS follow the channels chosen here
+        let gps_changed = self.render_track_map_controls(ui, tab_idx);
+        let lap_state = &self.tabs[tab_idx].lap_state;
+        if gps_changed && lap_state.source_guessed && lap_state.source == LapSource::GpsLine {
+            self.detect_laps(tab_idx);
+        }
+        ui.add_space(8.0);
+
+        let mut actions = TrackMapActions::default();
+        let color_range = self.render_track_map(ui, tab_idx, &mut actions);
+        self.render_track_map_legend(ui, tab_idx, color_range);
+
+        let tab = &mut self.tabs[tab_idx];
+        if let Some(origin) = actions.drag_origin {
+            tab.track_map_state.drag_origin = Some(origin);
+        }
+        if let Some(record) = actions.cursor_record {
+            tab.cursor_time = self
+                .files
+                .get(tab.file_index)
+                .and_then(|file| file.log.times.get(record).copied());
+            tab.cursor_record = Some(record);
+            self.is_playing = false;
+        }
+        match actions.region {
+            Some(Some((start, end))) => {
+                tab.track_map_state.drag_origin = None;
+                tab.view_request = Some((start, end));
+                self.show_toast_success(&format!(
+                    "Chart range set to {} - {}",
+                    Self::format_time(start),
+                    Self::format_time(end)
+                ));
+            }
+            Some(None) => {
+                tab.track_map_state.drag_origin = None;
+                self.show_toast_error("The path does not pass through the selected region");
+            }
+            None => {}
+        }
+    }
+
+    /// Latitude, longitude and color channel selectors. Returns whether the
+    /// GPS channels changed.
+    fn render_track_map_controls(&mut self, ui: &mut egui::Ui, tab_idx: usize) -> bool {
+        let Some(file) = self.files.get(self.tabs[tab_idx].file_index) else {
+            return false;
+        };
+        let sorted_channels = sort_channels_by_priority(
+            file.log.channels.len(),
+            |idx| file.log.channels[idx].name(),
+            self.field_normalization,
+            Some(&self.custom_normalizations),
+        );
+        let tab = &mut self.tabs[tab_idx];
+        let gps = &mut tab.gps_channels;
+        let before = (gps.lat, gps.lon);
+
+        ui.horizontal(|ui| {
+            for (label, slot, none_label) in [
+                ("Latitude:", &mut gps.lat, None),
+                ("Longitude:", &mut gps.lon, None),
+                (
+                    "Color by:",
+                    &mut tab.track_map_state.color_channel,
+                    Some("None"),
+                ),
+            ] {
+                ui.label(label);
+                let selected = slot
+                    .and_then(|i| sorted_channels.iter().find(|(c, _, _)| *c == i))
+                    .map(|(_, name, _)| name.clone())
+                    .or(none_label.map(str::to_string))
+                    .unwrap_or_else(|| "Select...".to_string());
+                egui::ComboBox::from_id_salt(("track_map_channel", label))
+                    .selected_text(selected)
+                    .width(160.0)
+                    .show_ui(ui, |ui| {
+                        if let Some(none_label) = none_label {
+                            if ui.selectable_label(slot.is_none(), none_label).clicked() {
+                                *slot = None;
+                            }
+                        }
+                        for (idx, name, _) in &sorted_channels {
+                            if ui.selectable_label(*slot == Some(*idx), name).clicked() {
+                                *slot = Some(*idx);
+                            }
+                        }
+                    });
+                ui.add_space(12.0);
+            }
+        });
+        (gps.lat, gps.lon) != before
+    }
+
+    /// Draw the path, timing lines and cursor, and handle clicks and drags.
+    /// Returns the range of the color channel over the drawn path.
+    fn render_track_map(
+        &self,
+        ui: &mut egui::Ui,
+        tab_idx: usize,
+        actions: &mut TrackMapActions,
+    ) -> Option<(f64, f64)> {
+        let tab = &self.tabs[tab_idx];
+        let file = self.files.get(tab.file_index)?;
+        let log = &file.log;
+        let state = &tab.track_map_state;
+
+        let available = ui.available_size();
+        let size = egui::vec2(available.x, (available.y - LEGEND_HEIGHT).max(100.0));
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 4.0, BACKGROUND_COLOR);
+        let placeholder = |text: &str| {
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                text,
+                egui::FontId::proportional(16.0),
+                egui::Color32::GRAY,
+            );
+        };
+
+        let (Some(lat_channel), Some(lon_channel)) = (tab.gps_channels.lat, tab.gps_channels.lon)
+        else {
+            placeholder("Select the GPS latitude and longitude channels");
+            return None;
+        };
+        let path = TrackPath::new(
+            &log.get_aligned_channel_data(lat_channel),
+            &log.get_aligned_channel_data(lon_channel),
+        );
+        // Only the selected lap, if any
+        let records = record_range(&log.times, tab.lap_state.selected_range());
+        let Some((min, max)) = path.bounds(records.clone()) else {
+            placeholder("No GPS fix in this log");
+            return None;
+        };
+        let transform = MapTransform::fit(min, max, rect.shrink(MAP_MARGIN));
+
+        // Color channel values and their range over the drawn records
+        let color_data = state.color_channel.map(|c| log.get_aligned_channel_data(c));
+        let color_range = color_data.as_deref().and_then(|values| {
+            values[records.clone()]
+                .iter()
+                .filter(|v| v.is_finite())
+                .fold(None, |range, &v| {
+                    let (lo, hi) = range.unwrap_or((v, v));
+                    Some((lo.min(v), hi.max(v)))
+                })
+        });
+        let segment_color = |record: usize| match (&color_data, color_range) {
+            (Some(values), Some((lo, hi))) => match values.get(record) {
+                Some(v) if v.is_finite() => {
+                    let span = if hi > lo { hi - lo } else { 1.0 };
+                    Self::get_heat_color((v - lo) / span)
+                }
+                _ => MISSING_COLOR,
+            },
+            _ => PATH_COLOR,
+        };
+
+        // Path, broken where there is no fix
+        let stride = (records.len() / MAX_TRACK_SEGMENTS).max(1);
+        let mut previous: Option<(usize, egui::Pos2)> = None;
+        for record in sampled_records(records.clone(), stride) {
+            let Some(p) = path.points[record] else {
+                previous = None;
+                continue;
+            };
+            let pos = transform.to_screen(p);
+            if let Some((prev_record, prev_pos)) = previous {
+                painter.line_segment(
+                    [prev_pos, pos],
+                    egui::Stroke::new(2.0, segment_color(prev_record)),
+                );
+            }
+            previous = Some((record, pos));
+        }
+
+        // Start/finish and sector lines placed in the laps window
+        if let Some(projection) = path.projection {
+            let lap_state = &tab.lap_state;
+            let lines = lap_state
+                .start_line
+                .iter()
+                .map(|line| (line, "S/F".to_string(), START_LINE_COLOR))
+                .chain(
+                    lap_state
+                        .sector_lines
+                        .iter()
+                        .enumerate()
+                        .map(|(i, line)| (line, format!("S{}", i + 1), SECTOR_LINE_COLOR)),
+                );
+            for (line, label, color) in lines {
+                let center = projection.to_local(line.lat, line.lon);
+                let heading = line.heading.to_radians();
+                let half = [
+                    heading.cos() * line.width / 2.0,
+                    -heading.sin() * line.width / 2.0,
+                ];
+                let a = transform.to_screen([center[0] - half[0], center[1] - half[1]]);
+                let b = transform.to_screen([center[0] + half[0], center[1] + half[1]]);
+                painter.line_segment([a, b], egui::Stroke::new(2.5, color));
+                painter.text(
+                    b + egui::vec2(4.0, 0.0),
+                    egui::Align2::LEFT_CENTER,
+                    label,
+                    egui::FontId::proportional(11.0),
+                    color,
+                );
+            }
+        }
+
+        // Cursor position
+        let cursor = tab
+            .cursor_time
+            .and_then(|t| log.find_record_at_time(t))
+            .and_then(|record| path.points.get(record).copied().flatten());
+        if let Some(p) = cursor {
+            let pos = transform.to_screen(p);
+            painter.circle_filled(pos, 6.0, CURSOR_COLOR);
+            painter.circle_stroke(pos, 6.0, egui::Stroke::new(1.5, egui::Color32::BLACK));
+        }
+
+        // Region selection
+        let pointer = response.interact_pointer_pos();
+        if response.drag_started() {
+            let origin = ui.input(|i| i.pointer.press_origin()).or(pointer);
+            actions.drag_origin = origin.map(|pos| transform.to_local(pos));
+        }
+        let origin = actions.drag_origin.or(state.drag_origin);
+        if let (Some(origin), Some(pos)) = (origin, pointer) {
+            if response.dragged() || response.drag_stopped() {
+                let selection = egui::Rect::from_two_pos(transform.to_screen(origin), pos);
+                painter.rect_filled(selection, 0.0, SELECTION_COLOR.gamma_multiply(0.1));
+                painter.rect_stroke(
+                    selection,
+                    0.0,
+                    egui::Stroke::new(1.0, SELECTION_COLOR),
+                    egui::StrokeKind::Inside,
+                );
+            }
+            if response.drag_stopped() {
+                actions.region = Some(path.region_time_span(
+                    &log.times,
+                    origin,
+                    transform.to_local(pos),
+                    records.clone(),
+                    tab.cursor_time,
+                ));
+            }
+        }
+
+        if response.clicked() {
+            if let Some(pos) = pointer {
+                actions.cursor_record = path.nearest_record(transform.to_local(pos), records);
+            }
+        } else if let Some(pos) = response.hover_pos().filter(|_| !response.dragged()) {
+            // Highlight and describe the nearest sample
+            if let Some(record) = path.nearest_record(transform.to_local(pos), records) {
+                if let Some(p) = path.points[record] {
+                    painter.circle_stroke(
+                        transform.to_screen(p),
+                        5.0,
+                        egui::Stroke::new(1.5, egui::Color32::WHITE),
+                    );
+                }
+                let mut text = format!("Time: {}", Self::format_time(log.times[record]));
+                if let Some(value) = color_data.as_deref().and_then(|v| v.get(record)) {
+                    if value.is_finite() {
+                        text.push_str(&format!("\nValue: {:.2}", value));
+                    }
+                }
+                response.on_hover_text_at_pointer(text);
+            }
+        }
+
+        color_range
+    }
+
+    /// Color scale of the color channel and usage hints
+    fn render_track_map_legend(
+        &self,
+        ui: &mut egui::Ui,
+        tab_idx: usize,
+        color_range: Option<(f64, f64)>,
+    ) {
+        let tab = &self.tabs[tab_idx];
+        let color_name = tab
+            .track_map_state
+            .color_channel
+            .and_then(|c| self.files.get(tab.file_index)?.log.channels.get(c))
+            .map(|c| c.name());
+
+        ui.horizontal(|ui| {
+            ui.add_space(4.0);
+            if let (Some(name), Some((lo, hi))) = (color_name, color_range) {
+                egui::Frame::NONE
+                    .fill(egui::Color32::from_rgba_unmultiplied(30, 30, 30, 220))
+                    .corner_radius(4)
+                    .inner_margin(6.0)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new(format!("{}:  {:.1}", name, lo))
+                                    .size(11.0)
+                                    .color(egui::Color32::WHITE),
+                            );
+                            let (rect, _) = ui
+                                .allocate_exact_size(egui::vec2(100.0, 14.0), egui::Sense::hover());
+                            let steps = 25;
+                            let step_width = rect.width() / steps as f32;
+                            for i in 0..steps {
+                                ui.painter().rect_filled(
+                                    egui::Rect::from_min_size(
+                                        egui::pos2(rect.left() + i as f32 * step_width, rect.top()),
+                                        egui::vec2(step_width + 1.0, rect.height()),
+                                    ),
+                                    0.0,
+                                    Self::get_heat_color(i as f64 / steps as f64),
+                                );
+                            }
+                            ui.label(
+                                egui::RichText::new(format!("{:.1}", hi))
+                                    .size(11.0)
+                                    .color(egui::Color32::WHITE),
+                            );
+                        });
+                    });
+                ui.add_space(8.0);
+            }
+            if let Some(lap) = tab.lap_state.selected_lap() {
+                ui.label(egui::RichText::new(lap.label()).color(egui::Color32::WHITE));
+                ui.add_space(8.0);
+            }
+            ui.label(
+                egui::RichText::new(
+                    "Click to move the cursor, drag a box to show that stretch on the chart",
+                )
+                .size(11.0)
+                .color(egui::Color32::GRAY),
+            );
+        });
+    }
+}
+
+/// Every `stride`-th record of `records`, always including the last one
+fn sampled_records(records: Range<usize>, stride: usize) -> impl Iterator<Item = usize> {
+    let last = records.end.checked_sub(1).filter(|&l| l >= records.start);
+    let stepped = records.clone().step_by(stride);
+    let ends_on_last = last.is_some_and(|l| (l - records.start).is_multiple_of(stride));
+    stepped.chain(last.filter(|_| !ends_on_last))
+}
@@ -0,0 +1,608 @@
+//! VE Analyze tool view.
+//!
+//! Bins the active log into the cells of an ECU fuel table, shows the
+//! suggested fuel multiplier for each cell colored by AFR error, and copies
+//! or exports the corrections (or a pasted base table with them applied)
+//! for pasting back into the tuning software.
+
+use eframe::egui;
+
+use crate::app::UltraLogApp;
+use crate::lookup_table::{parse_axis, LookupTable};
+use crate::normalize::{normalize_channel_name_with_custom, sort_channels_by_priority};
+use crate::state::{VeAnalyzeOutput, VeAnalyzeState};
+use crate::ve_analyze::{coolant_celsius, values_to_tsv, AfrTarget, VeAnalysis, VeAnalyzeData};
+
+/// Width of the settings column
+const SETTINGS_WIDTH: f32 = 320.0;
+
+/// Size of a cell in the result grid
+const CELL_SIZE: egui::Vec2 = egui::vec2(52.0, 22.0);
+
+/// AFR error (percent) shown at full color
+const FULL_SCALE_ERROR: f64 = 10.0;
+
+/// Normalized channel names tried for each input, in order of preference
+const RPM_NAMES: &[&str] = &["RPM"];
+const LOAD_NAMES: &[&str] = &["MAP", "TPS"];
+const AFR_NAMES: &[&str] = &["AFR", "AFR 1", "Lambda 1"];
+const TARGET_NAMES: &[&str] = &["AFR Target"];
+const COOLANT_NAMES: &[&str] = &["Coolant Temp"];
+const TRANSIENT_NAMES: &[&str] = &["TPS"];
+
+/// A channel selector the settings column renders
+#[derive(Clone, Copy)]
+enum ChannelField {
+    Rpm,
+    Load,
+    Afr,
+    Target,
+    Coolant,
+    Transient,
+    ClosedLoop,
+}
+
+impl ChannelField {
+    fn slot(self, state: &mut VeAnalyzeState) -> &mut Option<usize> {
+        match self {
+            ChannelField::Rpm => &mut state.rpm_channel,
+            ChannelField::Load => &mut state.load_channel,
+            ChannelField::Afr => &mut state.afr_channel,
+            ChannelField::Target => &mut state.target_channel,
+            ChannelField::Coolant => &mut state.coolant_channel,
+            ChannelField::Transient => &mut state.transient_channel,
+            ChannelField::ClosedLoop => &mut state.closed_loop_channel,
+        }
+    }
+}
+
+/// What the user asked for this frame
+#[derive(Default)]
+struct VeAnalyzeActions {
+    analyze: bool,
+    use_base_axes: bool,
+    copy: bool,
+    export: bool,
+}
+
+impl UltraLogApp {
+    /// Render the VE Analyze view for the active tab
+    pub fn render_ve_analyze_view(&mut self, ui: &mut egui::Ui) {
+        let Some(tab_idx) = self.active_tab.filter(|_| !self.files.is_empty()) else {
+            ui.centered_and_justified(|ui| {
+                ui.label(
+                    egui::RichText::new("Load a log file to analyze fuel table corrections")
+                        .size(20.0)
+                        .color(egui::Color32::GRAY),
+                );
+            });
+            return;
+        };
+
+        self.render_tab_bar(ui);
+        ui.add_space(10.0);
+
+        if !self.tabs[tab_idx].ve_analyze_state.channels_guessed {
+            self.guess_ve_analyze_channels(tab_idx);
+        }
+
+        let mut actions = VeAnalyzeActions::default();
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.set_width(SETTINGS_WIDTH);
+                egui::ScrollArea::vertical()
+                    .id_salt("ve_analyze_settings")
+                    .show(ui, |ui| {
+                        self.render_ve_analyze_settings(ui, tab_idx, &mut actions);
+                    });
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                self.render_ve_analyze_result(ui, tab_idx, &mut actions);
+            });
+        });
+
+        if actions.use_base_axes {
+            let state = &mut self.tabs[tab_idx].ve_analyze_state;
+            match parse_base_table(&state.base_table) {
+                Ok(table) => {
+                    state.rpm_axis = join_axis(&table.x_axis);
+                    state.load_axis = join_axis(&table.y_axis);
+                    state.error = None;
+                }
+                Err(e) => state.error = Some(e),
+            }
+        }
+        if actions.analyze {
+            self.run_ve_analysis(tab_idx);
+        }
+        if actions.copy {
+            match self.ve_analyze_output_table(tab_idx) {
+                Ok(table) => {
+                    ui.ctx()
+                        .copy_text(values_to_tsv(&table, output_decimals(&table)));
+                    self.show_toast_success("Table copied to clipboard");
+                }
+                Err(e) => self.show_toast_error(&e),
+            }
+        }
+        if actions.export {
+            self.export_ve_analyze_table(tab_idx);
+        }
+    }
+
+    /// Pick channels for the analysis from the log's normalized channel names
+    fn guess_ve_analyze_channels(&mut self, tab_idx: usize) {
+        let file_idx = self.tabs[tab_idx].file_index;
+        let Some(file) = self.files.get(file_idx) else {
+            return;
+        };
+        let names: Vec<(String, String)> = file
+            .log
+            .channels
+            .iter()
+            .map(|c| {
+                let name = c.name();
+                let normalized =
+                    normalize_channel_name_with_custom(&name, Some(&self.custom_normalizations));
+                (name, normalized)
+            })
+            .collect();
+        let find = |candidates: &[&str]| {
+            candidates.iter().find_map(|candidate| {
+                names
+                    .iter()
+                    .position(|(_, normalized)| normalized.eq_ignore_ascii_case(candidate))
+            })
+        };
+
+        let state = &mut self.tabs[tab_idx].ve_analyze_state;
+        state.rpm_channel = find(RPM_NAMES);
+        state.load_channel = find(LOAD_NAMES);
+        state.afr_channel = find(AFR_NAMES);
+        state.target_channel = find(TARGET_NAMES);
+        state.coolant_channel = find(COOLANT_NAMES);
+        state.transient_channel = find(TRANSIENT_NAMES);
+        state.closed_loop_channel = names
+            .iter()
+            .position(|(name, _)| name.to_lowercase().contains("closed loop"));
+        if state
+            .afr_channel
+            .is_some_and(|i| names[i].1.to_lowercase().contains("lambda"))
+        {
+            state.fixed_target = 1.0;
+        }
+        state.channels_guessed = true;
+    }
+
+    /// Channel pickers, breakpoints, filters and the analyze button
+    fn render_ve_analyze_settings(
+        &mut self,
+        ui: &mut egui::Ui,
+        tab_idx: usize,
+        actions: &mut VeAnalyzeActions,
+    ) {
+        let file_idx = self.tabs[tab_idx].file_index;
+        let Some(file) = self.files.get(file_idx) else {
+            return;
+        };
+        let sorted_channels = sort_channels_by_priority(
+            file.log.channels.len(),
+            |idx| file.log.channels[idx].name(),
+            self.field_normalization,
+            Some(&self.custom_normalizations),
+        );
+        let channel_name = |idx: Option<usize>| {
+            idx.and_then(|i| sorted_channels.iter().find(|(c, _, _)| *c == i))
+                .map(|(_, name, _)| name.clone())
+        };
+        let state = &mut self.tabs[tab_idx].ve_analyze_state;
+
+        let channel_combo = |ui: &mut egui::Ui,
+                             state: &mut VeAnalyzeState,
+                             field: ChannelField,
+                             label: &str,
+                             none_label: Option<&str>| {
+            ui.label(label);
+            let slot = field.slot(state);
+            let selected = channel_name(*slot)
+                .or(none_label.map(str::to_string))
+                .unwrap_or_else(|| "Select...".to_string());
+            egui::ComboBox::from_id_salt(("ve_analyze_channel", label))
+                .selected_text(selected)
+                .width(180.0)
+                .show_ui(ui, |ui| {
+                    if let Some(none_label) = none_label {
+                        if ui.selectable_label(slot.is_none(), none_label).clicked() {
+                            *slot = None;
+                        }
+                    }
+                    for (idx, name, _) in &sorted_channels {
+                        if ui.selectable_label(*slot == Some(*idx), name).clicked() {
+                            *slot = Some(*idx);
+                        }
+                    }
+                });
+            ui.end_row();
+        };
+
+        ui.label(egui::RichText::new("Channels").strong());
+        egui::Grid::new("ve_analyze_channels")
+            .num_columns(2)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                channel_combo(ui, state, ChannelField::Rpm, "RPM (X)", None);
+                channel_combo(ui, state, ChannelField::Load, "Load (Y)", None);
+                channel_combo(ui, state, ChannelField::Afr, "AFR / Lambda", None);
+                channel_combo(ui, state, ChannelField::Target, "Target", Some("Fixed"));
+                if state.target_channel.is_none() {
+                    ui.label("");
+                    ui.add(
+                        egui::DragValue::new(&mut state.fixed_target)
+                            .speed(0.01)
+                            .range(0.1..=30.0),
+                    );
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(8.0);
+        ui.label(egui::RichText::new("Table Breakpoints").strong());
+        ui.label("RPM:");
+        ui.add(egui::TextEdit::multiline(&mut state.rpm_axis).desired_rows(2));
+        ui.label("Load:");
+        ui.add(egui::TextEdit::multiline(&mut state.load_axis).desired_rows(2));
+        ui.label(
+            egui::RichText::new("Current table (optional, pasted with its axes):")
+                .small()
+                .color(egui::Color32::GRAY),
+        );
+        ui.add(
+            egui::TextEdit::multiline(&mut state.base_table)
+                .desired_rows(3)
+                .font(egui::TextStyle::Monospace),
+        );
+        ui.add_enabled_ui(!state.base_table.trim().is_empty(), |ui| {
+            actions.use_base_axes = ui
+                .button("Use Table Axes")
+                .on_hover_text("Take the breakpoints from the pasted table")
+                .clicked();
+        });
+
+        ui.add_space(8.0);
+        ui.label(egui::RichText::new("Filters").strong());
+        egui::Grid::new("ve_analyze_filters")
+            .num_columns(2)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                channel_combo(ui, state, ChannelField::Coolant, "Coolant", Some("None"));
+                let mut warmup = state.filters.min_coolant.is_some();
+                ui.checkbox(&mut warmup, "Min coolant");
+                let mut min_coolant = state.filters.min_coolant.unwrap_or(70.0);
+                ui.add_enabled(warmup, egui::DragValue::new(&mut min_coolant).suffix(" °C"));
+                state.filters.min_coolant = warmup.then_some(min_coolant);
+                ui.end_row();
+
+                channel_combo(
+                    ui,
+                    state,
+                    ChannelField::Transient,
+                    "Transient",
+                    Some("None"),
+                );
+                let mut transients = state.filters.max_transient_rate.is_some();
+                ui.checkbox(&mut transients, "Max rate /s");
+                let mut max_rate = state.filters.max_transient_rate.unwrap_or(50.0);
+                ui.add_enabled(
+                    transients,
+                    egui::DragValue::new(&mut max_rate).range(0.0..=f64::MAX),
+                );
+                state.filters.max_transient_rate = transients.then_some(max_rate);
+                ui.end_row();
+                ui.label("Hold-off");
+                ui.add_enabled(
+                    transients,
+                    egui::DragValue::new(&mut state.filters.transient_holdoff)
+                        .speed(0.05)
+                        .range(0.0..=10.0)
+                        .suffix(" s"),
+                );
+                ui.end_row();
+
+                channel_combo(
+                    ui,
+                    state,
+                    ChannelField::ClosedLoop,
+                    "Closed loop",
+                    Some("None"),
+                );
+                ui.label("");
+                ui.checkbox(
+                    &mut state.filters.exclude_closed_loop,
+                    "Exclude closed loop",
+                );
+                ui.end_row();
+
+                ui.label("Min samples");
+                ui.add(egui::DragValue::new(&mut state.filters.min_samples).range(1..=10_000));
+                ui.end_row();
+            });
+
+        ui.add_space(10.0);
+        let ready = state.rpm_channel.is_some()
+            && state.load_channel.is_some()
+            && state.afr_channel.is_some();
+        ui.add_enabled_ui(ready, |ui| {
+            actions.analyze = ui
+                .add(egui::Button::new("Analyze").min_size(egui::vec2(120.0, 28.0)))
+                .on_disabled_hover_text("Select RPM, load and AFR channels")
+                .clicked();
+        });
+        if let Some(error) = &state.error {
+            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+        }
+    }
+
+    /// Correction grid, filter statistics and output buttons
+    fn render_ve_analyze_result(
+        &mut self,
+        ui: &mut egui::Ui,
+        tab_idx: usize,
+        actions: &mut VeAnalyzeActions,
+    ) {
+        let state = &mut self.tabs[tab_idx].ve_analyze_state;
+        let Some(result) = &state.result else {
+            ui.label(
+                egui::RichText::new(
+                    "Choose channels and the breakpoints of your fuel table, then press Analyze",
+                )
+                .color(egui::Color32::GRAY),
+            );
+            return;
+        };
+
+        let counts = result.counts;
+        ui.label(format!(
+            "{} of {} samples used, {} of {} cells corrected",
+            counts.used,
+            counts.total,
+            result.corrected_cells(),
+            result.x_axis.len() * result.y_axis.len()
+        ));
+        ui.label(
+            egui::RichText::new(format!(
+                "Left out: {} warm-up, {} transient, {} closed loop, {} missing data",
+                counts.warmup, counts.transient, counts.closed_loop, counts.invalid
+            ))
+            .small()
+            .color(egui::Color32::GRAY),
+        );
+
+        ui.add_space(6.0);
+        let has_base = !state.base_table.trim().is_empty();
+        ui.horizontal(|ui| {
+            ui.label("Output:");
+            ui.radio_value(
+                &mut state.output,
+                VeAnalyzeOutput::Corrections,
+                "Multipliers",
+            );
+            ui.add_enabled_ui(has_base, |ui| {
+                ui.radio_value(
+                    &mut state.output,
+                    VeAnalyzeOutput::CorrectedTable,
+                    "Corrected table",
+                )
+                .on_disabled_hover_text("Paste the current table to apply the corrections to it");
+            });
+            ui.add_space(12.0);
+            actions.copy = ui
+                .button("Copy")
+                .on_hover_text("Copy the values as tab-separated rows")
+                .clicked();
+            actions.export = ui.button("Export CSV...").clicked();
+        });
+        if !has_base {
+            state.output = VeAnalyzeOutput::Corrections;
+        }
+
+        let output = match state.output {
+            VeAnalyzeOutput::Corrections => Ok(result.correction_table("VE Correction")),
+            VeAnalyzeOutput::CorrectedTable => {
+                parse_base_table(&state.base_table).and_then(|base| result.corrected_table(&base))
+            }
+        };
+        let table = match output {
+            Ok(table) => table,
+            Err(e) => {
+                ui.colored_label(egui::Color32::from_rgb(255, 100, 100), e);
+                return;
+            }
+        };
+
+        ui.add_space(6.0);
+        let decimals = output_decimals(&table);
+        egui::ScrollArea::both()
+            .id_salt("ve_analyze_result")
+            .show(ui, |ui| {
+                egui::Grid::new("ve_analyze_grid")
+                    .spacing([2.0, 2.0])
+                    .show(ui, |ui| {
+                        ui.label(
+                            egui::RichText::new("Load \\ RPM")
+                                .small()
+                                .color(egui::Color32::GRAY),
+                        );
+                        for x in &result.x_axis {
+                            ui.label(egui::RichText::new(format!("{}", x)).small().strong());
+                        }
+                        ui.end_row();
+
+                        for (y_idx, y) in result.y_axis.iter().enumerate() {
+                            ui.label(egui::RichText::new(format!("{}", y)).small().strong());
+                            for x_idx in 0..result.x_axis.len() {
+                                let cell = result.cells[y_idx][x_idx];
+                                let corrected = result.correction(x_idx, y_idx).is_some();
+                                let error = cell.error_percent().filter(|_| corrected);
+                                let (rect, response) =
+                                    ui.allocate_exact_size(CELL_SIZE, egui::Sense::hover());
+                                ui.painter().rect_filled(rect, 2.0, error_color(error));
+                                ui.painter().text(
+                                    rect.center(),
+                                    egui::Align2::CENTER_CENTER,
+                                    format!("{:.*}", decimals, table.values[y_idx][x_idx]),
+                                    egui::FontId::monospace(11.0),
+                                    if corrected {
+                                        egui::Color32::WHITE
+                                    } else {
+                                        egui::Color32::GRAY
+                                    },
+                                );
+                                response.on_hover_text(match cell.error_percent() {
+                                    Some(error) => format!(
+                                        "{} samples, average error {:+.1}%",
+                                        cell.samples, error
+                                    ),
+                                    None => "No samples".to_string(),
+                                });
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    /// Run the analysis on the tab's log with the current settings
+    fn run_ve_analysis(&mut self, tab_idx: usize) {
+        let file_idx = self.tabs[tab_idx].file_index;
+        let Some(file) = self.files.get(file_idx) else {
+            return;
+        };
+        let state = &self.tabs[tab_idx].ve_analyze_state;
+        let log = &file.log;
+        let channel = |idx: Option<usize>| idx.map(|i| log.get_aligned_channel_data(i));
+
+        let result = (|| {
+            let x_axis = parse_axis("RPM", &state.rpm_axis)?;
+            let y_axis = parse_axis("Load", &state.load_axis)?;
+            let (Some(rpm), Some(load), Some(measured)) = (
+                channel(state.rpm_channel),
+                channel(state.load_channel),
+                channel(state.afr_channel),
+            ) else {
+                return Err("Select RPM, load and AFR channels".to_string());
+            };
+            let target = channel(state.target_channel);
+            let coolant = state
+                .coolant_channel
+                .map(|c| coolant_celsius(log.channels[c].unit(), log.get_aligned_channel_data(c)));
+            let transient = channel(state.transient_channel);
+            let closed_loop = channel(state.closed_loop_channel);
+
+            let data = VeAnalyzeData {
+                times: &log.times,
+                rpm: &rpm,
+                load: &load,
+                measured: &measured,
+                target: match &target {
+                    Some(values) => AfrTarget::Channel(values),
+                    None => AfrTarget::Fixed(state.fixed_target),
+                },
+                coolant: coolant.as_deref(),
+                transient: transient.as_deref(),
+                closed_loop: closed_loop.as_deref(),
+            };
+            VeAnalysis::run(&data, &x_axis, &y_axis, &state.filters)
+        })();
+
+        let state = &mut self.tabs[tab_idx].ve_analyze_state;
+        match result {
+            Ok(analysis) => {
+                state.result = Some(analysis);
+                state.error = None;
+            }
+            Err(e) => state.error = Some(e),
+        }
+    }
+
+    /// The table the user chose to copy or export
+    fn ve_analyze_output_table(&self, tab_idx: usize) -> Result<LookupTable, String> {
+        let state = &self.tabs[tab_idx].ve_analyze_state;
+        let Some(result) = &state.result else {
+            return Err("Run the analysis first".to_string());
+        };
+        match state.output {
+            VeAnalyzeOutput::Corrections => Ok(result.correction_table("VE Correction")),
+            VeAnalyzeOutput::CorrectedTable => {
+                result.corrected_table(&parse_base_table(&state.base_table)?)
+            }
+        }
+    }
+
+    /// Save the output table with its axes as CSV
+    fn export_ve_analyze_table(&mut self, tab_idx: usize) {
+        let table = match self.ve_analyze_output_table(tab_idx) {
+            Ok(table) => table,
+            Err(e) => {
+                self.show_toast_error(&e);
+                return;
+            }
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("{}.csv", table.name))
+            .save_file()
+        else {
+            return;
+        };
+
+        match std::fs::write(&path, table.to_csv()) {
+            Ok(()) => self.show_toast_success("Table exported"),
+            Err(e) => self.show_toast_error(&format!("Failed to export table: {}", e)),
+        }
+    }
+}
+
+/// Parse the pasted current fuel table, which must be 2D
+fn parse_base_table(text: &str) -> Result<LookupTable, String> {
+    let table = LookupTable::from_csv("VE", text)?;
+    if !table.is_2d() {
+        return Err("The pasted table needs an RPM axis row and a load axis column".to_string());
+    }
+    Ok(table)
+}
+
+fn join_axis(axis: &[f64]) -> String {
+    axis.iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Decimals to show: multipliers need more than table values
+fn output_decimals(table: &LookupTable) -> usize {
+    let max = table
+        .values
+        .iter()
+        .flatten()
+        .fold(0.0_f64, |m, v| m.max(v.abs()));
+    if max < 10.0 {
+        3
+    } else {
+        1
+    }
+}
+
+/// Cell color: red for lean, blue for rich, dark gray without a correction
+fn error_color(error: Option<f64>) -> egui::Color32 {
+    let Some(error) = error else {
+        return egui::Color32::from_rgb(40, 40, 40);
+    };
+    let t = (error / FULL_SCALE_ERROR).clamp(-1.0, 1.0) as f32;
+    let neutral = egui::Color32::from_rgb(60, 90, 60);
+    if t >= 0.0 {
+        neutral.lerp_to_gamma(egui::Color32::from_rgb(200, 50, 40), t)
+    } else {
+        neutral.lerp_to_gamma(egui::Color32::from_rgb(40, 80, 210), -t)
+    }
+}
@@ -0,0 +1,308 @@
+//! Vehicle Profiles UI.
+//!
+//! Lets users pick the vehicle profile used by the active file's formulas
+//! and edit the constants of each profile.
+
+use eframe::egui;
+
+use crate::app::UltraLogApp;
+use crate::vehicle_profile::{ProfileConstant, VehicleProfile};
+
+impl UltraLogApp {
+    /// Render the vehicle profile selector of the computed channels manager
+    pub fn render_vehicle_profile_selector(&mut self, ui: &mut egui::Ui) {
+        let file_idx = self.active_tab.map(|tab_idx| self.tabs[tab_idx].file_index);
+
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Vehicle Profile:").strong());
+
+            if let Some(file) = file_idx.and_then(|idx| self.files.get(idx)) {
+                let current = file.vehicle_profile.clone();
+                let current_name = current
+                    .as_deref()
+                    .and_then(|id| self.vehicle_profiles.find(id))
+                    .map_or("None", |p| p.name.as_str())
+                    .to_string();
+                let mut selected = current.clone();
+
+                egui::ComboBox::from_id_salt("file_vehicle_profile")
+                    .selected_text(current_name)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut selected, None, "None");
+                        for profile in &self.vehicle_profiles.profiles {
+                            ui.selectable_value(
+                                &mut selected,
+                                Some(profile.id.clone()),
+                                &profile.name,
+                            );
+                        }
+                    });
+
+                if selected != current {
+                    if let Some(idx) = file_idx {
+                        self.set_file_vehicle_profile(idx, selected);
+                    }
+                }
+            } else {
+                ui.label(egui::RichText::new("No file loaded").color(egui::Color32::GRAY));
+            }
+
+            if ui.small_button("Manage Profiles...").clicked() {
+                self.open_vehicle_profiles();
+            }
+        });
+        ui.label(
+            egui::RichText::new(
+                "Formulas read the profile's constants as $name, e.g. $injector_cc.",
+            )
+            .small()
+            .color(egui::Color32::GRAY),
+        );
+    }
+
+    /// Open the vehicle profiles window on the active file's profile
+    pub fn open_vehicle_profiles(&mut self) {
+        let active = self
+            .active_tab
+            .and_then(|tab_idx| self.files.get(self.tabs[tab_idx].file_index))
+            .and_then(|file| file.vehicle_profile.as_deref())
+            .and_then(|id| self.vehicle_profiles.find(id))
+            .or_else(|| self.vehicle_profiles.profiles.first())
+            .cloned();
+
+        let state = &mut self.profile_editor_state;
+        state.open = true;
+        state.draft = active;
+        state.error = None;
+    }
+
+    /// Render the vehicle profiles window
+    pub fn render_vehicle_profiles(&mut self, ctx: &egui::Context) {
+        if !self.profile_editor_state.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut profile_to_select: Option<VehicleProfile> = None;
+        let mut create_profile = false;
+        let mut duplicate_profile = false;
+        let mut delete_profile = false;
+        let mut should_save = false;
+        let mut default_changed = false;
+
+        egui::Window::new("Vehicle Profiles")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(560.0)
+            .default_height(380.0)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.horizontal_top(|ui| {
+                    // Profile list
+                    ui.vertical(|ui| {
+                        ui.set_width(160.0);
+                        let selected_id = self
+                            .profile_editor_state
+                            .draft
+                            .as_ref()
+                            .map(|p| p.id.clone());
+                        egui::ScrollArea::vertical()
+                            .id_salt("vehicle_profiles_list")
+                            .max_height(260.0)
+                            .show(ui, |ui| {
+                                if self.vehicle_profiles.profiles.is_empty() {
+                                    ui.label(
+                                        egui::RichText::new("No profiles yet")
+                                            .color(egui::Color32::GRAY),
+                                    );
+                                }
+                                for profile in &self.vehicle_profiles.profiles {
+                                    let is_selected =
+                                        selected_id.as_deref() == Some(profile.id.as_str());
+                                    if ui.selectable_label(is_selected, &profile.name).clicked() {
+                                        profile_to_select = Some(profile.clone());
+                                    }
+                                }
+                            });
+                        ui.add_space(6.0);
+                        ui.horizontal(|ui| {
+                            create_profile = ui.small_button("+ New").clicked();
+                            let has_selection = selected_id.is_some();
+                            duplicate_profile = ui
+                                .add_enabled(has_selection, egui::Button::new("Duplicate").small())
+                                .clicked();
+                            delete_profile = ui
+                                .add_enabled(has_selection, egui::Button::new("Delete").small())
+                                .clicked();
+                        });
+                    });
+
+                    ui.separator();
+
+                    // Selected profile
+                    ui.vertical(|ui| {
+                        let default_profile = &mut self.vehicle_profiles.default_profile;
+                        let state = &mut self.profile_editor_state;
+                        let Some(draft) = &mut state.draft else {
+                            ui.label(
+                                egui::RichText::new("Select or create a profile.")
+                                    .color(egui::Color32::GRAY),
+                            );
+                            return;
+                        };
+
+                        ui.horizontal(|ui| {
+                            ui.label("Name:");
+                            ui.text_edit_singleline(&mut draft.name);
+                        });
+                        let mut is_default = default_profile.as_deref() == Some(draft.id.as_str());
+                        if ui
+                            .checkbox(&mut is_default, "Use for newly loaded files")
+                            .changed()
+                        {
+                            *default_profile = is_default.then(|| draft.id.clone());
+                            default_changed = true;
+                        }
+                        ui.add_space(6.0);
+
+                        let mut constant_to_remove: Option<usize> = None;
+                        egui::ScrollArea::vertical()
+                            .id_salt("vehicle_profile_constants")
+                            .max_height(220.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("vehicle_profile_constants_grid")
+                                    .num_columns(4)
+                                    .spacing([6.0, 4.0])
+                                    .show(ui, |ui| {
+                                        ui.label(egui::RichText::new("Name").small().strong());
+                                        ui.label(egui::RichText::new("Value").small().strong());
+                                        ui.label(egui::RichText::new("Unit").small().strong());
+                                        ui.end_row();
+
+                                        for (idx, constant) in
+                                            draft.constants.iter_mut().enumerate()
+                                        {
+                                            ui.horizontal(|ui| {
+                                                ui.label("$");
+                                                ui.add(
+                                                    egui::TextEdit::singleline(&mut constant.name)
+                                                        .desired_width(140.0),
+                                                );
+                                            });
+                                            ui.add(
+                                                egui::DragValue::new(&mut constant.value)
+                                                    .speed(0.1)
+                                                    .max_decimals(4),
+                                            );
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut constant.unit)
+                                                    .desired_width(60.0),
+                                            );
+                                            if ui.small_button("x").clicked() {
+                                                constant_to_remove = Some(idx);
+                                            }
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                        if let Some(idx) = constant_to_remove {
+                            draft.constants.remove(idx);
+                        }
+                        if ui.small_button("+ Add Constant").clicked() {
+                            draft.constants.push(ProfileConstant::new("", 0.0, ""));
+                        }
+
+                        if let Some(error) = &state.error {
+                            ui.add_space(4.0);
+                            ui.label(egui::RichText::new(error).color(egui::Color32::RED));
+                        }
+
+                        ui.add_space(8.0);
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                            if ui.button("Save").clicked() {
+                                should_save = true;
+                            }
+                        });
+                    });
+                });
+            });
+
+        if should_save {
+            self.save_profile_draft();
+        } else if default_changed {
+            self.save_vehicle_profiles();
+        }
+
+        if let Some(profile) = profile_to_select {
+            self.profile_editor_state.draft = Some(profile);
+            self.profile_editor_state.error = None;
+        }
+
+        if create_profile || duplicate_profile {
+            let mut profile = VehicleProfile::new(format!(
+                "Vehicle {}",
+                self.vehicle_profiles.profiles.len() + 1
+            ));
+            if let (true, Some(draft)) = (duplicate_profile, &self.profile_editor_state.draft) {
+                profile.name = format!("{} (copy)", draft.name);
+                profile.constants = draft.constants.clone();
+            }
+            self.vehicle_profiles.profiles.push(profile.clone());
+            self.profile_editor_state.draft = Some(profile);
+            self.profile_editor_state.error = None;
+            self.save_vehicle_profiles();
+        }
+
+        if delete_profile {
+            if let Some(draft) = self.profile_editor_state.draft.take() {
+                self.vehicle_profiles.remove(&draft.id);
+                self.save_vehicle_profiles();
+                for file_idx in 0..self.files.len() {
+                    if self.files[file_idx].vehicle_profile.as_deref() == Some(draft.id.as_str()) {
+                        self.set_file_vehicle_profile(file_idx, None);
+                    }
+                }
+            }
+        }
+
+        if !open {
+            self.profile_editor_state.open = false;
+        }
+    }
+
+    /// Store the edited profile and re-evaluate the files using it
+    fn save_profile_draft(&mut self) {
+        let state = &mut self.profile_editor_state;
+        let Some(draft) = &mut state.draft else {
+            return;
+        };
+        for constant in &mut draft.constants {
+            constant.name = constant.name.trim().trim_start_matches('$').to_string();
+        }
+        if let Err(e) = draft.validate() {
+            state.error = Some(e);
+            return;
+        }
+        state.error = None;
+
+        let draft = draft.clone();
+        match self.vehicle_profiles.find_mut(&draft.id) {
+            Some(profile) => *profile = draft.clone(),
+            None => self.vehicle_profiles.profiles.push(draft.clone()),
+        }
+        self.save_vehicle_profiles();
+
+        for file_idx in 0..self.files.len() {
+            if self.files[file_idx].vehicle_profile.as_deref() == Some(draft.id.as_str()) {
+                self.reevaluate_constant_users(file_idx);
+            }
+        }
+    }
+
+    /// Save the vehicle profiles to disk
+    fn save_vehicle_profiles(&mut self) {
+        if let Err(e) = self.vehicle_profiles.save() {
+            self.show_toast_error(&format!("Failed to save profiles: {}", e));
+        }
+    }
+}
@@ -2,6 +2,14 @@
 //!
 //! This module provides user-configurable unit preferences for displaying
 //! ECU log data in various measurement systems (metric, imperial, etc.).
+//!
+//! It also recognises the unit strings ECUs log with ([`Unit::parse`]) so
+//! channel values can be converted to the canonical unit of their dimension
+//! before computed channel formulas read them.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::{Div, Mul};
 
 /// Temperature unit preference
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
@@ -277,6 +285,330 @@ impl UnitPreferences {
     }
 }
 
+/// Physical dimension as powers of length, mass, time and temperature
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Dimension {
+    pub length: i8,
+    pub mass: i8,
+    pub time: i8,
+    pub temperature: i8,
+}
+
+impl Dimension {
+    pub const NONE: Self = Self::new(0, 0, 0, 0);
+    pub const LENGTH: Self = Self::new(1, 0, 0, 0);
+    pub const MASS: Self = Self::new(0, 1, 0, 0);
+    pub const TIME: Self = Self::new(0, 0, 1, 0);
+    pub const TEMPERATURE: Self = Self::new(0, 0, 0, 1);
+    pub const FREQUENCY: Self = Self::new(0, 0, -1, 0);
+    pub const SPEED: Self = Self::new(1, 0, -1, 0);
+    pub const ACCELERATION: Self = Self::new(1, 0, -2, 0);
+    pub const PRESSURE: Self = Self::new(-1, 1, -2, 0);
+    pub const VOLUME: Self = Self::new(3, 0, 0, 0);
+    pub const FLOW: Self = Self::new(3, 0, -1, 0);
+    /// Volume per distance, as in L/100km
+    pub const FUEL_ECONOMY: Self = Self::new(2, 0, 0, 0);
+
+    pub const fn new(length: i8, mass: i8, time: i8, temperature: i8) -> Self {
+        Self {
+            length,
+            mass,
+            time,
+            temperature,
+        }
+    }
+
+    pub fn is_dimensionless(self) -> bool {
+        self == Self::NONE
+    }
+
+    /// Raise to an integer power
+    pub fn powi(self, n: i8) -> Self {
+        Self::new(
+            self.length * n,
+            self.mass * n,
+            self.time * n,
+            self.temperature * n,
+        )
+    }
+
+    /// Square root, if every exponent is even
+    pub fn sqrt(self) -> Option<Self> {
+        let exponents = [self.length, self.mass, self.time, self.temperature];
+        exponents.iter().all(|e| e % 2 == 0).then(|| {
+            Self::new(
+                self.length / 2,
+                self.mass / 2,
+                self.time / 2,
+                self.temperature / 2,
+            )
+        })
+    }
+}
+
+impl Mul for Dimension {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.length + rhs.length,
+            self.mass + rhs.mass,
+            self.time + rhs.time,
+            self.temperature + rhs.temperature,
+        )
+    }
+}
+
+impl Div for Dimension {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        self * rhs.powi(-1)
+    }
+}
+
+impl fmt::Display for Dimension {
+    /// The quantity name, e.g. "pressure", or base dimensions like "L²·T⁻¹"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match *self {
+            Self::NONE => "dimensionless",
+            Self::LENGTH => "distance",
+            Self::MASS => "mass",
+            Self::TIME => "time",
+            Self::TEMPERATURE => "temperature",
+            Self::FREQUENCY => "frequency",
+            Self::SPEED => "speed",
+            Self::ACCELERATION => "acceleration",
+            Self::PRESSURE => "pressure",
+            Self::VOLUME => "volume",
+            Self::FLOW => "flow",
+            Self::FUEL_ECONOMY => "fuel economy",
+            _ => {
+                let parts: Vec<String> = [
+                    ("L", self.length),
+                    ("M", self.mass),
+                    ("T", self.time),
+                    ("Θ", self.temperature),
+                ]
+                .iter()
+                .filter(|(_, e)| *e != 0)
+                .map(|(symbol, e)| format!("{}{}", symbol, superscript(*e)))
+                .collect();
+                return write!(f, "{}", parts.join("·"));
+            }
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn superscript(exponent: i8) -> String {
+    if exponent == 1 {
+        return String::new();
+    }
+    exponent
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            '-' => '⁻',
+            '0' => '⁰',
+            '1' => '¹',
+            '2' => '²',
+            '3' => '³',
+            '4' => '⁴',
+            '5' => '⁵',
+            '6' => '⁶',
+            '7' => '⁷',
+            '8' => '⁸',
+            _ => '⁹',
+        })
+        .collect()
+}
+
+/// A unit of measurement channels are logged in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Unit {
+    pub symbol: &'static str,
+    pub dimension: Dimension,
+    /// Size of the unit in SI base units (K, kg, m, s)
+    pub scale: f64,
+    /// SI value of zero in this unit (temperatures only)
+    offset: f64,
+}
+
+impl Unit {
+    const fn new(symbol: &'static str, dimension: Dimension, scale: f64, offset: f64) -> Self {
+        Self {
+            symbol,
+            dimension,
+            scale,
+            offset,
+        }
+    }
+
+    /// Look up a unit by any of its usual spellings (case-insensitive)
+    pub fn parse(text: &str) -> Option<Unit> {
+        let text = text.trim().to_lowercase();
+        UNIT_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == text)
+            .map(|(_, unit)| *unit)
+    }
+
+    /// Find the unit with the given dimension and size, preferring the
+    /// canonical one
+    pub fn find(dimension: Dimension, scale: f64) -> Option<Unit> {
+        let matches = |u: &&Unit| u.dimension == dimension && same_scale(u.scale, scale);
+        CANONICAL_UNITS
+            .iter()
+            .find(matches)
+            .or_else(|| {
+                UNIT_ALIASES
+                    .iter()
+                    .map(|(_, unit)| unit)
+                    .filter(|u| u.offset == 0.0)
+                    .find(matches)
+            })
+            .copied()
+    }
+
+    /// The unit formulas read values of this unit in: one fixed unit per
+    /// dimension, or the unit itself
+    pub fn canonical(&self) -> Unit {
+        CANONICAL_UNITS
+            .iter()
+            .find(|u| u.dimension == self.dimension)
+            .copied()
+            .unwrap_or(*self)
+    }
+
+    /// Whether this is the canonical unit of a dimension, which formula
+    /// results are labeled with
+    pub fn has_preference(&self) -> bool {
+        CANONICAL_UNITS.contains(self)
+    }
+
+    /// Convert a value of this unit to `target`, which has the same dimension
+    pub fn convert(&self, value: f64, target: &Unit) -> f64 {
+        (value * self.scale + self.offset - target.offset) / target.scale
+    }
+}
+
+/// Whether two unit sizes are equal up to rounding
+pub fn same_scale(a: f64, b: f64) -> bool {
+    ((a - b) / a).abs() < 1e-9
+}
+
+/// Values logged in `unit`, converted to the canonical unit of its dimension
+pub fn to_canonical<'a>(unit: &str, values: Cow<'a, [f64]>) -> Cow<'a, [f64]> {
+    match Unit::parse(unit) {
+        Some(unit) if unit.canonical() != unit => {
+            let canonical = unit.canonical();
+            Cow::Owned(
+                values
+                    .iter()
+                    .map(|&v| unit.convert(v, &canonical))
+                    .collect(),
+            )
+        }
+        _ => values,
+    }
+}
+
+const KELVIN: Unit = Unit::new("K", Dimension::TEMPERATURE, 1.0, 0.0);
+const CELSIUS: Unit = Unit::new("°C", Dimension::TEMPERATURE, 1.0, 273.15);
+const FAHRENHEIT: Unit = Unit::new(
+    "°F",
+    Dimension::TEMPERATURE,
+    5.0 / 9.0,
+    273.15 - 32.0 * 5.0 / 9.0,
+);
+const KPA: Unit = Unit::new("kPa", Dimension::PRESSURE, 1000.0, 0.0);
+const KMH: Unit = Unit::new("km/h", Dimension::SPEED, 1.0 / 3.6, 0.0);
+const KM: Unit = Unit::new("km", Dimension::LENGTH, 1000.0, 0.0);
+const L_PER_100KM: Unit = Unit::new("L/100km", Dimension::FUEL_ECONOMY, 1e-8, 0.0);
+const LITRE: Unit = Unit::new("L", Dimension::VOLUME, 1e-3, 0.0);
+const CC_PER_MIN: Unit = Unit::new("cc/min", Dimension::FLOW, 1e-6 / 60.0, 0.0);
+const M_PER_S2: Unit = Unit::new("m/s²", Dimension::ACCELERATION, 1.0, 0.0);
+
+/// The units formulas read values in, one per dimension. Temperatures stay
+/// in °C, the unit saved formulas were written against.
+const CANONICAL_UNITS: [Unit; 8] = [
+    CELSIUS,
+    KPA,
+    KMH,
+    KM,
+    L_PER_100KM,
+    LITRE,
+    CC_PER_MIN,
+    M_PER_S2,
+];
+
+/// Lowercase spellings of known units
+const UNIT_ALIASES: &[(&str, Unit)] = &[
+    ("k", KELVIN),
+    ("kelvin", KELVIN),
+    ("°c", CELSIUS),
+    ("℃", CELSIUS),
+    ("c", CELSIUS),
+    ("degc", CELSIUS),
+    ("deg c", CELSIUS),
+    ("°f", FAHRENHEIT),
+    ("℉", FAHRENHEIT),
+    ("f", FAHRENHEIT),
+    ("degf", FAHRENHEIT),
+    ("deg f", FAHRENHEIT),
+    ("kpa", KPA),
+    ("pa", Unit::new("Pa", Dimension::PRESSURE, 1.0, 0.0)),
+    ("hpa", Unit::new("hPa", Dimension::PRESSURE, 100.0, 0.0)),
+    ("mbar", Unit::new("mbar", Dimension::PRESSURE, 100.0, 0.0)),
+    ("bar", Unit::new("bar", Dimension::PRESSURE, 1e5, 0.0)),
+    ("psi", Unit::new("psi", Dimension::PRESSURE, 6894.757, 0.0)),
+    (
+        "inhg",
+        Unit::new("inHg", Dimension::PRESSURE, 3386.389, 0.0),
+    ),
+    ("km/h", KMH),
+    ("kph", KMH),
+    ("kmh", KMH),
+    ("mph", Unit::new("mph", Dimension::SPEED, 0.44704, 0.0)),
+    ("m/s", Unit::new("m/s", Dimension::SPEED, 1.0, 0.0)),
+    ("km", KM),
+    ("m", Unit::new("m", Dimension::LENGTH, 1.0, 0.0)),
+    ("mi", Unit::new("mi", Dimension::LENGTH, 1609.344, 0.0)),
+    ("l/100km", L_PER_100KM),
+    ("l", LITRE),
+    ("cc", Unit::new("cc", Dimension::VOLUME, 1e-6, 0.0)),
+    ("ml", Unit::new("mL", Dimension::VOLUME, 1e-6, 0.0)),
+    (
+        "gal",
+        Unit::new("gal", Dimension::VOLUME, 3.785_411_784e-3, 0.0),
+    ),
+    ("cc/min", CC_PER_MIN),
+    ("ml/min", CC_PER_MIN),
+    ("l/h", Unit::new("L/h", Dimension::FLOW, 1e-3 / 3600.0, 0.0)),
+    ("m/s²", M_PER_S2),
+    ("m/s2", M_PER_S2),
+    ("m/s^2", M_PER_S2),
+    ("g", Unit::new("g", Dimension::ACCELERATION, 9.80665, 0.0)),
+    ("s", Unit::new("s", Dimension::TIME, 1.0, 0.0)),
+    ("sec", Unit::new("s", Dimension::TIME, 1.0, 0.0)),
+    ("ms", Unit::new("ms", Dimension::TIME, 1e-3, 0.0)),
+    ("μs", Unit::new("μs", Dimension::TIME, 1e-6, 0.0)),
+    ("us", Unit::new("μs", Dimension::TIME, 1e-6, 0.0)),
+    ("hz", Unit::new("Hz", Dimension::FREQUENCY, 1.0, 0.0)),
+    (
+        "rpm",
+        Unit::new("RPM", Dimension::FREQUENCY, 1.0 / 60.0, 0.0),
+    ),
+    ("kg", Unit::new("kg", Dimension::MASS, 1.0, 0.0)),
+    ("%", Unit::new("%", Dimension::NONE, 1.0, 0.0)),
+    ("°", Unit::new("°", Dimension::NONE, 1.0, 0.0)),
+    ("deg", Unit::new("°", Dimension::NONE, 1.0, 0.0)),
+    ("λ", Unit::new("λ", Dimension::NONE, 1.0, 0.0)),
+    ("lambda", Unit::new("λ", Dimension::NONE, 1.0, 0.0)),
+    ("afr", Unit::new("AFR", Dimension::NONE, 1.0, 0.0)),
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -598,6 +930,82 @@ mod tests {
         assert_eq!(unit, "AFR");
     }
 
+    #[test]
+    fn test_unit_preferences_keep_other_spellings() {
+        // Only the units the preferences are expressed against are converted
+        let prefs = UnitPreferences::default();
+        assert_eq!(prefs.convert_value(14.5, "psi"), (14.5, "psi"));
+        assert_eq!(prefs.convert_value(100.0, "deg C"), (100.0, "deg C"));
+    }
+
+    // ============================================
+    // Dimension and Unit Tests
+    // ============================================
+
+    #[test]
+    fn test_dimension_arithmetic() {
+        assert_eq!(Dimension::LENGTH / Dimension::TIME, Dimension::SPEED);
+        assert_eq!(Dimension::SPEED / Dimension::TIME, Dimension::ACCELERATION);
+        assert_eq!(Dimension::LENGTH.powi(3), Dimension::VOLUME);
+        assert_eq!(Dimension::FUEL_ECONOMY.sqrt(), Some(Dimension::LENGTH));
+        assert_eq!(Dimension::SPEED.sqrt(), None);
+        assert_eq!(Dimension::PRESSURE.to_string(), "pressure");
+        assert_eq!(
+            (Dimension::FLOW * Dimension::TIME.powi(2)).to_string(),
+            "L³·T"
+        );
+    }
+
+    #[test]
+    fn test_unit_parse_and_canonical() {
+        let celsius = Unit::parse(" Deg C ").unwrap();
+        assert_eq!(celsius.symbol, "°C");
+        assert_eq!(celsius.canonical(), celsius);
+        let kelvin = Unit::parse("K").unwrap();
+        assert!((kelvin.convert(293.15, &kelvin.canonical()) - 20.0).abs() < 1e-9);
+
+        let fahrenheit = Unit::parse("°F").unwrap();
+        assert!((fahrenheit.convert(212.0, &celsius) - 100.0).abs() < 1e-9);
+
+        // Units without a preference are their own canonical unit
+        let rpm = Unit::parse("RPM").unwrap();
+        assert_eq!(rpm.canonical(), rpm);
+        assert!(!rpm.has_preference());
+        assert!(celsius.canonical().has_preference());
+        assert!(Unit::parse("V").is_none());
+    }
+
+    #[test]
+    fn test_unit_find() {
+        assert_eq!(
+            Unit::find(Dimension::PRESSURE, 1000.0).unwrap().symbol,
+            "kPa"
+        );
+        assert_eq!(Unit::find(Dimension::TIME, 1e-3).unwrap().symbol, "ms");
+        assert_eq!(
+            Unit::find(Dimension::TEMPERATURE, 1.0).unwrap().symbol,
+            "°C"
+        );
+        assert!(Unit::find(Dimension::SPEED, 1000.0).is_none());
+    }
+
+    #[test]
+    fn test_to_canonical() {
+        let values = to_canonical("mph", Cow::Owned(vec![10.0, f64::NAN]));
+        assert!((values[0] - 16.09344).abs() < 1e-9);
+        assert!(values[1].is_nan());
+
+        let raw = [1.0, 2.0];
+        assert!(matches!(
+            to_canonical("kPa", Cow::Borrowed(&raw)),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            to_canonical("RPM", Cow::Borrowed(&raw)),
+            Cow::Borrowed(_)
+        ));
+    }
+
     #[test]
     fn test_unit_preferences_all_conversions() {
         let prefs = UnitPreferences {
@@ -0,0 +1,461 @@
+//! VE / fuel table correction from air-fuel ratio error
+//!
+//! Log samples are binned into the cells of an ECU fuel table by RPM and
+//! load, and the ratio of measured to target AFR (or lambda) is averaged per
+//! cell. A ratio above 1 means the engine ran lean there and the cell needs
+//! that much more fuel. Samples taken during transients, warm-up or
+//! closed-loop fuelling are left out, since their error says little about
+//! the base table.
+
+use std::borrow::Cow;
+
+use crate::lookup_table::{check_axis, nearest_breakpoint, LookupTable};
+use crate::units::{Dimension, Unit};
+
+/// What the measured AFR or lambda is compared against
+#[derive(Clone, Copy, Debug)]
+pub enum AfrTarget<'a> {
+    /// The same target everywhere
+    Fixed(f64),
+    /// A target channel, one value per record
+    Channel(&'a [f64]),
+}
+
+/// Channel data for an analysis, each slice holding one value per record
+#[derive(Clone, Copy, Debug)]
+pub struct VeAnalyzeData<'a> {
+    /// Record times in seconds
+    pub times: &'a [f64],
+    /// Table X axis input
+    pub rpm: &'a [f64],
+    /// Table Y axis input (MAP, TPS, ...)
+    pub load: &'a [f64],
+    /// Measured AFR or lambda
+    pub measured: &'a [f64],
+    pub target: AfrTarget<'a>,
+    /// Coolant temperature in °C, for the warm-up filter
+    pub coolant: Option<&'a [f64]>,
+    /// Channel whose rate of change marks transients (usually TPS)
+    pub transient: Option<&'a [f64]>,
+    /// Non-zero while the ECU runs closed loop
+    pub closed_loop: Option<&'a [f64]>,
+}
+
+/// Which samples to leave out of an analysis
+#[derive(Clone, Debug, PartialEq)]
+pub struct VeAnalyzeFilters {
+    /// Leave out samples below this coolant temperature (°C)
+    pub min_coolant: Option<f64>,
+    /// Leave out samples where the transient channel changes faster than
+    /// this many units per second
+    pub max_transient_rate: Option<f64>,
+    /// Seconds after a transient during which samples are still left out
+    pub transient_holdoff: f64,
+    /// Leave out samples taken in closed loop
+    pub exclude_closed_loop: bool,
+    /// Samples a cell needs before it gets a correction
+    pub min_samples: usize,
+}
+
+impl Default for VeAnalyzeFilters {
+    fn default() -> Self {
+        Self {
+            min_coolant: Some(70.0),
+            max_transient_rate: Some(50.0),
+            transient_holdoff: 0.5,
+            exclude_closed_loop: true,
+            min_samples: 10,
+        }
+    }
+}
+
+/// Coolant temperatures logged in `unit` converted to °C; values in an
+/// unknown unit are taken to be °C already
+pub fn coolant_celsius<'a>(unit: &str, values: Cow<'a, [f64]>) -> Cow<'a, [f64]> {
+    let (Some(unit), Some(celsius)) = (Unit::parse(unit), Unit::parse("°C")) else {
+        return values;
+    };
+    if unit.dimension != Dimension::TEMPERATURE || unit == celsius {
+        return values;
+    }
+    Cow::Owned(values.iter().map(|&v| unit.convert(v, &celsius)).collect())
+}
+
+/// Samples that fell into one table cell
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CellStats {
+    pub samples: usize,
+    ratio_sum: f64,
+}
+
+impl CellStats {
+    fn add(&mut self, ratio: f64) {
+        self.samples += 1;
+        self.ratio_sum += ratio;
+    }
+
+    /// Average ratio of measured to target
+    pub fn mean_ratio(&self) -> Option<f64> {
+        (self.samples > 0).then(|| self.ratio_sum / self.samples as f64)
+    }
+
+    /// Average error in percent; positive is lean
+    pub fn error_percent(&self) -> Option<f64> {
+        self.mean_ratio().map(|r| (r - 1.0) * 100.0)
+    }
+}
+
+/// How many samples each filter left out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FilterCounts {
+    pub total: usize,
+    /// Missing data or a non-positive AFR or target
+    pub invalid: usize,
+    pub warmup: usize,
+    pub transient: usize,
+    pub closed_loop: usize,
+    /// Samples binned into the table
+    pub used: usize,
+}
+
+/// AFR error averaged per cell of a fuel table
+#[derive(Clone, Debug, PartialEq)]
+pub struct VeAnalysis {
+    /// RPM breakpoints
+    pub x_axis: Vec<f64>,
+    /// Load breakpoints
+    pub y_axis: Vec<f64>,
+    /// One row per `y_axis` entry, each with one cell per `x_axis` entry
+    pub cells: Vec<Vec<CellStats>>,
+    pub counts: FilterCounts,
+    /// Samples a cell needs before it gets a correction
+    pub min_samples: usize,
+}
+
+impl VeAnalysis {
+    /// Bin the samples that pass `filters` into the cells of a table with
+    /// the given breakpoints, each sample going to its nearest cell
+    pub fn run(
+        data: &VeAnalyzeData,
+        x_axis: &[f64],
+        y_axis: &[f64],
+        filters: &VeAnalyzeFilters,
+    ) -> Result<Self, String> {
+        check_axis("RPM", x_axis)?;
+        check_axis("Load", y_axis)?;
+
+        let len = data.times.len();
+        let target_len = match data.target {
+            AfrTarget::Fixed(_) => len,
+            AfrTarget::Channel(values) => values.len(),
+        };
+        let optional_lens = [data.coolant, data.transient, data.closed_loop]
+            .into_iter()
+            .flatten()
+            .map(<[f64]>::len);
+        if [
+            data.rpm.len(),
+            data.load.len(),
+            data.measured.len(),
+            target_len,
+        ]
+        .into_iter()
+        .chain(optional_lens)
+        .any(|l| l != len)
+        {
+            return Err("Channels have different numbers of records".to_string());
+        }
+
+        let transients = match (data.transient, filters.max_transient_rate) {
+            (Some(values), Some(max_rate)) => {
+                transient_mask(data.times, values, max_rate, filters.transient_holdoff)
+            }
+            _ => vec![false; len],
+        };
+
+        let mut cells = vec![vec![CellStats::default(); x_axis.len()]; y_axis.len()];
+        let mut counts = FilterCounts {
+            total: len,
+            ..FilterCounts::default()
+        };
+
+        for i in 0..len {
+            let (rpm, load, measured) = (data.rpm[i], data.load[i], data.measured[i]);
+            let target = match data.target {
+                AfrTarget::Fixed(target) => target,
+                AfrTarget::Channel(values) => values[i],
+            };
+            let invalid = |v: f64| v.is_nan() || v <= 0.0;
+            if rpm.is_nan() || load.is_nan() || invalid(measured) || invalid(target) {
+                counts.invalid += 1;
+                continue;
+            }
+            if let (Some(coolant), Some(min)) = (data.coolant, filters.min_coolant) {
+                if coolant[i].is_nan() || coolant[i] < min {
+                    counts.warmup += 1;
+                    continue;
+                }
+            }
+            if transients[i] {
+                counts.transient += 1;
+                continue;
+            }
+            if let Some(closed_loop) = data.closed_loop.filter(|_| filters.exclude_closed_loop) {
+                if closed_loop[i] != 0.0 && !closed_loop[i].is_nan() {
+                    counts.closed_loop += 1;
+                    continue;
+                }
+            }
+
+            cells[nearest_breakpoint(y_axis, load)][nearest_breakpoint(x_axis, rpm)]
+                .add(measured / target);
+            counts.used += 1;
+        }
+
+        Ok(Self {
+            x_axis: x_axis.to_vec(),
+            y_axis: y_axis.to_vec(),
+            cells,
+            counts,
+            min_samples: filters.min_samples.max(1),
+        })
+    }
+
+    /// Fuel multiplier for a cell, if it has enough samples
+    pub fn correction(&self, x: usize, y: usize) -> Option<f64> {
+        self.cells
+            .get(y)
+            .and_then(|row| row.get(x))
+            .filter(|cell| cell.samples >= self.min_samples)
+            .and_then(CellStats::mean_ratio)
+    }
+
+    /// Number of cells with enough samples for a correction
+    pub fn corrected_cells(&self) -> usize {
+        self.cells
+            .iter()
+            .flatten()
+            .filter(|cell| cell.samples >= self.min_samples)
+            .count()
+    }
+
+    /// Table of fuel multipliers, 1.0 where a cell lacks samples
+    pub fn correction_table(&self, name: &str) -> LookupTable {
+        let values = (0..self.y_axis.len())
+            .map(|y| {
+                (0..self.x_axis.len())
+                    .map(|x| self.correction(x, y).unwrap_or(1.0))
+                    .collect()
+            })
+            .collect();
+        LookupTable::new_2d(
+            name.to_string(),
+            self.x_axis.clone(),
+            self.y_axis.clone(),
+            values,
+        )
+    }
+
+    /// The current fuel table with the corrections applied. Its axes must
+    /// match the analysis breakpoints.
+    pub fn corrected_table(&self, base: &LookupTable) -> Result<LookupTable, String> {
+        let same_axis = |a: &[f64], b: &[f64]| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-6)
+        };
+        if !same_axis(&base.x_axis, &self.x_axis) || !same_axis(&base.y_axis, &self.y_axis) {
+            return Err(format!(
+                "Table is {} but the analysis uses {} x {} breakpoints",
+                base.size_label(),
+                self.x_axis.len(),
+                self.y_axis.len()
+            ));
+        }
+
+        let correction = self.correction_table(&base.name);
+        let values = base
+            .values
+            .iter()
+            .zip(&correction.values)
+            .map(|(row, factors)| row.iter().zip(factors).map(|(v, f)| v * f).collect())
+            .collect();
+        Ok(LookupTable {
+            values,
+            ..base.clone()
+        })
+    }
+}
+
+/// Table values as tab-separated rows, the layout tuning software accepts
+/// when pasting into a selected table
+pub fn values_to_tsv(table: &LookupTable, decimals: usize) -> String {
+    table
+        .values
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|v| format!("{:.*}", decimals, v))
+                .collect::<Vec<_>>()
+                .join("\t")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Mark records where `values` changes faster than `max_rate` per second,
+/// and those up to `holdoff` seconds after
+fn transient_mask(times: &[f64], values: &[f64], max_rate: f64, holdoff: f64) -> Vec<bool> {
+    let mut mask = vec![false; times.len()];
+    let mut previous: Option<(f64, f64)> = None;
+    let mut last_transient: Option<f64> = None;
+
+    for (i, (&t, &v)) in times.iter().zip(values).enumerate() {
+        if !v.is_nan() {
+            if let Some((pt, pv)) = previous {
+                if t > pt && ((v - pv) / (t - pt)).abs() > max_rate {
+                    last_transient = Some(t);
+                }
+            }
+            previous = Some((t, v));
+        }
+        mask[i] = last_transient.is_some_and(|lt| t - lt <= holdoff);
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(n: usize) -> Vec<f64> {
+        (0..n).map(|i| i as f64 * 0.1).collect()
+    }
+
+    #[test]
+    fn test_bins_to_nearest_cell() {
+        let times = times(4);
+        let rpm = [1000.0, 1400.0, 1600.0, 9000.0];
+        let load = [50.0, 50.0, 100.0, 100.0];
+        let afr = [14.7, 16.17, 13.23, 14.7];
+        let data = VeAnalyzeData {
+            times: &times,
+            rpm: &rpm,
+            load: &load,
+            measured: &afr,
+            target: AfrTarget::Fixed(14.7),
+            coolant: None,
+            transient: None,
+            closed_loop: None,
+        };
+        let filters = VeAnalyzeFilters {
+            min_samples: 1,
+            ..VeAnalyzeFilters::default()
+        };
+        let analysis = VeAnalysis::run(&data, &[1000.0, 2000.0], &[50.0, 100.0], &filters).unwrap();
+
+        assert_eq!(analysis.cells[0][0].samples, 2);
+        assert!((analysis.correction(0, 0).unwrap() - 1.05).abs() < 1e-9);
+        // 1600 is nearer 2000; 9000 clamps to the last column
+        assert_eq!(analysis.cells[1][1].samples, 2);
+        assert!((analysis.cells[1][1].error_percent().unwrap() + 5.0).abs() < 1e-9);
+        assert_eq!(analysis.correction(1, 0), None);
+        assert_eq!(analysis.counts.used, 4);
+    }
+
+    #[test]
+    fn test_filters() {
+        let times = times(8);
+        let rpm = [2000.0; 8];
+        let load = [80.0; 8];
+        let afr = [12.0, 13.0, 14.0, 15.0, 15.0, 15.0, 15.0, f64::NAN];
+        let target = [13.0; 8];
+        let coolant = [40.0, 90.0, 90.0, 90.0, 90.0, 90.0, 90.0, 90.0];
+        // TPS snaps open at 0.3 s
+        let tps = [10.0, 10.0, 10.0, 60.0, 60.0, 60.0, 60.0, 60.0];
+        let closed_loop = [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let data = VeAnalyzeData {
+            times: &times,
+            rpm: &rpm,
+            load: &load,
+            measured: &afr,
+            target: AfrTarget::Channel(&target),
+            coolant: Some(&coolant),
+            transient: Some(&tps),
+            closed_loop: Some(&closed_loop),
+        };
+        let filters = VeAnalyzeFilters {
+            min_coolant: Some(70.0),
+            max_transient_rate: Some(100.0),
+            transient_holdoff: 0.15,
+            exclude_closed_loop: true,
+            min_samples: 1,
+        };
+        let analysis = VeAnalysis::run(&data, &[2000.0], &[80.0], &filters).unwrap();
+
+        assert_eq!(
+            analysis.counts,
+            FilterCounts {
+                total: 8,
+                invalid: 1,
+                warmup: 1,
+                transient: 2,
+                closed_loop: 1,
+                used: 3,
+            }
+        );
+        // Records 1, 2 and 5: (13 + 14 + 15) / 3 / 13
+        let expected = 14.0 / 13.0;
+        assert!((analysis.correction(0, 0).unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_correction_tables() {
+        let times = times(3);
+        let data = VeAnalyzeData {
+            times: &times,
+            rpm: &[1000.0, 1000.0, 1000.0],
+            load: &[50.0, 50.0, 50.0],
+            measured: &[1.1, 1.1, 1.1],
+            target: AfrTarget::Fixed(1.0),
+            coolant: None,
+            transient: None,
+            closed_loop: None,
+        };
+        let filters = VeAnalyzeFilters {
+            min_samples: 3,
+            ..VeAnalyzeFilters::default()
+        };
+        let analysis = VeAnalysis::run(&data, &[1000.0, 2000.0], &[50.0], &filters).unwrap();
+        assert_eq!(analysis.corrected_cells(), 1);
+
+        let factors = analysis.correction_table("Correction");
+        assert_eq!(values_to_tsv(&factors, 3), "1.100\t1.000");
+
+        let base = LookupTable::new_2d(
+            "VE".to_string(),
+            vec![1000.0, 2000.0],
+            vec![50.0],
+            vec![vec![60.0, 70.0]],
+        );
+        let corrected = analysis.corrected_table(&base).unwrap();
+        assert!((corrected.values[0][0] - 66.0).abs() < 1e-9);
+        assert_eq!(corrected.values[0][1], 70.0);
+
+        let mismatched = LookupTable::new_2d(
+            "VE".to_string(),
+            vec![1000.0, 3000.0],
+            vec![50.0],
+            vec![vec![60.0, 70.0]],
+        );
+        assert!(analysis.corrected_table(&mismatched).is_err());
+    }
+
+    #[test]
+    fn test_coolant_celsius() {
+        let kelvin = coolant_celsius("K", Cow::Owned(vec![343.15]));
+        assert!((kelvin[0] - 70.0).abs() < 1e-9);
+        let fahrenheit = coolant_celsius("°F", Cow::Owned(vec![212.0]));
+        assert!((fahrenheit[0] - 100.0).abs() < 1e-9);
+        assert_eq!(coolant_celsius("°C", Cow::Owned(vec![90.0]))[0], 90.0);
+        assert_eq!(coolant_celsius("", Cow::Owned(vec![90.0]))[0], 90.0);
+    }
+}
@@ -0,0 +1,262 @@
+//! Vehicle profiles for computed channel formulas
+//!
+//! A profile is a named set of per-car constants (injector size, displacement,
+//! vehicle mass, ...) that formulas reference as `$name`, e.g.
+//! `IPW * $injector_cc / 60000`. Each loaded file uses one profile, and the
+//! profiles are stored next to the computed channel library.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::computed::ComputedChannelLibrary;
+
+/// A named constant referenced from formulas as `$name`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProfileConstant {
+    /// Name used after `$` in formulas
+    pub name: String,
+    pub value: f64,
+    /// Unit for user reference
+    #[serde(default)]
+    pub unit: String,
+}
+
+impl ProfileConstant {
+    pub fn new(name: &str, value: f64, unit: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value,
+            unit: unit.to_string(),
+        }
+    }
+}
+
+/// A named set of constants describing one vehicle
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VehicleProfile {
+    /// Unique identifier (UUID)
+    pub id: String,
+    /// Display name
+    pub name: String,
+    #[serde(default)]
+    pub constants: Vec<ProfileConstant>,
+}
+
+impl VehicleProfile {
+    /// Create a profile pre-filled with commonly used constants
+    pub fn new(name: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            constants: vec![
+                ProfileConstant::new("injector_cc", 550.0, "cc/min"),
+                ProfileConstant::new("cylinders", 4.0, ""),
+                ProfileConstant::new("displacement_l", 2.0, "L"),
+                ProfileConstant::new("vehicle_mass_kg", 1300.0, "kg"),
+                ProfileConstant::new("tyre_circumference_m", 1.95, "m"),
+                ProfileConstant::new("final_drive", 4.1, ""),
+                ProfileConstant::new("stoich_afr", 14.7, ""),
+            ],
+        }
+    }
+
+    /// Find a constant by name (case-insensitive, with or without `$`)
+    pub fn constant(&self, name: &str) -> Option<&ProfileConstant> {
+        let name = name.trim_start_matches('$');
+        self.constants
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Check that constant names are usable in formulas and unique
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Profile name cannot be empty".to_string());
+        }
+        for (i, constant) in self.constants.iter().enumerate() {
+            if !is_constant_name(&constant.name) {
+                return Err(format!(
+                    "'{}' is not a valid constant name (letters, digits and _, not starting with a digit)",
+                    constant.name
+                ));
+            }
+            if !constant.value.is_finite() {
+                return Err(format!("${} must be a number", constant.name));
+            }
+            if self.constants[..i]
+                .iter()
+                .any(|c| c.name.eq_ignore_ascii_case(&constant.name))
+            {
+                return Err(format!("${} is defined twice", constant.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check whether `name` can follow `$` in a formula
+pub fn is_constant_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// All vehicle profiles, persisted in the config directory
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VehicleProfiles {
+    /// File format version (for future migrations)
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub profiles: Vec<VehicleProfile>,
+    /// Profile assigned to newly loaded files
+    #[serde(default)]
+    pub default_profile: Option<String>,
+}
+
+impl VehicleProfiles {
+    /// Current file format version
+    pub const CURRENT_VERSION: u32 = 1;
+
+    /// Create an empty set of profiles
+    pub fn new() -> Self {
+        Self {
+            version: Self::CURRENT_VERSION,
+            profiles: Vec::new(),
+            default_profile: None,
+        }
+    }
+
+    /// Profiles of a fresh install: a "Default" profile with the common
+    /// constants, assigned to newly loaded files
+    pub fn with_default() -> Self {
+        let profile = VehicleProfile::new("Default".to_string());
+        Self {
+            default_profile: Some(profile.id.clone()),
+            profiles: vec![profile],
+            ..Self::new()
+        }
+    }
+
+    /// Find a profile by ID
+    pub fn find(&self, id: &str) -> Option<&VehicleProfile> {
+        self.profiles.iter().find(|p| p.id == id)
+    }
+
+    /// Find a profile by ID (mutable)
+    pub fn find_mut(&mut self, id: &str) -> Option<&mut VehicleProfile> {
+        self.profiles.iter_mut().find(|p| p.id == id)
+    }
+
+    /// Remove a profile by ID, clearing it as the default
+    pub fn remove(&mut self, id: &str) -> Option<VehicleProfile> {
+        let pos = self.profiles.iter().position(|p| p.id == id)?;
+        if self.default_profile.as_deref() == Some(id) {
+            self.default_profile = None;
+        }
+        Some(self.profiles.remove(pos))
+    }
+
+    /// Get the path to the profiles JSON file
+    pub fn get_profiles_path() -> Option<PathBuf> {
+        ComputedChannelLibrary::get_config_dir().map(|p| p.join("vehicle_profiles.json"))
+    }
+
+    /// Load the profiles from disk, starting with a default profile when
+    /// none have been saved yet
+    pub fn load() -> Self {
+        let Some(path) = Self::get_profiles_path() else {
+            tracing::warn!("Could not determine config directory for vehicle profiles");
+            return Self::with_default();
+        };
+
+        if !path.exists() {
+            return Self::with_default();
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(profiles) => {
+                    tracing::info!("Loaded vehicle profiles from {:?}", path);
+                    profiles
+                }
+                Err(e) => {
+                    tracing::error!("Failed to parse vehicle profiles: {}", e);
+                    Self::new()
+                }
+            },
+            Err(e) => {
+                tracing::error!("Failed to read vehicle profiles: {}", e);
+                Self::new()
+            }
+        }
+    }
+
+    /// Save the profiles to disk
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::get_profiles_path()
+            .ok_or_else(|| "Could not determine config directory".to_string())?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize vehicle profiles: {}", e))?;
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write vehicle profiles file: {}", e))?;
+
+        tracing::info!("Saved vehicle profiles to {:?}", path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_profile_constants() {
+        let profile = VehicleProfile::new("Track Car".to_string());
+        assert!(profile.validate().is_ok());
+        assert_eq!(profile.constant("$INJECTOR_CC").unwrap().value, 550.0);
+        assert!(profile.constant("boost").is_none());
+    }
+
+    #[test]
+    fn test_validate_constant_names() {
+        let mut profile = VehicleProfile::new("Car".to_string());
+        profile
+            .constants
+            .push(ProfileConstant::new("2fast", 1.0, ""));
+        assert!(profile.validate().unwrap_err().contains("not a valid"));
+
+        profile.constants.pop();
+        profile
+            .constants
+            .push(ProfileConstant::new("Final_Drive", 3.9, ""));
+        assert!(profile.validate().unwrap_err().contains("twice"));
+    }
+
+    #[test]
+    fn test_with_default_profile() {
+        let profiles = VehicleProfiles::with_default();
+        let id = profiles.default_profile.as_deref().unwrap();
+        let profile = profiles.find(id).unwrap();
+        assert_eq!(profile.name, "Default");
+        assert!(profile.constant("injector_cc").is_some());
+    }
+
+    #[test]
+    fn test_remove_clears_default() {
+        let mut profiles = VehicleProfiles::new();
+        let profile = VehicleProfile::new("Car".to_string());
+        let id = profile.id.clone();
+        profiles.profiles.push(profile);
+        profiles.default_profile = Some(id.clone());
+
+        assert!(profiles.remove(&id).is_some());
+        assert!(profiles.default_profile.is_none());
+        assert!(profiles.find(&id).is_none());
+    }
+}
@@ -46,6 +46,8 @@ pub mod example_files {
     // ECUMaster example files
     pub const ECUMASTER_STANDARD: &str = "exampleLogs/ecumaster/2025_1218_1903.csv";
     pub const ECUMASTER_LARGE: &str = "exampleLogs/ecumaster/Largest.csv";
+    pub const ECUMASTER_EMUBLOG: &str = "exampleLogs/ecumaster/220kpa.emublog";
+    pub const ECUMASTER_EMUPROLOG_GZ: &str = "exampleLogs/ecumaster/20250529_1248_31.emuprolog.gz";
 
     // Speeduino example files
     pub const SPEEDUINO_MLG: &str = "exampleLogs/speeduino/speeduino.mlg";
@@ -71,7 +73,38 @@ pub mod example_files {
 
 /// Test data generators for synthetic tests
 pub mod synthetic {
-    use ultralog::parsers::types::Value;
+    use ultralog::parsers::types::{Log, LogData, Value};
+
+    /// Build a log from its timestamps and one column of values per channel
+    pub fn log(times: Vec<f64>, columns: Vec<Vec<f64>>) -> Log {
+        Log {
+            times,
+            data: LogData::from_columns(columns),
+            ..Log::default()
+        }
+    }
+
+    /// GPS fixes for laps of an oval (about 410 m east-west by 445 m
+    /// north-south) sampled at 5 Hz, one lap per entry of `lap_times` in
+    /// seconds. Each lap starts at the south end, heading east.
+    ///
+    /// Returns `(times, latitudes, longitudes)`.
+    pub fn oval_laps(lap_times: &[f64]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let (mut times, mut lat, mut lon) = (Vec::new(), Vec::new(), Vec::new());
+        let mut t = 0.0;
+        for &lap_time in lap_times {
+            let samples = (lap_time * 5.0) as usize;
+            for i in 0..samples {
+                let angle = -std::f64::consts::FRAC_PI_2
+                    + i as f64 / samples as f64 * std::f64::consts::TAU;
+                times.push(t);
+                lat.push(52.0 + 0.002 * angle.sin());
+                lon.push(-1.0 + 0.003 * angle.cos());
+                t += 0.2;
+            }
+        }
+        (times, lat, lon)
+    }
 
     /// Create a simple data matrix with linear values
     pub fn linear_data(channels: usize, records: usize) -> Vec<Vec<Value>> {
@@ -151,8 +184,7 @@ pub mod assertions {
     /// Assert that all data values are finite (not NaN or Infinity)
     pub fn assert_finite_values(log: &Log) {
         for (row_idx, row) in log.data.iter().enumerate() {
-            for (col_idx, value) in row.iter().enumerate() {
-                let f = value.as_f64();
+            for (col_idx, f) in row.iter().enumerate() {
                 assert!(
                     f.is_finite(),
                     "Value at row {}, col {} should be finite, got {}",
@@ -12,7 +12,7 @@ use ultralog::expression::{
     validate_formula,
 };
 use ultralog::parsers::haltech::Haltech;
-use ultralog::parsers::types::{Parseable, Value};
+use ultralog::parsers::types::{LogData, Parseable, Value};
 
 /// Helper function to read a file, panicking with a clear message if not found.
 fn read_example_file(file_path: &str) -> String {
@@ -243,11 +243,11 @@ fn test_validate_case_insensitive_channel_match() {
 
 #[test]
 fn test_evaluate_simple_addition() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(100.0), Value::Float(10.0)],
         vec![Value::Float(200.0), Value::Float(20.0)],
         vec![Value::Float(300.0), Value::Float(30.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("A".to_string(), 0);
@@ -263,10 +263,10 @@ fn test_evaluate_simple_addition() {
 
 #[test]
 fn test_evaluate_multiplication() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(2.0), Value::Float(3.0)],
         vec![Value::Float(4.0), Value::Float(5.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -281,7 +281,7 @@ fn test_evaluate_multiplication() {
 
 #[test]
 fn test_evaluate_with_constants() {
-    let data = vec![vec![Value::Float(100.0)], vec![Value::Float(200.0)]];
+    let data = LogData::from_rows(vec![vec![Value::Float(100.0)], vec![Value::Float(200.0)]]);
     let times = vec![0.0, 0.1];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -295,11 +295,11 @@ fn test_evaluate_with_constants() {
 
 #[test]
 fn test_evaluate_with_math_functions() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(4.0)],
         vec![Value::Float(9.0)],
         vec![Value::Float(16.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -314,11 +314,11 @@ fn test_evaluate_with_math_functions() {
 
 #[test]
 fn test_evaluate_index_offset_previous() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(1000.0)],
         vec![Value::Float(2000.0)],
         vec![Value::Float(3000.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("RPM".to_string(), 0);
@@ -334,11 +334,11 @@ fn test_evaluate_index_offset_previous() {
 
 #[test]
 fn test_evaluate_index_offset_future() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(1000.0)],
         vec![Value::Float(2000.0)],
         vec![Value::Float(3000.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("RPM".to_string(), 0);
@@ -355,13 +355,13 @@ fn test_evaluate_index_offset_future() {
 #[test]
 fn test_evaluate_time_offset() {
     // Create data with known time steps
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(100.0)],
         vec![Value::Float(200.0)],
         vec![Value::Float(300.0)],
         vec![Value::Float(400.0)],
         vec![Value::Float(500.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2, 0.3, 0.4];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -380,25 +380,25 @@ fn test_evaluate_time_offset() {
 
 #[test]
 fn test_evaluate_handles_nan_and_infinity() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(0.0)], // Division by zero case
         vec![Value::Float(1.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
 
-    // 1/X would be infinity at X=0, should be converted to 0
+    // 1/X would be infinity at X=0, recorded as missing
     let result = evaluate_all_records("1/X", &bindings, &data, &times).unwrap();
 
     assert_eq!(result.len(), 2);
-    assert_eq!(result[0], 0.0); // Infinity converted to 0
+    assert!(result[0].is_nan()); // Infinity recorded as missing
     assert_eq!(result[1], 1.0);
 }
 
 #[test]
 fn test_evaluate_empty_data() {
-    let data: Vec<Vec<Value>> = vec![];
+    let data = LogData::default();
     let times: Vec<f64> = vec![];
     let bindings = HashMap::new();
 
@@ -412,7 +412,7 @@ fn test_evaluate_empty_data() {
 
 #[test]
 fn test_generate_preview() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(1.0)],
         vec![Value::Float(2.0)],
         vec![Value::Float(3.0)],
@@ -423,7 +423,7 @@ fn test_generate_preview() {
         vec![Value::Float(8.0)],
         vec![Value::Float(9.0)],
         vec![Value::Float(10.0)],
-    ];
+    ]);
     let times: Vec<f64> = (0..10).map(|i| i as f64 * 0.1).collect();
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -879,11 +879,11 @@ fn test_formula_with_scientific_notation() {
 
 #[test]
 fn test_large_index_offset_clamping() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(1.0)],
         vec![Value::Float(2.0)],
         vec![Value::Float(3.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -903,11 +903,11 @@ fn test_large_index_offset_clamping() {
 
 #[test]
 fn test_time_offset_out_of_range_clamping() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(100.0)],
         vec![Value::Float(200.0)],
         vec![Value::Float(300.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.5, 1.0];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -927,7 +927,7 @@ fn test_time_offset_out_of_range_clamping() {
 
 #[test]
 fn test_single_record_data() {
-    let data = vec![vec![Value::Float(42.0)]];
+    let data = LogData::from_rows(vec![vec![Value::Float(42.0)]]);
     let times = vec![0.0];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -0,0 +1,109 @@
+//! Tests for chart data processing
+//!
+//! Tests cover:
+//! - LTTB downsampling, including gaps from missing samples
+//! - Point normalization
+
+use ultralog::app::UltraLogApp;
+
+#[test]
+fn test_downsample_passthrough_when_small() {
+    let times = vec![0.0, 1.0, 2.0];
+    let values = vec![5.0, 6.0, 7.0];
+
+    let points = UltraLogApp::downsample_lttb(&times, &values, 100);
+
+    assert_eq!(points, vec![[0.0, 5.0], [1.0, 6.0], [2.0, 7.0]]);
+}
+
+#[test]
+fn test_downsample_reduces_point_count() {
+    let times: Vec<f64> = (0..1000).map(|i| i as f64).collect();
+    let values: Vec<f64> = times.iter().map(|t| t.sin()).collect();
+
+    let points = UltraLogApp::downsample_lttb(&times, &values, 100);
+
+    assert_eq!(points.len(), 100);
+    assert_eq!(points[0], [0.0, values[0]]);
+    assert_eq!(points[99], [999.0, values[999]]);
+}
+
+#[test]
+fn test_downsample_keeps_gaps() {
+    let times: Vec<f64> = (0..1000).map(|i| i as f64).collect();
+    let values: Vec<f64> = times
+        .iter()
+        .map(|&t| {
+            if (400.0..600.0).contains(&t) {
+                f64::NAN
+            } else {
+                t
+            }
+        })
+        .collect();
+
+    let points = UltraLogApp::downsample_lttb(&times, &values, 100);
+
+    // A single break point separates the two runs
+    let gaps: Vec<_> = points.iter().filter(|p| p[1].is_nan()).collect();
+    assert_eq!(gaps.len(), 1);
+    assert_eq!(gaps[0][0], 400.0);
+
+    // Both runs keep their end points
+    assert!(points.contains(&[399.0, 399.0]));
+    assert!(points.contains(&[600.0, 600.0]));
+    assert!(points.len() <= 102);
+}
+
+#[test]
+fn test_downsample_all_missing() {
+    let times = vec![0.0, 1.0, 2.0, 3.0];
+    let values = vec![f64::NAN; 4];
+
+    let points = UltraLogApp::downsample_lttb(&times, &values, 3);
+
+    assert!(points.is_empty());
+}
+
+#[test]
+fn test_downsample_alternating_gaps_stays_within_target() {
+    // Every third sample is missing, with one wider gap in the middle
+    let times: Vec<f64> = (0..30_000)
+        .map(|i| {
+            if i >= 15_000 {
+                i as f64 + 100.0
+            } else {
+                i as f64
+            }
+        })
+        .collect();
+    let values: Vec<f64> = (0..30_000)
+        .map(|i| if i % 3 == 2 { f64::NAN } else { i as f64 })
+        .collect();
+
+    let points = UltraLogApp::downsample_lttb(&times, &values, 2000);
+
+    assert!(points.len() <= 2000);
+    // The widest gap is still drawn as a break
+    assert!(points.iter().any(|p| p[1].is_nan() && p[0] == 14_999.0));
+}
+
+#[test]
+fn test_normalize_points_ignores_missing() {
+    let points = vec![[0.0, 10.0], [1.0, f64::NAN], [2.0, 20.0]];
+
+    let normalized = UltraLogApp::normalize_points(&points);
+
+    assert_eq!(normalized[0], [0.0, 0.0]);
+    assert!(normalized[1][1].is_nan());
+    assert_eq!(normalized[2], [2.0, 1.0]);
+}
+
+#[test]
+fn test_normalize_points_constant() {
+    let points = vec![[0.0, 3.0], [1.0, 3.0]];
+
+    let normalized = UltraLogApp::normalize_points(&points);
+
+    assert_eq!(normalized, vec![[0.0, 0.5], [1.0, 0.5]]);
+}
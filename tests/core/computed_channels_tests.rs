@@ -3,16 +3,23 @@
 //! These tests verify the computed channels functionality works correctly
 //! with parsed log files and integrates properly with the existing features.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use ultralog::builtin_templates::builtin_templates;
 use ultralog::computed::{
-    ComputedChannel, ComputedChannelLibrary, ComputedChannelTemplate, FormulaEditorState, TimeShift,
+    computed_dependencies, evaluation_order, find_reference_cycle, run_evaluation_steps,
+    ComputedChannel, ComputedChannelLibrary, ComputedChannelTemplate, EvaluationStep,
+    FormulaEditorState, StepInput, TimeShift,
 };
 use ultralog::expression::{
     build_channel_bindings, evaluate_all_records, extract_channel_references, generate_preview,
-    validate_formula,
+    validate_formula, CompiledFormula, FormulaContext, InputColumn,
 };
+use ultralog::library_sharing::ConflictPolicy;
+use ultralog::lookup_table::LookupTable;
 use ultralog::parsers::haltech::Haltech;
-use ultralog::parsers::types::{Parseable, Value};
+use ultralog::parsers::types::{LogData, Parseable, Value};
+use ultralog::units::to_canonical;
 
 /// Helper function to read a file, panicking with a clear message if not found.
 fn read_example_file(file_path: &str) -> String {
@@ -243,11 +250,11 @@ fn test_validate_case_insensitive_channel_match() {
 
 #[test]
 fn test_evaluate_simple_addition() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(100.0), Value::Float(10.0)],
         vec![Value::Float(200.0), Value::Float(20.0)],
         vec![Value::Float(300.0), Value::Float(30.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("A".to_string(), 0);
@@ -263,10 +270,10 @@ fn test_evaluate_simple_addition() {
 
 #[test]
 fn test_evaluate_multiplication() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(2.0), Value::Float(3.0)],
         vec![Value::Float(4.0), Value::Float(5.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -281,7 +288,7 @@ fn test_evaluate_multiplication() {
 
 #[test]
 fn test_evaluate_with_constants() {
-    let data = vec![vec![Value::Float(100.0)], vec![Value::Float(200.0)]];
+    let data = LogData::from_rows(vec![vec![Value::Float(100.0)], vec![Value::Float(200.0)]]);
     let times = vec![0.0, 0.1];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -295,11 +302,11 @@ fn test_evaluate_with_constants() {
 
 #[test]
 fn test_evaluate_with_math_functions() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(4.0)],
         vec![Value::Float(9.0)],
         vec![Value::Float(16.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -314,11 +321,11 @@ fn test_evaluate_with_math_functions() {
 
 #[test]
 fn test_evaluate_index_offset_previous() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(1000.0)],
         vec![Value::Float(2000.0)],
         vec![Value::Float(3000.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("RPM".to_string(), 0);
@@ -334,11 +341,11 @@ fn test_evaluate_index_offset_previous() {
 
 #[test]
 fn test_evaluate_index_offset_future() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(1000.0)],
         vec![Value::Float(2000.0)],
         vec![Value::Float(3000.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("RPM".to_string(), 0);
@@ -355,13 +362,13 @@ fn test_evaluate_index_offset_future() {
 #[test]
 fn test_evaluate_time_offset() {
     // Create data with known time steps
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(100.0)],
         vec![Value::Float(200.0)],
         vec![Value::Float(300.0)],
         vec![Value::Float(400.0)],
         vec![Value::Float(500.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2, 0.3, 0.4];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -380,25 +387,25 @@ fn test_evaluate_time_offset() {
 
 #[test]
 fn test_evaluate_handles_nan_and_infinity() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(0.0)], // Division by zero case
         vec![Value::Float(1.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
 
-    // 1/X would be infinity at X=0, should be converted to 0
+    // 1/X would be infinity at X=0, recorded as missing
     let result = evaluate_all_records("1/X", &bindings, &data, &times).unwrap();
 
     assert_eq!(result.len(), 2);
-    assert_eq!(result[0], 0.0); // Infinity converted to 0
+    assert!(result[0].is_nan()); // Infinity recorded as missing
     assert_eq!(result[1], 1.0);
 }
 
 #[test]
 fn test_evaluate_empty_data() {
-    let data: Vec<Vec<Value>> = vec![];
+    let data = LogData::default();
     let times: Vec<f64> = vec![];
     let bindings = HashMap::new();
 
@@ -412,7 +419,7 @@ fn test_evaluate_empty_data() {
 
 #[test]
 fn test_generate_preview() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(1.0)],
         vec![Value::Float(2.0)],
         vec![Value::Float(3.0)],
@@ -423,7 +430,7 @@ fn test_generate_preview() {
         vec![Value::Float(8.0)],
         vec![Value::Float(9.0)],
         vec![Value::Float(10.0)],
-    ];
+    ]);
     let times: Vec<f64> = (0..10).map(|i| i as f64 * 0.1).collect();
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -879,11 +886,11 @@ fn test_formula_with_scientific_notation() {
 
 #[test]
 fn test_large_index_offset_clamping() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(1.0)],
         vec![Value::Float(2.0)],
         vec![Value::Float(3.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.1, 0.2];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -903,11 +910,11 @@ fn test_large_index_offset_clamping() {
 
 #[test]
 fn test_time_offset_out_of_range_clamping() {
-    let data = vec![
+    let data = LogData::from_rows(vec![
         vec![Value::Float(100.0)],
         vec![Value::Float(200.0)],
         vec![Value::Float(300.0)],
-    ];
+    ]);
     let times = vec![0.0, 0.5, 1.0];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -927,7 +934,7 @@ fn test_time_offset_out_of_range_clamping() {
 
 #[test]
 fn test_single_record_data() {
-    let data = vec![vec![Value::Float(42.0)]];
+    let data = LogData::from_rows(vec![vec![Value::Float(42.0)]]);
     let times = vec![0.0];
     let mut bindings = HashMap::new();
     bindings.insert("X".to_string(), 0);
@@ -940,3 +947,272 @@ fn test_single_record_data() {
     let result = evaluate_all_records("X - X[-1]", &bindings, &data, &times).unwrap();
     assert_eq!(result[0], 0.0);
 }
+
+// ============================================
+// Chained Computed Channel Tests
+// ============================================
+
+fn applied_channel(name: &str, formula: &str) -> ComputedChannel {
+    ComputedChannel::from_template(ComputedChannelTemplate::new(
+        name.to_string(),
+        formula.to_string(),
+        String::new(),
+        String::new(),
+    ))
+}
+
+#[test]
+fn test_chained_computed_channels_evaluate_in_order() {
+    let data = LogData::from_rows(vec![
+        vec![Value::Float(1000.0), Value::Float(2.0)],
+        vec![Value::Float(2000.0), Value::Float(4.0)],
+        vec![Value::Float(3000.0), Value::Float(6.0)],
+    ]);
+    let times = vec![0.0, 0.1, 0.2];
+    let regular = vec!["RPM".to_string(), "IPW".to_string()];
+
+    // Listed before the channel it references
+    let channels = vec![
+        applied_channel("Fuel Flow", "\"Injector Duty\" * 2"),
+        applied_channel("Injector Duty", "IPW * RPM / 1200"),
+    ];
+    let deps = computed_dependencies(&channels, &regular);
+    let plan = evaluation_order(&deps, &[1]);
+    assert_eq!(plan.order, vec![1, 0]);
+
+    // Log channels followed by computed channels, as in the app
+    let mut available = regular.clone();
+    available.extend(channels.iter().map(|c| c.name().to_string()));
+
+    let mut steps: Vec<EvaluationStep> = Vec::new();
+    for &idx in &plan.order {
+        let formula = channels[idx].formula();
+        let refs = extract_channel_references(formula);
+        let bindings = build_channel_bindings(&refs, &available).unwrap();
+        let compiled = CompiledFormula::compile(formula, &bindings).unwrap();
+        let inputs = compiled
+            .channels()
+            .iter()
+            .map(|&ch| {
+                if ch < regular.len() {
+                    StepInput::Data(InputColumn::from_log(&data, ch).into_owned())
+                } else {
+                    let name = channels[ch - regular.len()].name();
+                    StepInput::Step(steps.iter().position(|s| s.name == name).unwrap())
+                }
+            })
+            .collect();
+        steps.push(EvaluationStep {
+            name: channels[idx].name().to_string(),
+            formula: compiled,
+            inputs,
+            unit: channels[idx].unit().to_string(),
+        });
+    }
+
+    let mut results = HashMap::new();
+    run_evaluation_steps(&steps, &times, data.len(), &|_| {}, |name, values| {
+        results.insert(name.to_string(), values.to_vec());
+    });
+
+    let duty = &results["Injector Duty"];
+    let flow = &results["Fuel Flow"];
+    for i in 0..3 {
+        assert!((flow[i] - duty[i] * 2.0).abs() < 1e-9);
+    }
+    assert!((duty[2] - 15.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_circular_reference_is_detected() {
+    let regular = vec!["RPM".to_string()];
+    let channels = vec![
+        applied_channel("A", "B + RPM"),
+        applied_channel("B", "C * 2"),
+        applied_channel("C", "RPM"),
+    ];
+
+    let cycle = find_reference_cycle("C", "A / 2", &channels, &regular).unwrap();
+    assert_eq!(cycle, vec!["C", "A", "B", "C"]);
+
+    // A regular channel with the same name is not a computed reference
+    assert!(find_reference_cycle("RPM", "RPM * 2", &channels, &regular).is_none());
+}
+
+// ============================================
+// Lookup Table Tests
+// ============================================
+
+#[test]
+fn test_library_stores_lookup_tables() {
+    let mut library = ComputedChannelLibrary::new();
+    library.set_table(LookupTable::new_1d(
+        "Deadtime".to_string(),
+        vec![8.0, 14.0],
+        vec![1.6, 0.7],
+    ));
+    assert!(library.find_table("deadtime").is_some());
+
+    // Replacing by name keeps a single table
+    library.set_table(LookupTable::new_1d(
+        "DEADTIME".to_string(),
+        vec![8.0, 14.0],
+        vec![1.5, 0.6],
+    ));
+    assert_eq!(library.tables.len(), 1);
+
+    let json = serde_json::to_string(&library).unwrap();
+    let loaded: ComputedChannelLibrary = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.tables, library.tables);
+
+    // Libraries saved before tables existed still load
+    let old: ComputedChannelLibrary =
+        serde_json::from_str(r#"{"version": 1, "templates": []}"#).unwrap();
+    assert!(old.tables.is_empty());
+
+    assert!(library.remove_table("Deadtime").is_some());
+    assert!(library.tables.is_empty());
+}
+
+#[test]
+fn test_table_formula_with_library() {
+    let data = LogData::from_columns(vec![vec![9.0, 12.0, 16.0], vec![2.0, 2.0, 2.0]]);
+    let times = vec![0.0, 0.1, 0.2];
+    let available = vec!["Battery Voltage".to_string(), "IPW".to_string()];
+    let csv = "Voltage,Deadtime\n8,1.6\n10,1.2\n12,0.9\n14,0.7\n";
+
+    let mut library = ComputedChannelLibrary::new();
+    library.set_table(LookupTable::from_csv("Deadtime", csv).unwrap());
+
+    let formula = "IPW - table(\"Deadtime\", \"Battery Voltage\")";
+    assert!(validate_formula(formula, &available).is_ok());
+    let refs = extract_channel_references(formula);
+    let bindings = build_channel_bindings(&refs, &available).unwrap();
+    let compiled = CompiledFormula::compile_with_context(
+        formula,
+        &bindings,
+        &FormulaContext {
+            tables: &library.tables,
+            constants: &[],
+        },
+    )
+    .unwrap();
+    let result = compiled.evaluate(&data, &times);
+
+    assert!((result[0] - 0.6).abs() < 1e-9);
+    assert!((result[1] - 1.1).abs() < 1e-9);
+    assert!((result[2] - 1.3).abs() < 1e-9); // Clamped to the last point
+}
+
+#[test]
+fn test_formula_units_across_ecus() {
+    // The same coolant temperature logged in Kelvin and in Celsius
+    let times = vec![0.0, 0.1];
+    let available = vec!["ECT".to_string(), "MAP".to_string()];
+    let refs = extract_channel_references("ECT - 10");
+    let bindings = build_channel_bindings(&refs, &available).unwrap();
+    let compiled = CompiledFormula::compile("ECT - 10", &bindings).unwrap();
+
+    let kelvin = to_canonical("K", Cow::Owned(vec![363.15, 373.15]));
+    let celsius = to_canonical("°C", Cow::Owned(vec![90.0, 100.0]));
+    let from_kelvin = compiled.evaluate_slices(&[&kelvin], &times, 2, &|_| {});
+    let from_celsius = compiled.evaluate_slices(&[&celsius], &times, 2, &|_| {});
+    for (a, b) in from_kelvin.iter().zip(&from_celsius) {
+        assert!((a - b).abs() < 1e-9);
+    }
+
+    // Temperatures are read in °C, so saved formulas keep their meaning
+    assert!((from_celsius[0] - 80.0).abs() < 1e-9);
+    let analysis = compiled.analyze_units(&["K"]);
+    assert_eq!(analysis.unit, Some("°C"));
+
+    // Adding a pressure to a temperature is flagged
+    let refs = extract_channel_references("MAP + ECT");
+    let bindings = build_channel_bindings(&refs, &available).unwrap();
+    let compiled = CompiledFormula::compile("MAP + ECT", &bindings).unwrap();
+    let units: Vec<&str> = compiled
+        .channels()
+        .iter()
+        .map(|&c| if c == 0 { "K" } else { "psi" })
+        .collect();
+    let analysis = compiled.analyze_units(&units);
+    assert_eq!(analysis.unit, None);
+    assert_eq!(
+        analysis.warnings,
+        vec!["Adding pressure (kPa) and temperature (°C)".to_string()]
+    );
+}
+
+#[test]
+fn test_computed_channel_uses_inferred_unit() {
+    let mut channel = ComputedChannel::from_template(ComputedChannelTemplate::new(
+        "Boost".to_string(),
+        "MAP - 100".to_string(),
+        "psi".to_string(),
+        String::new(),
+    ));
+    assert_eq!(channel.unit(), "psi");
+
+    channel.result_unit = Some("kPa");
+    assert_eq!(channel.unit(), "kPa");
+}
+
+#[test]
+fn test_builtin_templates_resolve_ecu_channel_names() {
+    let boost = builtin_templates()
+        .into_iter()
+        .find(|t| t.name == "Boost (Gauge)")
+        .unwrap();
+    let refs = extract_channel_references(&boost.formula);
+
+    // Haltech, ECUMaster and Link names for manifold and barometric pressure
+    for available in [
+        ["Manifold Pressure", "Barometric Pressure"],
+        ["MAP", "Baro"],
+        ["Manifold Absolute Pressure", "Barometer"],
+    ] {
+        let available: Vec<String> = available.iter().map(|s| s.to_string()).collect();
+        let bindings = build_channel_bindings(&refs, &available)
+            .unwrap_or_else(|e| panic!("{:?}: {}", available, e));
+        assert_eq!(bindings["MAP"], 0);
+        assert_eq!(bindings["Baro"], 1);
+    }
+
+    let library = ComputedChannelLibrary::with_builtin_templates();
+    assert!(library.templates.iter().all(|t| t.is_builtin()));
+    assert_eq!(library.templates.len(), builtin_templates().len());
+}
+
+#[test]
+fn test_library_export_import_round_trip() {
+    let mut team = ComputedChannelLibrary::new();
+    team.add_template(ComputedChannelTemplate::new(
+        "Corrected Fuel".to_string(),
+        "table(\"Trim\", RPM) * IPW".to_string(),
+        "ms".to_string(),
+        String::new(),
+    ));
+    team.set_table(LookupTable::new_1d(
+        "Trim".to_string(),
+        vec![1000.0, 6000.0],
+        vec![1.0, 1.1],
+    ));
+    let ids: Vec<String> = team.templates.iter().map(|t| t.id.clone()).collect();
+    let shared = team.subset(&ids).to_json().unwrap();
+
+    // A user who already has the built-ins imports the shared file twice
+    let mut library = ComputedChannelLibrary::with_builtin_templates();
+    let imported = ComputedChannelLibrary::from_json(&shared).unwrap();
+    assert_eq!(library.count_conflicts(&imported), 0);
+    let report = library.merge(imported, ConflictPolicy::KeepExisting);
+    assert_eq!(report.added, 1);
+    assert_eq!(report.changed_tables, vec!["Trim".to_string()]);
+
+    let report = library.merge(
+        ComputedChannelLibrary::from_json(&shared).unwrap(),
+        ConflictPolicy::KeepBoth,
+    );
+    assert_eq!(report.unchanged, 2);
+    assert_eq!(library.templates.len(), builtin_templates().len() + 1);
+    assert!(library.find_table("trim").is_some());
+}
@@ -0,0 +1,46 @@
+//! Tests for the GPS track path behind the track map
+
+use crate::common::synthetic;
+use ultralog::gps::{find_gps_channels, TrackPath};
+use ultralog::parsers::types::Log;
+
+/// Two laps of a 30 s oval sampled at 5 Hz
+fn oval_log() -> Log {
+    let (times, lat, lon) = synthetic::oval_laps(&[30.0, 30.0]);
+    synthetic::log(times, vec![lat, lon])
+}
+
+#[test]
+fn test_track_map_click_and_region() {
+    let log = oval_log();
+    let names = ["Latitude".to_string(), "Longitude".to_string()];
+    let (lat, lon) = find_gps_channels(&names).unwrap();
+    let path = TrackPath::new(
+        &log.get_aligned_channel_data(lat),
+        &log.get_aligned_channel_data(lon),
+    );
+    let (min, max) = path.bounds(0..300).unwrap();
+    // About 410 m east-west and 445 m north-south
+    assert!((max[0] - min[0] - 410.0).abs() < 5.0);
+    assert!((max[1] - min[1] - 445.0).abs() < 5.0);
+
+    // Clicking the north end of the oval during the second lap
+    let north = [(min[0] + max[0]) / 2.0, max[1]];
+    let record = path.nearest_record(north, 150..300).unwrap();
+    assert!((log.times[record] - 45.0).abs() < 0.3);
+
+    // A box over the north end selects that stretch of the lap at the cursor
+    let (a, b) = (
+        [north[0] - 60.0, north[1] - 30.0],
+        [north[0] + 60.0, north[1] + 5.0],
+    );
+    let (start, end) = path
+        .region_time_span(&log.times, a, b, 0..300, Some(44.0))
+        .unwrap();
+    assert!(start > 40.0 && start < 45.0);
+    assert!(end > 45.0 && end < 50.0);
+    let (start, _) = path
+        .region_time_span(&log.times, a, b, 0..300, Some(10.0))
+        .unwrap();
+    assert!(start < 15.0);
+}
@@ -0,0 +1,48 @@
+//! Tests for scatter plot heatmaps binned by table breakpoints
+
+use crate::common::synthetic;
+use ultralog::heatmap::{auto_breakpoints, BreakpointHistogram};
+use ultralog::lookup_table::LookupTable;
+
+#[test]
+fn test_histogram_over_imported_table_axes() {
+    // RPM, MAP and AFR sampled across two table cells
+    let log = synthetic::log(
+        (0..6).map(|i| i as f64 * 0.1).collect(),
+        vec![
+            vec![1950.0, 2050.0, 2100.0, 3900.0, 4100.0, 4000.0],
+            vec![98.0, 101.0, 103.0, 150.0, 155.0, f64::NAN],
+            vec![12.5, 12.7, 12.9, 11.8, 11.6, 11.7],
+        ],
+    );
+    let table = LookupTable::from_csv("VE", ",2000,4000\n100,80,90\n150,85,95").unwrap();
+
+    let histogram = BreakpointHistogram::build(
+        &log.get_aligned_channel_data(0),
+        &log.get_aligned_channel_data(1),
+        Some(&log.get_aligned_channel_data(2)),
+        &table.x_axis,
+        &table.y_axis,
+    );
+
+    let low = histogram.cells[0][0];
+    assert_eq!(low.hits, 3);
+    assert!((low.mean().unwrap() - 12.7).abs() < 1e-9);
+    assert_eq!((low.min(), low.max()), (Some(12.5), Some(12.9)));
+    // The record without MAP is left out
+    assert_eq!(histogram.cells[1][1].hits, 2);
+    assert_eq!(histogram.max_hits, 3);
+
+    let csv = histogram.to_csv("RPM", "MAP", Some("AFR"));
+    assert_eq!(csv.lines().count(), 5);
+    assert!(csv.lines().nth(2).unwrap().starts_with("4000,100,0,,"));
+}
+
+#[test]
+fn test_auto_breakpoints_cover_data() {
+    let map: Vec<f64> = (0..200).map(|i| 22.0 + i as f64 * 0.9).collect();
+    let axis = auto_breakpoints(&map, 12);
+    assert!(axis[0] <= 22.0 && *axis.last().unwrap() >= map[199]);
+    assert!(axis.windows(2).all(|w| w[1] > w[0]));
+    assert!(axis.len() >= 10 && axis.len() <= 13);
+}
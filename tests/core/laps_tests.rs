@@ -0,0 +1,68 @@
+//! Tests for lap detection from beacon markers and GPS timing lines
+
+use crate::common::synthetic;
+use ultralog::data_export::record_range;
+use ultralog::gps::find_gps_channels;
+use ultralog::laps::{
+    beacon_crossings, best_lap, laps_from_crossings, LapKind, TimingLine, DEFAULT_LINE_WIDTH_M,
+};
+use ultralog::parsers::types::{Log, Marker, MarkerKind};
+
+/// Three 30 s laps of an oval sampled at 5 Hz, the second one 2 s quicker
+fn oval_log() -> (Log, Vec<String>) {
+    let (times, lat, lon) = synthetic::oval_laps(&[30.0, 28.0, 30.0]);
+    let speed = vec![100.0; times.len()];
+    let log = synthetic::log(times, vec![speed, lat, lon]);
+    let names = ["Speed", "GPS Latitude", "GPS Longitude"]
+        .map(str::to_string)
+        .to_vec();
+    (log, names)
+}
+
+#[test]
+fn test_laps_from_gps_start_finish_line() {
+    let (log, names) = oval_log();
+    let (lat_idx, lon_idx) = find_gps_channels(&names).unwrap();
+    let lat = log.get_aligned_channel_data(lat_idx);
+    let lon = log.get_aligned_channel_data(lon_idx);
+    let log_range = (log.times[0], *log.times.last().unwrap());
+
+    // Start/finish a second into the out lap, a sector line half a lap on
+    let start_line = TimingLine::at_record(&lat, &lon, 5, DEFAULT_LINE_WIDTH_M).unwrap();
+    let sector_line = TimingLine::at_record(&lat, &lon, 80, DEFAULT_LINE_WIDTH_M).unwrap();
+    let crossings = start_line.crossings(&log.times, &lat, &lon);
+    let sectors = vec![sector_line.crossings(&log.times, &lat, &lon)];
+    let laps = laps_from_crossings(&crossings, log_range, &sectors);
+
+    let timed: Vec<_> = laps.iter().filter(|l| l.kind == LapKind::Timed).collect();
+    assert_eq!(timed.len(), 2);
+    assert!((timed[0].duration() - 29.93).abs() < 0.1);
+    assert!((timed[1].duration() - 28.07).abs() < 0.1);
+    assert_eq!(timed[0].sectors.len(), 2);
+    assert!((timed[0].sectors.iter().sum::<f64>() - timed[0].duration()).abs() < 1e-9);
+    assert_eq!(laps[best_lap(&laps).unwrap()].number, 2);
+
+    // Restricting to a lap keeps only its records
+    let (start, end) = (timed[1].start, timed[1].end);
+    let records = record_range(&log.times, Some((start, end)));
+    assert!(log.times[records.start] >= start && log.times[records.start - 1] < start);
+    assert!(log.times[records.end - 1] <= end && log.times[records.end] > end);
+}
+
+#[test]
+fn test_laps_from_beacon_markers() {
+    let (mut log, _) = oval_log();
+    log.markers = vec![
+        Marker::new(MarkerKind::Lap, "Lap 1", 1.0),
+        Marker::new(MarkerKind::Lap, "Lap 2", 31.0),
+        Marker::new(MarkerKind::Lap, "Lap 3", 59.0),
+    ];
+    let log_range = (log.times[0], *log.times.last().unwrap());
+    let laps = laps_from_crossings(&beacon_crossings(&log.markers), log_range, &[]);
+
+    let labels: Vec<String> = laps.iter().map(|l| l.label()).collect();
+    // The second before the first beacon is too short for an out lap
+    assert_eq!(labels, ["Lap 1", "Lap 2", "In"]);
+    assert_eq!(laps[1].duration(), 28.0);
+    assert!(laps[0].sectors.is_empty());
+}
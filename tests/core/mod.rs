@@ -6,9 +6,21 @@
 //! - Unit conversions
 //! - State management
 //! - Computed channels
+//! - Chart data processing
+//! - Heatmap breakpoint binning
+//! - GPS track paths
+//! - Lap and sector detection
+//! - Log overlay alignment
+//! - VE Analyze fuel table corrections
 
+pub mod chart_tests;
 pub mod computed_channels_tests;
 pub mod expression_tests;
+pub mod gps_tests;
+pub mod heatmap_tests;
+pub mod laps_tests;
 pub mod normalize_tests;
+pub mod overlay_tests;
 pub mod state_tests;
 pub mod units_tests;
+pub mod ve_analyze_tests;
@@ -0,0 +1,106 @@
+//! Tests for aligning overlaid logs by trigger and by distance
+
+use crate::common::synthetic;
+use ultralog::overlay::{
+    delta_trace, reference_unit_converter, speed_scale, DistanceTrace, TimeMap, Trigger,
+    TriggerEdge,
+};
+use ultralog::parsers::types::Log;
+
+/// A 20 s pull at 10 Hz: TPS opens at `start` and speed (km/h) climbs
+/// by `accel` per second from then on
+fn pull_log(start: f64, accel: f64) -> Log {
+    let times: Vec<f64> = (0..200).map(|i| i as f64 / 10.0).collect();
+    let tps = times
+        .iter()
+        .map(|&t| if t < start { 5.0 } else { 100.0 })
+        .collect();
+    let speed = times
+        .iter()
+        .map(|&t| 20.0 + accel * (t - start).max(0.0))
+        .collect();
+    synthetic::log(times, vec![tps, speed])
+}
+
+#[test]
+fn test_trigger_aligns_logs() {
+    let reference = pull_log(3.0, 10.0);
+    let overlay = pull_log(7.5, 12.0);
+    let trigger = Trigger::default();
+    let zero = |log: &Log| {
+        trigger
+            .first_crossing(&log.times, &log.get_aligned_channel_data(0))
+            .unwrap()
+    };
+    let map = TimeMap::Offset(zero(&reference) - zero(&overlay));
+
+    // The throttle opens at the same reference time in both logs
+    let opened = map.to_reference(zero(&overlay)).unwrap();
+    assert!((opened - zero(&reference)).abs() < 1e-9);
+
+    // Same speed at the trigger, so the delta starts at zero and grows
+    let speed = reference.get_aligned_channel_data(1);
+    let points: Vec<[f64; 2]> = [3.0, 5.0]
+        .iter()
+        .map(|&t| [t, speed[(t * 10.0) as usize]])
+        .collect();
+    let delta = delta_trace(
+        &points,
+        &overlay.times,
+        &overlay.get_aligned_channel_data(1),
+        &map,
+    );
+    assert!(delta[0][1].abs() < 0.5);
+    assert!((delta[1][1] - 4.0).abs() < 0.5);
+
+    let never = Trigger {
+        threshold: 100.0,
+        edge: TriggerEdge::Falling,
+    };
+    assert_eq!(
+        never.first_crossing(&reference.times, &reference.get_aligned_channel_data(0)),
+        None
+    );
+}
+
+#[test]
+fn test_distance_alignment() {
+    let reference = pull_log(0.0, 0.0);
+    let overlay = pull_log(0.0, 0.0);
+    let scale = speed_scale("km/h");
+    assert!((scale - 1.0 / 3.6).abs() < 1e-12);
+
+    // Both at a steady 20 km/h, the overlay's distance counted from 2 s
+    let distance = |log: &Log| {
+        let speed: Vec<f64> = log
+            .get_aligned_channel_data(1)
+            .iter()
+            .map(|v| v * scale)
+            .collect();
+        DistanceTrace::new(&log.times, &speed)
+    };
+    let map = TimeMap::Distance {
+        reference: distance(&reference),
+        overlay: distance(&overlay),
+        reference_zero: 0.0,
+        overlay_zero: 2.0,
+        shift: 0.5,
+    };
+    let t = map.to_reference(6.0).unwrap();
+    assert!((t - 4.5).abs() < 1e-6);
+    assert!((map.to_overlay(t).unwrap() - 6.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_overlay_values_in_reference_unit() {
+    // Haltech logs coolant in K, Link in °C
+    let convert = reference_unit_converter("K", "°C");
+    assert!((convert(363.15) - 90.0).abs() < 1e-9);
+
+    let convert = reference_unit_converter("psi", "kPa");
+    assert!((convert(14.5) - 99.974).abs() < 1e-3);
+
+    // Unknown or unrelated units are left alone
+    assert_eq!(reference_unit_converter("V", "°C")(12.0), 12.0);
+    assert_eq!(reference_unit_converter("kPa", "°C")(100.0), 100.0);
+}
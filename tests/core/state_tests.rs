@@ -9,12 +9,12 @@
 
 use std::path::PathBuf;
 use ultralog::parsers::haltech::{ChannelType, HaltechChannel};
-use ultralog::parsers::types::{EcuType, Log, Value};
+use ultralog::parsers::types::{EcuType, Log, LogData, Value};
 use ultralog::parsers::Channel;
 use ultralog::state::{
     ActiveTool, CacheKey, LoadResult, LoadedFile, LoadingState, ScatterPlotConfig,
     ScatterPlotState, SelectedChannel, SelectedHeatmapPoint, Tab, ToastType, CHART_COLORS,
-    COLORBLIND_COLORS, MAX_CHANNELS, MAX_CHART_POINTS, SUPPORTED_EXTENSIONS,
+    COLORBLIND_COLORS, MAX_CHANNELS, MAX_CHART_POINTS,
 };
 
 // ============================================
@@ -38,22 +38,6 @@ fn test_max_chart_points_reasonable() {
     assert_eq!(MAX_CHART_POINTS, 2000, "Expected 2000 max chart points");
 }
 
-#[test]
-fn test_supported_extensions_not_empty() {
-    assert!(
-        !SUPPORTED_EXTENSIONS.is_empty(),
-        "Should have supported extensions"
-    );
-}
-
-#[test]
-fn test_supported_extensions_contains_common() {
-    assert!(SUPPORTED_EXTENSIONS.contains(&"csv"), "Should support CSV");
-    assert!(SUPPORTED_EXTENSIONS.contains(&"mlg"), "Should support MLG");
-    assert!(SUPPORTED_EXTENSIONS.contains(&"xrk"), "Should support XRK");
-    assert!(SUPPORTED_EXTENSIONS.contains(&"llg"), "Should support LLG");
-}
-
 // ============================================
 // Color Palette Tests
 // ============================================
@@ -181,6 +165,8 @@ fn test_active_tool_default() {
 fn test_active_tool_names() {
     assert_eq!(ActiveTool::LogViewer.name(), "Log Viewer");
     assert_eq!(ActiveTool::ScatterPlot.name(), "Scatter Plots");
+    assert_eq!(ActiveTool::VeAnalyze.name(), "VE Analyze");
+    assert_eq!(ActiveTool::TrackMap.name(), "Track Map");
 }
 
 #[test]
@@ -301,6 +287,7 @@ fn test_scatter_plot_config_default() {
     assert!(config.y_channel.is_none());
     assert!(config.z_channel.is_none());
     assert!(config.selected_point.is_none());
+    assert!(!config.breakpoints.enabled);
 }
 
 #[test]
@@ -462,11 +449,12 @@ fn create_test_log() -> Log {
             }),
         ],
         times: vec![0.0, 0.1, 0.2],
-        data: vec![
+        data: LogData::from_rows(vec![
             vec![Value::Float(5000.0), Value::Float(50.0)],
             vec![Value::Float(5100.0), Value::Float(0.0)],
             vec![Value::Float(0.0), Value::Float(0.0)],
-        ],
+        ]),
+        markers: vec![],
     }
 }
 
@@ -532,11 +520,12 @@ fn test_loaded_file_all_zero_channel() {
             display_max: Some(100.0),
         })],
         times: vec![0.0, 0.1, 0.2],
-        data: vec![
+        data: LogData::from_rows(vec![
             vec![Value::Float(0.0)],
             vec![Value::Float(0.0)],
             vec![Value::Float(0.0)],
-        ],
+        ]),
+        markers: vec![],
     };
 
     let file = LoadedFile::new(
@@ -563,10 +552,11 @@ fn test_loaded_file_near_zero_channel() {
             display_max: Some(100.0),
         })],
         times: vec![0.0, 0.1],
-        data: vec![
+        data: LogData::from_rows(vec![
             vec![Value::Float(0.00001)], // Below threshold
             vec![Value::Float(0.00002)], // Below threshold
-        ],
+        ]),
+        markers: vec![],
     };
 
     let file = LoadedFile::new(
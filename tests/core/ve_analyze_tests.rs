@@ -0,0 +1,84 @@
+//! Tests for VE Analyze fuel table corrections
+
+use crate::common::synthetic;
+use ultralog::lookup_table::{parse_axis, LookupTable};
+use ultralog::parsers::types::Log;
+use ultralog::ve_analyze::{values_to_tsv, AfrTarget, VeAnalysis, VeAnalyzeData, VeAnalyzeFilters};
+
+/// A steady-state log: 1 s at each RPM / MAP point, running 10% lean at
+/// 3000 RPM / 100 kPa and on target everywhere else
+fn steady_state_log() -> Log {
+    let points = [
+        (2000.0, 50.0),
+        (3000.0, 50.0),
+        (2000.0, 100.0),
+        (3000.0, 100.0),
+    ];
+    let mut columns = vec![Vec::new(); 5];
+    for (rpm, map) in points {
+        for _ in 0..20 {
+            let afr = if rpm == 3000.0 && map == 100.0 {
+                13.2
+            } else {
+                12.0
+            };
+            for (column, value) in columns.iter_mut().zip([rpm, map, afr, 12.0, 85.0]) {
+                column.push(value);
+            }
+        }
+    }
+    synthetic::log((0..80).map(|i| i as f64 * 0.05).collect(), columns)
+}
+
+#[test]
+fn test_corrects_pasted_fuel_table() {
+    let log = steady_state_log();
+    // Copied from the tuning software with its axes, tab-separated
+    let base = LookupTable::from_csv("VE", "\t2000\t3000\n50\t60\t70\n100\t80\t90").unwrap();
+    let x_axis = parse_axis("RPM", "2000, 3000").unwrap();
+    let y_axis = parse_axis("Load", "50, 100").unwrap();
+
+    let data = VeAnalyzeData {
+        times: &log.times,
+        rpm: &log.get_aligned_channel_data(0),
+        load: &log.get_aligned_channel_data(1),
+        measured: &log.get_aligned_channel_data(2),
+        target: AfrTarget::Channel(&log.get_aligned_channel_data(3)),
+        coolant: Some(&log.get_aligned_channel_data(4)),
+        transient: None,
+        closed_loop: None,
+    };
+    let analysis = VeAnalysis::run(&data, &x_axis, &y_axis, &VeAnalyzeFilters::default()).unwrap();
+    assert_eq!(analysis.counts.used, 80);
+    assert_eq!(analysis.corrected_cells(), 4);
+
+    let corrected = analysis.corrected_table(&base).unwrap();
+    assert_eq!(values_to_tsv(&corrected, 1), "60.0\t70.0\n80.0\t99.0");
+}
+
+#[test]
+fn test_transient_filter_drops_throttle_blip() {
+    let log = steady_state_log();
+    let times = &log.times;
+    // Throttle snaps open halfway through and stays there
+    let tps: Vec<f64> = (0..80).map(|i| if i < 40 { 20.0 } else { 80.0 }).collect();
+    let data = VeAnalyzeData {
+        times,
+        rpm: &log.get_aligned_channel_data(0),
+        load: &log.get_aligned_channel_data(1),
+        measured: &log.get_aligned_channel_data(2),
+        target: AfrTarget::Fixed(12.0),
+        coolant: None,
+        transient: Some(&tps),
+        closed_loop: None,
+    };
+    let filters = VeAnalyzeFilters {
+        max_transient_rate: Some(100.0),
+        transient_holdoff: 0.22,
+        ..VeAnalyzeFilters::default()
+    };
+    let analysis = VeAnalysis::run(&data, &[2000.0, 3000.0], &[50.0, 100.0], &filters).unwrap();
+    // The step at 2.0 s and the 4 records within 0.22 s after it
+    assert_eq!(analysis.counts.transient, 5);
+    assert_eq!(analysis.cells[1][0].samples, 15);
+}
@@ -3,8 +3,15 @@
 //! Tests for field normalization, expressions, units, state management,
 //! and computed channels.
 
+// Each test binary only uses part of the common helpers
 #[path = "common/mod.rs"]
+#[allow(dead_code)]
 mod common;
 
 #[path = "core/mod.rs"]
+#[allow(
+    clippy::assertions_on_constants,
+    clippy::field_reassign_with_default,
+    clippy::useless_vec
+)]
 mod core_tests;
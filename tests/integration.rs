@@ -3,8 +3,12 @@
 //! Tests for complete file loading cycles, format detection,
 //! and cross-format data integrity.
 
+// Each test binary, and some test files, include their own copy of the
+// common helpers and only use part of it
 #[path = "common/mod.rs"]
+#[allow(dead_code)]
 mod common;
 
 #[path = "integration/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod)]
 mod integration_tests;
@@ -0,0 +1,325 @@
+//! Command-line interface tests
+//!
+//! Tests cover argument parsing and each subcommand run end-to-end against
+//! a small MegaSquirt log written to a temporary directory.
+
+use std::path::PathBuf;
+use ultralog::cli::{self, Command, FormulaSource, OutputFormat, TimeRange};
+use ultralog::computed::ComputedChannelTemplate;
+use ultralog::expression::FormulaContext;
+use ultralog::vehicle_profile::{VehicleProfile, VehicleProfiles};
+
+const SAMPLE_MSL: &str = "\"MS3 Format 0435.14P\"\n\
+    Time\tRPM\tMAP\tAFR\n\
+    s\tRPM\tkPa\tAFR\n\
+    0.000\t800\t30.0\t14.7\n\
+    0.100\t1000\t40.0\t14.5\n\
+    0.200\t1200\t\t14.3\n\
+    0.300\t1400\t60.0\t14.1\n";
+
+/// Write the sample log to a unique temporary file
+fn sample_log(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ultralog_cli_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.msl", name));
+    std::fs::write(&path, SAMPLE_MSL).unwrap();
+    path
+}
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+fn run(list: &[&str]) -> String {
+    let command = cli::parse_args(args(list)).expect("Should parse arguments");
+    let mut out = Vec::new();
+    cli::run(&command, &mut out).expect("Command should succeed");
+    String::from_utf8(out).unwrap()
+}
+
+// ============================================
+// Argument Parsing Tests
+// ============================================
+
+#[test]
+fn test_parse_no_args_is_help() {
+    assert_eq!(cli::parse_args(Vec::new()), Ok(Command::Help));
+    assert_eq!(cli::parse_args(args(&["--help"])), Ok(Command::Help));
+}
+
+#[test]
+fn test_parse_export_options() {
+    let command = cli::parse_args(args(&[
+        "export", "log.csv", "-c", "RPM, MAP", "--start", "1.5", "--end", "3", "-f", "json", "-o",
+        "out.json",
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        command,
+        Command::Export {
+            file: PathBuf::from("log.csv"),
+            channels: vec!["RPM".to_string(), "MAP".to_string()],
+            range: TimeRange {
+                start: Some(1.5),
+                end: Some(3.0),
+            },
+            format: OutputFormat::Json,
+            output: Some(PathBuf::from("out.json")),
+        }
+    );
+}
+
+#[test]
+fn test_parse_computed_requires_formula() {
+    assert!(cli::parse_args(args(&["computed", "log.csv"])).is_err());
+
+    let command = cli::parse_args(args(&["computed", "log.csv", "-t", "Boost"])).unwrap();
+    assert!(matches!(
+        command,
+        Command::Computed { source: FormulaSource::Template(ref t), .. } if t == "Boost"
+    ));
+
+    let command = cli::parse_args(args(&[
+        "computed",
+        "log.csv",
+        "--formula",
+        "RPM",
+        "--profile",
+        "Car",
+    ]))
+    .unwrap();
+    assert!(matches!(
+        command,
+        Command::Computed { profile: Some(ref p), .. } if p == "Car"
+    ));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(cli::parse_args(args(&["frobnicate", "log.csv"])).is_err());
+    assert!(cli::parse_args(args(&["info"])).is_err());
+    assert!(cli::parse_args(args(&["info", "a.csv", "b.csv"])).is_err());
+    assert!(cli::parse_args(args(&["export", "log.csv", "--start"])).is_err());
+    assert!(cli::parse_args(args(&["export", "log.csv", "--start", "soon"])).is_err());
+    assert!(cli::parse_args(args(&["export", "log.csv", "--format", "xml"])).is_err());
+    assert!(cli::parse_args(args(&["stats", "log.csv", "--bogus"])).is_err());
+}
+
+// ============================================
+// Command Tests
+// ============================================
+
+#[test]
+fn test_info_command() {
+    let path = sample_log("info");
+    let output = run(&["info", path.to_str().unwrap()]);
+
+    assert!(output.contains("Format:      MegaSquirt"));
+    assert!(output.contains("Records:     4"));
+    assert!(output.contains("Sample rate: 10.0 Hz"));
+    assert!(output.contains("RPM [RPM]"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&run(&["info", path.to_str().unwrap(), "--json"])).unwrap();
+    assert_eq!(json["records"], 4);
+    assert_eq!(json["channels"][1]["name"], "MAP");
+    assert_eq!(json["channels"][1]["unit"], "kPa");
+}
+
+#[test]
+fn test_stats_command() {
+    let path = sample_log("stats");
+    let json: serde_json::Value = serde_json::from_str(&run(&[
+        "stats",
+        path.to_str().unwrap(),
+        "-c",
+        "rpm",
+        "--json",
+    ]))
+    .unwrap();
+
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["min"], 800.0);
+    assert_eq!(json[0]["max"], 1400.0);
+    assert_eq!(json[0]["mean"], 1100.0);
+    assert_eq!(json[0]["samples"], 4);
+
+    let text = run(&["stats", path.to_str().unwrap()]);
+    assert!(text.lines().next().unwrap().starts_with("Channel"));
+    assert_eq!(text.lines().count(), 4);
+}
+
+#[test]
+fn test_stats_unknown_channel_fails() {
+    let path = sample_log("stats_unknown");
+    let command = cli::parse_args(args(&["stats", path.to_str().unwrap(), "-c", "Nope"])).unwrap();
+    assert!(cli::run(&command, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn test_export_command_csv() {
+    let path = sample_log("export");
+    let output = run(&[
+        "export",
+        path.to_str().unwrap(),
+        "-c",
+        "RPM,AFR",
+        "--start",
+        "0.1",
+        "--end",
+        "0.2",
+    ]);
+
+    assert_eq!(
+        output,
+        "Time (s),RPM (RPM),AFR (AFR)\n0.1,1000,14.5\n0.2,1200,14.3\n"
+    );
+}
+
+#[test]
+fn test_export_command_to_file() {
+    let path = sample_log("export_file");
+    let out_path = path.with_extension("json");
+    run(&[
+        "export",
+        path.to_str().unwrap(),
+        "-f",
+        "json",
+        "-o",
+        out_path.to_str().unwrap(),
+    ]);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&out_path).unwrap()).unwrap();
+    assert_eq!(json["times"].as_array().unwrap().len(), 4);
+    assert_eq!(json["channels"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn test_computed_command_formula() {
+    let path = sample_log("computed");
+    let output = run(&[
+        "computed",
+        path.to_str().unwrap(),
+        "--formula",
+        "RPM / 100",
+        "--end",
+        "0.1",
+    ]);
+
+    assert_eq!(output, "Time (s),Computed\n0,8\n0.1,10\n");
+}
+
+#[test]
+fn test_computed_formula_constants() {
+    let path = sample_log("computed_constants");
+    let (log, _) = cli::load_log(&path).unwrap();
+    let mut profiles = VehicleProfiles::new();
+    profiles
+        .profiles
+        .push(VehicleProfile::new("Car".to_string()));
+    profiles.default_profile = Some(profiles.profiles[0].id.clone());
+
+    // The default profile is used unless one is named
+    let profile = cli::resolve_profile(&profiles, None).unwrap();
+    assert_eq!(profile.map(|p| p.name.as_str()), Some("Car"));
+    assert!(cli::resolve_profile(&profiles, Some("car"))
+        .unwrap()
+        .is_some());
+    assert!(cli::resolve_profile(&profiles, Some("Bike")).is_err());
+
+    let template = ComputedChannelTemplate::new(
+        "Cylinder RPM".to_string(),
+        "RPM * $cylinders".to_string(),
+        String::new(),
+        String::new(),
+    );
+    let context = FormulaContext {
+        tables: &[],
+        constants: &profile.unwrap().constants,
+    };
+    let (values, _) = cli::evaluate_template(&log, &template, &context).unwrap();
+    assert_eq!(values[0], 3200.0);
+
+    let empty = FormulaContext {
+        tables: &[],
+        constants: &[],
+    };
+    let error = cli::evaluate_template(&log, &template, &empty).unwrap_err();
+    assert!(error.contains("$cylinders"));
+}
+
+#[test]
+fn test_computed_command_template_library() {
+    let path = sample_log("computed_library");
+    let library_path = path.with_extension("json");
+    std::fs::write(
+        &library_path,
+        r#"{"version": 1, "templates": [{"id": "abc", "name": "Half RPM",
+            "formula": "RPM * 0.5", "unit": "RPM", "created_at": 0, "modified_at": 0}]}"#,
+    )
+    .unwrap();
+
+    let output = run(&[
+        "computed",
+        path.to_str().unwrap(),
+        "-t",
+        "half rpm",
+        "--library",
+        library_path.to_str().unwrap(),
+        "--start",
+        "0.3",
+    ]);
+    assert_eq!(output, "Time (s),Half RPM (RPM)\n0.3,700\n");
+
+    // Formulas look up tables of the library
+    std::fs::write(
+        &library_path,
+        r#"{"version": 1, "templates": [], "tables": [{"name": "Scale",
+            "x_axis": [0, 2000], "values": [[0, 1]]}]}"#,
+    )
+    .unwrap();
+    let output = run(&[
+        "computed",
+        path.to_str().unwrap(),
+        "--formula",
+        "table(\"Scale\", RPM)",
+        "--library",
+        library_path.to_str().unwrap(),
+        "--end",
+        "0",
+    ]);
+    assert_eq!(output, "Time (s),Computed\n0,0.4\n");
+
+    // Shared files written before library versions are migrated
+    for content in [
+        r#"[{"name": "Half RPM", "formula": "RPM * 0.5"}]"#,
+        r#"{"version": 0, "templates": [{"name": "Half RPM", "formula": "RPM * 0.5"}]}"#,
+    ] {
+        std::fs::write(&library_path, content).unwrap();
+        let output = run(&[
+            "computed",
+            path.to_str().unwrap(),
+            "-t",
+            "Half RPM",
+            "--library",
+            library_path.to_str().unwrap(),
+            "--end",
+            "0",
+        ]);
+        assert_eq!(output, "Time (s),Half RPM\n0,400\n");
+    }
+
+    // Unknown templates are an error
+    let command = cli::parse_args(args(&[
+        "computed",
+        path.to_str().unwrap(),
+        "-t",
+        "missing",
+        "--library",
+        library_path.to_str().unwrap(),
+    ]))
+    .unwrap();
+    assert!(cli::run(&command, &mut Vec::new()).is_err());
+}
@@ -94,8 +94,7 @@ fn test_link_time_monotonicity() {
 
 fn assert_all_values_finite(log: &Log, format: &str) {
     for (row_idx, row) in log.data.iter().enumerate() {
-        for (col_idx, value) in row.iter().enumerate() {
-            let f = value.as_f64();
+        for (col_idx, f) in row.iter().enumerate() {
             assert!(
                 f.is_finite(),
                 "{}: Value at row {}, col {} should be finite, got {}",
@@ -340,7 +339,7 @@ fn test_channel_data_extraction_consistency() {
 
         // Values should match direct access
         for (i, &value) in data.iter().enumerate() {
-            let direct = log.data[i][idx].as_f64();
+            let direct = log.data.row(i)[idx];
             assert_eq!(value, direct, "Channel {} data at row {} mismatch", idx, i);
         }
     }
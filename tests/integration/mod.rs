@@ -4,6 +4,8 @@
 //! - Complete file loading cycles
 //! - Cross-format data integrity
 //! - Format detection across all types
+//! - Command-line interface
 
+pub mod cli_tests;
 pub mod data_integrity_tests;
 pub mod file_loading_tests;
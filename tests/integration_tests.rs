@@ -241,8 +241,8 @@ fn test_romraider_parsing() {
     );
 
     // Verify data values
-    assert_eq!(log.data[0][0].as_f64(), 850.0);
-    assert_eq!(log.data[3][0].as_f64(), 1000.0);
+    assert_eq!(log.data.row(0)[0], 850.0);
+    assert_eq!(log.data.row(3)[0], 1000.0);
 
     // Verify data integrity
     assert_eq!(
@@ -487,8 +487,7 @@ fn test_data_values_are_finite() {
 
     // Verify all data values are finite (not NaN or Infinity)
     for (row_idx, row) in log.data.iter().enumerate() {
-        for (col_idx, value) in row.iter().enumerate() {
-            let f = value.as_f64();
+        for (col_idx, f) in row.iter().enumerate() {
             assert!(
                 f.is_finite(),
                 "Value at row {}, col {} should be finite, got {}",
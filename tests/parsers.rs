@@ -2,8 +2,12 @@
 //!
 //! This module includes comprehensive tests for each ECU format parser.
 
+// Each test binary, and some test files, include their own copy of the
+// common helpers and only use part of it
 #[path = "common/mod.rs"]
+#[allow(dead_code)]
 mod common;
 
 #[path = "parsers/mod.rs"]
+#[allow(dead_code, clippy::duplicate_mod, clippy::overly_complex_bool_expr)]
 mod parser_tests;
@@ -6,6 +6,8 @@
 //! - Unit inference from channel names
 //! - Path parsing for nested channel names
 //! - Real file parsing with example logs
+//! - EMU Black native .emublog logs and gzip decompression
+//! - EMU PRO native .emuprolog.gz logs
 
 #[path = "../common/mod.rs"]
 mod common;
@@ -13,9 +15,11 @@ mod common;
 use common::assertions::*;
 use common::example_files::*;
 use common::float_cmp::*;
-use common::{example_file_exists, read_example_file};
+use common::{example_file_exists, read_example_binary, read_example_file};
 use ultralog::parsers::ecumaster::EcuMaster;
-use ultralog::parsers::types::Parseable;
+use ultralog::parsers::emublog::EmuBlog;
+use ultralog::parsers::types::{Channel, EcuType, Parseable};
+use ultralog::parsers::ParserRegistry;
 
 // ============================================
 // Format Detection Tests
@@ -357,7 +361,7 @@ fn test_ecumaster_negative_values() {
     let parser = EcuMaster;
     let log = parser.parse(sample).expect("Should parse negative values");
 
-    assert!(log.data[0][0].as_f64() < 0.0);
+    assert!(log.data.row(0)[0] < 0.0);
 }
 
 #[test]
@@ -367,7 +371,7 @@ fn test_ecumaster_decimal_precision() {
     let parser = EcuMaster;
     let log = parser.parse(sample).expect("Should parse decimals");
 
-    assert_approx_eq(log.data[0][0].as_f64(), 12.3456789, 0.0000001);
+    assert_approx_eq(log.data.row(0)[0], 12.3456789, 0.0000001);
 }
 
 #[test]
@@ -391,7 +395,7 @@ fn test_ecumaster_many_channels() {
     let log = parser.parse(&sample).expect("Should parse many channels");
 
     assert_eq!(log.channels.len(), 50);
-    assert_eq!(log.data[0].len(), 50);
+    assert_eq!(log.data.row(0).len(), 50);
 }
 
 #[test]
@@ -420,13 +424,13 @@ fn test_ecumaster_data_alignment() {
     let log = parser.parse(sample).expect("Should parse");
 
     // Verify data alignment
-    assert_approx_eq(log.data[0][0].as_f64(), 1.0, DEFAULT_TOLERANCE);
-    assert_approx_eq(log.data[0][1].as_f64(), 2.0, DEFAULT_TOLERANCE);
-    assert_approx_eq(log.data[0][2].as_f64(), 3.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(0)[0], 1.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(0)[1], 2.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(0)[2], 3.0, DEFAULT_TOLERANCE);
 
-    assert_approx_eq(log.data[2][0].as_f64(), 7.0, DEFAULT_TOLERANCE);
-    assert_approx_eq(log.data[2][1].as_f64(), 8.0, DEFAULT_TOLERANCE);
-    assert_approx_eq(log.data[2][2].as_f64(), 9.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(2)[0], 7.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(2)[1], 8.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(2)[2], 9.0, DEFAULT_TOLERANCE);
 }
 
 #[test]
@@ -445,3 +449,122 @@ fn test_ecumaster_channel_data_extraction() {
     assert_approx_eq(channel_a[0], 10.0, DEFAULT_TOLERANCE);
     assert_approx_eq(channel_b[2], 22.0, DEFAULT_TOLERANCE);
 }
+
+// ============================================
+// EMU Black Native Log Tests
+// ============================================
+
+#[test]
+fn test_emublog_example_file() {
+    if !example_file_exists(ECUMASTER_EMUBLOG) {
+        eprintln!("Skipping test: {} not found", ECUMASTER_EMUBLOG);
+        return;
+    }
+
+    let compressed = read_example_binary(ECUMASTER_EMUBLOG);
+    let data = ParserRegistry::decompress(&compressed).expect("Should decompress");
+    assert!(EmuBlog::detect(&data), "Should detect EMU Black log");
+
+    let log = EmuBlog::parse_binary(&data).expect("Should parse EMU Black log");
+
+    assert_valid_log_structure(&log);
+    assert_monotonic_times(&log);
+    assert_finite_values(&log);
+    assert_minimum_records(&log, 1000);
+
+    // The log is a pull to 220 kPa
+    let map_idx = log
+        .channels
+        .iter()
+        .position(|c| c.name() == "map")
+        .expect("Should have MAP channel");
+    let max_map = log
+        .get_channel_data(map_idx)
+        .iter()
+        .copied()
+        .fold(f64::MIN, f64::max);
+    assert!(
+        (200.0..=240.0).contains(&max_map),
+        "Peak MAP should be around 220 kPa, got {}",
+        max_map
+    );
+
+    // Internal logger runs at 25 Hz
+    assert_approx_eq(log.times[1] - log.times[0], 0.04, DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn test_emublog_registry_loads_gzip_file() {
+    if !example_file_exists(ECUMASTER_EMUBLOG) {
+        eprintln!("Skipping test: {} not found", ECUMASTER_EMUBLOG);
+        return;
+    }
+
+    let data = read_example_binary(ECUMASTER_EMUBLOG);
+    let (log, ecu_type) = ParserRegistry::new()
+        .parse(&data)
+        .expect("Registry should decompress and parse .emublog");
+
+    assert_eq!(ecu_type, EcuType::EcuMaster);
+    assert!(!log.data.is_empty());
+}
+
+// ============================================
+// EMU PRO Native Log Tests
+// ============================================
+
+#[test]
+fn test_emuprolog_gz_example_file() {
+    if !example_file_exists(ECUMASTER_EMUPROLOG_GZ) {
+        eprintln!("Skipping test: {} not found", ECUMASTER_EMUPROLOG_GZ);
+        return;
+    }
+
+    let data = read_example_binary(ECUMASTER_EMUPROLOG_GZ);
+    let (log, ecu_type) = ParserRegistry::new()
+        .parse(&data)
+        .expect("Registry should decompress and parse .emuprolog.gz");
+
+    assert_eq!(ecu_type, EcuType::EcuMaster);
+    assert_valid_log_structure(&log);
+    assert_monotonic_times(&log);
+    assert_finite_values(&log);
+    assert_minimum_records(&log, 10_000);
+
+    // Records are logged at roughly 23 Hz
+    let duration = log.times.last().copied().unwrap_or(0.0);
+    assert!(
+        (400.0..=500.0).contains(&duration),
+        "Log should span about 7 minutes, got {} s",
+        duration
+    );
+
+    let range = |path: &str| {
+        let idx = log
+            .channels
+            .iter()
+            .position(|c| match c {
+                Channel::EcuMaster(ch) => ch.path == path,
+                _ => false,
+            })
+            .unwrap_or_else(|| panic!("Should have {} channel", path));
+        assert_eq!(log.channels[idx].name(), path.rsplit('/').next().unwrap());
+        let values = log.get_channel_data(idx);
+        let min = values.iter().copied().fold(f64::MAX, f64::min);
+        let max = values.iter().copied().fold(f64::MIN, f64::max);
+        (min, max)
+    };
+
+    let (_, max_rpm) = range("engine/rpm");
+    assert!((1000.0..=8000.0).contains(&max_rpm), "RPM {}", max_rpm);
+
+    let (min_map, max_map) = range("sensors/map");
+    assert_approx_eq(min_map, 22.8, DEFAULT_TOLERANCE);
+    assert_approx_eq(max_map, 100.6, DEFAULT_TOLERANCE);
+
+    let (min_lambda, _) = range("sensors/lambda1");
+    assert!((0.7..=1.0).contains(&min_lambda), "Lambda {}", min_lambda);
+
+    let (_, max_tps) = range("sensors/tps1");
+    assert!((0.0..=100.0).contains(&max_tps), "TPS {}", max_tps);
+}
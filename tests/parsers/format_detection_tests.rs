@@ -3,8 +3,7 @@
 //! These tests verify that format detection is mutually exclusive
 //! and correctly identifies each ECU format.
 
-#[path = "../common/mod.rs"]
-mod common;
+use crate::common;
 
 use common::example_files::*;
 use common::{example_file_exists, read_example_binary, read_example_file};
@@ -13,6 +12,7 @@ use ultralog::parsers::ecumaster::EcuMaster;
 use ultralog::parsers::link::Link;
 use ultralog::parsers::romraider::RomRaider;
 use ultralog::parsers::speeduino::Speeduino;
+use ultralog::parsers::{EcuType, ParserRegistry};
 
 // ============================================
 // Format Marker Tests
@@ -400,3 +400,43 @@ fn test_text_formats_handle_binary() {
         let _ = RomRaider::detect(&as_str);
     }
 }
+
+// ============================================
+// Parser Registry Tests
+// ============================================
+
+#[test]
+fn test_registry_detects_example_files() {
+    let registry = ParserRegistry::new();
+    let cases = [
+        (HALTECH_SMALL, EcuType::Haltech),
+        (ECUMASTER_STANDARD, EcuType::EcuMaster),
+        (SPEEDUINO_MLG, EcuType::Speeduino),
+        (RUSEFI_LOG1, EcuType::Speeduino),
+        (AIM_GENERIC, EcuType::Aim),
+        (LINK_STANDARD, EcuType::Link),
+        (ROMRAIDER_EUROPEAN, EcuType::RomRaider),
+    ];
+
+    for (path, expected) in cases {
+        if !example_file_exists(path) {
+            continue;
+        }
+        let data = read_example_binary(path);
+        let detected = registry.detect(&data).map(|f| f.ecu_type());
+        assert_eq!(detected, Some(expected), "Wrong format for {}", path);
+    }
+}
+
+#[test]
+fn test_registry_parse_reports_format_name_on_error() {
+    let registry = ParserRegistry::new();
+    // Valid LLG magic but header size larger than the file
+    let llg = [0xd7, 0x00, 0x00, 0x00, b'l', b'f', b'3', 0x00];
+    let err = registry.parse(&llg).unwrap_err();
+    assert!(
+        err.to_string().contains("Link ECU LLG"),
+        "Error should name the detected format: {}",
+        err
+    );
+}
@@ -160,7 +160,7 @@ DisplayMaxMin : 10000, 0
     let log = parser.parse(sample).expect("Should parse log");
 
     // RPM should be stored as-is (no conversion)
-    assert_approx_eq(log.data[0][0].as_f64(), 5000.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(0)[0], 5000.0, DEFAULT_TOLERANCE);
 }
 
 #[test]
@@ -180,7 +180,7 @@ DisplayMaxMin : 100, 0
     let log = parser.parse(sample).expect("Should parse log");
 
     // Percentage is converted (raw/10), so 755 -> 75.5
-    assert_approx_eq(log.data[0][0].as_f64(), 75.5, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(0)[0], 75.5, DEFAULT_TOLERANCE);
 }
 
 // ============================================
@@ -392,7 +392,7 @@ DisplayMaxMin : 200, -50
     let log = parser.parse(sample).expect("Should parse negative values");
 
     // Verify negative values are preserved (Raw type, no conversion)
-    assert!(log.data[0][0].as_f64() < 0.0);
+    assert!(log.data.row(0)[0] < 0.0);
 }
 
 #[test]
@@ -411,7 +411,7 @@ DisplayMaxMin : 1000000000, 0
     let parser = Haltech;
     let log = parser.parse(sample).expect("Should parse large values");
 
-    assert_approx_eq(log.data[0][0].as_f64(), 999999999.0, 1.0);
+    assert_approx_eq(log.data.row(0)[0], 999999999.0, 1.0);
 }
 
 #[test]
@@ -430,7 +430,7 @@ DisplayMaxMin : 100, 0
     let parser = Haltech;
     let log = parser.parse(sample).expect("Should parse decimal values");
 
-    assert_approx_eq(log.data[0][0].as_f64(), 12.345678, 0.000001);
+    assert_approx_eq(log.data.row(0)[0], 12.345678, 0.000001);
 }
 
 // ============================================
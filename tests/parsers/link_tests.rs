@@ -260,13 +260,15 @@ fn test_link_channel_data_extraction() {
 
     // Test get_channel_data for each channel
     for idx in 0..log.channels.len() {
+        // Channels keep their own samples, paired with their own timebase
         let channel_data = log.get_channel_data(idx);
         assert_eq!(
             channel_data.len(),
-            log.data.len(),
-            "Channel {} data length should match record count",
+            log.get_channel_times(idx).len(),
+            "Channel {} data length should match its sample times",
             idx
         );
+        assert_eq!(log.get_aligned_channel_data(idx).len(), log.data.len());
     }
 
     // Out of bounds should return empty
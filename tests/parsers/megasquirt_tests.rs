@@ -0,0 +1,181 @@
+//! Comprehensive tests for the MegaSquirt / TunerStudio MSL parser
+//!
+//! Tests cover:
+//! - Format detection
+//! - Title and capture date metadata
+//! - Units row mapping
+//! - MARK rows as markers
+//! - Edge cases (missing units row, blank values, restarted headers)
+
+use crate::common;
+
+use common::assertions::*;
+use ultralog::parsers::megasquirt::MegaSquirt;
+use ultralog::parsers::types::{EcuType, MarkerKind, Meta, Parseable};
+use ultralog::parsers::ParserRegistry;
+
+const MS3_LOG: &str = "\"MS3 Format 0435.14P  \"\n\
+\"Capture Date: Sat Oct 17 09:12:44 CEST 2026\"\n\
+Time\tSecL\tRPM\tMAP\tTPS\tAFR\tCLT\tMAT\tBatt V\tSPK: Spark Advance\n\
+s\tsec\tRPM\tkPa\t%\tAFR\tdeg F\tdeg F\tV\tdeg\n\
+0.000\t0\t912\t38.1\t1.2\t14.70\t178.2\t88.0\t13.9\t14.0\n\
+0.041\t0\t918\t38.0\t1.2\t14.68\t178.2\t88.0\t13.9\t14.0\n\
+0.082\t0\t1450\t55.3\t18.4\t13.90\t178.3\t88.1\t13.8\t22.5\n\
+MARK 000 - Manual - 09:12:45\n\
+0.123\t0\t2210\t82.4\t41.0\t12.80\t178.3\t88.1\t13.8\t28.0\n\
+0.164\t0\t3105\t98.7\t76.5\t12.30\t178.4\t88.2\t13.7\t30.5\n\
+MARK 001 - Manual - 09:12:46\n\
+0.205\t0\t3550\t101.2\t80.0\t12.20\t178.4\t88.2\t13.7\t31.0\n";
+
+// ============================================
+// Format Detection Tests
+// ============================================
+
+#[test]
+fn test_megasquirt_detection() {
+    assert!(MegaSquirt::detect(MS3_LOG), "Should detect MS3 MSL log");
+}
+
+#[test]
+fn test_megasquirt_detection_with_bom() {
+    let with_bom = format!("\u{feff}{}", MS3_LOG);
+    assert!(MegaSquirt::detect(&with_bom), "Should tolerate UTF-8 BOM");
+}
+
+#[test]
+fn test_megasquirt_not_detected_for_other_formats() {
+    assert!(!MegaSquirt::detect("TIME;engine/rpm\n0.0;1000\n"));
+    assert!(!MegaSquirt::detect("TIME\tengine/rpm\n0.0\t1000\n"));
+    assert!(!MegaSquirt::detect(
+        "Time (msec),Engine Speed (rpm)\n0,1000\n"
+    ));
+    assert!(!MegaSquirt::detect("%DataLog%\nDataLogVersion : 1.1\n"));
+    assert!(!MegaSquirt::detect(""));
+}
+
+#[test]
+fn test_megasquirt_registry_detection() {
+    let registry = ParserRegistry::new();
+    let format = registry
+        .detect(MS3_LOG.as_bytes())
+        .expect("Should detect format");
+    assert_eq!(format.ecu_type(), EcuType::MegaSquirt);
+    assert!(format.extensions().contains(&"msl"));
+
+    let (log, ecu_type) = registry.parse(MS3_LOG.as_bytes()).expect("Should parse");
+    assert_eq!(ecu_type, EcuType::MegaSquirt);
+    assert_eq!(log.data.len(), 6);
+}
+
+// ============================================
+// Parsing Tests
+// ============================================
+
+#[test]
+fn test_megasquirt_parse_structure() {
+    let log = MegaSquirt.parse(MS3_LOG).expect("Should parse");
+
+    assert_eq!(log.channels.len(), 9, "Time column is not a channel");
+    assert_eq!(log.data.len(), 6, "MARK rows are not data");
+    assert_valid_log_structure(&log);
+    assert_monotonic_times(&log);
+    assert_finite_values(&log);
+}
+
+#[test]
+fn test_megasquirt_metadata() {
+    let log = MegaSquirt.parse(MS3_LOG).expect("Should parse");
+
+    let Meta::MegaSquirt(meta) = &log.meta else {
+        panic!("Expected MegaSquirt metadata");
+    };
+    assert_eq!(meta.title, "MS3 Format 0435.14P");
+    assert_eq!(meta.capture_date, "Sat Oct 17 09:12:44 CEST 2026");
+}
+
+#[test]
+fn test_megasquirt_channel_units() {
+    let log = MegaSquirt.parse(MS3_LOG).expect("Should parse");
+
+    let unit_of = |name: &str| {
+        log.channels
+            .iter()
+            .find(|c| c.name() == name)
+            .map(|c| c.unit().to_string())
+            .unwrap_or_else(|| panic!("Missing channel {}", name))
+    };
+    assert_eq!(unit_of("RPM"), "RPM");
+    assert_eq!(unit_of("MAP"), "kPa");
+    assert_eq!(unit_of("TPS"), "%");
+    assert_eq!(unit_of("CLT"), "°F");
+    assert_eq!(unit_of("Batt V"), "V");
+    assert_eq!(unit_of("SPK: Spark Advance"), "°");
+    assert_eq!(log.channels[0].type_name(), "MegaSquirt");
+}
+
+#[test]
+fn test_megasquirt_values() {
+    let log = MegaSquirt.parse(MS3_LOG).expect("Should parse");
+
+    assert_eq!(log.times[2], 0.082);
+    // RPM column
+    assert_eq!(log.data.row(2)[1], 1450.0);
+    // AFR column
+    assert_eq!(log.data.row(5)[4], 12.2);
+}
+
+#[test]
+fn test_megasquirt_markers() {
+    let log = MegaSquirt.parse(MS3_LOG).expect("Should parse");
+
+    assert_eq!(log.markers.len(), 2);
+    assert_eq!(log.markers[0].kind, MarkerKind::Manual);
+    assert_eq!(log.markers[0].time, 0.082);
+    assert_eq!(log.markers[0].label, "MARK 000 - Manual - 09:12:45");
+    assert_eq!(log.markers[1].time, 0.164);
+}
+
+// ============================================
+// Edge Case Tests
+// ============================================
+
+#[test]
+fn test_megasquirt_without_units_row() {
+    let data = "\"MS2Extra comms342h2\"\nTime\tRPM\tMAP\n0.0\t900\t40\n0.1\t950\t41\n";
+    let log = MegaSquirt.parse(data).expect("Should parse");
+
+    assert_eq!(
+        log.data.len(),
+        2,
+        "First data row must not be used as units"
+    );
+    assert_eq!(log.channels[0].unit(), "");
+}
+
+#[test]
+fn test_megasquirt_blank_values_are_missing() {
+    let data = "\"MS3\"\nTime\tRPM\tMAP\ns\tRPM\tkPa\n0.0\t900\t40\n0.1\t\t41\n0.2\t1000\n";
+    let log = MegaSquirt.parse(data).expect("Should parse");
+
+    assert!(log.data.row(1)[0].is_nan(), "Blank fields are missing");
+    assert_eq!(log.data.row(1)[1], 41.0);
+    assert!(log.data.row(2)[1].is_nan(), "Short rows are missing values");
+}
+
+#[test]
+fn test_megasquirt_skips_repeated_header() {
+    let data = "\"MS3\"\nTime\tRPM\ns\tRPM\n0.0\t900\nTime\tRPM\n0.1\t950\n";
+    let log = MegaSquirt.parse(data).expect("Should parse");
+
+    assert_eq!(log.times, vec![0.0, 0.1]);
+}
+
+#[test]
+fn test_megasquirt_no_header_error() {
+    assert!(MegaSquirt.parse("\"MS3\"\njust text\n").is_err());
+}
+
+#[test]
+fn test_megasquirt_no_data_error() {
+    assert!(MegaSquirt.parse("\"MS3\"\nTime\tRPM\ns\tRPM\n").is_err());
+}
@@ -11,5 +11,7 @@ pub mod ecumaster_tests;
 pub mod format_detection_tests;
 pub mod haltech_tests;
 pub mod link_tests;
+pub mod megasquirt_tests;
+pub mod motec_tests;
 pub mod romraider_tests;
 pub mod speeduino_tests;
@@ -0,0 +1,420 @@
+//! Comprehensive tests for the MoTeC i2 LD binary parser
+//!
+//! Tests cover:
+//! - Binary header detection (0x40 marker)
+//! - Header, event, venue and vehicle metadata
+//! - Channel linked list traversal
+//! - Data types and value scaling
+//! - Resampling of channels with different sample rates
+//! - Registry integration
+
+use crate::common;
+
+use common::assertions::*;
+use ultralog::parsers::motec::MotEc;
+use ultralog::parsers::types::{Channel, EcuType, Meta, Parseable};
+use ultralog::parsers::ParserRegistry;
+
+// ============================================
+// Synthetic File Builder
+// ============================================
+
+const EVENT_PTR: usize = 1800;
+const VENUE_PTR: usize = 3000;
+const VEHICLE_PTR: usize = 4200;
+const CHANNELS_PTR: usize = 4500;
+
+/// A channel definition for the synthetic file builder
+struct TestChannel {
+    name: &'static str,
+    short: &'static str,
+    unit: &'static str,
+    freq: u16,
+    type_class: u16,
+    type_size: u16,
+    shift: i16,
+    mul: i16,
+    scale: i16,
+    dec: i16,
+    raw: Vec<u8>,
+    samples: u32,
+}
+
+fn write_u16(buf: &mut [u8], offset: usize, value: u16) {
+    buf[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_u32(buf: &mut [u8], offset: usize, value: u32) {
+    buf[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_str(buf: &mut [u8], offset: usize, value: &str) {
+    buf[offset..offset + value.len()].copy_from_slice(value.as_bytes());
+}
+
+fn i16_channel(name: &'static str, unit: &'static str, freq: u16, values: &[i16]) -> TestChannel {
+    TestChannel {
+        name,
+        short: "",
+        unit,
+        freq,
+        type_class: 0x03,
+        type_size: 2,
+        shift: 0,
+        mul: 1,
+        scale: 1,
+        dec: 0,
+        raw: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        samples: values.len() as u32,
+    }
+}
+
+fn f32_channel(name: &'static str, unit: &'static str, freq: u16, values: &[f32]) -> TestChannel {
+    TestChannel {
+        name,
+        short: "",
+        unit,
+        freq,
+        type_class: 0x07,
+        type_size: 4,
+        shift: 0,
+        mul: 1,
+        scale: 1,
+        dec: 0,
+        raw: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        samples: values.len() as u32,
+    }
+}
+
+/// Build a minimal .ld file with metadata blocks and the given channels
+fn build_ld(channels: &[TestChannel]) -> Vec<u8> {
+    let data_start = CHANNELS_PTR + channels.len() * 124;
+    let data_len: usize = channels.iter().map(|c| c.raw.len()).sum();
+    let mut buf = vec![0u8; data_start + data_len];
+
+    // Header
+    write_u32(&mut buf, 0, 0x40);
+    write_u32(
+        &mut buf,
+        8,
+        if channels.is_empty() {
+            0
+        } else {
+            CHANNELS_PTR as u32
+        },
+    );
+    write_u32(&mut buf, 12, data_start as u32);
+    write_u32(&mut buf, 36, EVENT_PTR as u32);
+    write_u32(&mut buf, 70, 12345);
+    write_str(&mut buf, 74, "M150");
+    write_u16(&mut buf, 82, 420);
+    write_u32(&mut buf, 86, channels.len() as u32);
+    write_str(&mut buf, 94, "18/10/2026");
+    write_str(&mut buf, 126, "14:30:00");
+    write_str(&mut buf, 158, "Test Driver");
+    write_str(&mut buf, 222, "Header Car");
+    write_str(&mut buf, 350, "Header Venue");
+    write_str(&mut buf, 1572, "Shakedown");
+
+    // Event
+    write_str(&mut buf, EVENT_PTR, "Club Day");
+    write_str(&mut buf, EVENT_PTR + 64, "Session 2");
+    write_str(&mut buf, EVENT_PTR + 128, "Dry track");
+    write_u16(&mut buf, EVENT_PTR + 1152, VENUE_PTR as u16);
+
+    // Venue
+    write_str(&mut buf, VENUE_PTR, "Thunderhill");
+    write_u16(&mut buf, VENUE_PTR + 1098, VEHICLE_PTR as u16);
+
+    // Vehicle
+    write_str(&mut buf, VEHICLE_PTR, "E46 M3");
+    write_u32(&mut buf, VEHICLE_PTR + 192, 1450);
+    write_str(&mut buf, VEHICLE_PTR + 196, "Car");
+    write_str(&mut buf, VEHICLE_PTR + 228, "Race spec");
+
+    // Channel headers and data
+    let mut data_ptr = data_start;
+    for (i, ch) in channels.iter().enumerate() {
+        let h = CHANNELS_PTR + i * 124;
+        let prev = if i == 0 { 0 } else { h - 124 };
+        let next = if i + 1 == channels.len() { 0 } else { h + 124 };
+        write_u32(&mut buf, h, prev as u32);
+        write_u32(&mut buf, h + 4, next as u32);
+        write_u32(&mut buf, h + 8, data_ptr as u32);
+        write_u32(&mut buf, h + 12, ch.samples);
+        write_u16(&mut buf, h + 18, ch.type_class);
+        write_u16(&mut buf, h + 20, ch.type_size);
+        write_u16(&mut buf, h + 22, ch.freq);
+        write_u16(&mut buf, h + 24, ch.shift as u16);
+        write_u16(&mut buf, h + 26, ch.mul as u16);
+        write_u16(&mut buf, h + 28, ch.scale as u16);
+        write_u16(&mut buf, h + 30, ch.dec as u16);
+        write_str(&mut buf, h + 32, ch.name);
+        write_str(&mut buf, h + 64, ch.short);
+        write_str(&mut buf, h + 72, ch.unit);
+
+        buf[data_ptr..data_ptr + ch.raw.len()].copy_from_slice(&ch.raw);
+        data_ptr += ch.raw.len();
+    }
+
+    buf
+}
+
+fn value_at(log: &ultralog::parsers::types::Log, record: usize, channel: usize) -> f64 {
+    log.data.row(record)[channel]
+}
+
+// ============================================
+// Format Detection Tests
+// ============================================
+
+#[test]
+fn test_motec_detection_valid_file() {
+    let data = build_ld(&[i16_channel("Engine Speed", "rpm", 10, &[1000, 2000])]);
+    assert!(MotEc::detect(&data), "Should detect synthetic .ld file");
+}
+
+#[test]
+fn test_motec_detection_wrong_marker() {
+    let mut data = build_ld(&[i16_channel("Engine Speed", "rpm", 10, &[1000])]);
+    data[0] = 0x41;
+    assert!(!MotEc::detect(&data), "Should not detect wrong marker");
+}
+
+#[test]
+fn test_motec_detection_meta_pointer_out_of_range() {
+    let mut data = build_ld(&[i16_channel("Engine Speed", "rpm", 10, &[1000])]);
+    let len = data.len() as u32;
+    write_u32(&mut data, 8, len + 100);
+    assert!(!MotEc::detect(&data), "Should reject out-of-range pointer");
+}
+
+#[test]
+fn test_motec_detection_too_short() {
+    assert!(!MotEc::detect(&[0x40, 0, 0, 0, 0, 0, 0, 0]));
+    assert!(!MotEc::detect(b""));
+}
+
+#[test]
+fn test_motec_not_detected_for_other_formats() {
+    assert!(!MotEc::detect(b"MLVLG\x00\x00\x01"));
+    assert!(!MotEc::detect(b"%DataLog%\nDataLogVersion : 1.1\n"));
+    assert!(!MotEc::detect(b"<hCNF\x00\x3c\xa5\x00\x00"));
+}
+
+// ============================================
+// Metadata Tests
+// ============================================
+
+#[test]
+fn test_motec_metadata() {
+    let data = build_ld(&[i16_channel("Engine Speed", "rpm", 10, &[1000])]);
+    let log = MotEc::parse_binary(&data).expect("Should parse");
+
+    let Meta::MotEc(meta) = &log.meta else {
+        panic!("Expected MoTeC metadata");
+    };
+    assert_eq!(meta.device_type, "M150");
+    assert_eq!(meta.device_serial, 12345);
+    assert_eq!(meta.device_version, 420);
+    assert_eq!(meta.date, "18/10/2026");
+    assert_eq!(meta.time, "14:30:00");
+    assert_eq!(meta.driver, "Test Driver");
+    assert_eq!(meta.short_comment, "Shakedown");
+    assert_eq!(meta.event, "Club Day");
+    assert_eq!(meta.session, "Session 2");
+    assert_eq!(meta.event_comment, "Dry track");
+    // Venue and vehicle blocks take precedence over header strings
+    assert_eq!(meta.venue, "Thunderhill");
+    assert_eq!(meta.vehicle, "E46 M3");
+    assert_eq!(meta.vehicle_weight, 1450);
+    assert_eq!(meta.vehicle_type, "Car");
+    assert_eq!(meta.vehicle_comment, "Race spec");
+}
+
+#[test]
+fn test_motec_metadata_without_event_block() {
+    let mut data = build_ld(&[i16_channel("Engine Speed", "rpm", 10, &[1000])]);
+    write_u32(&mut data, 36, 0);
+    let log = MotEc::parse_binary(&data).expect("Should parse");
+
+    let Meta::MotEc(meta) = &log.meta else {
+        panic!("Expected MoTeC metadata");
+    };
+    assert!(meta.event.is_empty());
+    assert_eq!(meta.venue, "Header Venue");
+    assert_eq!(meta.vehicle, "Header Car");
+}
+
+// ============================================
+// Channel Parsing Tests
+// ============================================
+
+#[test]
+fn test_motec_channel_list() {
+    let data = build_ld(&[
+        i16_channel("Engine Speed", "rpm", 10, &[1000, 2000]),
+        f32_channel("Throttle Position", "%", 10, &[12.5, 50.0]),
+    ]);
+    let log = MotEc::parse_binary(&data).expect("Should parse");
+
+    assert_eq!(log.channels.len(), 2);
+    assert_eq!(log.channels[0].name(), "Engine Speed");
+    assert_eq!(log.channels[0].unit(), "rpm");
+    assert_eq!(log.channels[0].type_name(), "MoTeC");
+    assert_eq!(log.channels[1].name(), "Throttle Position");
+    assert_eq!(log.channels[1].unit(), "%");
+    assert!(matches!(log.channels[0], Channel::MotEc(_)));
+
+    assert_valid_log_structure(&log);
+    assert_monotonic_times(&log);
+}
+
+#[test]
+fn test_motec_float_values() {
+    let data = build_ld(&[f32_channel("Lambda", "LA", 10, &[0.85, 1.0, 1.1])]);
+    let log = MotEc::parse_binary(&data).expect("Should parse");
+
+    assert_eq!(log.data.len(), 3);
+    assert!((value_at(&log, 0, 0) - 0.85).abs() < 1e-6);
+    assert!((value_at(&log, 2, 0) - 1.1).abs() < 1e-6);
+}
+
+#[test]
+fn test_motec_half_float_values() {
+    let mut ch = f32_channel("Gear", "", 10, &[]);
+    ch.type_size = 2;
+    // 1.0, 2.5 as IEEE half floats
+    ch.raw = [0x3c00u16, 0x4100u16]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    ch.samples = 2;
+    let log = MotEc::parse_binary(&build_ld(&[ch])).expect("Should parse");
+
+    assert_eq!(value_at(&log, 0, 0), 1.0);
+    assert_eq!(value_at(&log, 1, 0), 2.5);
+}
+
+#[test]
+fn test_motec_i32_values() {
+    let mut ch = i16_channel("Distance", "m", 1, &[]);
+    ch.type_size = 4;
+    ch.raw = [100_000i32, -5]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    ch.samples = 2;
+    let log = MotEc::parse_binary(&build_ld(&[ch])).expect("Should parse");
+
+    assert_eq!(value_at(&log, 0, 0), 100_000.0);
+    assert_eq!(value_at(&log, 1, 0), -5.0);
+}
+
+#[test]
+fn test_motec_scaling() {
+    let mut ch = i16_channel("Coolant Temp", "C", 10, &[905, 1000]);
+    ch.dec = 1;
+    ch.shift = -10;
+    ch.mul = 2;
+    ch.scale = 1;
+    let log = MotEc::parse_binary(&build_ld(&[ch])).expect("Should parse");
+
+    // (905 * 0.1 - 10) * 2 = 161
+    assert!((value_at(&log, 0, 0) - 161.0).abs() < 1e-9);
+    // (1000 * 0.1 - 10) * 2 = 180
+    assert!((value_at(&log, 1, 0) - 180.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_motec_unsupported_datatype_is_skipped() {
+    let mut bad = i16_channel("Mystery", "", 10, &[1, 2]);
+    bad.type_class = 0x01;
+    let data = build_ld(&[i16_channel("Engine Speed", "rpm", 10, &[1000, 2000]), bad]);
+    let log = MotEc::parse_binary(&data).expect("Should parse");
+
+    assert_eq!(log.channels.len(), 1);
+    assert_eq!(log.channels[0].name(), "Engine Speed");
+}
+
+#[test]
+fn test_motec_truncated_channel_data() {
+    let mut ch = i16_channel("Engine Speed", "rpm", 10, &[1000, 2000]);
+    ch.samples = 50;
+    let log = MotEc::parse_binary(&build_ld(&[ch])).expect("Should parse");
+
+    assert_eq!(log.data.len(), 2, "Only present samples should be read");
+}
+
+#[test]
+fn test_motec_channel_list_cycle_is_error() {
+    let mut data = build_ld(&[i16_channel("Engine Speed", "rpm", 10, &[1000])]);
+    // Point the only channel's next pointer back at itself
+    write_u32(&mut data, CHANNELS_PTR + 4, CHANNELS_PTR as u32);
+    assert!(MotEc::parse_binary(&data).is_err());
+}
+
+// ============================================
+// Sample Rate Tests
+// ============================================
+
+#[test]
+fn test_motec_mixed_sample_rates() {
+    let data = build_ld(&[
+        i16_channel("Engine Speed", "rpm", 20, &[1, 2, 3, 4, 5, 6, 7, 8]),
+        i16_channel("Coolant Temp", "C", 5, &[80, 81]),
+    ]);
+    let log = MotEc::parse_binary(&data).expect("Should parse");
+
+    // Timebase follows the fastest channel (20 Hz over 0.4 s)
+    assert_eq!(log.times.len(), 8);
+    assert!((log.times[1] - 0.05).abs() < 1e-9);
+
+    // The slow channel holds its value until the next sample
+    let coolant: Vec<f64> = (0..8).map(|i| value_at(&log, i, 1)).collect();
+    assert_eq!(
+        coolant,
+        vec![80.0, 80.0, 80.0, 80.0, 81.0, 81.0, 81.0, 81.0]
+    );
+
+    // Native samples are kept on the channel's own timebase
+    assert_eq!(log.get_channel_data(1), &[80.0, 81.0]);
+    assert_eq!(log.get_channel_times(1), &[0.0, 0.2]);
+    assert_eq!(log.get_channel_times(0), log.times.as_slice());
+}
+
+#[test]
+fn test_motec_no_channels() {
+    // A null channel list pointer means there is nothing to import
+    let data = build_ld(&[]);
+    assert!(!MotEc::detect(&data));
+    assert!(MotEc::parse_binary(&data).is_err());
+}
+
+#[test]
+fn test_motec_text_parse_returns_error() {
+    assert!(MotEc.parse("not binary").is_err());
+}
+
+#[test]
+fn test_motec_invalid_header_error() {
+    assert!(MotEc::parse_binary(b"not an ld file").is_err());
+}
+
+// ============================================
+// Registry Tests
+// ============================================
+
+#[test]
+fn test_motec_registry_detection() {
+    let data = build_ld(&[i16_channel("Engine Speed", "rpm", 10, &[1000])]);
+    let registry = ParserRegistry::new();
+
+    let format = registry.detect(&data).expect("Should detect format");
+    assert_eq!(format.ecu_type(), EcuType::MotEc);
+    assert!(format.extensions().contains(&"ld"));
+
+    let (log, ecu_type) = registry.parse(&data).expect("Should parse");
+    assert_eq!(ecu_type, EcuType::MotEc);
+    assert_eq!(log.channels.len(), 1);
+}
@@ -298,10 +298,10 @@ fn test_romraider_data_values() {
     let parser = RomRaider;
     let log = parser.parse(sample).expect("Should parse");
 
-    assert_approx_eq(log.data[0][0].as_f64(), 1000.0, DEFAULT_TOLERANCE);
-    assert_approx_eq(log.data[0][1].as_f64(), 50.0, DEFAULT_TOLERANCE);
-    assert_approx_eq(log.data[1][0].as_f64(), 2000.0, DEFAULT_TOLERANCE);
-    assert_approx_eq(log.data[1][1].as_f64(), 75.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(0)[0], 1000.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(0)[1], 50.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(1)[0], 2000.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(1)[1], 75.0, DEFAULT_TOLERANCE);
 }
 
 // ============================================
@@ -338,7 +338,7 @@ fn test_romraider_negative_values() {
     let parser = RomRaider;
     let log = parser.parse(sample).expect("Should parse negative values");
 
-    assert!(log.data[0][0].as_f64() < 0.0);
+    assert!(log.data.row(0)[0] < 0.0);
 }
 
 #[test]
@@ -348,7 +348,7 @@ fn test_romraider_decimal_precision() {
     let parser = RomRaider;
     let log = parser.parse(sample).expect("Should parse decimals");
 
-    assert_approx_eq(log.data[0][0].as_f64(), 12.345678, 0.000001);
+    assert_approx_eq(log.data.row(0)[0], 12.345678, 0.000001);
 }
 
 #[test]
@@ -386,7 +386,7 @@ fn test_romraider_large_values() {
     let parser = RomRaider;
     let log = parser.parse(sample).expect("Should parse large values");
 
-    assert_approx_eq(log.data[0][0].as_f64(), 999999999.0, 1.0);
+    assert_approx_eq(log.data.row(0)[0], 999999999.0, 1.0);
 }
 
 // ============================================
@@ -507,9 +507,9 @@ fn test_romraider_parse_european_locale() {
     assert_eq!(log.data.len(), 3);
 
     // Check first row values (European decimals converted)
-    assert_approx_eq(log.data[0][0].as_f64(), 14.0, DEFAULT_TOLERANCE);
-    assert_approx_eq(log.data[0][1].as_f64(), 860.0, DEFAULT_TOLERANCE);
-    assert_approx_eq(log.data[0][2].as_f64(), 0.38, 0.001);
+    assert_approx_eq(log.data.row(0)[0], 14.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(0)[1], 860.0, DEFAULT_TOLERANCE);
+    assert_approx_eq(log.data.row(0)[2], 0.38, 0.001);
 
     // Check relative timestamps (converted from ms to seconds)
     let times = log.get_times_as_f64();